@@ -0,0 +1,133 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use invalid_mutations::limits::{
+    deep_field, deep_local, generic_self_calls, nested_loops, sequential_loops, straight_line_code,
+};
+use move_binary_format::file_format::CompiledModule;
+use move_bytecode_verifier::{verify_module, verify_module_with_config, VerifierConfig};
+use move_core_types::vm_status::StatusCode;
+
+fn verify_with(config: VerifierConfig, module: &CompiledModule) -> Result<(), StatusCode> {
+    verify_module_with_config(&config, module).map_err(|e| e.major_status())
+}
+
+#[test]
+fn default_config_is_unbounded() {
+    for module in [
+        nested_loops(20),
+        sequential_loops(100),
+        straight_line_code(1000),
+        deep_local(100),
+        deep_field(100),
+        generic_self_calls(100, 10),
+    ] {
+        assert!(verify_module(&module).is_ok());
+        assert!(verify_with(VerifierConfig::default(), &module).is_ok());
+    }
+}
+
+#[test]
+fn max_loop_depth() {
+    let config = |depth| VerifierConfig {
+        max_loop_depth: Some(depth),
+        ..Default::default()
+    };
+    assert_eq!(verify_with(config(5), &nested_loops(5)), Ok(()));
+    assert_eq!(
+        verify_with(config(5), &nested_loops(6)),
+        Err(StatusCode::LOOP_MAX_DEPTH_REACHED)
+    );
+}
+
+#[test]
+fn max_back_edges() {
+    let config = |edges| VerifierConfig {
+        max_back_edges_per_function: Some(edges),
+        ..Default::default()
+    };
+    assert_eq!(verify_with(config(10), &sequential_loops(10)), Ok(()));
+    assert_eq!(
+        verify_with(config(10), &sequential_loops(11)),
+        Err(StatusCode::TOO_MANY_BACK_EDGES)
+    );
+    // Nested loops have a back edge per loop as well.
+    assert_eq!(
+        verify_with(config(10), &nested_loops(11)),
+        Err(StatusCode::TOO_MANY_BACK_EDGES)
+    );
+}
+
+#[test]
+fn max_basic_blocks() {
+    let config = |blocks| VerifierConfig {
+        max_basic_blocks: Some(blocks),
+        ..Default::default()
+    };
+    assert_eq!(verify_with(config(11), &sequential_loops(10)), Ok(()));
+    assert_eq!(
+        verify_with(config(10), &sequential_loops(10)),
+        Err(StatusCode::TOO_MANY_BASIC_BLOCKS)
+    );
+}
+
+#[test]
+fn max_type_nodes() {
+    let config = |nodes| VerifierConfig {
+        max_type_nodes: Some(nodes),
+        ..Default::default()
+    };
+    assert_eq!(verify_with(config(10), &deep_local(9)), Ok(()));
+    assert_eq!(
+        verify_with(config(10), &deep_local(10)),
+        Err(StatusCode::TOO_MANY_TYPE_NODES)
+    );
+    assert_eq!(verify_with(config(10), &deep_field(9)), Ok(()));
+    assert_eq!(
+        verify_with(config(10), &deep_field(10)),
+        Err(StatusCode::TOO_MANY_TYPE_NODES)
+    );
+}
+
+#[test]
+fn max_meter_units() {
+    let config = |units| VerifierConfig {
+        max_per_fun_meter_units: Some(units),
+        ..Default::default()
+    };
+    // Each of the three code unit passes interprets all 201 instructions once.
+    let module = straight_line_code(100);
+    assert_eq!(verify_with(config(3 * 201), &module), Ok(()));
+    assert_eq!(
+        verify_with(config(3 * 201 - 1), &module),
+        Err(StatusCode::PROGRAM_TOO_COMPLEX)
+    );
+}
+
+#[test]
+fn max_module_meter_units() {
+    let config = |units| VerifierConfig {
+        max_per_module_meter_units: Some(units),
+        ..Default::default()
+    };
+    // The instantiation loop check charges its 4 nodes and 10 * 4 edges, then each of the three
+    // code unit passes interprets all 11 instructions once.
+    let module = generic_self_calls(10, 4);
+    let graph_units = 4 + 10 * 4;
+    assert_eq!(verify_with(config(graph_units + 3 * 11), &module), Ok(()));
+    assert_eq!(
+        verify_with(config(graph_units + 3 * 11 - 1), &module),
+        Err(StatusCode::PROGRAM_TOO_COMPLEX)
+    );
+    assert_eq!(
+        verify_with(config(graph_units - 1), &module),
+        Err(StatusCode::PROGRAM_TOO_COMPLEX)
+    );
+
+    // The instantiation loop check is not charged to the budget of any function.
+    let config = VerifierConfig {
+        max_per_fun_meter_units: Some(3 * 11),
+        ..Default::default()
+    };
+    assert_eq!(verify_with(config, &module), Ok(()));
+}
@@ -5,4 +5,5 @@
 
 pub mod bounds;
 mod helpers;
+pub mod limits;
 pub mod signature;
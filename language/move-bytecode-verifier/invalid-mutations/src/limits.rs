@@ -0,0 +1,111 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generators for well-formed modules which are expensive to verify. They are used to check that
+//! the limits of the verifier configuration are enforced.
+
+use move_binary_format::file_format::{
+    basic_test_module, AbilitySet, Bytecode, CodeOffset, CompiledModule, FunctionHandleIndex,
+    FunctionInstantiation, FunctionInstantiationIndex, Signature, SignatureIndex, SignatureToken,
+    StructFieldInformation, TableIndex,
+};
+
+/// Returns `basic_test_module` with the body of `foo` replaced by `code`.
+fn module_with_code(code: Vec<Bytecode>) -> CompiledModule {
+    let mut module = basic_test_module();
+    module.function_defs[0].code.as_mut().unwrap().code = code;
+    module
+}
+
+/// A module whose function `foo` has `depth` nested loops:
+///
+/// ```text
+/// 0:       Nop           // head of loop 0
+/// ...
+/// depth-1: Nop           // head of loop depth-1
+/// depth:   Branch(depth-1)
+/// ...
+/// 2*depth-1: Branch(0)
+/// ```
+pub fn nested_loops(depth: usize) -> CompiledModule {
+    let mut code: Vec<_> = (0..depth).map(|_| Bytecode::Nop).collect();
+    code.extend(
+        (0..depth)
+            .rev()
+            .map(|head| Bytecode::Branch(head as CodeOffset)),
+    );
+    module_with_code(code)
+}
+
+/// A module whose function `foo` has `count` consecutive loops, each made of a single basic
+/// block with a back edge to itself. The body has `count + 1` basic blocks.
+pub fn sequential_loops(count: usize) -> CompiledModule {
+    let mut code = vec![];
+    for _ in 0..count {
+        let head = code.len() as CodeOffset;
+        code.push(Bytecode::Nop);
+        code.push(Bytecode::Branch(head));
+    }
+    code.push(Bytecode::Ret);
+    module_with_code(code)
+}
+
+/// A module whose function `foo` is a single basic block of `2 * len + 1` instructions.
+pub fn straight_line_code(len: usize) -> CompiledModule {
+    let mut code = vec![];
+    for _ in 0..len {
+        code.push(Bytecode::LdTrue);
+        code.push(Bytecode::Pop);
+    }
+    code.push(Bytecode::Ret);
+    module_with_code(code)
+}
+
+/// `vector<vector<...<u64>...>>` with `depth` vectors, which is made of `depth + 1` nodes.
+pub fn nested_vector(depth: usize) -> SignatureToken {
+    (0..depth).fold(SignatureToken::U64, |ty, _| {
+        SignatureToken::Vector(Box::new(ty))
+    })
+}
+
+/// A module whose function `foo` has a single local of type `nested_vector(depth)`.
+pub fn deep_local(depth: usize) -> CompiledModule {
+    let mut module = basic_test_module();
+    module
+        .signatures
+        .push(Signature(vec![nested_vector(depth)]));
+    let locals = SignatureIndex((module.signatures.len() - 1) as TableIndex);
+    module.function_defs[0].code.as_mut().unwrap().locals = locals;
+    module
+}
+
+/// A module whose struct `Bar` has a single field of type `nested_vector(depth)`.
+pub fn deep_field(depth: usize) -> CompiledModule {
+    let mut module = basic_test_module();
+    if let StructFieldInformation::Declared(fields) = &mut module.struct_defs[0].field_information {
+        fields[0].signature.0 = nested_vector(depth);
+    }
+    module
+}
+
+/// A module whose function `foo` has `type_params` type parameters and calls itself `calls` times
+/// with them. Its instantiation graph has a node per type parameter and `calls * type_params`
+/// edges, and its body is made of `calls + 1` instructions.
+pub fn generic_self_calls(calls: usize, type_params: usize) -> CompiledModule {
+    let mut module = basic_test_module();
+    module.function_handles[0].type_parameters = vec![AbilitySet::EMPTY; type_params];
+    module.signatures.push(Signature(
+        (0..type_params)
+            .map(|idx| SignatureToken::TypeParameter(idx as u16))
+            .collect(),
+    ));
+    module.function_instantiations.push(FunctionInstantiation {
+        handle: FunctionHandleIndex(0),
+        type_parameters: SignatureIndex((module.signatures.len() - 1) as TableIndex),
+    });
+    let inst = FunctionInstantiationIndex((module.function_instantiations.len() - 1) as TableIndex);
+    let mut code = vec![Bytecode::CallGeneric(inst); calls];
+    code.push(Bytecode::Ret);
+    module.function_defs[0].code.as_mut().unwrap().code = code;
+    module
+}
@@ -1,9 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::meter::Meter;
 use move_binary_format::{
     binary_views::FunctionView,
     control_flow_graph::{BlockId, ControlFlowGraph},
+    errors::PartialVMResult,
     file_format::{Bytecode, CodeOffset},
 };
 use std::collections::BTreeMap;
@@ -72,11 +74,14 @@ pub trait TransferFunctions {
 
 pub trait AbstractInterpreter: TransferFunctions {
     /// Analyze procedure local@function_view starting from pre-state local@initial_state.
+    /// Every instruction interpreted and every join performed is charged to local@meter; the
+    /// analysis is aborted once the meter runs out.
     fn analyze_function(
         &mut self,
         initial_state: Self::State,
         function_view: &FunctionView,
-    ) -> InvariantMap<Self::State, Self::AnalysisError> {
+        meter: &mut Meter,
+    ) -> PartialVMResult<InvariantMap<Self::State, Self::AnalysisError>> {
         let mut inv_map: InvariantMap<Self::State, Self::AnalysisError> = InvariantMap::new();
         let entry_block_id = function_view.cfg().entry_block_id();
         let mut next_block = Some(entry_block_id);
@@ -100,6 +105,7 @@ pub trait AbstractInterpreter: TransferFunctions {
             };
 
             let pre_state = &block_invariant.pre;
+            meter.add(function_view.cfg().instr_indexes(block_id).count() as u64)?;
             let post_state = match self.execute_block(block_id, pre_state, function_view) {
                 Err(e) => {
                     block_invariant.post = BlockPostcondition::Error(e);
@@ -117,6 +123,7 @@ pub trait AbstractInterpreter: TransferFunctions {
             for successor_block_id in function_view.cfg().successors(block_id) {
                 match inv_map.get_mut(successor_block_id) {
                     Some(next_block_invariant) => {
+                        meter.add(1)?;
                         let join_result = {
                             let old_pre = &mut next_block_invariant.pre;
                             old_pre.join(&post_state)
@@ -155,7 +162,7 @@ pub trait AbstractInterpreter: TransferFunctions {
             }
             next_block = next_block_candidate;
         }
-        inv_map
+        Ok(inv_map)
     }
 
     fn execute_block(
@@ -4,20 +4,24 @@
 //! This module implements the checker for verifying correctness of function bodies.
 //! The overall verification is split between stack_usage_verifier.rs and
 //! abstract_interpreter.rs. CodeUnitVerifier simply orchestrates calls into these two files.
+//! It also enforces the per function limits of the `VerifierConfig`.
 use crate::{
-    acquires_list_verifier::AcquiresVerifier, control_flow, locals_safety, reference_safety,
-    stack_usage_verifier::StackUsageVerifier, type_safety,
+    acquires_list_verifier::AcquiresVerifier, control_flow, locals_safety, meter::Meter,
+    reference_safety, stack_usage_verifier::StackUsageVerifier, type_safety,
+    verifier::VerifierConfig,
 };
 use move_binary_format::{
     access::ModuleAccess,
     binary_views::{BinaryIndexedView, FunctionView},
-    errors::{Location, PartialVMResult, VMResult},
+    control_flow_graph::ControlFlowGraph,
+    errors::{Location, PartialVMError, PartialVMResult, VMResult},
     file_format::{
         CompiledModule, CompiledScript, FunctionDefinition, FunctionDefinitionIndex,
         IdentifierIndex, TableIndex,
     },
     IndexKind,
 };
+use move_core_types::vm_status::StatusCode;
 use std::collections::HashMap;
 
 pub struct CodeUnitVerifier<'a> {
@@ -28,25 +32,59 @@ pub struct CodeUnitVerifier<'a> {
 
 impl<'a> CodeUnitVerifier<'a> {
     pub fn verify_module(module: &'a CompiledModule) -> VMResult<()> {
-        Self::verify_module_impl(module).map_err(|e| e.finish(Location::Module(module.self_id())))
+        Self::verify_module_with_config(&VerifierConfig::default(), module)
     }
 
-    fn verify_module_impl(module: &'a CompiledModule) -> PartialVMResult<()> {
+    pub fn verify_module_with_config(
+        verifier_config: &VerifierConfig,
+        module: &'a CompiledModule,
+    ) -> VMResult<()> {
+        Self::verify_module_with_meter(verifier_config, &mut Meter::new(verifier_config), module)
+    }
+
+    /// Same as `verify_module_with_config`, but charges `meter`, which may already have been
+    /// charged by other passes over the module.
+    pub fn verify_module_with_meter(
+        verifier_config: &VerifierConfig,
+        meter: &mut Meter,
+        module: &'a CompiledModule,
+    ) -> VMResult<()> {
+        Self::verify_module_impl(verifier_config, meter, module)
+            .map_err(|e| e.finish(Location::Module(module.self_id())))
+    }
+
+    fn verify_module_impl(
+        verifier_config: &VerifierConfig,
+        meter: &mut Meter,
+        module: &'a CompiledModule,
+    ) -> PartialVMResult<()> {
         for (idx, function_definition) in module.function_defs().iter().enumerate() {
             let index = FunctionDefinitionIndex(idx as TableIndex);
-            Self::verify_function(index, function_definition, module)
+            Self::verify_function(verifier_config, meter, index, function_definition, module)
                 .map_err(|err| err.at_index(IndexKind::FunctionDefinition, index.0))?
         }
         Ok(())
     }
 
     pub fn verify_script(module: &'a CompiledScript) -> VMResult<()> {
-        Self::verify_script_impl(module).map_err(|e| e.finish(Location::Script))
+        Self::verify_script_with_config(&VerifierConfig::default(), module)
+    }
+
+    pub fn verify_script_with_config(
+        verifier_config: &VerifierConfig,
+        module: &'a CompiledScript,
+    ) -> VMResult<()> {
+        Self::verify_script_impl(verifier_config, &mut Meter::new(verifier_config), module)
+            .map_err(|e| e.finish(Location::Script))
     }
 
-    fn verify_script_impl(script: &'a CompiledScript) -> PartialVMResult<()> {
+    fn verify_script_impl(
+        verifier_config: &VerifierConfig,
+        meter: &mut Meter,
+        script: &'a CompiledScript,
+    ) -> PartialVMResult<()> {
         // create `FunctionView` and `BinaryIndexedView`
-        control_flow::verify(None, &script.code)?;
+        control_flow::verify_with_config(verifier_config, None, &script.code)?;
         let function_view = FunctionView::script(script);
         let resolver = BinaryIndexedView::Script(script);
         //verify
@@ -55,10 +93,12 @@ impl<'a> CodeUnitVerifier<'a> {
             function_view,
             name_def_map: HashMap::new(),
         };
-        code_unit_verifier.verify_common()
+        code_unit_verifier.verify_common(verifier_config, meter)
     }
 
     fn verify_function(
+        verifier_config: &VerifierConfig,
+        meter: &mut Meter,
         index: FunctionDefinitionIndex,
         function_definition: &'a FunctionDefinition,
         module: &'a CompiledModule,
@@ -70,7 +110,7 @@ impl<'a> CodeUnitVerifier<'a> {
         };
         // create `FunctionView` and `BinaryIndexedView`
         let function_handle = module.function_handle_at(function_definition.function);
-        control_flow::verify(Some(index), code)?;
+        control_flow::verify_with_config(verifier_config, Some(index), code)?;
         let function_view = FunctionView::function(module, index, code, function_handle);
         let resolver = BinaryIndexedView::Module(module);
         let mut name_def_map = HashMap::new();
@@ -84,14 +124,38 @@ impl<'a> CodeUnitVerifier<'a> {
             function_view,
             name_def_map,
         };
-        code_unit_verifier.verify_common()?;
+        code_unit_verifier.verify_common(verifier_config, meter)?;
         AcquiresVerifier::verify(module, index, function_definition)
     }
 
-    fn verify_common(&self) -> PartialVMResult<()> {
+    fn verify_common(
+        &self,
+        verifier_config: &VerifierConfig,
+        meter: &mut Meter,
+    ) -> PartialVMResult<()> {
+        if let Some(max_basic_blocks) = verifier_config.max_basic_blocks {
+            let num_blocks = self.function_view.cfg().num_blocks() as usize;
+            if num_blocks > max_basic_blocks {
+                return Err(
+                    PartialVMError::new(StatusCode::TOO_MANY_BASIC_BLOCKS).with_message(format!(
+                        "{} basic blocks exceed the limit of {}",
+                        num_blocks, max_basic_blocks
+                    )),
+                );
+            }
+        }
         StackUsageVerifier::verify(&self.resolver, &self.function_view)?;
-        type_safety::verify(&self.resolver, &self.function_view)?;
-        locals_safety::verify(&self.resolver, &self.function_view)?;
-        reference_safety::verify(&self.resolver, &self.function_view, &self.name_def_map)
+        // The passes below share a single budget for the function
+        meter.start_function();
+        type_safety::verify(&self.resolver, &self.function_view, meter)?;
+        locals_safety::verify(&self.resolver, &self.function_view, meter)?;
+        reference_safety::verify(
+            &self.resolver,
+            &self.function_view,
+            &self.name_def_map,
+            meter,
+        )?;
+        meter.end_function();
+        Ok(())
     }
 }
@@ -12,6 +12,7 @@
 //! instances. We do reject recursive functions that create a new type upon each call but do
 //! terminate eventually.
 
+use crate::{meter::Meter, verifier::VerifierConfig};
 use move_binary_format::{
     access::ModuleAccess,
     errors::{Location, PartialVMError, PartialVMResult, VMResult},
@@ -85,12 +86,19 @@ impl<'a> InstantiationLoopChecker<'a> {
     }
 
     pub fn verify_module(module: &'a CompiledModule) -> VMResult<()> {
-        Self::verify_module_impl(module).map_err(|e| e.finish(Location::Module(module.self_id())))
+        Self::verify_module_with_meter(&mut Meter::new(&VerifierConfig::default()), module)
     }
 
-    fn verify_module_impl(module: &'a CompiledModule) -> PartialVMResult<()> {
+    /// Same as `verify_module`, but charges `meter` one unit for every node and edge added to the
+    /// graph, and fails once its budget is exceeded.
+    pub fn verify_module_with_meter(meter: &mut Meter, module: &'a CompiledModule) -> VMResult<()> {
+        Self::verify_module_impl(meter, module)
+            .map_err(|e| e.finish(Location::Module(module.self_id())))
+    }
+
+    fn verify_module_impl(meter: &mut Meter, module: &'a CompiledModule) -> PartialVMResult<()> {
         let mut checker = Self::new(module);
-        checker.build_graph();
+        checker.build_graph(meter)?;
         let mut components = checker.find_non_trivial_components();
 
         match components.pop() {
@@ -122,15 +130,16 @@ impl<'a> InstantiationLoopChecker<'a> {
 
     /// Retrieves the node corresponding to the specified type formal.
     /// If none exists in the graph yet, create one.
-    fn get_or_add_node(&mut self, node: Node) -> NodeIndex {
-        match self.node_map.entry(node) {
+    fn get_or_add_node(&mut self, meter: &mut Meter, node: Node) -> PartialVMResult<NodeIndex> {
+        Ok(match self.node_map.entry(node) {
             hash_map::Entry::Occupied(entry) => *entry.get(),
             hash_map::Entry::Vacant(entry) => {
+                meter.add(1)?;
                 let idx = self.graph.add_node(node);
                 entry.insert(idx);
                 idx
             }
-        }
+        })
     }
 
     /// Helper function that extracts type parameters from a given type.
@@ -162,50 +171,63 @@ impl<'a> InstantiationLoopChecker<'a> {
 
     /// Helper function that creates an edge from one given node to the other.
     /// If a node does not exist, create one.
-    fn add_edge(&mut self, node_from: Node, node_to: Node, edge: Edge<'a>) {
-        let node_from_idx = self.get_or_add_node(node_from);
-        let node_to_idx = self.get_or_add_node(node_to);
+    fn add_edge(
+        &mut self,
+        meter: &mut Meter,
+        node_from: Node,
+        node_to: Node,
+        edge: Edge<'a>,
+    ) -> PartialVMResult<()> {
+        let node_from_idx = self.get_or_add_node(meter, node_from)?;
+        let node_to_idx = self.get_or_add_node(meter, node_to)?;
+        meter.add(1)?;
         self.graph.add_edge(node_from_idx, node_to_idx, edge);
+        Ok(())
     }
 
     /// Helper of 'fn build_graph' that inspects a function call. If type parameters of the caller
     /// appear in the type actuals to the callee, nodes and edges are added to the graph.
     fn build_graph_call(
         &mut self,
+        meter: &mut Meter,
         caller_idx: FunctionDefinitionIndex,
         callee_idx: FunctionDefinitionIndex,
         type_actuals_idx: SignatureIndex,
-    ) {
+    ) -> PartialVMResult<()> {
         let type_actuals = &self.module.signature_at(type_actuals_idx).0;
 
         for (formal_idx, ty) in type_actuals.iter().enumerate() {
             let formal_idx = formal_idx as TypeParameterIndex;
             match ty {
                 SignatureToken::TypeParameter(actual_idx) => self.add_edge(
+                    meter,
                     Node(caller_idx, *actual_idx),
                     Node(callee_idx, formal_idx),
                     Edge::Identity,
-                ),
+                )?,
                 _ => {
                     for type_param in self.extract_type_parameters(ty) {
                         self.add_edge(
+                            meter,
                             Node(caller_idx, type_param),
                             Node(callee_idx, formal_idx),
                             Edge::TyConApp(ty),
-                        );
+                        )?;
                     }
                 }
             }
         }
+        Ok(())
     }
 
     /// Helper of `fn build_graph` that inspects a function definition for calls between two generic
     /// functions defined in the current module.
     fn build_graph_function_def(
         &mut self,
+        meter: &mut Meter,
         caller_idx: FunctionDefinitionIndex,
         caller_def: &FunctionDefinition,
-    ) {
+    ) -> PartialVMResult<()> {
         if let Some(code) = &caller_def.code {
             for instr in &code.code {
                 if let Bytecode::CallGeneric(callee_inst_idx) = instr {
@@ -215,11 +237,17 @@ impl<'a> InstantiationLoopChecker<'a> {
                     let callee_si = self.module.function_instantiation_at(*callee_inst_idx);
                     if let Some(callee_idx) = self.func_handle_def_map.get(&callee_si.handle) {
                         let callee_idx = *callee_idx;
-                        self.build_graph_call(caller_idx, callee_idx, callee_si.type_parameters)
+                        self.build_graph_call(
+                            meter,
+                            caller_idx,
+                            callee_idx,
+                            callee_si.type_parameters,
+                        )?
                     }
                 }
             }
         }
+        Ok(())
     }
 
     /// Builds a graph G such that
@@ -227,7 +255,7 @@ impl<'a> InstantiationLoopChecker<'a> {
     ///   - There is an edge from type formal f_T to g_T if f_T is used to instantiate g_T in a
     ///     call.
     ///     - Each edge is labeled either `Identity` or `TyConApp`. See `Edge` for details.
-    fn build_graph(&mut self) {
+    fn build_graph(&mut self, meter: &mut Meter) -> PartialVMResult<()> {
         for (def_idx, func_def) in self
             .module
             .function_defs()
@@ -235,8 +263,13 @@ impl<'a> InstantiationLoopChecker<'a> {
             .filter(|def| !def.is_native())
             .enumerate()
         {
-            self.build_graph_function_def(FunctionDefinitionIndex::new(def_idx as u16), func_def)
+            self.build_graph_function_def(
+                meter,
+                FunctionDefinitionIndex::new(def_idx as u16),
+                func_def,
+            )?
         }
+        Ok(())
     }
 
     /// Computes the strongly connected components of the graph built and keep the ones that
@@ -17,6 +17,7 @@ pub mod dependencies;
 pub mod friends;
 pub mod instantiation_loops;
 pub mod instruction_consistency;
+pub mod meter;
 pub mod script_signature;
 pub mod signature;
 pub mod struct_defs;
@@ -30,7 +31,10 @@ pub use script_signature::{
 };
 pub use signature::SignatureChecker;
 pub use struct_defs::RecursiveStructDefChecker;
-pub use verifier::{verify_module, verify_script};
+pub use verifier::{
+    verify_module, verify_module_with_config, verify_script, verify_script_with_config,
+    VerifierConfig,
+};
 
 mod acquires_list_verifier;
 mod locals_safety;
@@ -7,7 +7,10 @@
 
 mod abstract_state;
 
-use crate::absint::{AbstractInterpreter, BlockInvariant, BlockPostcondition, TransferFunctions};
+use crate::{
+    absint::{AbstractInterpreter, BlockInvariant, BlockPostcondition, TransferFunctions},
+    meter::Meter,
+};
 use abstract_state::{AbstractState, LocalState};
 use move_binary_format::{
     binary_views::{BinaryIndexedView, FunctionView},
@@ -19,9 +22,10 @@ use move_core_types::vm_status::StatusCode;
 pub(crate) fn verify<'a>(
     resolver: &BinaryIndexedView,
     function_view: &'a FunctionView<'a>,
+    meter: &mut Meter,
 ) -> PartialVMResult<()> {
     let initial_state = AbstractState::new(resolver, function_view)?;
-    let inv_map = LocalsSafetyAnalysis().analyze_function(initial_state, function_view);
+    let inv_map = LocalsSafetyAnalysis().analyze_function(initial_state, function_view, meter)?;
     // Report all the join failures
     for (_block_id, BlockInvariant { post, .. }) in inv_map {
         match post {
@@ -0,0 +1,76 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements a meter bounding the amount of work the verifier performs on a module
+//! or script, and on each of its function bodies. The passes based on abstract interpretation
+//! charge one unit for every instruction they interpret and for every join of abstract states,
+//! and the instantiation loop check charges one unit for every node and edge of its graph, so
+//! code which is expensive to verify is rejected instead of being verified at any cost.
+use crate::verifier::VerifierConfig;
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
+use move_core_types::vm_status::StatusCode;
+
+pub struct Meter {
+    max_per_module_units: Option<u64>,
+    max_per_fun_units: Option<u64>,
+    module_units: u64,
+    /// The units charged for the function being verified, if any.
+    fun_units: Option<u64>,
+}
+
+impl Meter {
+    /// Create a meter with the budgets of `verifier_config`. A meter without budgets never fails.
+    pub fn new(verifier_config: &VerifierConfig) -> Self {
+        Self {
+            max_per_module_units: verifier_config.max_per_module_meter_units,
+            max_per_fun_units: verifier_config.max_per_fun_meter_units,
+            module_units: 0,
+            fun_units: None,
+        }
+    }
+
+    /// Start verifying a function body. Until `end_function` is called, units are charged to the
+    /// budget of the function as well as to the one of the module.
+    pub fn start_function(&mut self) {
+        self.fun_units = Some(0);
+    }
+
+    /// Stop charging units to the budget of the current function.
+    pub fn end_function(&mut self) {
+        self.fun_units = None;
+    }
+
+    /// Charge `units` and fail with `PROGRAM_TOO_COMPLEX` if a budget is exceeded.
+    pub fn add(&mut self, units: u64) -> PartialVMResult<()> {
+        self.module_units = self.module_units.saturating_add(units);
+        if let Some(max_units) = self.max_per_module_units {
+            if self.module_units > max_units {
+                return Err(
+                    PartialVMError::new(StatusCode::PROGRAM_TOO_COMPLEX).with_message(format!(
+                        "module verification budget of {} units exceeded",
+                        max_units
+                    )),
+                );
+            }
+        }
+        if let Some(fun_units) = &mut self.fun_units {
+            *fun_units = fun_units.saturating_add(units);
+            if let Some(max_units) = self.max_per_fun_units {
+                if *fun_units > max_units {
+                    return Err(
+                        PartialVMError::new(StatusCode::PROGRAM_TOO_COMPLEX).with_message(format!(
+                            "function verification budget of {} units exceeded",
+                            max_units
+                        )),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of units charged for the module so far.
+    pub fn units(&self) -> u64 {
+        self.module_units
+    }
+}
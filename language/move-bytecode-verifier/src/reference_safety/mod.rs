@@ -9,7 +9,10 @@
 
 mod abstract_state;
 
-use crate::absint::{AbstractInterpreter, BlockInvariant, BlockPostcondition, TransferFunctions};
+use crate::{
+    absint::{AbstractInterpreter, BlockInvariant, BlockPostcondition, TransferFunctions},
+    meter::Meter,
+};
 use abstract_state::{AbstractState, AbstractValue};
 use move_binary_format::{
     binary_views::{BinaryIndexedView, FunctionView},
@@ -48,11 +51,12 @@ pub(crate) fn verify<'a>(
     resolver: &'a BinaryIndexedView<'a>,
     function_view: &FunctionView,
     name_def_map: &'a HashMap<IdentifierIndex, FunctionDefinitionIndex>,
+    meter: &mut Meter,
 ) -> PartialVMResult<()> {
     let initial_state = AbstractState::new(function_view);
 
     let mut verifier = ReferenceSafetyAnalysis::new(resolver, function_view, name_def_map);
-    let inv_map = verifier.analyze_function(initial_state, function_view);
+    let inv_map = verifier.analyze_function(initial_state, function_view, meter)?;
     // Report all the join failures
     for (_block_id, BlockInvariant { post, .. }) in inv_map {
         match post {
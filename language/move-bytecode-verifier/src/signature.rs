@@ -4,6 +4,8 @@
 //! This module implements a checker for verifying signature tokens used in types of function
 //! parameters, locals, and fields of structs are well-formed. References can only occur at the
 //! top-level in all tokens.  Additionally, references cannot occur at all in field types.
+//! Types can also be bounded in size through `VerifierConfig::max_type_nodes`.
+use crate::verifier::VerifierConfig;
 use move_binary_format::{
     access::{ModuleAccess, ScriptAccess},
     binary_views::BinaryIndexedView,
@@ -19,16 +21,29 @@ use move_core_types::vm_status::StatusCode;
 
 pub struct SignatureChecker<'a> {
     resolver: BinaryIndexedView<'a>,
+    max_type_nodes: Option<usize>,
 }
 
 impl<'a> SignatureChecker<'a> {
     pub fn verify_module(module: &'a CompiledModule) -> VMResult<()> {
-        Self::verify_module_impl(module).map_err(|e| e.finish(Location::Module(module.self_id())))
+        Self::verify_module_with_config(&VerifierConfig::default(), module)
     }
 
-    fn verify_module_impl(module: &'a CompiledModule) -> PartialVMResult<()> {
+    pub fn verify_module_with_config(
+        verifier_config: &VerifierConfig,
+        module: &'a CompiledModule,
+    ) -> VMResult<()> {
+        Self::verify_module_impl(verifier_config, module)
+            .map_err(|e| e.finish(Location::Module(module.self_id())))
+    }
+
+    fn verify_module_impl(
+        verifier_config: &VerifierConfig,
+        module: &'a CompiledModule,
+    ) -> PartialVMResult<()> {
         let sig_check = Self {
             resolver: BinaryIndexedView::Module(module),
+            max_type_nodes: verifier_config.max_type_nodes,
         };
         sig_check.verify_signature_pool(module.signatures())?;
         sig_check.verify_function_signatures(module.function_handles())?;
@@ -37,12 +52,23 @@ impl<'a> SignatureChecker<'a> {
     }
 
     pub fn verify_script(module: &'a CompiledScript) -> VMResult<()> {
-        Self::verify_script_impl(module).map_err(|e| e.finish(Location::Script))
+        Self::verify_script_with_config(&VerifierConfig::default(), module)
     }
 
-    fn verify_script_impl(script: &'a CompiledScript) -> PartialVMResult<()> {
+    pub fn verify_script_with_config(
+        verifier_config: &VerifierConfig,
+        module: &'a CompiledScript,
+    ) -> VMResult<()> {
+        Self::verify_script_impl(verifier_config, module).map_err(|e| e.finish(Location::Script))
+    }
+
+    fn verify_script_impl(
+        verifier_config: &VerifierConfig,
+        script: &'a CompiledScript,
+    ) -> PartialVMResult<()> {
         let sig_check = Self {
             resolver: BinaryIndexedView::Script(script),
+            max_type_nodes: verifier_config.max_type_nodes,
         };
         sig_check.verify_signature_pool(script.signatures())?;
         sig_check.verify_function_signatures(script.function_handles())?;
@@ -52,7 +78,12 @@ impl<'a> SignatureChecker<'a> {
 
     fn verify_signature_pool(&self, signatures: &[Signature]) -> PartialVMResult<()> {
         for i in 0..signatures.len() {
-            self.check_signature(SignatureIndex::new(i as TableIndex))?
+            let idx = SignatureIndex::new(i as TableIndex);
+            self.check_signature(idx)?;
+            for token in &self.resolver.signature_at(idx).0 {
+                self.check_type_nodes(token)
+                    .map_err(|err| err.at_index(IndexKind::Signature, idx.0))?
+            }
         }
         Ok(())
     }
@@ -94,6 +125,8 @@ impl<'a> SignatureChecker<'a> {
             for (field_offset, field_def) in fields.iter().enumerate() {
                 self.check_signature_token(&field_def.signature.0)
                     .map_err(|err| err_handler(err, field_offset))?;
+                self.check_type_nodes(&field_def.signature.0)
+                    .map_err(|err| err_handler(err, field_offset))?;
                 let type_param_constraints: Vec<_> =
                     struct_handle.type_param_constraints().collect();
                 self.check_type_instantiation(&field_def.signature.0, &type_param_constraints)
@@ -298,6 +331,17 @@ impl<'a> SignatureChecker<'a> {
         }
     }
 
+    /// Checks that the given type is not made of more nodes than the configuration allows.
+    fn check_type_nodes(&self, ty: &SignatureToken) -> PartialVMResult<()> {
+        if let Some(max_type_nodes) = self.max_type_nodes {
+            if ty.preorder_traversal().nth(max_type_nodes).is_some() {
+                return Err(PartialVMError::new(StatusCode::TOO_MANY_TYPE_NODES)
+                    .with_message(format!("type exceeds {} nodes", max_type_nodes)));
+            }
+        }
+        Ok(())
+    }
+
     fn check_instantiation(
         &self,
         idx: SignatureIndex,
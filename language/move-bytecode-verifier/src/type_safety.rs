@@ -4,6 +4,7 @@
 //! This module defines the transfer functions for verifying type safety of a procedure body.
 //! It does not utilize control flow, but does check each block independently
 
+use crate::meter::Meter;
 use move_binary_format::{
     binary_views::{BinaryIndexedView, FunctionView},
     control_flow_graph::ControlFlowGraph,
@@ -81,11 +82,13 @@ impl<'a> TypeSafetyChecker<'a> {
 pub(crate) fn verify<'a>(
     resolver: &'a BinaryIndexedView<'a>,
     function_view: &'a FunctionView<'a>,
+    meter: &mut Meter,
 ) -> PartialVMResult<()> {
     let verifier = &mut TypeSafetyChecker::new(resolver, function_view);
 
     for block_id in function_view.cfg().blocks() {
         for offset in function_view.cfg().instr_indexes(block_id) {
+            meter.add(1)?;
             let instr = &verifier.function_view.code().code[offset as usize];
             verify_instr(verifier, instr, offset)?
         }
@@ -6,7 +6,7 @@ use crate::{
     ability_field_requirements, check_duplication::DuplicationChecker,
     code_unit_verifier::CodeUnitVerifier, constants, friends,
     instantiation_loops::InstantiationLoopChecker, instruction_consistency::InstructionConsistency,
-    script_signature, script_signature::no_additional_script_signature_checks,
+    meter::Meter, script_signature, script_signature::no_additional_script_signature_checks,
     signature::SignatureChecker, struct_defs::RecursiveStructDefChecker,
 };
 use move_binary_format::{
@@ -15,6 +15,28 @@ use move_binary_format::{
     file_format::{CompiledModule, CompiledScript},
 };
 
+/// Limits on the resources the verifier may spend on a module or script. Each limit is
+/// disabled when set to `None`, which is the default, so that code verified without a
+/// configuration is judged exactly as before.
+#[derive(Debug, Clone, Default)]
+pub struct VerifierConfig {
+    /// Maximal number of basic blocks in a function body.
+    pub max_basic_blocks: Option<usize>,
+    /// Maximal nesting depth of loops in a function body.
+    pub max_loop_depth: Option<usize>,
+    /// Maximal number of back edges (jumps to a loop head) in a function body.
+    pub max_back_edges_per_function: Option<usize>,
+    /// Maximal number of nodes in a type, e.g. `vector<vector<u8>>` has three.
+    pub max_type_nodes: Option<usize>,
+    /// Maximal number of meter units the code unit passes may spend on a single function. One
+    /// unit is charged per instruction interpreted and per join of abstract states.
+    pub max_per_fun_meter_units: Option<u64>,
+    /// Maximal number of meter units all metered passes may spend on a module or script. On top
+    /// of the units charged for its functions, the instantiation loop check charges one unit per
+    /// node and edge of its graph.
+    pub max_per_module_meter_units: Option<u64>,
+}
+
 /// Helper for a "canonical" verification of a module.
 ///
 /// Clients that rely on verification should call the proper passes
@@ -26,20 +48,29 @@ use move_binary_format::{
 /// minimize the code locations that need to be updated should a new checker
 /// is introduced.
 pub fn verify_module(module: &CompiledModule) -> VMResult<()> {
+    verify_module_with_config(&VerifierConfig::default(), module)
+}
+
+/// Same as `verify_module`, but rejects modules which exceed the limits of `verifier_config`.
+pub fn verify_module_with_config(
+    verifier_config: &VerifierConfig,
+    module: &CompiledModule,
+) -> VMResult<()> {
     BoundsChecker::verify_module(module).map_err(|e| {
         // We can't point the error at the module, because if bounds-checking
         // failed, we cannot safely index into module's handle to itself.
         e.finish(Location::Undefined)
     })?;
     DuplicationChecker::verify_module(module)?;
-    SignatureChecker::verify_module(module)?;
+    SignatureChecker::verify_module_with_config(verifier_config, module)?;
     InstructionConsistency::verify_module(module)?;
     constants::verify_module(module)?;
     friends::verify_module(module)?;
     ability_field_requirements::verify_module(module)?;
     RecursiveStructDefChecker::verify_module(module)?;
-    InstantiationLoopChecker::verify_module(module)?;
-    CodeUnitVerifier::verify_module(module)?;
+    let mut meter = Meter::new(verifier_config);
+    InstantiationLoopChecker::verify_module_with_meter(&mut meter, module)?;
+    CodeUnitVerifier::verify_module_with_meter(verifier_config, &mut meter, module)?;
     script_signature::verify_module(module, no_additional_script_signature_checks)
 }
 
@@ -54,11 +85,19 @@ pub fn verify_module(module: &CompiledModule) -> VMResult<()> {
 /// minimize the code locations that need to be updated should a new checker
 /// is introduced.
 pub fn verify_script(script: &CompiledScript) -> VMResult<()> {
+    verify_script_with_config(&VerifierConfig::default(), script)
+}
+
+/// Same as `verify_script`, but rejects scripts which exceed the limits of `verifier_config`.
+pub fn verify_script_with_config(
+    verifier_config: &VerifierConfig,
+    script: &CompiledScript,
+) -> VMResult<()> {
     BoundsChecker::verify_script(script).map_err(|e| e.finish(Location::Script))?;
     DuplicationChecker::verify_script(script)?;
-    SignatureChecker::verify_script(script)?;
+    SignatureChecker::verify_script_with_config(verifier_config, script)?;
     InstructionConsistency::verify_script(script)?;
     constants::verify_script(script)?;
-    CodeUnitVerifier::verify_script(script)?;
+    CodeUnitVerifier::verify_script_with_config(verifier_config, script)?;
     script_signature::verify_script(script, no_additional_script_signature_checks)
 }
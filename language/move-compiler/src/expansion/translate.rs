@@ -652,16 +652,50 @@ fn attribute_value(
     sp!(loc, avalue_): P::AttributeValue,
 ) -> Option<E::AttributeValue> {
     use E::AttributeValue_ as EV;
-    use P::AttributeValue_ as PV;
+    use P::{AttributeValue_ as PV, NameAccessChain_ as PN};
     Some(sp(
         loc,
         match avalue_ {
             PV::Value(v) => EV::Value(value(context, v)?),
+            // Modules and module members share the namespace of attribute values. A module alias
+            // or a module with an address, e.g., `Self`, `0x1::M` or `Std::M`, names a module.
+            PV::ModuleAccess(sp!(ident_loc, PN::One(n)))
+                if context.aliases.module_alias_get(&n).is_some() =>
+            {
+                let sp!(_, mident_) = *context.aliases.module_alias_get(&n).unwrap();
+                EV::Module(sp(ident_loc, mident_))
+            }
+            PV::ModuleAccess(sp!(ident_loc, PN::Two(ln, n))) if is_module_address(context, &ln) => {
+                let addr = address(context, /* suggest_declaration */ false, ln);
+                let mident = sp(ident_loc, ModuleIdent_::new(addr, ModuleName(n)));
+                if context.module_members.get(&mident).is_none() {
+                    context.env.add_diag(diag!(
+                        NameResolution::UnboundModule,
+                        (ident_loc, format!("Unbound module '{}'", mident))
+                    ));
+                    return None;
+                }
+                EV::Module(mident)
+            }
             PV::ModuleAccess(ma) => EV::ModuleAccess(name_access_chain(context, Access::Type, ma)?),
         },
     ))
 }
 
+// Whether the leading name of a two-part name access in an attribute value is an address, i.e.,
+// whether the access names a module rather than a member of an aliased module
+fn is_module_address(context: &mut Context, sp!(_, ln_): &P::LeadingNameAccess) -> bool {
+    match ln_ {
+        P::LeadingNameAccess_::AnonymousAddress(_) => true,
+        P::LeadingNameAccess_::Name(n) => {
+            context.aliases.module_alias_get(n).is_none()
+                && context
+                    .named_address_mapping
+                    .map_or(false, |mapping| mapping.contains_key(&n.value))
+        }
+    }
+}
+
 //**************************************************************************************************
 // Aliases
 //**************************************************************************************************
@@ -601,6 +601,12 @@ pub mod known_attributes {
         pub const EXPECTED_FAILURE: &'static str = "expected_failure";
         pub const TEST_ONLY: &'static str = "test_only";
         pub const CODE_ASSIGNMENT_NAME: &'static str = "abort_code";
+        pub const CATEGORY_ASSIGNMENT_NAME: &'static str = "category";
+        pub const REASON_ASSIGNMENT_NAME: &'static str = "reason";
+        pub const LOCATION_ASSIGNMENT_NAME: &'static str = "location";
+        pub const MAJOR_STATUS_ASSIGNMENT_NAME: &'static str = "major_status";
+        pub const ARITHMETIC_ERROR_NAME: &'static str = "arithmetic_error";
+        pub const OUT_OF_GAS_NAME: &'static str = "out_of_gas";
 
         pub const fn name(&self) -> &str {
             match self {
@@ -27,6 +27,9 @@ impl<'env> Context<'env> {
 
 const UNIT_TEST_MODULE_NAME: &str = "UnitTest";
 const STDLIB_ADDRESS_NAME: &str = "Std";
+/// The name of the function added to modules compiled in test mode which prevents them from
+/// being published outside of tests.
+pub const UNIT_TEST_POISON_FUN_NAME: &str = "unit_test_poison";
 
 // This filters out all test, and test-only annotated module member from `prog` if the `test` flag
 // in `compilation_env` is not set. If the test flag is set, no filtering is performed, and instead
@@ -280,7 +283,7 @@ fn insert_test_poison(context: &mut Context, mloc: Loc, members: &mut Vec<P::Mod
         visibility: P::Visibility::Internal,
         acquires: vec![],
         signature,
-        name: P::FunctionName(sp(mloc, "unit_test_poison".into())),
+        name: P::FunctionName(sp(mloc, UNIT_TEST_POISON_FUN_NAME.into())),
         body: sp(
             mloc,
             P::FunctionBody_::Defined((
@@ -8,9 +8,9 @@ use crate::{
 };
 use move_core_types::{
     account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
-    value::MoveValue,
+    value::MoveValue, vm_status::StatusCode,
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fmt};
 
 pub mod filter_test_members;
 pub mod plan_builder;
@@ -43,6 +43,41 @@ pub enum ExpectedFailure {
     Expected,
     // expected failure, abort code checked
     ExpectedWithCode(u64),
+    // expected failure, the parts of the error given in the attribute checked
+    ExpectedWithError(ExpectedMoveError),
+}
+
+/// The error a test is expected to fail with, e.g., from
+/// `#[expected_failure(category = Errors::INVALID_ARGUMENT, location = Self)]` or
+/// `#[expected_failure(arithmetic_error)]`. The parts which are `None` are not checked.
+#[derive(Debug, Clone)]
+pub struct ExpectedMoveError {
+    /// The status of the error, `ABORTED` if the test is expected to abort
+    pub major_status: StatusCode,
+    /// The code the test is expected to abort with
+    pub abort_code: Option<ExpectedCode>,
+    /// The category of the abort code, in the encoding of the `Errors` module
+    pub category: Option<ExpectedCode>,
+    /// The reason of the abort code, in the encoding of the `Errors` module
+    pub reason: Option<ExpectedCode>,
+    /// The module the error is expected to be raised in
+    pub location: Option<ModuleId>,
+}
+
+/// An expected abort code, or part of one, with the constant it was given as, if any.
+#[derive(Debug, Clone)]
+pub struct ExpectedCode {
+    pub value: u64,
+    pub name: Option<String>,
+}
+
+impl fmt::Display for ExpectedCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", name, self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
 }
 
 impl ModuleTestPlan {
@@ -9,27 +9,42 @@ use crate::{
         known_attributes::{KnownAttribute, TestingAttribute},
         CompilationEnv, Identifier, NumericalAddress,
     },
-    unit_test::{ExpectedFailure, ModuleTestPlan, TestCase},
+    unit_test::{ExpectedCode, ExpectedFailure, ExpectedMoveError, ModuleTestPlan, TestCase},
+};
+use move_core_types::{
+    account_address::AccountAddress as MoveAddress,
+    identifier::Identifier as MoveIdentifier,
+    language_storage::ModuleId,
+    value::MoveValue,
+    vm_status::{StatusCode, StatusType},
 };
-use move_core_types::{account_address::AccountAddress as MoveAddress, value::MoveValue};
 use move_ir_types::location::Loc;
 use move_symbol_pool::Symbol;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, convert::TryFrom};
 
-struct Context<'env> {
+struct Context<'env, 'prog> {
     env: &'env mut CompilationEnv,
+    prog: &'prog G::Program,
 }
 
-impl<'env> Context<'env> {
-    fn new(compilation_env: &'env mut CompilationEnv) -> Self {
+impl<'env, 'prog> Context<'env, 'prog> {
+    fn new(compilation_env: &'env mut CompilationEnv, prog: &'prog G::Program) -> Self {
         Self {
             env: compilation_env,
+            prog,
         }
     }
 
     fn resolve_address(&self, addr: &Address) -> NumericalAddress {
         (*addr).into_addr_bytes()
     }
+
+    fn module_id(&self, sp!(_, ModuleIdent_ { address, module }): &ModuleIdent) -> ModuleId {
+        ModuleId::new(
+            MoveAddress::new(self.resolve_address(address).into_bytes()),
+            MoveIdentifier::new(module.0.value.as_str()).unwrap(),
+        )
+    }
 }
 
 //***************************************************************************
@@ -45,7 +60,7 @@ pub fn construct_test_plan(
     if !compilation_env.flags().is_testing() {
         return None;
     }
-    let mut context = Context::new(compilation_env);
+    let mut context = Context::new(compilation_env, prog);
     Some(
         prog.modules
             .key_cloned_iter()
@@ -65,7 +80,7 @@ fn construct_module_test_plan(
         .functions
         .iter()
         .filter_map(|(loc, fn_name, func)| {
-            build_test_info(context, loc, fn_name, func)
+            build_test_info(context, module_ident, loc, fn_name, func)
                 .map(|test_case| (fn_name.to_string(), test_case))
         })
         .collect();
@@ -81,6 +96,7 @@ fn construct_module_test_plan(
 
 fn build_test_info<'func>(
     context: &mut Context,
+    module_ident: ModuleIdent,
     fn_loc: Loc,
     fn_name: &str,
     function: &'func G::Function,
@@ -148,7 +164,7 @@ fn build_test_info<'func>(
 
     let expected_failure = match abort_attribute_opt {
         None => None,
-        Some(abort_attribute) => parse_failure_attribute(context, abort_attribute),
+        Some(abort_attribute) => parse_failure_attribute(context, module_ident, abort_attribute),
     };
 
     Some(TestCase {
@@ -224,6 +240,7 @@ fn parse_test_attribute(
 
 fn parse_failure_attribute(
     context: &mut Context,
+    module_ident: ModuleIdent,
     sp!(aloc, expected_attr): &E::Attribute,
 ) -> Option<ExpectedFailure> {
     use E::{AttributeValue_ as EAV, Attribute_ as EA, Value_ as EV};
@@ -250,79 +267,290 @@ fn parse_failure_attribute(
             None
         }
         EA::Parameterized(sp!(_, nm), attrs) => {
-            let mut attrs_vec = attrs.iter().map(|(_, _, attr)| attr).collect::<Vec<_>>();
-            if attrs_vec.len() != 1 {
-                let invalid_attr_msg = format!(
-                    "Invalid #[expected_failure(...)] attribute, expected 1 argument but found {}",
-                    attrs_vec.len()
-                );
+            assert!(
+                nm.as_str() == TestingAttribute::ExpectedFailure.name(),
+                "ICE: expected failure attribute must have the right name"
+            );
+            if attrs.is_empty() {
+                let invalid_attr_msg =
+                    "Invalid #[expected_failure(...)] attribute, expected at least 1 argument";
                 context
                     .env
                     .add_diag(diag!(Attributes::InvalidValue, (*aloc, invalid_attr_msg)));
                 return None;
             }
-            assert!(
-                nm.as_str() == TestingAttribute::ExpectedFailure.name(),
-                "ICE: expected failure attribute must have the right name"
-            );
-            let attr = attrs_vec.pop().unwrap();
-            match attr {
-                sp!(assign_loc, EA::Assigned(sp!(_, nm), value))
-                    if nm.as_str() == TestingAttribute::CODE_ASSIGNMENT_NAME =>
-                {
-                    match &**value {
-                        sp!(_, EAV::Value(sp!(_, EV::InferredNum(u))))
-                            if *u <= std::u64::MAX as u128 =>
-                        {
-                            Some(ExpectedFailure::ExpectedWithCode(*u as u64))
+            let mut valid = true;
+            let mut literal_abort_code = false;
+            let mut vm_error: Option<(Loc, StatusCode)> = None;
+            let mut expected = ExpectedMoveError {
+                major_status: StatusCode::ABORTED,
+                abort_code: None,
+                category: None,
+                reason: None,
+                location: None,
+            };
+            for (_, _, attr) in attrs.iter() {
+                match attr {
+                    sp!(loc, EA::Name(sp!(_, nm))) => match vm_error_status(nm.as_str()) {
+                        Some(status) => {
+                            if let Some((prev_loc, _)) = vm_error {
+                                let msg = "Only one VM error can be expected";
+                                context.env.add_diag(diag!(
+                                    Attributes::InvalidValue,
+                                    (*loc, msg),
+                                    (prev_loc, PREVIOUSLY_EXPECTED_MSG),
+                                ));
+                                valid = false;
+                            }
+                            vm_error = Some((*loc, status))
                         }
-                        sp!(_, EAV::Value(sp!(_, EV::U64(u)))) => {
-                            Some(ExpectedFailure::ExpectedWithCode(*u))
+                        None => {
+                            let msg = format!(
+                                "Unknown VM error '{}' in expected failure attribute. Expected \
+                                 '{}' or '{}'",
+                                nm,
+                                TestingAttribute::ARITHMETIC_ERROR_NAME,
+                                TestingAttribute::OUT_OF_GAS_NAME,
+                            );
+                            context
+                                .env
+                                .add_diag(diag!(Attributes::InvalidName, (*loc, msg)));
+                            valid = false;
                         }
-                        sp!(vloc, EAV::Value(sp!(_, EV::U8(_))))
-                        | sp!(vloc, EAV::Value(sp!(_, EV::U128(_)))) => {
-                            let msg = "Invalid value in expected failure code assignment";
-                            context.env.add_diag(diag!(
-                                Attributes::InvalidValue,
-                                (*assign_loc, msg),
-                                (*vloc, "Annotated non-u64 literals are not permitted"),
-                            ));
-                            None
+                    },
+                    sp!(assign_loc, EA::Assigned(sp!(nmloc, nm), value)) => match nm.as_str() {
+                        TestingAttribute::CODE_ASSIGNMENT_NAME => {
+                            literal_abort_code = matches!(
+                                &value.value,
+                                EAV::Value(sp!(_, EV::InferredNum(_)))
+                                    | EAV::Value(sp!(_, EV::U64(_)))
+                            );
+                            expected.abort_code =
+                                parse_expected_code(context, module_ident, *assign_loc, value);
+                            valid &= expected.abort_code.is_some();
+                        }
+                        TestingAttribute::CATEGORY_ASSIGNMENT_NAME => {
+                            expected.category =
+                                parse_expected_code(context, module_ident, *assign_loc, value);
+                            valid &= expected.category.is_some();
+                        }
+                        TestingAttribute::REASON_ASSIGNMENT_NAME => {
+                            expected.reason =
+                                parse_expected_code(context, module_ident, *assign_loc, value);
+                            valid &= expected.reason.is_some();
+                        }
+                        TestingAttribute::LOCATION_ASSIGNMENT_NAME => match &**value {
+                            sp!(_, EAV::Module(mident)) => {
+                                expected.location = Some(context.module_id(mident))
+                            }
+                            sp!(vloc, _) => {
+                                let msg = "Invalid value in expected failure location \
+                                           assignment. Expected a module, e.g., 'Self' or \
+                                           '0x1::M'";
+                                context.env.add_diag(diag!(
+                                    Attributes::InvalidValue,
+                                    (*vloc, msg),
+                                    (*assign_loc, "Unsupported value in this assignment"),
+                                ));
+                                valid = false;
+                            }
+                        },
+                        TestingAttribute::MAJOR_STATUS_ASSIGNMENT_NAME => {
+                            match parse_expected_code(context, module_ident, *assign_loc, value)
+                                .and_then(|code| StatusCode::try_from(code.value).ok())
+                            {
+                                Some(status) if status.status_type() == StatusType::Execution => {
+                                    vm_error = Some((*assign_loc, status))
+                                }
+                                _ => {
+                                    let msg = "Invalid value in expected failure major status \
+                                               assignment. Expected the status code of an \
+                                               execution error";
+                                    context.env.add_diag(diag!(
+                                        Attributes::InvalidValue,
+                                        (value.loc, msg),
+                                        (*assign_loc, "Unsupported value in this assignment"),
+                                    ));
+                                    valid = false;
+                                }
+                            }
                         }
-                        sp!(vloc, _) => {
+                        _ => {
+                            let invalid_name_msg = format!(
+                                "Invalid name in expected failure code assignment. Did you mean \
+                                 to use '{}'?",
+                                TestingAttribute::CODE_ASSIGNMENT_NAME
+                            );
                             context.env.add_diag(diag!(
-                                Attributes::InvalidValue,
-                                (*vloc, "Invalid value in expected failure code assignment"),
-                                (*assign_loc, "Unsupported value in this assignment"),
+                                Attributes::InvalidName,
+                                (*nmloc, invalid_name_msg),
+                                (*assign_loc, "Invalid name in this assignment"),
                             ));
-                            None
+                            valid = false;
                         }
+                    },
+                    sp!(loc, _) => {
+                        let msg = "Unsupported attribute value for expected failure attribute";
+                        context.env.add_diag(diag!(
+                            Attributes::InvalidValue,
+                            (*aloc, msg),
+                            (*loc, "Unsupported value in this assignment")
+                        ));
+                        valid = false;
                     }
                 }
-                sp!(assign_loc, EA::Assigned(sp!(nmloc, _), _)) => {
-                    let invalid_name_msg = format!(
-                        "Invalid name in expected failure code assignment. Did you mean to use \
-                         '{}'?",
-                        TestingAttribute::CODE_ASSIGNMENT_NAME
+            }
+            if expected.abort_code.is_some()
+                && (expected.category.is_some() || expected.reason.is_some())
+            {
+                let msg = format!(
+                    "Invalid #[expected_failure(...)] attribute, '{}' cannot be combined with \
+                     '{}' or '{}'",
+                    TestingAttribute::CODE_ASSIGNMENT_NAME,
+                    TestingAttribute::CATEGORY_ASSIGNMENT_NAME,
+                    TestingAttribute::REASON_ASSIGNMENT_NAME,
+                );
+                context
+                    .env
+                    .add_diag(diag!(Attributes::InvalidValue, (*aloc, msg)));
+                valid = false;
+            }
+            if let Some((vm_error_loc, status)) = vm_error {
+                if expected.abort_code.is_some()
+                    || expected.category.is_some()
+                    || expected.reason.is_some()
+                {
+                    let msg = "Invalid #[expected_failure(...)] attribute, a VM error cannot be \
+                               combined with an abort code";
+                    context.env.add_diag(diag!(
+                        Attributes::InvalidValue,
+                        (*aloc, msg),
+                        (vm_error_loc, "VM error expected here"),
+                    ));
+                    valid = false;
+                }
+                expected.major_status = status;
+            }
+            if !valid {
+                return None;
+            }
+            match expected {
+                // A plain abort code, as supported by all backends
+                ExpectedMoveError {
+                    abort_code: Some(ExpectedCode { value, name: None }),
+                    category: None,
+                    reason: None,
+                    location: None,
+                    ..
+                } if literal_abort_code => Some(ExpectedFailure::ExpectedWithCode(value)),
+                _ => Some(ExpectedFailure::ExpectedWithError(expected)),
+            }
+        }
+    }
+}
+
+const PREVIOUSLY_EXPECTED_MSG: &str = "Previously expected here";
+
+// The status code of the VM error with the name `name` in an expected failure attribute
+fn vm_error_status(name: &str) -> Option<StatusCode> {
+    match name {
+        TestingAttribute::ARITHMETIC_ERROR_NAME => Some(StatusCode::ARITHMETIC_ERROR),
+        TestingAttribute::OUT_OF_GAS_NAME => Some(StatusCode::OUT_OF_GAS),
+        _ => None,
+    }
+}
+
+// Parse the value of an abort code (or of its category or reason) in an expected failure
+// attribute: a u64 literal, or an integer constant given as `NAME` for a constant of the module of
+// the test, or as `Module::NAME`
+fn parse_expected_code(
+    context: &mut Context,
+    module_ident: ModuleIdent,
+    assign_loc: Loc,
+    value: &E::AttributeValue,
+) -> Option<ExpectedCode> {
+    use E::{AttributeValue_ as EAV, ModuleAccess_ as EN, Value_ as EV};
+    match value {
+        sp!(_, EAV::Value(sp!(_, EV::InferredNum(u)))) if *u <= std::u64::MAX as u128 => {
+            Some(ExpectedCode {
+                value: *u as u64,
+                name: None,
+            })
+        }
+        sp!(_, EAV::Value(sp!(_, EV::U64(u)))) => Some(ExpectedCode {
+            value: *u,
+            name: None,
+        }),
+        sp!(vloc, EAV::Value(sp!(_, EV::U8(_)))) | sp!(vloc, EAV::Value(sp!(_, EV::U128(_)))) => {
+            let msg = "Invalid value in expected failure code assignment";
+            context.env.add_diag(diag!(
+                Attributes::InvalidValue,
+                (assign_loc, msg),
+                (*vloc, "Annotated non-u64 literals are not permitted"),
+            ));
+            None
+        }
+        sp!(vloc, EAV::ModuleAccess(sp!(_, access))) => {
+            let (mident, name) = match access {
+                EN::Name(name) => (module_ident, name),
+                EN::ModuleAccess(mident, name) => (*mident, name),
+            };
+            let constant = context
+                .prog
+                .modules
+                .get(&mident)
+                .and_then(|module| module.constants.get_(&name.value));
+            let value = match constant.and_then(|constant| constant.value.as_ref()) {
+                Some(MoveValue::U8(u)) => Some(*u as u64),
+                Some(MoveValue::U64(u)) => Some(*u),
+                Some(MoveValue::U128(u)) if *u <= std::u64::MAX as u128 => Some(*u as u64),
+                _ => None,
+            };
+            let name = format!(
+                "{}::{}",
+                context.module_id(&mident).short_str_lossless(),
+                name
+            );
+            match (constant, value) {
+                (_, Some(value)) => Some(ExpectedCode {
+                    value,
+                    name: Some(name),
+                }),
+                (None, _) => {
+                    let msg = format!(
+                        "Invalid value in expected failure code assignment. Unbound constant '{}'",
+                        name
                     );
                     context.env.add_diag(diag!(
-                        Attributes::InvalidName,
-                        (*nmloc, invalid_name_msg),
-                        (*assign_loc, "Invalid name in this assignment"),
+                        Attributes::InvalidValue,
+                        (*vloc, msg),
+                        (assign_loc, "Unsupported value in this assignment"),
                     ));
                     None
                 }
-                sp!(loc, _) => {
-                    let msg = "Unsupported attribute value for expected failure attribute";
+                (Some(constant), None) => {
+                    let msg = format!(
+                        "Invalid value in expected failure code assignment. Expected an integer \
+                         constant fitting in a u64, but '{}' is not",
+                        name
+                    );
                     context.env.add_diag(diag!(
                         Attributes::InvalidValue,
-                        (*aloc, msg),
-                        (*loc, "Unsupported value in this assignment")
+                        (*vloc, msg),
+                        (constant.loc, "Constant defined here"),
                     ));
                     None
                 }
             }
         }
+        sp!(vloc, _) => {
+            context.env.add_diag(diag!(
+                Attributes::InvalidValue,
+                (*vloc, "Invalid value in expected failure code assignment"),
+                (assign_loc, "Unsupported value in this assignment"),
+            ));
+            None
+        }
     }
 }
 
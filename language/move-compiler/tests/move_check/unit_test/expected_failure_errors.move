@@ -0,0 +1,69 @@
+// check the values of the error parts an expected failure can be given with
+module 0x2::Errors {
+    const INVALID_ARGUMENT: u8 = 7;
+    const E_WIDE: u128 = 18446744073709551616;
+    const E_FLAG: bool = true;
+}
+
+module 0x1::M {
+    #[test_only]
+    use 0x2::Errors;
+
+    const E_FOO: u64 = 1;
+
+    #[test]
+    #[expected_failure(abort_code = E_FOO, location = Self)]
+    fun ok_named_code() { }
+
+    #[test]
+    #[expected_failure(category = Errors::INVALID_ARGUMENT, reason = 0x1::M::E_FOO)]
+    fun ok_category_and_reason() { }
+
+    #[test]
+    #[expected_failure(arithmetic_error, location = 0x2::Errors)]
+    fun ok_vm_error() { }
+
+    #[test]
+    #[expected_failure(major_status = 4008)]
+    fun ok_major_status() { }
+
+    #[test]
+    #[expected_failure()]
+    fun fail_empty() { }
+
+    #[test]
+    #[expected_failure(abort_code = E_BAR)]
+    fun fail_unbound_constant() { }
+
+    #[test]
+    #[expected_failure(category = Errors::E_WIDE)]
+    fun fail_too_large_constant() { }
+
+    #[test]
+    #[expected_failure(reason = Errors::E_FLAG)]
+    fun fail_non_integer_constant() { }
+
+    #[test]
+    #[expected_failure(abort_code = 1, category = Errors::INVALID_ARGUMENT)]
+    fun fail_code_and_category() { }
+
+    #[test]
+    #[expected_failure(arithmetic_error, abort_code = 1)]
+    fun fail_vm_error_and_code() { }
+
+    #[test]
+    #[expected_failure(arithmetic_error, out_of_gas)]
+    fun fail_two_vm_errors() { }
+
+    #[test]
+    #[expected_failure(overflow)]
+    fun fail_unknown_vm_error() { }
+
+    #[test]
+    #[expected_failure(location = E_FOO)]
+    fun fail_location_not_module() { }
+
+    #[test]
+    #[expected_failure(major_status = 1)]
+    fun fail_major_status_not_execution() { }
+}
@@ -0,0 +1,10 @@
+// check that the module an expected failure is located in must be bound
+module 0x1::M {
+    #[test]
+    #[expected_failure(location = 0x1::N)]
+    fun fail_unbound_module() { }
+
+    #[test]
+    #[expected_failure(location = Std::N)]
+    fun fail_unbound_named_address_module() { }
+}
@@ -278,3 +278,46 @@ impl ChangeSet {
 }
 
 pub type Event = (Vec<u8>, u64, TypeTag, Vec<u8>);
+
+/// Storage and memory usage incurred while producing a `ChangeSet` and its events, together
+/// with the gas charged for each category (in internal gas units).
+///
+/// Hosts can use this to build fee models on top of the VM, e.g. to price storage separately
+/// from execution.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ChangeSetSummary {
+    /// Bytes loaded from global storage.
+    pub bytes_read: u64,
+    /// Bytes of resources written back to global storage.
+    pub bytes_written: u64,
+    /// Number of resources deleted from global storage.
+    pub resources_deleted: u64,
+    /// Bytes of module code published.
+    pub module_bytes_published: u64,
+    /// Bytes of serialized event payloads.
+    pub event_bytes: u64,
+    /// The peak (abstract) size of the values held by the VM during execution.
+    pub peak_value_memory: u64,
+
+    /// Gas charged for reading from global storage.
+    pub read_gas: u64,
+    /// Gas charged for writing resources to global storage.
+    pub write_gas: u64,
+    /// Gas charged for publishing modules.
+    pub module_publish_gas: u64,
+    /// Gas charged for event payloads.
+    pub event_gas: u64,
+    /// Gas charged for the peak value memory.
+    pub value_memory_gas: u64,
+}
+
+impl ChangeSetSummary {
+    /// The total gas charged for storage and memory.
+    pub fn total_gas(&self) -> u64 {
+        self.read_gas
+            + self.write_gas
+            + self.module_publish_gas
+            + self.event_gas
+            + self.value_memory_gas
+    }
+}
@@ -75,6 +75,18 @@ impl ErrorMapping {
         Ok(())
     }
 
+    /// Add the categories and module errors of `other`, replacing the entries of `self` with the
+    /// same codes.
+    pub fn extend(&mut self, other: ErrorMapping) {
+        self.error_categories.extend(other.error_categories);
+        for (module_id, module_error_map) in other.module_error_maps {
+            self.module_error_maps
+                .entry(module_id)
+                .or_default()
+                .extend(module_error_map);
+        }
+    }
+
     pub fn from_file<P: AsRef<Path>>(path: P) -> Self {
         let mut bytes = Vec::new();
         File::open(path).unwrap().read_to_end(&mut bytes).unwrap();
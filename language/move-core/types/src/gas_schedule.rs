@@ -227,6 +227,60 @@ impl Default for GasConstants {
         }
     }
 }
+
+/// Per-byte costs used by the storage metering extension of the VM gas meter.
+///
+/// Unlike the `CostTable`, which charges instructions by an abstract memory estimate, these
+/// costs are charged on the actual number of bytes read from and written to global storage,
+/// the size of event payloads, and the peak amount of memory held in values.
+#[derive(Clone, Debug, Serialize, PartialEq, Deserialize)]
+pub struct StorageGasSchedule {
+    /// The cost per-byte loaded from global storage (`MoveFrom`, `BorrowGlobal`, `Exists`, ...).
+    pub global_read_per_byte: InternalGasUnits<GasCarrier>,
+
+    /// The cost per-byte of resources written back to global storage.
+    pub global_write_per_byte: InternalGasUnits<GasCarrier>,
+
+    /// The cost per-byte of published module code.
+    pub module_publish_per_byte: InternalGasUnits<GasCarrier>,
+
+    /// The cost per-byte of serialized event payloads.
+    pub event_per_byte: InternalGasUnits<GasCarrier>,
+
+    /// The cost per-byte the peak value memory grows by.
+    pub value_memory_per_byte: InternalGasUnits<GasCarrier>,
+}
+
+impl StorageGasSchedule {
+    /// Derive a storage schedule from the global memory costs of the given `GasConstants`.
+    pub fn from_gas_constants(gas_constants: &GasConstants) -> Self {
+        Self {
+            global_read_per_byte: gas_constants.global_memory_per_byte_cost,
+            global_write_per_byte: gas_constants.global_memory_per_byte_write_cost,
+            module_publish_per_byte: gas_constants.global_memory_per_byte_write_cost,
+            event_per_byte: gas_constants.global_memory_per_byte_write_cost,
+            value_memory_per_byte: ONE_GAS_UNIT,
+        }
+    }
+
+    /// A schedule where storage and memory are free, but still tracked.
+    pub fn zero() -> Self {
+        Self {
+            global_read_per_byte: InternalGasUnits(0),
+            global_write_per_byte: InternalGasUnits(0),
+            module_publish_per_byte: InternalGasUnits(0),
+            event_per_byte: InternalGasUnits(0),
+            value_memory_per_byte: InternalGasUnits(0),
+        }
+    }
+}
+
+impl Default for StorageGasSchedule {
+    fn default() -> Self {
+        Self::from_gas_constants(&GasConstants::default())
+    }
+}
+
 /// The cost tables, keyed by the serialized form of the bytecode instruction.  We use the
 /// serialized form as opposed to the instruction enum itself as the key since this will be the
 /// on-chain representation of bytecode instructions in the future.
@@ -596,6 +596,16 @@ pub enum StatusCode {
     INVALID_PHANTOM_TYPE_PARAM_POSITION = 1108,
     VEC_UPDATE_EXISTS_MUTABLE_BORROW_ERROR = 1109,
     VEC_BORROW_ELEMENT_EXISTS_MUTABLE_BORROW_ERROR = 1110,
+    // A function has more basic blocks than the verifier allows.
+    TOO_MANY_BASIC_BLOCKS = 1111,
+    // Loops are nested deeper than the verifier allows.
+    LOOP_MAX_DEPTH_REACHED = 1112,
+    // A function has more back edges than the verifier allows.
+    TOO_MANY_BACK_EDGES = 1113,
+    // A type is made of more nodes than the verifier allows.
+    TOO_MANY_TYPE_NODES = 1114,
+    // Verifying a function exceeded the verifier's budget.
+    PROGRAM_TOO_COMPLEX = 1115,
 
     // These are errors that the VM might raise if a violation of internal
     // invariants takes place.
@@ -66,6 +66,7 @@ pub struct SpecFunDecl {
 #[derive(Debug, Clone)]
 pub enum AttributeValue {
     Value(NodeId, Value),
+    Module(NodeId, ModuleName),
     Name(NodeId, Option<ModuleName>, Symbol),
 }
 
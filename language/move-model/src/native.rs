@@ -4,4 +4,6 @@
 //! Contains constants for well-known names of native functions
 
 pub const VECTOR_BORROW_MUT: &str = "0x1::Vector::borrow_mut";
+pub const VECTOR_LENGTH: &str = "0x1::Vector::length";
+pub const VECTOR_SWAP: &str = "0x1::Vector::swap";
 pub const EVENT_EMIT_EVENT: &str = "0x1::Event::emit_event";
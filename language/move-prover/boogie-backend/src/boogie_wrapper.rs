@@ -3,7 +3,12 @@
 
 //! Wrapper around the boogie program. Allows to call boogie and analyze the output.
 
-use std::{collections::BTreeMap, fs, num::ParseIntError, option::Option::None};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    num::ParseIntError,
+    option::Option::None,
+};
 
 use anyhow::anyhow;
 use codespan::{ByteIndex, ColumnIndex, LineIndex, Location, Span};
@@ -14,12 +19,15 @@ use num::BigInt;
 use once_cell::sync::Lazy;
 use pretty::RcDoc;
 use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
 
 use move_binary_format::file_format::FunctionDefinitionIndex;
 use move_model::{
     ast::TempIndex,
     code_writer::CodeWriter,
     model::{FunId, GlobalEnv, Loc, ModuleId, NodeId, QualifiedId, StructId},
+    pragmas::TIMEOUT_PRAGMA,
     ty::{PrimitiveType, Type},
 };
 use move_stackless_bytecode::function_target_pipeline::{FunctionTargetsHolder, FunctionVariant};
@@ -27,7 +35,7 @@ use move_stackless_bytecode::function_target_pipeline::{FunctionTargetsHolder, F
 // DEBUG
 // use backtrace::Backtrace;
 use crate::{
-    boogie_helpers::boogie_struct_name,
+    boogie_helpers::{boogie_function_name, boogie_struct_name},
     options::{BoogieOptions, VectorTheory},
     prover_task_runner::{ProverTaskRunner, RunBoogieWithSeeds},
 };
@@ -57,7 +65,8 @@ pub struct BoogieOutput {
 }
 
 /// Kind of boogie error.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BoogieErrorKind {
     Assertion,
     Inconclusive,
@@ -101,6 +110,11 @@ static INCONCLUSIVE_DIAG_STARTS: Lazy<Regex> = Lazy::new(|| {
         .unwrap()
 });
 
+static VERIFICATION_TIME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^Verifying (?P<proc>\S+\$verify\S*) \.\.\.\s*\n\s*\[(?P<time>[0-9.]+) s")
+        .unwrap()
+});
+
 static INCONSISTENCY_DIAG_STARTS: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?m)^inconsistency_detected\((?P<args>[^)]*)\)").unwrap());
 
@@ -211,9 +225,47 @@ impl<'env> BoogieWrapper<'env> {
         })
     }
 
-    /// Calls boogie and analyzes output.
-    pub fn call_boogie_and_verify_output(&self, boogie_file: &str) -> anyhow::Result<()> {
-        let BoogieOutput { errors, all_output } = self.call_boogie(boogie_file)?;
+    /// Calls boogie and analyzes output. Functions whose verification times out are verified
+    /// again, as configured by the `timeout_retries` and `split_on_timeout` options. The errors
+    /// of the final attempts are added to the environment as diagnostics, and returned for
+    /// further processing, together with the output of all runs.
+    pub fn call_boogie_and_verify_output(&self, boogie_file: &str) -> anyhow::Result<BoogieOutput> {
+        let BoogieOutput {
+            mut errors,
+            mut all_output,
+        } = self.call_boogie(boogie_file)?;
+        let retries = self.options.timeout_retries + usize::from(self.options.split_on_timeout);
+        let mut timed_out = self.timed_out_functions(&errors);
+        for attempt in 1..=retries {
+            if timed_out.is_empty() {
+                break;
+            }
+            let split = self.options.split_on_timeout && attempt == retries;
+            info!(
+                "verifying {} timed out function(s) again{}",
+                timed_out.len(),
+                if split {
+                    " with split verification conditions"
+                } else {
+                    ""
+                }
+            );
+            let options = self.retry_options(&timed_out, attempt, split);
+            let retry = BoogieWrapper {
+                options: &options,
+                ..*self
+            }
+            .call_boogie(boogie_file)?;
+            // The retry verifies the timed out functions completely, so it reproduces all
+            // of their remaining errors.
+            errors.retain(|error| {
+                !matches!(self.error_function(error, |_| true), Some(fun_id) if timed_out.contains(&fun_id))
+            });
+            timed_out = self.timed_out_functions(&retry.errors);
+            errors.extend(retry.errors);
+            all_output.push_str(&retry.all_output);
+        }
+
         let boogie_log_file = self.options.get_boogie_log_file(boogie_file);
         let log_file_existed = std::path::Path::new(&boogie_log_file).exists();
         debug!("writing boogie log to {}", boogie_log_file);
@@ -227,7 +279,73 @@ impl<'env> BoogieWrapper<'env> {
             std::fs::remove_file(boogie_log_file).unwrap_or_default();
         }
 
-        Ok(())
+        Ok(BoogieOutput { errors, all_output })
+    }
+
+    /// Returns the functions whose verification timed out.
+    pub fn timed_out_functions(&self, errors: &[BoogieError]) -> BTreeSet<QualifiedId<FunId>> {
+        errors
+            .iter()
+            .filter(|error| error.kind == BoogieErrorKind::Inconclusive)
+            .filter_map(|error| self.error_function(error, |_| true))
+            .collect()
+    }
+
+    /// Returns the options for verifying the given functions again: only the verification
+    /// procedures of those functions are checked, with a random seed which depends on the
+    /// attempt.
+    fn retry_options(
+        &self,
+        funs: &BTreeSet<QualifiedId<FunId>>,
+        attempt: usize,
+        split: bool,
+    ) -> BoogieOptions {
+        let mut options = self.options.clone();
+        options.random_seed = options.random_seed.wrapping_add(attempt);
+        for fun_id in funs {
+            let name = boogie_function_name(&self.env.get_function(*fun_id), &[]);
+            // Verification procedures of generic functions carry an instantiation suffix.
+            options.boogie_flags.push(format!("-proc:{}$verify*", name));
+            options
+                .boogie_flags
+                .push(format!("-proc:{}'*$verify*", name));
+        }
+        if split {
+            options
+                .boogie_flags
+                .push("-vcsSplitOnEveryAssert".to_string());
+        }
+        options
+    }
+
+    /// Extracts the time spent on the verification of each function from the output of
+    /// boogie, which must have been called with the `time_summary` option. Times of several
+    /// verification procedures of a function, for example from retries, are added up.
+    pub fn extract_verification_times(&self, out: &str) -> BTreeMap<QualifiedId<FunId>, f64> {
+        let mut procs = BTreeMap::new();
+        for module_env in self.env.get_modules() {
+            for fun_env in module_env.get_functions() {
+                procs.insert(
+                    boogie_function_name(&fun_env, &[]),
+                    fun_env.get_qualified_id(),
+                );
+            }
+        }
+        let mut times = BTreeMap::new();
+        for cap in VERIFICATION_TIME.captures_iter(out) {
+            let proc_name = cap.name("proc").unwrap().as_str();
+            let fun_name = proc_name[..proc_name.find("$verify").unwrap()]
+                .split('\'')
+                .next()
+                .unwrap();
+            if let (Some(fun_id), Ok(time)) = (
+                procs.get(fun_name),
+                cap.name("time").unwrap().as_str().parse::<f64>(),
+            ) {
+                *times.entry(*fun_id).or_insert(0.0) += time;
+            }
+        }
+        times
     }
 
     /// Helper to add a boogie error as a codespan Diagnostic.
@@ -440,7 +558,7 @@ impl<'env> BoogieWrapper<'env> {
         self.env.add_diag(diag);
     }
 
-    fn get_abbreviated_source(&self, node_id: NodeId) -> String {
+    pub(crate) fn get_abbreviated_source(&self, node_id: NodeId) -> String {
         let loc = self.env.get_node_loc(node_id);
         let res = if let Ok(src) = self.env.get_source(&loc) {
             let src = src.lines().map(|s| s.trim()).join(" ");
@@ -726,18 +844,30 @@ impl<'env> BoogieWrapper<'env> {
                     let loc = self
                         .get_loc_from_pos(make_position(line, col))
                         .unwrap_or_else(|| self.env.unknown_loc());
+                    let message = if msg.contains("out of resource") || msg.contains("timed out")
+                    {
+                        // A timeout set by pragma overrides the global one.
+                        match self
+                            .env
+                            .get_enclosing_function(&loc)
+                            .filter(|fun_env| fun_env.is_num_pragma_set(TIMEOUT_PRAGMA))
+                        {
+                            Some(fun_env) => format!(
+                                "verification out of resources/timeout (timeout set to {}s by pragma)",
+                                fun_env.get_num_pragma(TIMEOUT_PRAGMA, || 0)
+                            ),
+                            None => format!(
+                                "verification out of resources/timeout (global timeout set to {}s)",
+                                self.options.adjust_timeout(self.options.vc_timeout)
+                            ),
+                        }
+                    } else {
+                        "verification inconclusive".to_string()
+                    };
                     Some(BoogieError {
                         kind: BoogieErrorKind::Inconclusive,
                         loc,
-                        message: if msg.contains("out of resource") || msg.contains("timed out") {
-                            let timeout = self.options.adjust_timeout(self.options.vc_timeout);
-                            format!(
-                                "verification out of resources/timeout (global timeout set to {}s)",
-                                timeout
-                            )
-                        } else {
-                            "verification inconclusive".to_string()
-                        },
+                        message,
                         execution_trace: vec![],
                         model: None,
                     })
@@ -861,7 +991,7 @@ fn create_domain_map(
 }
 
 /// Extract domain from the model
-fn extract_domain(
+pub(crate) fn extract_domain(
     model: &Model,
 ) -> Option<(
     BTreeMap<ModelValue, BTreeMap<usize, bool>>,
@@ -1201,7 +1331,7 @@ impl ModelValue {
     }
 
     /// Extract a i128 from a literal.
-    fn extract_i128(&self) -> Option<i128> {
+    pub(crate) fn extract_i128(&self) -> Option<i128> {
         if let Some(value) = self.extract_list("-").and_then(|values| {
             if values.len() == 1 {
                 values[0].extract_i128().map(i128::saturating_neg)
@@ -1444,6 +1574,172 @@ impl ModelValue {
         entries.push(PrettyDoc::text("Default: ").append(default));
         Some(Self::pretty_vec_or_struct_body(entries))
     }
+
+    /// Decodes the given model value which has given type into JSON. If decoding fails, falls
+    /// back to the debug value.
+    pub fn json_or_raw(&self, wrapper: &BoogieWrapper, model: &Model, ty: &Type) -> Value {
+        if wrapper.options.stable_test_output {
+            return json!("<redacted>");
+        }
+        self.json(wrapper, model, ty)
+            .unwrap_or_else(|| json!(format!("<? {:?}>", self)))
+    }
+
+    /// Decodes the given model value which has given type into JSON. Integers which may not fit
+    /// into a JSON number, as well as addresses, are represented as strings.
+    pub fn json(&self, wrapper: &BoogieWrapper, model: &Model, ty: &Type) -> Option<Value> {
+        if self.extract_list("Error").is_some() {
+            // This is an undefined value
+            return Some(Value::Null);
+        }
+        match ty {
+            Type::Primitive(PrimitiveType::U8) => Some(json!(self
+                .extract_literal()
+                .and_then(|s| s.parse::<u8>().ok())?)),
+            Type::Primitive(PrimitiveType::U64) => Some(json!(self
+                .extract_literal()
+                .and_then(|s| s.parse::<u64>().ok())?)),
+            Type::Primitive(PrimitiveType::U128) => Some(json!(self
+                .extract_literal()
+                .and_then(|s| s.parse::<u128>().ok())?
+                .to_string())),
+            Type::Primitive(PrimitiveType::Num) => Some(json!(self
+                .extract_literal()
+                .and_then(|s| s.parse::<i128>().ok())?
+                .to_string())),
+            Type::Primitive(PrimitiveType::Bool) => Some(json!(self
+                .extract_literal()
+                .and_then(|s| s.parse::<bool>().ok())?)),
+            Type::Primitive(PrimitiveType::Address) => {
+                let addr = BigInt::parse_bytes(&self.extract_literal()?.clone().into_bytes(), 10)?;
+                Some(json!(format!("0x{}", &addr.to_str_radix(16))))
+            }
+            Type::Primitive(PrimitiveType::Signer) => {
+                let l = self.extract_list("$signer")?;
+                let addr = BigInt::parse_bytes(&l[0].extract_literal()?.clone().into_bytes(), 10)?;
+                Some(json!(format!("0x{}", &addr.to_str_radix(16))))
+            }
+            Type::Vector(param) => self.json_vector(wrapper, model, param),
+            Type::Struct(module_id, struct_id, params) => {
+                self.json_struct(wrapper, model, *module_id, *struct_id, params)
+            }
+            Type::Reference(_, bt) => self.json(wrapper, model, &*bt),
+            // See `pretty` for why values of generic type are not decoded.
+            Type::TypeParameter(_) => Some(json!("<generic>")),
+            _ => None,
+        }
+    }
+
+    /// Decodes a vector into a JSON array. A vector of which only some elements are known is
+    /// decoded into an object with its size, the known elements by index, and the default value
+    /// of the other elements.
+    pub fn json_vector(
+        &self,
+        wrapper: &BoogieWrapper,
+        model: &Model,
+        param: &Type,
+    ) -> Option<Value> {
+        let values = self.extract_vector(model, param)?;
+        let elems: Vec<_> = values
+            .values
+            .iter()
+            .filter(|(idx, _)| **idx < values.size)
+            .map(|(idx, value)| (*idx, value.extract_box().json_or_raw(wrapper, model, param)))
+            .collect();
+        if elems.len() == values.size {
+            return Some(Value::Array(
+                elems.into_iter().map(|(_, value)| value).collect(),
+            ));
+        }
+        let default = values
+            .default
+            .extract_box()
+            .json(wrapper, model, param)
+            .unwrap_or(Value::Null);
+        Some(json!({
+            "size": values.size,
+            "elements": elems
+                .into_iter()
+                .map(|(idx, value)| (idx.to_string(), value))
+                .collect::<Map<_, _>>(),
+            "default": default,
+        }))
+    }
+
+    /// Decodes a struct into a JSON object with its fields.
+    pub fn json_struct(
+        &self,
+        wrapper: &BoogieWrapper,
+        model: &Model,
+        module_id: ModuleId,
+        struct_id: StructId,
+        inst: &[Type],
+    ) -> Option<Value> {
+        let module_env = wrapper.env.get_module(module_id);
+        let struct_env = module_env.get_struct(struct_id);
+        if struct_env.is_native_or_intrinsic() {
+            let mut rep = self.extract_literal()?.to_string();
+            if rep.starts_with("T@") {
+                if let Some(i) = rep.rfind('!') {
+                    rep = format!("#{}", &rep[i + 1..])
+                }
+            }
+            return Some(json!(rep));
+        }
+        let struct_name = &boogie_struct_name(&struct_env, inst);
+        let values = self
+            .extract_list(struct_name)
+            .or_else(|| self.extract_list(&format!("|{}|", struct_name)))?;
+        let fields = struct_env
+            .get_fields()
+            .enumerate()
+            .map(|(i, f)| {
+                let ty = f.get_type().instantiate(inst);
+                let default = ModelValue::error();
+                let v = values.get(i).unwrap_or(&default);
+                (
+                    f.get_name().display(struct_env.symbol_pool()).to_string(),
+                    v.json(wrapper, model, &ty)
+                        .unwrap_or_else(|| default.json_or_raw(wrapper, model, &ty)),
+                )
+            })
+            .collect::<Map<_, _>>();
+        Some(Value::Object(fields))
+    }
+
+    /// Decodes the global memory into a JSON object mapping addresses to resources. The value
+    /// at other addresses, if any, is given by the `default` entry.
+    pub fn json_mem(
+        &self,
+        wrapper: &BoogieWrapper,
+        domain: &ModelValue,
+        domain_info_opt: &Option<(
+            BTreeMap<ModelValue, BTreeMap<usize, bool>>,
+            Option<ModelValue>,
+            bool,
+        )>,
+        model: &Model,
+        param: &Type,
+    ) -> Option<Value> {
+        let values = self.extract_mem_vector(model, domain, domain_info_opt)?;
+        let mut entries = Map::new();
+        for (idx, value) in &values.values {
+            entries.insert(
+                format!("0x{:x}", idx),
+                value.json_or_raw(wrapper, model, param),
+            );
+        }
+        let default = if values.default == ModelValue::error() {
+            Value::Null
+        } else {
+            values
+                .default
+                .json(wrapper, model, param)
+                .unwrap_or(Value::Null)
+        };
+        entries.insert("default".to_string(), default);
+        Some(Value::Object(entries))
+    }
 }
 
 /// Represents an expression descriptor.
@@ -35,6 +35,19 @@ const VECTOR_SMT_ARRAY_EXT_THEORY: &[u8] =
     include_bytes!("prelude/vector-smt-array-ext-theory.bpl");
 const MULTISET_ARRAY_THEORY: &[u8] = include_bytes!("prelude/multiset-array-theory.bpl");
 
+/// The sources from which the prelude is assembled. Changes to them may change the outcome of
+/// verification.
+pub const PRELUDE_SOURCES: &[&[u8]] = &[
+    PRELUDE_TEMPLATE,
+    NATIVE_TEMPLATE,
+    VECTOR_ARRAY_THEORY,
+    VECTOR_ARRAY_INTERN_THEORY,
+    VECTOR_SMT_SEQ_THEORY,
+    VECTOR_SMT_ARRAY_THEORY,
+    VECTOR_SMT_ARRAY_EXT_THEORY,
+    MULTISET_ARRAY_THEORY,
+];
+
 const BCS_MODULE: &str = "0x1::BCS";
 const EVENT_MODULE: &str = "0x1::Event";
 
@@ -44,6 +57,7 @@ pub mod bytecode_translator;
 pub mod options;
 mod prover_task_runner;
 mod spec_translator;
+pub mod verification_report;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 struct TypeInfo {
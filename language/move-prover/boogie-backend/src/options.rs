@@ -105,6 +105,15 @@ pub struct BoogieOptions {
     pub vector_theory: VectorTheory,
     /// Whether to generate a z3 trace file and where to put it.
     pub z3_trace_file: Option<String>,
+    /// How many times to verify functions which timed out again, each time with a different
+    /// random seed.
+    pub timeout_retries: usize,
+    /// Whether to verify functions which still time out after all retries once more, with a
+    /// separate verification condition for each assertion. This identifies the conditions
+    /// which are responsible for the timeout.
+    pub split_on_timeout: bool,
+    /// Whether to report the time spent on the verification of each function.
+    pub time_summary: bool,
 }
 
 impl Default for BoogieOptions {
@@ -139,6 +148,9 @@ impl Default for BoogieOptions {
             hard_timeout_secs: 0,
             vector_theory: VectorTheory::BoogieArray,
             z3_trace_file: None,
+            timeout_retries: 0,
+            split_on_timeout: false,
+            time_summary: false,
         }
     }
 }
@@ -218,6 +230,9 @@ impl BoogieOptions {
         if self.generate_smt {
             add(&["-proverLog:@PROC@.smt"]);
         }
+        if self.time_summary {
+            add(&["-trace"]);
+        }
         for f in &self.boogie_flags {
             add(&[f.as_str()]);
         }
@@ -252,6 +267,12 @@ impl BoogieOptions {
             )?;
             Self::check_version_is_greater("boogie", &version, MIN_BOOGIE_VERSION)?;
         }
+        self.check_solver_versions()
+    }
+
+    /// Checks whether the expected version of the selected SMT solver is installed in the
+    /// environment.
+    pub fn check_solver_versions(&self) -> anyhow::Result<()> {
         if !self.z3_exe.is_empty() && !self.use_cvc5 {
             let version =
                 Self::get_version("z3", &self.z3_exe, &["--version"], r"version ([0-9.]*)")?;
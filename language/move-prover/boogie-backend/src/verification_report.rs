@@ -0,0 +1,445 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured verification results, for consumption by tools. The report lists the outcome
+//! of verification for each function, together with the failing conditions and the
+//! counterexamples found by the solver, with their values decoded into Move values.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use move_model::{
+    model::{FunId, GlobalEnv, Loc, QualifiedId},
+    ty::Type,
+};
+use move_stackless_bytecode::function_target_pipeline::FunctionVariant;
+
+use crate::boogie_wrapper::{
+    extract_domain, BoogieError, BoogieErrorKind, BoogieWrapper, Model, ModelValue, TraceEntry,
+};
+
+/// The outcome of a verification run.
+#[derive(Debug, Default, Serialize)]
+pub struct VerificationReport {
+    /// The functions which were verification targets.
+    pub functions: Vec<FunctionReport>,
+    /// Errors which could not be attributed to a function.
+    pub errors: Vec<ErrorReport>,
+}
+
+/// The outcome of the verification of a function.
+#[derive(Debug, Serialize)]
+pub struct FunctionReport {
+    /// The full name of the function.
+    pub name: String,
+    #[serde(skip)]
+    pub fun_id: QualifiedId<FunId>,
+    pub location: Option<SourceSpan>,
+    pub status: VerificationStatus,
+    /// Whether verification was skipped because the function was verified before with the
+    /// same inputs.
+    pub cached: bool,
+    pub errors: Vec<ErrorReport>,
+}
+
+/// The status of a function. The variants are ordered by precedence: a function with several
+/// errors gets the status of the one which comes last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Verified,
+    /// The outcome is unknown, because of an error which could not be attributed.
+    Unknown,
+    Timeout,
+    Inconsistent,
+    Failed,
+}
+
+/// An error reported by the backend.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub kind: BoogieErrorKind,
+    pub message: String,
+    /// The location of the failing condition.
+    pub location: Option<SourceSpan>,
+    /// The source of the failing condition.
+    pub source: Option<String>,
+    pub counterexample: Option<Counterexample>,
+}
+
+/// A source location.
+#[derive(Debug, Serialize)]
+pub struct SourceSpan {
+    pub file: String,
+    /// The 1-based line of the start of the span.
+    pub line: usize,
+    /// The 1-based column of the start of the span.
+    pub column: usize,
+    /// The byte offsets of the span in the file.
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A counterexample to a verification condition.
+#[derive(Debug, Default, Serialize)]
+pub struct Counterexample {
+    /// The arguments the function was called with.
+    pub arguments: Vec<Binding>,
+    /// The values of sub-expressions of the failing condition.
+    pub related: Vec<Binding>,
+    /// The global memory the function accessed, before and after execution.
+    pub memory: Vec<MemoryReport>,
+    /// The execution trace leading to the failure.
+    pub trace: Vec<TraceStep>,
+    /// The abort which happened during execution, if any.
+    pub abort: Option<AbortReport>,
+}
+
+/// The value of a variable or expression.
+#[derive(Debug, Clone, Serialize)]
+pub struct Binding {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: Value,
+}
+
+/// The contents of the global memory of a resource, by address.
+#[derive(Debug, Serialize)]
+pub struct MemoryReport {
+    pub resource: String,
+    pub before: Value,
+    /// The memory at the end of the trace, if it differs from `before`.
+    pub after: Option<Value>,
+}
+
+/// A step of an execution trace.
+#[derive(Debug, Serialize)]
+pub struct TraceStep {
+    pub location: Option<SourceSpan>,
+    /// The full name of the function the step is in.
+    pub function: Option<String>,
+    /// The variables and expressions evaluated in this step.
+    pub bindings: Vec<Binding>,
+}
+
+/// An abort.
+#[derive(Debug, Serialize)]
+pub struct AbortReport {
+    pub location: Option<SourceSpan>,
+    /// The abort code, or `None` for an execution failure.
+    pub code: Option<u64>,
+}
+
+impl VerificationReport {
+    /// Adds the functions whose verification was skipped because they were verified before.
+    pub fn add_cached(&mut self, env: &GlobalEnv, functions: &BTreeSet<QualifiedId<FunId>>) {
+        for fun_id in functions {
+            let func_env = env.get_function(*fun_id);
+            self.functions.push(FunctionReport {
+                name: func_env.get_full_name_str(),
+                fun_id: *fun_id,
+                location: source_span(env, &func_env.get_loc()),
+                status: VerificationStatus::Verified,
+                cached: true,
+                errors: vec![],
+            });
+        }
+        self.functions.sort_by(|f1, f2| f1.name.cmp(&f2.name));
+    }
+}
+
+impl<'env> BoogieWrapper<'env> {
+    /// Creates the report of a verification run which found `errors`.
+    pub fn make_report(&self, errors: &[BoogieError]) -> VerificationReport {
+        let mut functions = BTreeMap::new();
+        for fun_id in self.targets.get_funs() {
+            let func_env = self.env.get_function(fun_id);
+            if self
+                .targets
+                .get_target_variants(&func_env)
+                .iter()
+                .any(|variant| variant.is_verified())
+            {
+                functions.insert(
+                    fun_id,
+                    FunctionReport {
+                        name: func_env.get_full_name_str(),
+                        fun_id,
+                        location: source_span(self.env, &func_env.get_loc()),
+                        status: VerificationStatus::Verified,
+                        cached: false,
+                        errors: vec![],
+                    },
+                );
+            }
+        }
+
+        let mut unattributed = vec![];
+        for error in errors {
+            let status = match error.kind {
+                BoogieErrorKind::Assertion => VerificationStatus::Failed,
+                BoogieErrorKind::Inconclusive => VerificationStatus::Timeout,
+                BoogieErrorKind::Inconsistency => VerificationStatus::Inconsistent,
+                BoogieErrorKind::Internal => VerificationStatus::Unknown,
+            };
+            let fun_id = self.error_function(error, |fun_id| functions.contains_key(fun_id));
+            let report = self.make_error_report(error, fun_id);
+            match fun_id.and_then(|fun_id| functions.get_mut(&fun_id)) {
+                Some(function) => {
+                    function.status = function.status.max(status);
+                    function.errors.push(report);
+                }
+                None => unattributed.push(report),
+            }
+        }
+        if !unattributed.is_empty() {
+            for function in functions.values_mut() {
+                function.status = function.status.max(VerificationStatus::Unknown);
+            }
+        }
+
+        let mut functions = functions.into_values().collect::<Vec<_>>();
+        functions.sort_by(|f1, f2| f1.name.cmp(&f2.name));
+        VerificationReport {
+            functions,
+            errors: unattributed,
+        }
+    }
+
+    /// Determines the verified function an error belongs to. This is the function whose
+    /// parameters are the first values in the execution trace, or else the function enclosing
+    /// the location of the error.
+    pub(crate) fn error_function(
+        &self,
+        error: &BoogieError,
+        is_verified: impl Fn(&QualifiedId<FunId>) -> bool,
+    ) -> Option<QualifiedId<FunId>> {
+        error
+            .execution_trace
+            .iter()
+            .find_map(|entry| match entry {
+                TraceEntry::Temporary(fun_id, ..)
+                | TraceEntry::Result(fun_id, ..)
+                | TraceEntry::Abort(fun_id, ..) => Some(*fun_id),
+                _ => None,
+            })
+            .or_else(|| {
+                self.env
+                    .get_enclosing_function(&error.loc)
+                    .map(|func_env| func_env.get_qualified_id())
+            })
+            .filter(is_verified)
+    }
+
+    fn make_error_report(
+        &self,
+        error: &BoogieError,
+        fun_id: Option<QualifiedId<FunId>>,
+    ) -> ErrorReport {
+        let counterexample = match (&error.model, fun_id) {
+            (Some(model), Some(fun_id)) if error.kind == BoogieErrorKind::Assertion => {
+                Some(self.make_counterexample(error, model, fun_id))
+            }
+            _ => None,
+        };
+        ErrorReport {
+            kind: error.kind,
+            message: error.message.clone(),
+            location: source_span(self.env, &error.loc),
+            source: self.env.get_source(&error.loc).ok().map(str::to_string),
+            counterexample,
+        }
+    }
+
+    fn make_counterexample(
+        &self,
+        error: &BoogieError,
+        model: &Model,
+        fun_id: QualifiedId<FunId>,
+    ) -> Counterexample {
+        let mut counterexample = Counterexample::default();
+        let mut arguments = BTreeMap::new();
+        let mut related = BTreeMap::new();
+        let mut memory: BTreeMap<String, (String, Value, Option<Value>)> = BTreeMap::new();
+        let domain_info = extract_domain(model);
+        let mut last_loc = self.env.unknown_loc();
+        for entry in &error.execution_trace {
+            use TraceEntry::*;
+            if counterexample.abort.is_some() && !matches!(entry, Exp(..)) {
+                // Once abort happened, only allow expression traces.
+                continue;
+            }
+            match entry {
+                AtLocation(loc) => {
+                    if loc != &last_loc {
+                        self.add_trace_step(&mut counterexample.trace, loc);
+                        last_loc = loc.clone();
+                    }
+                }
+                Temporary(fun, idx, value) => {
+                    let fun_env = self.env.get_function(*fun);
+                    let fun_target = self
+                        .targets
+                        .get_target(&fun_env, &FunctionVariant::Baseline);
+                    if *idx >= fun_target.get_local_count() {
+                        continue;
+                    }
+                    let name = fun_target
+                        .get_local_name(*idx)
+                        .display(self.env.symbol_pool())
+                        .to_string();
+                    let name = if self.options.stable_test_output && name.contains('$') {
+                        // See `add_error` for why those names are redacted.
+                        "<redacted>".to_string()
+                    } else {
+                        name
+                    };
+                    let binding =
+                        self.make_binding(name, fun_target.get_local_type(*idx), value, model);
+                    if *fun == fun_id && *idx < fun_target.get_parameter_count() {
+                        arguments.entry(*idx).or_insert_with(|| binding.clone());
+                    }
+                    self.add_binding(&mut counterexample.trace, binding);
+                }
+                Result(fun, idx, value) => {
+                    let fun_env = self.env.get_function(*fun);
+                    let fun_target = self
+                        .targets
+                        .get_target(&fun_env, &FunctionVariant::Baseline);
+                    let n = fun_target.get_return_count();
+                    if *idx >= n {
+                        continue;
+                    }
+                    let name = if n > 1 {
+                        format!("result_{}", idx.saturating_add(1))
+                    } else {
+                        "result".to_string()
+                    };
+                    let binding =
+                        self.make_binding(name, fun_target.get_return_type(*idx), value, model);
+                    self.add_binding(&mut counterexample.trace, binding);
+                }
+                Abort(_, value) => {
+                    let code = value.extract_i128().filter(|code| *code != -1);
+                    counterexample.abort = Some(AbortReport {
+                        location: source_span(self.env, &last_loc),
+                        code: code.and_then(|code| u64::try_from(code).ok()),
+                    });
+                }
+                Exp(node_id, value) => {
+                    let loc = self.env.get_node_loc(*node_id);
+                    if loc != last_loc {
+                        self.add_trace_step(&mut counterexample.trace, &loc);
+                        last_loc = loc;
+                    }
+                    let name = self
+                        .get_abbreviated_source(*node_id)
+                        .trim_matches('`')
+                        .to_string();
+                    let binding =
+                        self.make_binding(name, &self.env.get_node_type(*node_id), value, model);
+                    self.add_binding(&mut counterexample.trace, binding);
+                }
+                SubExp(node_id, value) => {
+                    let loc = self.env.get_node_loc(*node_id);
+                    if error.loc.is_enclosing(&loc) {
+                        // As in `add_error`, the latest value of a sub-expression wins.
+                        let denotation = self.env.get_source(&loc).unwrap_or("??").to_string();
+                        let binding = self.make_binding(
+                            denotation.clone(),
+                            &self.env.get_node_type(*node_id),
+                            value,
+                            model,
+                        );
+                        related.insert(denotation, binding);
+                    }
+                }
+                GlobalMem(node_id, ModelValue::List(elems)) => {
+                    // See `add_error` for the shape of the memory value.
+                    if elems.len() != 3 {
+                        continue;
+                    }
+                    if let ModelValue::Literal(s) = &elems[2] {
+                        let ty = self.env.get_node_type(*node_id);
+                        let value = elems[2]
+                            .json_mem(self, &elems[1], &domain_info, model, &ty)
+                            .unwrap_or(Value::Null);
+                        match memory.get_mut(s.as_str()) {
+                            Some((_, before, after)) => {
+                                *after = if &value == before { None } else { Some(value) };
+                            }
+                            None => {
+                                let resource =
+                                    ty.display(&self.env.get_type_display_ctx()).to_string();
+                                memory.insert(s.clone(), (resource, value, None));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        counterexample.arguments = arguments.into_values().collect();
+        counterexample.related = related.into_values().collect();
+        counterexample.memory = memory
+            .into_values()
+            .map(|(resource, before, after)| MemoryReport {
+                resource,
+                before,
+                after,
+            })
+            .collect();
+        counterexample
+    }
+
+    fn make_binding(&self, name: String, ty: &Type, value: &ModelValue, model: &Model) -> Binding {
+        Binding {
+            name,
+            ty: ty.display(&self.env.get_type_display_ctx()).to_string(),
+            value: value.json_or_raw(self, model, ty),
+        }
+    }
+
+    fn add_trace_step(&self, trace: &mut Vec<TraceStep>, loc: &Loc) {
+        trace.push(TraceStep {
+            location: source_span(self.env, loc),
+            function: self
+                .env
+                .get_enclosing_function(loc)
+                .map(|func_env| func_env.get_full_name_str()),
+            bindings: vec![],
+        });
+    }
+
+    fn add_binding(&self, trace: &mut Vec<TraceStep>, binding: Binding) {
+        if trace.is_empty() {
+            trace.push(TraceStep {
+                location: None,
+                function: None,
+                bindings: vec![],
+            });
+        }
+        trace.last_mut().unwrap().bindings.push(binding);
+    }
+}
+
+/// Returns the source span of a location, if it is known.
+fn source_span(env: &GlobalEnv, loc: &Loc) -> Option<SourceSpan> {
+    if loc == &env.unknown_loc() || loc == &env.internal_loc() {
+        return None;
+    }
+    let (file, location) = env.get_file_and_location(loc)?;
+    Some(SourceSpan {
+        file,
+        line: location.line.0 as usize + 1,
+        column: location.column.0 as usize + 1,
+        start: loc.span().start().0,
+        end: loc.span().end().0,
+    })
+}
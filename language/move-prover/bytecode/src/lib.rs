@@ -28,7 +28,9 @@ pub mod graph;
 pub mod inconsistency_check;
 pub mod livevar_analysis;
 pub mod loop_analysis;
+pub mod loop_invariant_inference;
 pub mod memory_instrumentation;
+pub mod modifies_inference;
 pub mod mono_analysis;
 pub mod mut_ref_instrumentation;
 pub mod mutation_tester;
@@ -36,6 +36,8 @@ pub struct FatLoop {
     pub val_targets: BTreeSet<TempIndex>,
     pub mut_targets: BTreeMap<TempIndex, bool>,
     pub back_edges: BTreeSet<CodeOffset>,
+    /// The code offsets of all instructions in the fat-loop, including the header block.
+    pub body: BTreeSet<CodeOffset>,
 }
 
 #[derive(Debug, Clone)]
@@ -82,6 +84,7 @@ impl FunctionTargetProcessor for LoopAnalysisProcessor {
             return data;
         }
         let loop_annotation = Self::build_loop_annotation(func_env, &data);
+        Self::check_invariant_placement(func_env, &data, &loop_annotation);
         Self::transform(func_env, data, &loop_annotation)
     }
 
@@ -375,7 +378,10 @@ impl LoopAnalysisProcessor {
 
     /// Find all loops in the function and collect information needed for invariant instrumentation
     /// and loop-to-DAG transformation.
-    fn build_loop_annotation(func_env: &FunctionEnv<'_>, data: &FunctionData) -> LoopAnnotation {
+    pub(crate) fn build_loop_annotation(
+        func_env: &FunctionEnv<'_>,
+        data: &FunctionData,
+    ) -> LoopAnnotation {
         // build for natural loops
         let func_target = FunctionTarget::new(func_env, data);
         let code = func_target.get_bytecode();
@@ -422,6 +428,14 @@ impl LoopAnalysisProcessor {
             let (val_targets, mut_targets) =
                 Self::collect_loop_targets(&cfg, &func_target, &sub_loops);
             let back_edges = Self::collect_loop_back_edges(code, &cfg, label, &sub_loops);
+            let body = sub_loops
+                .iter()
+                .flat_map(|l| l.loop_body.iter())
+                .flat_map(|block_id| {
+                    cfg.instr_indexes(*block_id)
+                        .expect("A loop body should never contain a dummy block")
+                })
+                .collect();
 
             // done with all information collection.
             fat_loops.insert(
@@ -431,12 +445,23 @@ impl LoopAnalysisProcessor {
                     val_targets,
                     mut_targets,
                     back_edges,
+                    body,
                 },
             );
         }
 
-        // check for redundant loop invariant declarations in the spec
-        let all_invariants: BTreeSet<_> = fat_loops
+        LoopAnnotation { fat_loops }
+    }
+
+    /// Check for redundant loop invariant declarations in the spec.
+    fn check_invariant_placement(
+        func_env: &FunctionEnv<'_>,
+        data: &FunctionData,
+        loop_annotation: &LoopAnnotation,
+    ) {
+        let func_target = FunctionTarget::new(func_env, data);
+        let all_invariants: BTreeSet<_> = loop_annotation
+            .fat_loops
             .values()
             .map(|l| l.invariants.values().map(|(attr_id, _)| *attr_id))
             .flatten()
@@ -450,7 +475,5 @@ impl LoopAnalysisProcessor {
                 consecutive sequence",
             );
         }
-
-        LoopAnnotation { fat_loops }
     }
 }
@@ -0,0 +1,675 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inference of simple loop invariants for loops in verified functions.
+//!
+//! The loop analysis havocs every variable modified in a loop, so without user-provided
+//! invariants nothing is known about those variables inside and after the loop. This pass
+//! recognizes a few common patterns and adds the corresponding invariants to the loop header
+//! as if the user had written them, so they are checked (base and induction case) and assumed
+//! by the loop analysis like any other loop invariant:
+//!
+//! - a counter `i` which is only incremented by a constant is bounded below by its value on
+//!   loop entry: `i@entry <= i`;
+//! - a counter incremented by one while the loop condition `i < b` holds is bounded above by
+//!   `b`, provided `b` does not change in the loop: `i@entry <= b ==> i <= b`. The bound can also
+//!   be the length of a vector whose length is preserved by the loop;
+//! - a mutable vector reference which is only updated element-wise keeps its length:
+//!   `len(v) == len(v@entry)`;
+//! - fields of a mutable struct reference which are not written in the loop keep their values:
+//!   `r.f == r@entry.f`.
+//!
+//! Variables and global memory which are not modified in the loop are not havoced by the loop
+//! analysis, hence no frame invariants are needed for them.
+//!
+//! The values on loop entry are captured in fresh temporaries assigned right before the loop
+//! header. Only loops whose header is entered by fall-through (and otherwise only by back
+//! edges) are considered, which is the shape the Move compiler produces for `while` and `loop`.
+
+use crate::{
+    function_data_builder::{FunctionDataBuilder, FunctionDataBuilderOptions},
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    loop_analysis::{FatLoop, LoopAnalysisProcessor, LoopAnnotation},
+    stackless_bytecode::{
+        AssignKind, BorrowEdge, BorrowNode, Bytecode, Constant, Label, Operation, PropKind,
+    },
+    verification_analysis,
+};
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::{self, Exp, TempIndex},
+    exp_generator::ExpGenerator,
+    model::{FunId, FunctionEnv, GlobalEnv, Loc, ModuleId, QualifiedId},
+    native::{VECTOR_BORROW_MUT, VECTOR_LENGTH, VECTOR_SWAP},
+    ty::{PrimitiveType, Type},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Formatter},
+    ops::Range,
+};
+
+/// The loop invariants inferred for each function, stored as an extension in the environment.
+#[derive(Debug, Clone, Default)]
+pub struct InferredLoopInvariants {
+    /// For each function, the location of each loop header together with a readable
+    /// description of the invariants inferred for it.
+    pub loops: BTreeMap<QualifiedId<FunId>, Vec<(Loc, Vec<String>)>>,
+}
+
+impl InferredLoopInvariants {
+    /// Returns the number of inferred invariants.
+    pub fn count(&self) -> usize {
+        self.loops
+            .values()
+            .flat_map(|loops| loops.iter().map(|(_, invs)| invs.len()))
+            .sum()
+    }
+
+    /// Returns a report of the inferred invariants, grouped by loop.
+    pub fn report(&self, env: &GlobalEnv) -> String {
+        let mut text = String::new();
+        for (fun_id, loops) in &self.loops {
+            let fun_env = env.get_function(*fun_id);
+            for (loc, invariants) in loops {
+                let line = env
+                    .get_location(loc)
+                    .map(|location| location.line.0 + 1)
+                    .unwrap_or_default();
+                text += &format!("{} (loop at line {}):\n", fun_env.get_full_name_str(), line);
+                for invariant in invariants {
+                    text += &format!("    invariant {};\n", invariant);
+                }
+            }
+        }
+        text
+    }
+}
+
+/// A bound on a loop counter.
+#[derive(Debug, Clone, Copy)]
+enum Bound {
+    /// A temporary which is not modified in the loop.
+    Temp(TempIndex),
+    /// The length of a vector which is either not modified in the loop or a mutable reference
+    /// whose length is preserved.
+    Length(TempIndex),
+}
+
+/// An invariant inferred for a loop.
+#[derive(Debug, Clone)]
+enum Candidate {
+    /// `i@entry <= i` for a counter which is only incremented.
+    LowerBound(TempIndex),
+    /// `i@entry <= b ==> i <= b` for a counter incremented by one while below `b`.
+    UpperBound(TempIndex, Bound),
+    /// `len(v) == len(v@entry)` for a mutable vector reference only updated element-wise.
+    VectorLength(TempIndex),
+    /// `r.f == r@entry.f` for the given fields of a mutable struct reference.
+    UnchangedFields(TempIndex, Vec<usize>),
+}
+
+impl Candidate {
+    /// The temporary whose value on loop entry the invariant refers to.
+    fn snapshot_of(&self) -> TempIndex {
+        match self {
+            Candidate::LowerBound(temp)
+            | Candidate::UpperBound(temp, _)
+            | Candidate::VectorLength(temp)
+            | Candidate::UnchangedFields(temp, _) => *temp,
+        }
+    }
+}
+
+pub struct LoopInvariantInferenceProcessor {}
+
+impl LoopInvariantInferenceProcessor {
+    pub fn new() -> Box<Self> {
+        Box::new(LoopInvariantInferenceProcessor {})
+    }
+}
+
+impl FunctionTargetProcessor for LoopInvariantInferenceProcessor {
+    fn initialize(&self, env: &GlobalEnv, _targets: &mut FunctionTargetsHolder) {
+        env.set_extension(InferredLoopInvariants::default());
+    }
+
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        func_env: &FunctionEnv<'_>,
+        data: FunctionData,
+    ) -> FunctionData {
+        if func_env.is_native_or_intrinsic() {
+            return data;
+        }
+        let func_target = FunctionTarget::new(func_env, &data);
+        if !verification_analysis::get_info(&func_target).verified {
+            return data;
+        }
+        let loop_annotation = LoopAnalysisProcessor::build_loop_annotation(func_env, &data);
+        let candidates: BTreeMap<_, _> = loop_annotation
+            .fat_loops
+            .keys()
+            .filter_map(|label| {
+                let candidates =
+                    LoopInference::new(&func_target, &loop_annotation, *label)?.infer();
+                if candidates.is_empty() {
+                    None
+                } else {
+                    Some((*label, candidates))
+                }
+            })
+            .collect();
+        if candidates.is_empty() {
+            return data;
+        }
+        Self::instrument(func_env, data, candidates)
+    }
+
+    fn name(&self) -> String {
+        "loop_invariant_inference".to_string()
+    }
+
+    fn dump_result(
+        &self,
+        f: &mut Formatter<'_>,
+        env: &GlobalEnv,
+        _targets: &FunctionTargetsHolder,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "\n********* Result of loop invariant inference *********\n"
+        )?;
+        if let Some(inferred) = env.get_extension::<InferredLoopInvariants>() {
+            write!(f, "{}", inferred.report(env))?;
+        }
+        Ok(())
+    }
+}
+
+impl LoopInvariantInferenceProcessor {
+    /// Snapshots the values needed by the candidates before each loop header and adds the
+    /// invariants as loop invariants right after the header label.
+    fn instrument(
+        func_env: &FunctionEnv<'_>,
+        data: FunctionData,
+        candidates: BTreeMap<Label, Vec<Candidate>>,
+    ) -> FunctionData {
+        let env = func_env.module_env.env;
+        let mut builder = FunctionDataBuilder::new_with_options(
+            func_env,
+            data,
+            FunctionDataBuilderOptions {
+                no_fallthrough_jump_removal: true,
+            },
+        );
+        let mut reported = vec![];
+        let code = std::mem::take(&mut builder.data.code);
+        let names = Self::local_names(func_env, &code);
+        for bytecode in code {
+            let (attr_id, label) = match &bytecode {
+                Bytecode::Label(attr_id, label) if candidates.contains_key(label) => {
+                    (*attr_id, *label)
+                }
+                _ => {
+                    builder.emit(bytecode);
+                    continue;
+                }
+            };
+            builder.set_loc_from_attr(attr_id);
+
+            // take snapshots of the values on loop entry
+            let mut snapshots = BTreeMap::new();
+            for candidate in &candidates[&label] {
+                let temp = candidate.snapshot_of();
+                if snapshots.contains_key(&temp) {
+                    continue;
+                }
+                let ty = builder.get_local_type(temp);
+                let snapshot = builder.new_temp(ty.skip_reference().clone());
+                builder.emit_with(|id| {
+                    if ty.is_mutable_reference() {
+                        Bytecode::Call(id, vec![snapshot], Operation::ReadRef, vec![temp], None)
+                    } else {
+                        Bytecode::Assign(id, snapshot, temp, AssignKind::Copy)
+                    }
+                });
+                snapshots.insert(temp, snapshot);
+            }
+            builder.emit(bytecode);
+
+            // add the invariants
+            let mut descriptions = vec![];
+            for candidate in &candidates[&label] {
+                for (exp, description) in
+                    Self::make_invariants(&builder, &names, &snapshots, candidate)
+                {
+                    builder.emit_with(|id| Bytecode::Prop(id, PropKind::Assert, exp));
+                    let attr_id = builder.data.code.last().unwrap().get_attr_id();
+                    builder.data.loop_invariants.insert(attr_id);
+                    descriptions.push(description);
+                }
+            }
+            reported.push((builder.get_loc(attr_id), descriptions));
+        }
+
+        env.update_extension(|inferred: &mut InferredLoopInvariants| {
+            inferred.loops.insert(func_env.get_qualified_id(), reported);
+        });
+        builder.data
+    }
+
+    /// Returns user names for temporaries without one, derived from the traces of user locals
+    /// which hold the value of the temporary.
+    fn local_names(func_env: &FunctionEnv<'_>, code: &[Bytecode]) -> BTreeMap<TempIndex, String> {
+        let pool = func_env.symbol_pool();
+        let user_local_count = func_env.get_local_count();
+        let mut names = BTreeMap::new();
+        for bc in code {
+            if let Bytecode::Call(_, _, Operation::TraceLocal(idx), srcs, _) = bc {
+                if srcs[0] >= user_local_count {
+                    names
+                        .entry(srcs[0])
+                        .or_insert_with(|| func_env.get_local_name(*idx).display(pool).to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Creates the invariant expressions of a candidate together with their descriptions.
+    fn make_invariants(
+        builder: &FunctionDataBuilder<'_>,
+        names: &BTreeMap<TempIndex, String>,
+        snapshots: &BTreeMap<TempIndex, TempIndex>,
+        candidate: &Candidate,
+    ) -> Vec<(Exp, String)> {
+        let env = builder.global_env();
+        let name = |temp: TempIndex| {
+            names.get(&temp).cloned().unwrap_or_else(|| {
+                builder
+                    .fun_env
+                    .get_local_name(temp)
+                    .display(env.symbol_pool())
+                    .to_string()
+            })
+        };
+        let mk_len = |temp: TempIndex| {
+            builder.mk_call(
+                &Type::Primitive(PrimitiveType::Num),
+                ast::Operation::Len,
+                vec![builder.mk_temporary(temp)],
+            )
+        };
+        let temp = candidate.snapshot_of();
+        let current = builder.mk_temporary(temp);
+        let entry = builder.mk_temporary(snapshots[&temp]);
+        match candidate {
+            Candidate::LowerBound(counter) => vec![(
+                builder.mk_bool_call(ast::Operation::Le, vec![entry, current]),
+                format!("{0}@entry <= {0}", name(*counter)),
+            )],
+            Candidate::UpperBound(counter, bound) => {
+                let (bound_exp, bound_str) = match bound {
+                    Bound::Temp(temp) => (builder.mk_temporary(*temp), name(*temp)),
+                    Bound::Length(temp) => (mk_len(*temp), format!("len({})", name(*temp))),
+                };
+                let exp = builder.mk_implies(
+                    builder.mk_bool_call(ast::Operation::Le, vec![entry, bound_exp.clone()]),
+                    builder.mk_bool_call(ast::Operation::Le, vec![current, bound_exp]),
+                );
+                vec![(
+                    exp,
+                    format!("{0}@entry <= {1} ==> {0} <= {1}", name(*counter), bound_str),
+                )]
+            }
+            Candidate::VectorLength(vector) => vec![(
+                builder.mk_eq(mk_len(*vector), mk_len(snapshots[vector])),
+                format!("len({0}) == len({0}@entry)", name(*vector)),
+            )],
+            Candidate::UnchangedFields(reference, offsets) => {
+                let (mid, sid, inst) = match builder.get_local_type(*reference).skip_reference() {
+                    Type::Struct(mid, sid, inst) => (*mid, *sid, inst.clone()),
+                    _ => unreachable!("struct reference"),
+                };
+                let struct_env = env.get_module(mid).into_struct(sid);
+                offsets
+                    .iter()
+                    .map(|offset| {
+                        let field_env = struct_env.get_field_by_offset(*offset);
+                        let exp = builder.mk_eq(
+                            builder.mk_field_select(&field_env, &inst, current.clone()),
+                            builder.mk_field_select(&field_env, &inst, entry.clone()),
+                        );
+                        let field_name =
+                            field_env.get_name().display(env.symbol_pool()).to_string();
+                        (
+                            exp,
+                            format!("{0}.{1} == {0}@entry.{1}", name(*reference), field_name),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Inference of invariants for one fat loop.
+struct LoopInference<'a> {
+    func_target: &'a FunctionTarget<'a>,
+    fat_loop: &'a FatLoop,
+    /// Code offsets in the bodies of loops nested in this loop.
+    nested: BTreeSet<CodeOffset>,
+    /// The code offsets of the header block, excluding the branch which ends it.
+    header: Range<CodeOffset>,
+}
+
+impl<'a> LoopInference<'a> {
+    /// Creates the inference for the loop with the given header label, or returns `None` if the
+    /// loop does not have the expected shape.
+    fn new(
+        func_target: &'a FunctionTarget<'a>,
+        loop_annotation: &'a LoopAnnotation,
+        label: Label,
+    ) -> Option<Self> {
+        let code = func_target.get_bytecode();
+        let label_offsets = Bytecode::label_offsets(code);
+        let fat_loop = &loop_annotation.fat_loops[&label];
+        let start = label_offsets[&label];
+        if !Self::is_entered_by_fallthrough(code, fat_loop, label, start) {
+            return None;
+        }
+        let mut end = start;
+        while (end as usize) < code.len() && !code[end as usize].is_branch() {
+            end += 1;
+        }
+        let nested = loop_annotation
+            .fat_loops
+            .iter()
+            .filter(|(other_label, _)| {
+                **other_label != label && fat_loop.body.contains(&label_offsets[other_label])
+            })
+            .flat_map(|(_, other)| other.body.iter().copied())
+            .collect();
+        Some(LoopInference {
+            func_target,
+            fat_loop,
+            nested,
+            header: start..end,
+        })
+    }
+
+    fn infer(&self) -> Vec<Candidate> {
+        let mut candidates = vec![];
+        let vector_refs: BTreeSet<_> = self
+            .fat_loop
+            .mut_targets
+            .iter()
+            .filter(|(temp, havoc_all)| !**havoc_all && self.preserves_length(**temp))
+            .map(|(temp, _)| *temp)
+            .collect();
+        let upper_bound = self.upper_bound(&vector_refs);
+        for temp in &self.fat_loop.val_targets {
+            if let Some(step) = self.counter_step(*temp) {
+                candidates.push(Candidate::LowerBound(*temp));
+                match upper_bound {
+                    Some((counter, bound)) if counter == *temp && step == 1 => {
+                        candidates.push(Candidate::UpperBound(*temp, bound))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        candidates.extend(vector_refs.into_iter().map(Candidate::VectorLength));
+        for (temp, havoc_all) in &self.fat_loop.mut_targets {
+            if !*havoc_all {
+                if let Some(offsets) = self.unchanged_fields(*temp) {
+                    candidates.push(Candidate::UnchangedFields(*temp, offsets));
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Checks whether the loop header is only entered by falling through from the preceding
+    /// instruction and otherwise only by back edges, so that code inserted before the header
+    /// label runs exactly once on loop entry.
+    fn is_entered_by_fallthrough(
+        code: &[Bytecode],
+        fat_loop: &FatLoop,
+        label: Label,
+        start: CodeOffset,
+    ) -> bool {
+        if start > 0 {
+            let prev = &code[start as usize - 1];
+            if matches!(prev, Bytecode::Jump(..) | Bytecode::Branch(..)) || prev.is_exit() {
+                return false;
+            }
+        }
+        code.iter().enumerate().all(|(offset, bc)| {
+            !bc.branch_dests().contains(&label)
+                || fat_loop.back_edges.contains(&(offset as CodeOffset))
+        })
+    }
+
+    /// Returns the offsets in the loop body which modify the value of the temporary.
+    fn body_defs(&self, temp: TempIndex) -> Vec<CodeOffset> {
+        let code = self.func_target.get_bytecode();
+        self.fat_loop
+            .body
+            .iter()
+            .filter(|offset| {
+                code[**offset as usize]
+                    .modifies(self.func_target)
+                    .0
+                    .contains(&temp)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns the single definition of the temporary in the loop header block, if the
+    /// temporary is not defined anywhere else in the loop.
+    fn header_def(&self, temp: TempIndex) -> Option<&'a Bytecode> {
+        match self.body_defs(temp).as_slice() {
+            [offset] if self.header.contains(offset) => {
+                Some(&self.func_target.get_bytecode()[*offset as usize])
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether the temporary is neither modified nor havoced in the loop.
+    fn is_unmodified(&self, temp: TempIndex) -> bool {
+        !self.fat_loop.val_targets.contains(&temp) && !self.fat_loop.mut_targets.contains_key(&temp)
+    }
+
+    /// If the temporary is a counter which is incremented by a positive constant exactly once
+    /// per iteration and not modified otherwise, returns the step.
+    fn counter_step(&self, temp: TempIndex) -> Option<u128> {
+        if !self.func_target.get_local_type(temp).is_number() {
+            return None;
+        }
+        let code = self.func_target.get_bytecode();
+        let offset = match self.body_defs(temp).as_slice() {
+            [offset] => *offset,
+            _ => return None,
+        };
+        if self.header.contains(&offset) || self.nested.contains(&offset) {
+            return None;
+        }
+        let is_increment = |bc: &Bytecode, dest: TempIndex| match bc {
+            Bytecode::Call(_, dests, Operation::Add, srcs, _) if dests == &[dest] => {
+                match srcs.as_slice() {
+                    [x, k] if *x == temp => Some(*k),
+                    [k, x] if *x == temp => Some(*k),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let step = match &code[offset as usize] {
+            Bytecode::Assign(_, _, src, _) if offset > 0 => {
+                is_increment(&code[offset as usize - 1], *src)?
+            }
+            bc => is_increment(bc, temp)?,
+        };
+        // the step must be a temporary which is only ever assigned a positive constant
+        let step_defs = code
+            .iter()
+            .filter(|bc| bc.modifies(self.func_target).0.contains(&step))
+            .collect::<Vec<_>>();
+        match step_defs.as_slice() {
+            [Bytecode::Load(_, _, constant)] => {
+                let value = match constant {
+                    Constant::U8(v) => *v as u128,
+                    Constant::U64(v) => *v as u128,
+                    Constant::U128(v) => *v,
+                    _ => return None,
+                };
+                if value > 0 {
+                    Some(value)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// If the loop header ends with a branch on a condition of the form `i < b` which continues
+    /// the loop while the condition holds, returns the counter `i` and the bound `b`.
+    fn upper_bound(&self, vector_refs: &BTreeSet<TempIndex>) -> Option<(TempIndex, Bound)> {
+        let code = self.func_target.get_bytecode();
+        let label_offsets = Bytecode::label_offsets(code);
+        let (then_label, else_label, cond) = match code.get(self.header.end as usize)? {
+            Bytecode::Branch(_, then_label, else_label, cond) => (*then_label, *else_label, *cond),
+            _ => return None,
+        };
+        let in_body = |label: Label| self.fat_loop.body.contains(&label_offsets[&label]);
+        let continue_if_true = match (in_body(then_label), in_body(else_label)) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => return None,
+        };
+        let (counter, bound) = match (self.header_def(cond)?, continue_if_true) {
+            (Bytecode::Call(_, _, Operation::Lt, srcs, _), true)
+            | (Bytecode::Call(_, _, Operation::Ge, srcs, _), false) => (srcs[0], srcs[1]),
+            (Bytecode::Call(_, _, Operation::Gt, srcs, _), true)
+            | (Bytecode::Call(_, _, Operation::Le, srcs, _), false) => (srcs[1], srcs[0]),
+            _ => return None,
+        };
+        if self.is_unmodified(bound) {
+            return Some((counter, Bound::Temp(bound)));
+        }
+        let vector = match self.header_def(bound)? {
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _)
+                if self.is_native(*mid, *fid, &[VECTOR_LENGTH]) =>
+            {
+                srcs[0]
+            }
+            _ => return None,
+        };
+        if self.is_unmodified(vector) {
+            return Some((counter, Bound::Length(vector)));
+        }
+        match self.header_def(vector)? {
+            Bytecode::Call(_, _, Operation::ReadRef, srcs, _) if vector_refs.contains(&srcs[0]) => {
+                Some((counter, Bound::Length(srcs[0])))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether the temporary is a mutable vector reference which is only modified
+    /// element-wise in the loop.
+    fn preserves_length(&self, temp: TempIndex) -> bool {
+        let ty = self.func_target.get_local_type(temp);
+        if !ty.is_mutable_reference() || !ty.skip_reference().is_vector() {
+            return false;
+        }
+        self.reference_writes(temp).into_iter().all(|bc| match bc {
+            Bytecode::Call(_, _, Operation::WriteBack(BorrowNode::Reference(_), edge), ..) => {
+                matches!(edge.flatten().first(), Some(BorrowEdge::Index))
+            }
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) => {
+                srcs.first() == Some(&temp)
+                    && self.is_native(*mid, *fid, &[VECTOR_BORROW_MUT, VECTOR_SWAP])
+            }
+            _ => false,
+        })
+    }
+
+    /// If the temporary is a mutable struct reference of which some fields are not written in
+    /// the loop, returns the offsets of those fields.
+    fn unchanged_fields(&self, temp: TempIndex) -> Option<Vec<usize>> {
+        let (mid, sid) = match self.func_target.get_local_type(temp) {
+            Type::Reference(true, ty) => match ty.as_ref() {
+                Type::Struct(mid, sid, _) => (*mid, *sid),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let mut written = BTreeSet::new();
+        for bc in self.reference_writes(temp) {
+            match bc {
+                Bytecode::Call(_, _, Operation::WriteBack(BorrowNode::Reference(_), edge), ..) => {
+                    match edge.flatten().first() {
+                        Some(BorrowEdge::Field(_, offset)) => {
+                            written.insert(*offset);
+                        }
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            }
+        }
+        let field_count = self
+            .func_target
+            .global_env()
+            .get_module(mid)
+            .into_struct(sid)
+            .get_field_count();
+        let unchanged = (0..field_count)
+            .filter(|offset| !written.contains(offset))
+            .collect::<Vec<_>>();
+        if unchanged.is_empty() {
+            None
+        } else {
+            Some(unchanged)
+        }
+    }
+
+    /// Returns the instructions in the loop body which modify the value behind the reference.
+    fn reference_writes(&self, temp: TempIndex) -> Vec<&'a Bytecode> {
+        let code = self.func_target.get_bytecode();
+        self.fat_loop
+            .body
+            .iter()
+            .map(|offset| &code[*offset as usize])
+            .filter(|bc| {
+                bc.modifies(self.func_target)
+                    .1
+                    .iter()
+                    .any(|(target, _)| *target == temp)
+            })
+            .collect()
+    }
+
+    /// Returns whether the function is one of the given well-known native functions.
+    fn is_native(&self, mid: ModuleId, fid: FunId, names: &[&str]) -> bool {
+        let callee_env = self
+            .func_target
+            .global_env()
+            .get_function_qid(mid.qualified(fid));
+        let pool = callee_env.symbol_pool();
+        let name = format!(
+            "{}::{}",
+            callee_env.module_env.get_name().display_full(pool),
+            callee_env.get_name().display(pool)
+        );
+        names.contains(&name.as_str())
+    }
+}
@@ -0,0 +1,384 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inference of `modifies` frame conditions from the read/write set analysis.
+//!
+//! For every function, the global resources written by the function or one of its callees
+//! (via `move_to`, `move_from`, or a write through `borrow_global_mut`) are collected from the
+//! summary computed by the read/write set analysis, together with a spec expression for each
+//! address at which they are written. An address can be expressed if it is a constant or is
+//! derived from the function's parameters. The result is attached to the function target as an
+//! `InferredModifies` annotation.
+//!
+//! For functions in target modules, the inferred frame is compared with the `modifies`
+//! conditions in the function's spec on the level of resource types. A warning is produced for
+//! each resource which is declared as modified but never written, and, if the spec has any
+//! `modifies` conditions, for each resource which is written but not declared.
+//!
+//! The read/write set analysis works on untransformed function targets, so this processor
+//! must run right after it and is not part of the verification pipeline.
+
+use crate::{
+    access_path::{AbsAddr, AccessPath, Addr, Offset, Root},
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    read_write_set_analysis::ReadWriteSetState,
+};
+use codespan_reporting::diagnostic::Severity;
+use itertools::Itertools;
+use move_model::{
+    ast::ConditionKind,
+    model::{FunctionEnv, GlobalEnv, QualifiedId, StructId},
+    ty::Type,
+};
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Formatter},
+};
+
+/// A global resource which may be modified by a function.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ModifiesTarget {
+    /// The resource type.
+    pub resource: QualifiedId<StructId>,
+    /// The instantiated resource type in spec syntax.
+    pub ty: String,
+    /// The address at which the resource is modified in spec syntax, or `None` if it cannot be
+    /// expressed in terms of the function's parameters.
+    pub address: Option<String>,
+}
+
+/// The global resources a function may modify, as inferred from its read/write set.
+#[derive(Debug, Clone, Default)]
+pub struct InferredModifies {
+    pub targets: BTreeSet<ModifiesTarget>,
+}
+
+impl InferredModifies {
+    /// Returns the resource types modified by the function.
+    pub fn resources(&self) -> BTreeSet<QualifiedId<StructId>> {
+        self.targets.iter().map(|target| target.resource).collect()
+    }
+
+    /// Returns the `modifies` conditions which cover the modifications of `resource`, or `None`
+    /// if the address of one of them cannot be expressed.
+    pub fn conditions_for(&self, resource: QualifiedId<StructId>) -> Option<Vec<String>> {
+        self.targets
+            .iter()
+            .filter(|target| target.resource == resource)
+            .map(|target| {
+                target
+                    .address
+                    .as_ref()
+                    .map(|addr| format!("modifies global<{}>({});", target.ty, addr))
+            })
+            .collect()
+    }
+}
+
+// =================================================================================================
+// Processor
+
+pub struct ModifiesInferenceProcessor();
+
+impl ModifiesInferenceProcessor {
+    pub fn new() -> Box<Self> {
+        Box::new(ModifiesInferenceProcessor())
+    }
+}
+
+impl FunctionTargetProcessor for ModifiesInferenceProcessor {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        func_env: &FunctionEnv<'_>,
+        mut data: FunctionData,
+    ) -> FunctionData {
+        if func_env.is_native_or_intrinsic() {
+            return data;
+        }
+        let inferred = match data.annotations.get::<ReadWriteSetState>() {
+            Some(state) => infer_modifies(func_env, state),
+            None => return data,
+        };
+        if func_env.module_env.is_target() {
+            check_modifies(func_env, &inferred);
+        }
+        data.annotations.set(inferred);
+        data
+    }
+
+    fn name(&self) -> String {
+        "modifies_inference".to_string()
+    }
+
+    fn dump_result(
+        &self,
+        f: &mut Formatter<'_>,
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+    ) -> fmt::Result {
+        writeln!(f, "\n********* Result of modifies inference *********\n")?;
+        for module_env in env.get_modules() {
+            if !module_env.is_target() {
+                continue;
+            }
+            for func_env in module_env.get_functions() {
+                let target = targets.get_target(&func_env, &FunctionVariant::Baseline);
+                if let Some(inferred) =
+                    get_inferred_modifies(&target).filter(|inferred| !inferred.targets.is_empty())
+                {
+                    writeln!(f, "{}:", func_env.get_full_name_str())?;
+                    for target in &inferred.targets {
+                        match &target.address {
+                            Some(addr) => {
+                                writeln!(f, "  modifies global<{}>({});", target.ty, addr)?
+                            }
+                            None => writeln!(f, "  modifies global<{}>(<unknown>);", target.ty)?,
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the inferred frame of `target`, if the modifies inference has been run on it.
+pub fn get_inferred_modifies<'a>(target: &'a FunctionTarget<'a>) -> Option<&'a InferredModifies> {
+    target.get_annotations().get::<InferredModifies>()
+}
+
+/// Computes the global resources written according to the read/write set `state`.
+fn infer_modifies(func_env: &FunctionEnv, state: &ReadWriteSetState) -> InferredModifies {
+    let renderer = SpecRenderer { func_env };
+    let mut targets = BTreeSet::new();
+    state.accesses().iter_paths(|ap, access| {
+        if !access.is_write() {
+            return;
+        }
+        if let Some((ty, addresses)) = renderer.written_resource(ap) {
+            let (mid, sid, _) = ty.require_struct();
+            for address in addresses {
+                targets.insert(ModifiesTarget {
+                    resource: mid.qualified(sid),
+                    ty: renderer.render_type(&ty),
+                    address,
+                });
+            }
+        }
+    });
+    InferredModifies { targets }
+}
+
+/// Reports differences between the inferred frame and the `modifies` conditions in the spec.
+fn check_modifies(func_env: &FunctionEnv, inferred: &InferredModifies) {
+    let env = func_env.module_env.env;
+    let modified = inferred.resources();
+    let mut declared = BTreeSet::new();
+    for cond in func_env.get_spec().filter_kind(ConditionKind::Modifies) {
+        for target in cond.all_exps() {
+            let inst = env.get_node_instantiation(target.node_id());
+            let (mid, sid, _) = inst[0].require_struct();
+            let resource = mid.qualified(sid);
+            if !modified.contains(&resource) {
+                env.diag(
+                    Severity::Warning,
+                    &cond.loc,
+                    &format!(
+                        "function `{}` is declared to modify `{}` but never modifies it",
+                        func_env.get_full_name_str(),
+                        env.get_struct(resource).get_full_name_str()
+                    ),
+                );
+            }
+            declared.insert(resource);
+        }
+    }
+    if declared.is_empty() {
+        return;
+    }
+    for resource in modified.difference(&declared) {
+        env.diag(
+            Severity::Warning,
+            &func_env.get_spec_loc(),
+            &format!(
+                "function `{}` modifies `{}` but has no `modifies` condition for it",
+                func_env.get_full_name_str(),
+                env.get_struct(*resource).get_full_name_str()
+            ),
+        );
+    }
+}
+
+// =================================================================================================
+// Rendering of access paths as spec expressions
+
+struct SpecRenderer<'a> {
+    func_env: &'a FunctionEnv<'a>,
+}
+
+impl<'a> SpecRenderer<'a> {
+    /// Returns the resource type written by a write to `ap`, together with the addresses at
+    /// which it is written, or `None` if `ap` does not point into global memory.
+    fn written_resource(&self, ap: &AccessPath) -> Option<(Type, Vec<Option<String>>)> {
+        let offsets = ap.offsets();
+        if let Some(pos) = offsets
+            .iter()
+            .rposition(|offset| matches!(offset, Offset::Global(_)))
+        {
+            let ty = match &offsets[pos] {
+                Offset::Global(struct_type) => struct_type.get_type(),
+                _ => unreachable!(),
+            };
+            Some((ty, self.render_address(ap.root(), &offsets[..pos])))
+        } else if let Root::Global(key) = ap.root() {
+            Some((
+                key.struct_type().get_type(),
+                self.render_addresses(key.address()),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Renders the address values of `addrs`.
+    fn render_addresses(&self, addrs: &AbsAddr) -> Vec<Option<String>> {
+        addrs
+            .iter()
+            .flat_map(|addr| match addr {
+                Addr::Constant(value) => vec![Some(format!("@0x{:x}", value))],
+                Addr::Footprint(ap) => self.render_address(ap.root(), ap.offsets()),
+            })
+            .collect()
+    }
+
+    /// Renders the value at `root` followed by `offsets` as an address, one expression for
+    /// each address the path may denote.
+    fn render_address(&self, root: &Root, offsets: &[Offset]) -> Vec<Option<String>> {
+        let (exps, ty) = self.render_path(root, offsets);
+        exps.into_iter()
+            .map(|exp| exp.map(|exp| self.as_address(exp, &ty)))
+            .collect()
+    }
+
+    /// Renders the value at `root` followed by `offsets`, returning the expressions for it
+    /// together with its type.
+    fn render_path(&self, root: &Root, offsets: &[Offset]) -> (Vec<Option<String>>, Type) {
+        let env = self.func_env.module_env.env;
+        let (mut exps, mut ty) = match root {
+            Root::Formal(idx) => {
+                let name = self
+                    .func_env
+                    .get_local_name(*idx)
+                    .display(env.symbol_pool())
+                    .to_string();
+                (vec![Some(name)], self.func_env.get_local_type(*idx))
+            }
+            Root::Global(key) => {
+                let ty = key.struct_type().get_type();
+                let ty_str = self.render_type(&ty);
+                let exps = self
+                    .render_addresses(key.address())
+                    .into_iter()
+                    .map(|addr| addr.map(|addr| format!("global<{}>({})", ty_str, addr)))
+                    .collect();
+                (exps, ty)
+            }
+            Root::Local(_) | Root::Return(_) => {
+                return (vec![None], root.get_type(self.func_env));
+            }
+        };
+        for offset in offsets {
+            let offset_ty = offset.get_type(&ty, env);
+            exps = match offset {
+                Offset::Field(field_offset) => {
+                    let (mid, sid, _) = ty.skip_reference().require_struct();
+                    let field_name = env
+                        .get_struct(mid.qualified(sid))
+                        .get_field_by_offset(*field_offset)
+                        .get_name()
+                        .display(env.symbol_pool())
+                        .to_string();
+                    exps.into_iter()
+                        .map(|exp| exp.map(|exp| format!("{}.{}", exp, field_name)))
+                        .collect()
+                }
+                Offset::VectorIndex => exps.into_iter().map(|_| None).collect(),
+                Offset::Global(_) => {
+                    let ty_str = self.render_type(&offset_ty);
+                    exps.into_iter()
+                        .map(|exp| {
+                            exp.map(|exp| {
+                                format!("global<{}>({})", ty_str, self.as_address(exp, &ty))
+                            })
+                        })
+                        .collect()
+                }
+            };
+            ty = offset_ty;
+        }
+        (exps, ty)
+    }
+
+    /// Converts `exp` of type `ty` into an address, which is needed for signers.
+    fn as_address(&self, exp: String, ty: &Type) -> String {
+        if !ty.skip_reference().is_signer() {
+            return exp;
+        }
+        let env = self.func_env.module_env.env;
+        let uses_signer = self
+            .func_env
+            .module_env
+            .get_used_modules(false)
+            .iter()
+            .any(|mid| {
+                env.symbol_pool()
+                    .string(env.get_module(*mid).get_name().name())
+                    .as_str()
+                    == "Signer"
+            });
+        if uses_signer {
+            format!("Signer::address_of({})", exp)
+        } else {
+            format!("0x1::Signer::address_of({})", exp)
+        }
+    }
+
+    /// Renders `ty` in spec syntax as seen from the function's module.
+    fn render_type(&self, ty: &Type) -> String {
+        let env = self.func_env.module_env.env;
+        match ty {
+            Type::Struct(mid, sid, inst) => {
+                let struct_env = env.get_struct(mid.qualified(*sid));
+                let struct_name = struct_env.get_name().display(env.symbol_pool()).to_string();
+                let name = if *mid == self.func_env.module_env.get_id() {
+                    struct_name
+                } else {
+                    format!(
+                        "{}::{}",
+                        struct_env.module_env.get_name().display(env.symbol_pool()),
+                        struct_name
+                    )
+                };
+                if inst.is_empty() {
+                    name
+                } else {
+                    format!(
+                        "{}<{}>",
+                        name,
+                        inst.iter().map(|ty| self.render_type(ty)).join(", ")
+                    )
+                }
+            }
+            Type::Vector(elem_ty) => format!("vector<{}>", self.render_type(elem_ty)),
+            Type::TypeParameter(idx) => self.func_env.get_named_type_parameters()[*idx as usize]
+                .0
+                .display(env.symbol_pool())
+                .to_string(),
+            _ => ty
+                .display(&self.func_env.get_type_display_ctx())
+                .to_string(),
+        }
+    }
+}
@@ -81,6 +81,8 @@ pub struct ProverOptions {
     pub unconditional_abort_as_inconsistency: bool,
     /// Whether to run the transformation passes for concrete interpretation (instead of proving)
     pub for_interpretation: bool,
+    /// Whether to infer simple loop invariants for loops in verified functions
+    pub infer_loop_invariants: bool,
 }
 
 // add custom struct for mutation options
@@ -112,6 +114,7 @@ impl Default for ProverOptions {
             check_inconsistency: false,
             unconditional_abort_as_inconsistency: false,
             for_interpretation: false,
+            infer_loop_invariants: false,
         }
     }
 }
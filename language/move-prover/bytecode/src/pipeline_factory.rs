@@ -13,6 +13,7 @@ use crate::{
     inconsistency_check::InconsistencyCheckInstrumenter,
     livevar_analysis::LiveVarAnalysisProcessor,
     loop_analysis::LoopAnalysisProcessor,
+    loop_invariant_inference::LoopInvariantInferenceProcessor,
     memory_instrumentation::MemoryInstrumentationProcessor,
     mono_analysis::MonoAnalysisProcessor,
     mut_ref_instrumentation::MutRefInstrumenter,
@@ -39,6 +40,14 @@ pub fn default_pipeline_with_options(options: &ProverOptions) -> FunctionTargetP
         CleanAndOptimizeProcessor::new(),
         UsageProcessor::new(),
         VerificationAnalysisProcessor::new(),
+    ];
+
+    if options.infer_loop_invariants {
+        // must run before the loop analysis which consumes the loop invariants
+        processors.push(LoopInvariantInferenceProcessor::new());
+    }
+
+    processors.append(&mut vec![
         LoopAnalysisProcessor::new(),
         // spec instrumentation
         SpecInstrumentationProcessor::new(),
@@ -48,7 +57,7 @@ pub fn default_pipeline_with_options(options: &ProverOptions) -> FunctionTargetP
         DataInvariantInstrumentationProcessor::new(),
         // monomorphization
         MonoAnalysisProcessor::new(),
-    ];
+    ]);
 
     if options.mutation {
         // pass which may do nothing
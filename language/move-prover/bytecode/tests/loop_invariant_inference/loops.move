@@ -0,0 +1,71 @@
+// dep: ../../move-stdlib/sources/Vector.move
+module 0x42::Loops {
+    use Std::Vector;
+
+    struct Counter has drop {
+        value: u64,
+        limit: u64,
+    }
+
+    fun sum_to(n: u64): u64 {
+        let i = 0;
+        let s = 0;
+        while (i < n) {
+            i = i + 1;
+            s = s + 2;
+        };
+        s
+    }
+
+    fun zero_all(v: &mut vector<u64>) {
+        let i = 0;
+        let len = Vector::length(v);
+        while (i < len) {
+            *Vector::borrow_mut(v, i) = 0;
+            i = i + 1;
+        }
+    }
+
+    fun count(v: &vector<u64>, x: u64): u64 {
+        let i = 0;
+        let c = 0;
+        while (i < Vector::length(v)) {
+            if (*Vector::borrow(v, i) == x) c = c + 1;
+            i = i + 1;
+        };
+        c
+    }
+
+    fun count_up(c: &mut Counter) {
+        while (c.value < c.limit) {
+            c.value = c.value + 1;
+        }
+    }
+
+    fun step_by_two(n: u64): u64 {
+        let i = 0;
+        while ({
+            spec {
+                invariant i % 2 == 0;
+            };
+            i < n
+        }) {
+            i = i + 2;
+        };
+        i
+    }
+
+    fun count_down(n: u64) {
+        while (n > 0) {
+            n = n - 1;
+        }
+    }
+
+    fun push_all(v: &mut vector<u64>, n: u64) {
+        let i = 0;
+        while (i < n) {
+            Vector::push_back(v, i);
+            i = i + 1;
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// dep: ../../move-stdlib/sources/Signer.move
+module 0x42::Frames {
+    use Std::Signer;
+
+    struct Counter has key { value: u64 }
+
+    struct Config has key { admin: address }
+
+    struct Box<T: store> has key { content: T }
+
+    fun publish(s: &signer) {
+        move_to(s, Counter { value: 0 })
+    }
+
+    fun increment(addr: address) acquires Counter {
+        let c = borrow_global_mut<Counter>(addr);
+        c.value = c.value + 1;
+    }
+
+    fun increment_own(s: &signer) acquires Counter {
+        increment(Signer::address_of(s))
+    }
+
+    fun reset_admin() acquires Config, Counter {
+        let admin = borrow_global<Config>(@0x1).admin;
+        borrow_global_mut<Counter>(admin).value = 0;
+    }
+
+    fun read(addr: address): u64 acquires Counter {
+        borrow_global<Counter>(addr).value
+    }
+
+    fun remove<T: store>(addr: address): T acquires Box {
+        let Box { content } = move_from<Box<T>>(addr);
+        content
+    }
+
+    fun correct(addr: address) acquires Counter {
+        increment(addr)
+    }
+    spec correct {
+        modifies global<Counter>(addr);
+    }
+
+    fun too_large(addr: address): u64 acquires Counter {
+        read(addr)
+    }
+    spec too_large {
+        modifies global<Counter>(addr);
+    }
+
+    fun too_small(s: &signer, addr: address) acquires Counter {
+        increment(addr);
+        move_to(s, Config { admin: addr });
+    }
+    spec too_small {
+        modifies global<Counter>(addr);
+    }
+}
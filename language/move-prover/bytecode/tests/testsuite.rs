@@ -19,7 +19,9 @@ use move_stackless_bytecode::{
     global_invariant_analysis::GlobalInvariantAnalysisProcessor,
     global_invariant_instrumentation::GlobalInvariantInstrumentationProcessor,
     livevar_analysis::LiveVarAnalysisProcessor,
+    loop_invariant_inference::LoopInvariantInferenceProcessor,
     memory_instrumentation::MemoryInstrumentationProcessor,
+    modifies_inference::ModifiesInferenceProcessor,
     mono_analysis::MonoAnalysisProcessor,
     mut_ref_instrumentation::MutRefInstrumenter,
     options::ProverOptions,
@@ -121,6 +123,20 @@ fn get_tested_transformation_pipeline(
             pipeline.add_processor(VerificationAnalysisProcessor::new());
             Ok(Some(pipeline))
         }
+        "loop_invariant_inference" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(EliminateImmRefsProcessor::new());
+            pipeline.add_processor(MutRefInstrumenter::new());
+            pipeline.add_processor(ReachingDefProcessor::new());
+            pipeline.add_processor(LiveVarAnalysisProcessor::new());
+            pipeline.add_processor(BorrowAnalysisProcessor::new());
+            pipeline.add_processor(MemoryInstrumentationProcessor::new());
+            pipeline.add_processor(CleanAndOptimizeProcessor::new());
+            pipeline.add_processor(UsageProcessor::new());
+            pipeline.add_processor(VerificationAnalysisProcessor::new());
+            pipeline.add_processor(LoopInvariantInferenceProcessor::new());
+            Ok(Some(pipeline))
+        }
         "spec_instrumentation" => {
             let mut pipeline = FunctionTargetPipeline::default();
             pipeline.add_processor(EliminateImmRefsProcessor::new());
@@ -188,6 +204,12 @@ fn get_tested_transformation_pipeline(
             pipeline.add_processor(Box::new(ReadWriteSetProcessor {}));
             Ok(Some(pipeline))
         }
+        "modifies_inference" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(ReadWriteSetProcessor::new());
+            pipeline.add_processor(ModifiesInferenceProcessor::new());
+            Ok(Some(pipeline))
+        }
         "mono_analysis" => {
             let mut pipeline = FunctionTargetPipeline::default();
             pipeline.add_processor(UsageProcessor::new());
@@ -120,12 +120,13 @@ impl<'env> Evaluator<'env> {
     // entry points
     //
 
-    /// Check whether an assert expression holds
-    pub fn check_assert(&self, exp: &Exp) {
+    /// Check whether an assert expression holds. `vc_info` describes the property the assertion
+    /// was instrumented for (e.g., a post-condition), if any.
+    pub fn check_assert(&self, exp: &Exp, vc_info: Option<&str>) {
         match self.evaluate(exp) {
             Ok(val) => {
                 if !val.into_bool() {
-                    self.record_checking_failure(exp);
+                    self.record_checking_failure(exp, vc_info);
                 }
             }
             Err(err) => {
@@ -167,7 +168,7 @@ impl<'env> Evaluator<'env> {
             Some((local_idx, TypedValue::fuse_base(local_ty, local_val)))
         } else {
             // for all other cases, treat with as an assertion
-            self.check_assert(exp);
+            self.check_assert(exp, None);
             None
         }
     }
@@ -1273,10 +1274,20 @@ impl<'env> Evaluator<'env> {
         env.error(&loc, "failed to evaluate expression");
     }
 
-    fn record_checking_failure(&self, exp: &Exp) {
+    fn record_checking_failure(&self, exp: &Exp, vc_info: Option<&str>) {
         let env = self.target.global_env();
         let loc = env.get_node_loc(exp.node_id());
-        env.error(&loc, "property does not hold");
+        match vc_info {
+            None => env.error(&loc, "property does not hold"),
+            Some(info) => env.error(
+                &loc,
+                &format!(
+                    "{} in function `{}`",
+                    info,
+                    self.target.func_env.get_name_string()
+                ),
+            ),
+        }
     }
 
     fn eval_failure_code() -> BigInt {
@@ -25,8 +25,8 @@ use move_stackless_bytecode::{
     function_target::FunctionTarget,
     function_target_pipeline::FunctionTargetsHolder,
     stackless_bytecode::{
-        AbortAction, AssignKind, BorrowEdge, BorrowNode, Bytecode, Constant, HavocKind, Label,
-        Operation, PropKind,
+        AbortAction, AssignKind, AttrId, BorrowEdge, BorrowNode, Bytecode, Constant, HavocKind,
+        Label, Operation, PropKind,
     },
 };
 
@@ -357,9 +357,9 @@ impl<'env> FunctionContext<'env> {
                 global_state,
                 eval_state,
             ),
-            Bytecode::Prop(_, PropKind::Assert, exp) => {
+            Bytecode::Prop(attr_id, PropKind::Assert, exp) => {
                 if !self.skip_specs {
-                    self.handle_prop_assert(exp, eval_state, local_state, global_state)
+                    self.handle_prop_assert(*attr_id, exp, eval_state, local_state, global_state)
                 }
             }
             Bytecode::Prop(_, PropKind::Assume, exp) => {
@@ -1822,6 +1822,7 @@ impl<'env> FunctionContext<'env> {
 
     fn handle_prop_assert(
         &self,
+        attr_id: AttrId,
         exp: &Exp,
         eval_state: &EvalState,
         local_state: &LocalState,
@@ -1837,7 +1838,7 @@ impl<'env> FunctionContext<'env> {
             local_state,
             global_state,
         );
-        evaluator.check_assert(exp);
+        evaluator.check_assert(exp, self.target.get_vc_info(attr_id).map(String::as_str));
     }
 
     fn handle_prop_assume(
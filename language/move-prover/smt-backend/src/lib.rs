@@ -0,0 +1,182 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A backend for the Move Prover which translates the stackless bytecode of verified functions
+//! directly into SMT-LIB2 and checks it with a locally installed Z3 or CVC5, without going
+//! through Boogie.
+//!
+//! The backend supports a subset of Move: functions over integers, booleans, addresses and
+//! structs, with arithmetic, opaque calls, and specifications without quantifiers or global
+//! storage. Functions outside of this subset are reported as errors, unless skipping them is
+//! allowed by the options, in which case they are skipped with a warning.
+
+#![forbid(unsafe_code)]
+
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use log::info;
+
+use move_model::model::GlobalEnv;
+use move_stackless_bytecode::function_target_pipeline::{
+    FunctionTargetsHolder, FunctionVariant, VerificationFlavor,
+};
+
+use crate::{
+    options::SmtOptions,
+    solver::{SatResult, SolverProcess, PRELUDE},
+    translator::{translate_function, SmtFunction},
+};
+
+pub mod options;
+pub mod solver;
+pub mod translator;
+
+/// The number of functions verified, failed and skipped by a run of the backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerificationSummary {
+    pub verified: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+/// Verifies all functions in the targets which have a regular verification variant, reporting
+/// failures as diagnostics in the environment.
+pub fn verify(
+    env: &GlobalEnv,
+    targets: &FunctionTargetsHolder,
+    options: &SmtOptions,
+) -> anyhow::Result<VerificationSummary> {
+    let mut scripts = PRELUDE.to_string();
+    let mut solver = None;
+    let mut summary = VerificationSummary::default();
+    for module_env in env.get_modules() {
+        for fun_env in module_env.get_functions() {
+            if fun_env.is_native_or_intrinsic() {
+                continue;
+            }
+            for (variant, target) in targets.get_targets(&fun_env) {
+                if variant != FunctionVariant::Verification(VerificationFlavor::Regular) {
+                    continue;
+                }
+                let fun = match translate_function(env, &target) {
+                    Ok(fun) => fun,
+                    Err(reason) => {
+                        let message = format!(
+                            "function `{}` is not verified by the SMT backend because it {}",
+                            fun_env.get_full_name_str(),
+                            reason
+                        );
+                        if options.allow_skipped {
+                            env.diag(Severity::Warning, &fun_env.get_loc(), &message);
+                        } else {
+                            env.add_diag(
+                                Diagnostic::new(Severity::Error)
+                                    .with_message(message)
+                                    .with_labels(vec![Label::primary(
+                                        fun_env.get_loc().file_id(),
+                                        fun_env.get_loc().span(),
+                                    )])
+                                    .with_notes(vec![
+                                        "use `--smt-allow-skipped` to verify the other functions \
+                                         only"
+                                            .to_string(),
+                                    ]),
+                            );
+                        }
+                        summary.skipped += 1;
+                        continue;
+                    }
+                };
+                info!(
+                    "checking {} verification conditions of `{}`",
+                    fun.conditions.len(),
+                    fun_env.get_full_name_str()
+                );
+                // Scope each function like `check_function` does, as the declarations of different
+                // functions share names
+                scripts.push_str("(push 1)\n");
+                scripts.push_str(&fun.script);
+                for i in 0..fun.conditions.len() {
+                    scripts.push_str(&fun.query(i));
+                    scripts.push_str("(pop 1)\n");
+                }
+                scripts.push_str("(pop 1)\n");
+                if options.generate_only {
+                    continue;
+                }
+                if fun.conditions.is_empty() {
+                    summary.verified += 1;
+                    continue;
+                }
+                if solver.is_none() {
+                    solver = Some(SolverProcess::start(options)?);
+                }
+                if check_function(env, solver.as_mut().unwrap(), &fun, options)? {
+                    summary.verified += 1;
+                } else {
+                    summary.failed += 1;
+                }
+            }
+        }
+    }
+    if let Some(file) = &options.script_file {
+        std::fs::write(file, scripts)?;
+    }
+    if !options.generate_only {
+        info!(
+            "SMT backend: {} functions verified, {} failed, {} skipped",
+            summary.verified, summary.failed, summary.skipped
+        );
+    }
+    Ok(summary)
+}
+
+/// Checks the verification conditions of a function and returns whether all of them hold.
+fn check_function(
+    env: &GlobalEnv,
+    solver: &mut SolverProcess,
+    fun: &SmtFunction,
+    options: &SmtOptions,
+) -> anyhow::Result<bool> {
+    let mut verified = true;
+    solver.send("(push 1)\n")?;
+    solver.send(&fun.script)?;
+    for (i, vc) in fun.conditions.iter().enumerate() {
+        let (message, notes) = match solver.check(&fun.query(i))? {
+            SatResult::Unsat => (None, vec![]),
+            SatResult::Sat => {
+                let names = fun
+                    .parameters
+                    .iter()
+                    .map(|(_, smt_name)| smt_name.clone())
+                    .collect::<Vec<_>>();
+                let values = solver.get_values(&names)?;
+                let notes = fun
+                    .parameters
+                    .iter()
+                    .zip(values)
+                    .map(|((name, _), (_, value))| format!("{} = {}", name, value))
+                    .collect();
+                (Some(vc.message.clone()), notes)
+            }
+            SatResult::Unknown => (
+                Some(format!(
+                    "verification out of resources/timeout (global timeout set to {}s)",
+                    options.vc_timeout
+                )),
+                vec![],
+            ),
+        };
+        solver.send("(pop 1)\n")?;
+        if let Some(message) = message {
+            verified = false;
+            env.add_diag(
+                Diagnostic::new(Severity::Error)
+                    .with_message(message)
+                    .with_labels(vec![Label::primary(vc.loc.file_id(), vc.loc.span())])
+                    .with_notes(notes),
+            );
+        }
+    }
+    solver.send("(pop 1)\n")?;
+    Ok(verified)
+}
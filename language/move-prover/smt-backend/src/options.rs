@@ -0,0 +1,75 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Options for the SMT-LIB backend.
+#[derive(Debug, Clone)]
+pub struct SmtOptions {
+    /// Path to the Z3 executable.
+    pub z3_exe: String,
+    /// Whether to use CVC5 instead of Z3.
+    pub use_cvc5: bool,
+    /// Path to the CVC5 executable.
+    pub cvc5_exe: String,
+    /// Random seed passed to the solver.
+    pub random_seed: usize,
+    /// Timeout in seconds for each verification condition.
+    pub vc_timeout: usize,
+    /// If set, the generated SMT-LIB scripts are written to this file.
+    pub script_file: Option<String>,
+    /// Whether to only generate the scripts without running the solver.
+    pub generate_only: bool,
+    /// Whether functions which are not supported by the backend are skipped with a warning
+    /// instead of being reported as errors.
+    pub allow_skipped: bool,
+}
+
+impl Default for SmtOptions {
+    fn default() -> Self {
+        Self {
+            z3_exe: String::new(),
+            use_cvc5: false,
+            cvc5_exe: String::new(),
+            random_seed: 1,
+            vc_timeout: 40,
+            script_file: None,
+            generate_only: false,
+            allow_skipped: false,
+        }
+    }
+}
+
+impl SmtOptions {
+    /// Returns the solver executable and the arguments to run it in interactive mode.
+    pub fn solver_command(&self) -> anyhow::Result<(String, Vec<String>)> {
+        let timeout_ms = self.vc_timeout * 1000;
+        if self.use_cvc5 {
+            if self.cvc5_exe.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "No CVC5 executable set. Please set CVC5_EXE"
+                ));
+            }
+            Ok((
+                self.cvc5_exe.clone(),
+                vec![
+                    "--lang=smt2".to_string(),
+                    "--incremental".to_string(),
+                    format!("--tlimit-per={}", timeout_ms),
+                    format!("--seed={}", self.random_seed),
+                ],
+            ))
+        } else {
+            if self.z3_exe.is_empty() {
+                return Err(anyhow::anyhow!("No Z3 executable set. Please set Z3_EXE"));
+            }
+            Ok((
+                self.z3_exe.clone(),
+                vec![
+                    "-in".to_string(),
+                    "-smt2".to_string(),
+                    format!("-t:{}", timeout_ms),
+                    format!("smt.random_seed={}", self.random_seed),
+                ],
+            ))
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interaction with an SMT solver process over the SMT-LIB2 text interface.
+
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use anyhow::anyhow;
+
+use crate::options::SmtOptions;
+
+/// The result of a `check-sat` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SatResult {
+    Sat,
+    Unsat,
+    Unknown,
+}
+
+/// A running solver in incremental mode.
+pub struct SolverProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+/// The options and logic every script starts with.
+pub const PRELUDE: &str = "(set-option :print-success false)\n\
+                           (set-option :produce-models true)\n\
+                           (set-logic ALL)\n";
+
+impl SolverProcess {
+    pub fn start(options: &SmtOptions) -> anyhow::Result<Self> {
+        let (exe, args) = options.solver_command()?;
+        let mut child = Command::new(&exe)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| anyhow!("cannot execute solver `{}`: {}", exe, err))?;
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+        let mut solver = Self {
+            child,
+            stdin,
+            stdout,
+        };
+        solver.send(PRELUDE)?;
+        Ok(solver)
+    }
+
+    /// Sends commands to the solver.
+    pub fn send(&mut self, commands: &str) -> anyhow::Result<()> {
+        self.stdin.write_all(commands.as_bytes())?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Reads the next response, which is either an atom or a balanced s-expression.
+    pub fn read_response(&mut self) -> anyhow::Result<String> {
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(anyhow!("solver terminated unexpectedly"));
+            }
+            response.push_str(&line);
+            let trimmed = response.trim();
+            if !trimmed.is_empty() && parens_balanced(trimmed) {
+                break;
+            }
+        }
+        let response = response.trim().to_string();
+        if response.starts_with("(error") {
+            Err(anyhow!("solver reported {}", response))
+        } else {
+            Ok(response)
+        }
+    }
+
+    /// Checks the given query, which must end with `(check-sat)`.
+    pub fn check(&mut self, query: &str) -> anyhow::Result<SatResult> {
+        self.send(query)?;
+        match self.read_response()?.as_str() {
+            "sat" => Ok(SatResult::Sat),
+            "unsat" => Ok(SatResult::Unsat),
+            "unknown" | "timeout" => Ok(SatResult::Unknown),
+            other => Err(anyhow!("unexpected solver response `{}`", other)),
+        }
+    }
+
+    /// Returns the values of the given constants in the current model.
+    pub fn get_values(&mut self, names: &[String]) -> anyhow::Result<Vec<(String, SExp)>> {
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+        self.send(&format!("(get-value ({}))\n", names.join(" ")))?;
+        let response = self.read_response()?;
+        let malformed = || anyhow!("malformed solver response `{}`", response);
+        match SExp::parse(&response).ok_or_else(malformed)? {
+            SExp::List(pairs) => pairs
+                .into_iter()
+                .map(|pair| match pair {
+                    SExp::List(mut elems) if elems.len() == 2 => {
+                        let value = elems.pop().unwrap();
+                        Ok((elems.pop().unwrap().to_string(), value))
+                    }
+                    _ => Err(malformed()),
+                })
+                .collect(),
+            SExp::Atom(_) => Err(malformed()),
+        }
+    }
+}
+
+impl Drop for SolverProcess {
+    fn drop(&mut self) {
+        let _ = self.send("(exit)\n");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn parens_balanced(text: &str) -> bool {
+    let mut depth = 0i64;
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '|' | '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0 && !quoted
+}
+
+/// An s-expression as returned by the solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SExp {
+    Atom(String),
+    List(Vec<SExp>),
+}
+
+impl SExp {
+    pub fn parse(text: &str) -> Option<SExp> {
+        let mut tokens = vec![];
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '(' | ')' => tokens.push(c.to_string()),
+                c if c.is_whitespace() => {}
+                '|' => {
+                    let mut atom = String::from('|');
+                    for c in chars.by_ref() {
+                        atom.push(c);
+                        if c == '|' {
+                            break;
+                        }
+                    }
+                    tokens.push(atom);
+                }
+                _ => {
+                    let mut atom = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c == '(' || c == ')' || c.is_whitespace() {
+                            break;
+                        }
+                        atom.push(c);
+                        chars.next();
+                    }
+                    tokens.push(atom);
+                }
+            }
+        }
+        let mut pos = 0;
+        let result = Self::parse_tokens(&tokens, &mut pos)?;
+        if pos == tokens.len() {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    fn parse_tokens(tokens: &[String], pos: &mut usize) -> Option<SExp> {
+        let token = tokens.get(*pos)?;
+        *pos += 1;
+        match token.as_str() {
+            "(" => {
+                let mut elems = vec![];
+                while tokens.get(*pos)? != ")" {
+                    elems.push(Self::parse_tokens(tokens, pos)?);
+                }
+                *pos += 1;
+                Some(SExp::List(elems))
+            }
+            ")" => None,
+            _ => Some(SExp::Atom(token.clone())),
+        }
+    }
+}
+
+impl fmt::Display for SExp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SExp::Atom(atom) => write!(f, "{}", atom.trim_matches('|')),
+            // Negative numbers are printed as `(- n)` by solvers.
+            SExp::List(elems) => match elems.as_slice() {
+                [SExp::Atom(minus), SExp::Atom(n)] if minus == "-" => write!(f, "-{}", n),
+                _ => {
+                    write!(f, "(")?;
+                    for (i, elem) in elems.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, " ")?;
+                        }
+                        write!(f, "{}", elem)?;
+                    }
+                    write!(f, ")")
+                }
+            },
+        }
+    }
+}
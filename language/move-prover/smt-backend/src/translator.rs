@@ -0,0 +1,859 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Translation of a function target into an SMT-LIB2 script.
+//!
+//! The code of the function must be acyclic, which is the case for the verification variant
+//! after loop analysis has replaced loops by havocs. The blocks of the function are put into
+//! SSA form in a forward pass, and the weakest precondition of each block is defined in a
+//! backward pass. Every assertion is checked by its own query, which selects the assertion via
+//! the constant `$sel`; all other assertions act as assumptions in that query.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+
+use move_model::{
+    ast::{Exp, ExpData, Operation as SpecOp, Value},
+    model::{GlobalEnv, Loc, NodeId, QualifiedInstId, StructId},
+    ty::{PrimitiveType, Type},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget,
+    stackless_bytecode::{
+        AbortAction, AttrId, Bytecode, Constant, HavocKind, Label, Operation, PropKind,
+    },
+};
+
+/// The abort code used for arithmetic errors, as in the Boogie backend.
+const EXECUTION_FAILURE_CODE: &str = "(- 1)";
+
+/// An assertion of a translated function, checked by its own query.
+#[derive(Debug)]
+pub struct VerificationCondition {
+    pub attr_id: AttrId,
+    pub loc: Loc,
+    pub message: String,
+}
+
+/// The SMT-LIB translation of a function.
+#[derive(Debug)]
+pub struct SmtFunction {
+    /// Declarations and definitions shared by all queries of the function.
+    pub script: String,
+    /// The verification conditions of the function.
+    pub conditions: Vec<VerificationCondition>,
+    /// The parameters of the function which are used in the script, together with the SMT
+    /// constant representing their value on entry.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl SmtFunction {
+    /// Returns the commands checking the i-th verification condition. The query opens a scope
+    /// which must be closed with `(pop 1)` after the result has been processed.
+    pub fn query(&self, i: usize) -> String {
+        format!(
+            "(push 1)\n(assert (= $sel {}))\n(assert (not {}))\n(check-sat)\n",
+            i,
+            block_name(0)
+        )
+    }
+}
+
+/// Translates the given function target. Returns the reason if the function uses features
+/// not supported by this backend; the reason reads as a continuation of "the function ...".
+pub fn translate_function(env: &GlobalEnv, target: &FunctionTarget) -> Result<SmtFunction, String> {
+    if !target.func_env.get_type_parameters().is_empty() {
+        return Err("is generic".to_string());
+    }
+    let code = target.get_bytecode();
+    let blocks = split_blocks(code);
+    let label_blocks: BTreeMap<Label, usize> = blocks
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (start, _))| match &code[*start] {
+            Bytecode::Label(_, label) => Some((*label, idx)),
+            _ => None,
+        })
+        .collect();
+    let successors = blocks
+        .iter()
+        .enumerate()
+        .map(|(idx, range)| block_successors(code, idx, *range, &label_blocks, blocks.len()))
+        .collect_vec();
+    let order = topological_order(&successors).ok_or_else(|| "contains loops".to_string())?;
+
+    let mut translator = Translator {
+        env,
+        target,
+        label_blocks,
+        datatypes: vec![],
+        declared_datatypes: BTreeSet::new(),
+        consts: vec![],
+        declared: BTreeSet::new(),
+        counters: vec![0; target.get_local_count()],
+        current: vec![0; target.get_local_count()],
+        edges: vec![],
+        conditions: vec![],
+    };
+    let mut block_code = BTreeMap::new();
+    for &idx in &order {
+        if idx != 0 {
+            translator.join(idx)?;
+        }
+        let (start, end) = blocks[idx];
+        let next = if idx + 1 < blocks.len() {
+            Some(idx + 1)
+        } else {
+            None
+        };
+        block_code.insert(idx, translator.translate_block(&code[start..end], next)?);
+    }
+
+    let mut defs = vec![];
+    for &idx in order.iter().rev() {
+        let (stmts, exit) = &block_code[&idx];
+        defs.push(format!(
+            "(define-fun {} () Bool {})",
+            block_name(idx),
+            translator.weakest_precondition(stmts, exit)
+        ));
+    }
+    let mut parameters = vec![];
+    for idx in 0..target.get_parameter_count() {
+        if translator.declared.contains(&(idx, 0)) {
+            parameters.push((
+                target
+                    .get_local_name(idx)
+                    .display(env.symbol_pool())
+                    .to_string(),
+                incarnation_name(idx, 0),
+            ));
+        }
+    }
+    let mut script = format!("; function {}\n", target.func_env.get_full_name_str());
+    for line in translator
+        .datatypes
+        .iter()
+        .chain(std::iter::once(&"(declare-const $sel Int)".to_string()))
+        .chain(translator.consts.iter())
+        .chain(defs.iter())
+    {
+        script.push_str(line);
+        script.push('\n');
+    }
+    Ok(SmtFunction {
+        script,
+        conditions: translator.conditions,
+        parameters,
+    })
+}
+
+/// A statement of a block after translation.
+enum Stmt {
+    Assume(String),
+    /// An assertion with the index of its verification condition.
+    Assert(usize, String),
+    /// Continues with the edge if the condition holds, otherwise with the next statement.
+    Split(String, usize),
+}
+
+/// How a block is left after translation.
+enum Exit {
+    Goto(usize),
+    Branch(String, usize, usize),
+    Return,
+}
+
+/// A control flow edge, carrying the incarnations of the temporaries at the source, and
+/// assumptions which hold when the edge is taken.
+struct Edge {
+    target: usize,
+    incarnations: Vec<usize>,
+    assumptions: Vec<String>,
+}
+
+struct Translator<'env> {
+    env: &'env GlobalEnv,
+    target: &'env FunctionTarget<'env>,
+    label_blocks: BTreeMap<Label, usize>,
+    datatypes: Vec<String>,
+    declared_datatypes: BTreeSet<String>,
+    consts: Vec<String>,
+    declared: BTreeSet<(usize, usize)>,
+    counters: Vec<usize>,
+    current: Vec<usize>,
+    edges: Vec<Edge>,
+    conditions: Vec<VerificationCondition>,
+}
+
+fn block_name(idx: usize) -> String {
+    format!("|ok {}|", idx)
+}
+
+fn incarnation_name(temp: usize, incarnation: usize) -> String {
+    format!("t{}_{}", temp, incarnation)
+}
+
+fn is_terminator(bc: &Bytecode) -> bool {
+    matches!(
+        bc,
+        Bytecode::Jump(..) | Bytecode::Branch(..) | Bytecode::Ret(..) | Bytecode::Abort(..)
+    )
+}
+
+/// Splits the code into blocks, returned as ranges of code offsets.
+fn split_blocks(code: &[Bytecode]) -> Vec<(usize, usize)> {
+    let mut starts = BTreeSet::new();
+    starts.insert(0);
+    for (offset, bc) in code.iter().enumerate() {
+        if matches!(bc, Bytecode::Label(..)) {
+            starts.insert(offset);
+        } else if is_terminator(bc) && offset + 1 < code.len() {
+            starts.insert(offset + 1);
+        }
+    }
+    let starts = starts.into_iter().collect_vec();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(idx, start)| (*start, starts.get(idx + 1).cloned().unwrap_or(code.len())))
+        .collect()
+}
+
+fn block_successors(
+    code: &[Bytecode],
+    idx: usize,
+    (start, end): (usize, usize),
+    label_blocks: &BTreeMap<Label, usize>,
+    block_count: usize,
+) -> Vec<usize> {
+    let mut successors = vec![];
+    for bc in &code[start..end] {
+        match bc {
+            Bytecode::Call(_, _, _, _, Some(AbortAction(label, _))) | Bytecode::Jump(_, label) => {
+                successors.push(label_blocks[label])
+            }
+            Bytecode::Branch(_, then_label, else_label, _) => {
+                successors.push(label_blocks[then_label]);
+                successors.push(label_blocks[else_label]);
+            }
+            _ => {}
+        }
+    }
+    if end > start && !is_terminator(&code[end - 1]) && idx + 1 < block_count {
+        successors.push(idx + 1);
+    }
+    successors
+}
+
+/// Returns the blocks reachable from the entry in topological order, or None if the control
+/// flow graph has cycles.
+fn topological_order(successors: &[Vec<usize>]) -> Option<Vec<usize>> {
+    // 0: not visited, 1: on the current path, 2: done
+    fn visit(
+        block: usize,
+        successors: &[Vec<usize>],
+        state: &mut [u8],
+        post_order: &mut Vec<usize>,
+    ) -> bool {
+        state[block] = 1;
+        for &succ in &successors[block] {
+            let visited = state[succ];
+            if visited == 1 || (visited == 0 && !visit(succ, successors, state, post_order)) {
+                return false;
+            }
+        }
+        state[block] = 2;
+        post_order.push(block);
+        true
+    }
+    let mut state = vec![0; successors.len()];
+    let mut post_order = vec![];
+    if !successors.is_empty() && !visit(0, successors, &mut state, &mut post_order) {
+        return None;
+    }
+    post_order.reverse();
+    Some(post_order)
+}
+
+fn conjunction(conds: Vec<String>) -> String {
+    let conds = conds.into_iter().filter(|c| c != "true").collect_vec();
+    match conds.len() {
+        0 => "true".to_string(),
+        1 => conds.into_iter().next().unwrap(),
+        _ => format!("(and {})", conds.join(" ")),
+    }
+}
+
+fn max_value(ty: &Type) -> Option<String> {
+    match ty.skip_reference() {
+        Type::Primitive(PrimitiveType::U8) => Some(u8::MAX.to_string()),
+        Type::Primitive(PrimitiveType::U64) => Some(u64::MAX.to_string()),
+        Type::Primitive(PrimitiveType::U128) => Some(u128::MAX.to_string()),
+        _ => None,
+    }
+}
+
+impl<'env> Translator<'env> {
+    // ---------------------------------------------------------------------------------------
+    // Sorts and temporaries
+
+    fn sort(&mut self, ty: &Type) -> Result<String, String> {
+        use PrimitiveType::*;
+        match ty {
+            Type::Primitive(Bool) => Ok("Bool".to_string()),
+            Type::Primitive(U8 | U64 | U128 | Num | Address | Signer) => Ok("Int".to_string()),
+            Type::Reference(false, ty) => self.sort(ty),
+            Type::Reference(true, _) => Err("uses mutable references".to_string()),
+            Type::Struct(mid, sid, inst) => self.datatype(&mid.qualified_inst(*sid, inst.clone())),
+            Type::Vector(_) => Err("uses vectors".to_string()),
+            Type::TypeParameter(_) => Err("is generic".to_string()),
+            _ => Err(format!(
+                "uses values of type `{}`",
+                ty.display(&self.env.get_type_display_ctx())
+            )),
+        }
+    }
+
+    fn struct_name(&self, qid: &QualifiedInstId<StructId>) -> String {
+        let struct_env = self.env.get_struct(qid.to_qualified_id());
+        let inst = if qid.inst.is_empty() {
+            String::new()
+        } else {
+            let ctx = self.env.get_type_display_ctx();
+            format!(
+                "<{}>",
+                qid.inst.iter().map(|ty| ty.display(&ctx)).join(", ")
+            )
+        };
+        format!(
+            "{}::{}{}",
+            struct_env.module_env.get_full_name_str(),
+            struct_env.get_name().display(self.env.symbol_pool()),
+            inst
+        )
+    }
+
+    /// Declares the datatype for the struct if needed, and returns its sort.
+    fn datatype(&mut self, qid: &QualifiedInstId<StructId>) -> Result<String, String> {
+        let name = self.struct_name(qid);
+        let sort = format!("|{}|", name);
+        if self.declared_datatypes.contains(&name) {
+            return Ok(sort);
+        }
+        let struct_env = self.env.get_struct(qid.to_qualified_id());
+        if struct_env.is_native_or_intrinsic() {
+            return Err(format!("uses the native struct `{}`", name));
+        }
+        let mut fields = vec![];
+        for field_env in struct_env.get_fields() {
+            let field_sort = self.sort(&field_env.get_type().instantiate(&qid.inst))?;
+            fields.push(format!(
+                "(|{}.{}| {})",
+                name,
+                field_env.get_name().display(self.env.symbol_pool()),
+                field_sort
+            ));
+        }
+        self.datatypes.push(format!(
+            "(declare-datatypes (({} 0)) (((|pack {}| {}))))",
+            sort,
+            name,
+            fields.join(" ")
+        ));
+        self.declared_datatypes.insert(name);
+        Ok(sort)
+    }
+
+    fn constructor(&mut self, qid: &QualifiedInstId<StructId>) -> Result<String, String> {
+        self.datatype(qid)?;
+        Ok(format!("|pack {}|", self.struct_name(qid)))
+    }
+
+    fn selector(
+        &mut self,
+        qid: &QualifiedInstId<StructId>,
+        field_name: &str,
+    ) -> Result<String, String> {
+        self.datatype(qid)?;
+        Ok(format!("|{}.{}|", self.struct_name(qid), field_name))
+    }
+
+    fn struct_of_type(&self, ty: &Type) -> Result<QualifiedInstId<StructId>, String> {
+        match ty.skip_reference() {
+            Type::Struct(mid, sid, inst) => Ok(mid.qualified_inst(*sid, inst.clone())),
+            _ => Err("uses unsupported struct operations".to_string()),
+        }
+    }
+
+    /// Returns the SMT constant for an incarnation of a temporary, declaring it if needed.
+    fn var(&mut self, temp: usize, incarnation: usize) -> Result<String, String> {
+        let name = incarnation_name(temp, incarnation);
+        if !self.declared.contains(&(temp, incarnation)) {
+            let sort = self.sort(&self.target.get_local_type(temp).clone())?;
+            self.consts
+                .push(format!("(declare-const {} {})", name, sort));
+            self.declared.insert((temp, incarnation));
+        }
+        Ok(name)
+    }
+
+    fn cur(&mut self, temp: usize) -> Result<String, String> {
+        self.var(temp, self.current[temp])
+    }
+
+    fn fresh(&mut self, temp: usize) -> Result<String, String> {
+        self.counters[temp] += 1;
+        self.current[temp] = self.counters[temp];
+        self.cur(temp)
+    }
+
+    fn assign(&mut self, dest: usize, value: String, stmts: &mut Vec<Stmt>) -> Result<(), String> {
+        let var = self.fresh(dest)?;
+        stmts.push(Stmt::Assume(format!("(= {} {})", var, value)));
+        Ok(())
+    }
+
+    // ---------------------------------------------------------------------------------------
+    // Control flow
+
+    fn edge(&mut self, target: usize, assumptions: Vec<String>) -> usize {
+        self.edges.push(Edge {
+            target,
+            incarnations: self.current.clone(),
+            assumptions,
+        });
+        self.edges.len() - 1
+    }
+
+    fn label_edge(&mut self, label: &Label) -> usize {
+        let target = self.label_blocks[label];
+        self.edge(target, vec![])
+    }
+
+    /// Sets the current incarnations at the start of a block from its incoming edges. Where
+    /// edges disagree, a fresh incarnation is introduced and equated on each edge.
+    fn join(&mut self, block: usize) -> Result<(), String> {
+        let incoming = (0..self.edges.len())
+            .filter(|e| self.edges[*e].target == block)
+            .collect_vec();
+        self.current = self.edges[incoming[0]].incarnations.clone();
+        for temp in 0..self.current.len() {
+            let incarnations: BTreeSet<usize> = incoming
+                .iter()
+                .map(|e| self.edges[*e].incarnations[temp])
+                .collect();
+            if incarnations.len() > 1 {
+                let var = self.fresh(temp)?;
+                for e in &incoming {
+                    let old = self.var(temp, self.edges[*e].incarnations[temp])?;
+                    self.edges[*e]
+                        .assumptions
+                        .push(format!("(= {} {})", var, old));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a statement for an operation which aborts if the condition holds.
+    fn abort_if(
+        &mut self,
+        cond: String,
+        abort_action: &Option<AbortAction>,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<(), String> {
+        match abort_action {
+            Some(AbortAction(label, code)) => {
+                let saved = self.current.clone();
+                let code = self.fresh(*code)?;
+                let target = self.label_blocks[label];
+                let edge = self.edge(
+                    target,
+                    vec![format!("(= {} {})", code, EXECUTION_FAILURE_CODE)],
+                );
+                self.current = saved;
+                stmts.push(Stmt::Split(cond, edge));
+            }
+            None => stmts.push(Stmt::Assume(format!("(not {})", cond))),
+        }
+        Ok(())
+    }
+
+    fn translate_block(
+        &mut self,
+        code: &[Bytecode],
+        next: Option<usize>,
+    ) -> Result<(Vec<Stmt>, Exit), String> {
+        use Bytecode::*;
+        let mut stmts = vec![];
+        for bc in code {
+            match bc {
+                Assign(_, dest, src, _) => {
+                    let value = self.cur(*src)?;
+                    self.assign(*dest, value, &mut stmts)?;
+                }
+                Load(_, dest, constant) => {
+                    let value = match constant {
+                        Constant::Bool(b) => b.to_string(),
+                        Constant::U8(n) => n.to_string(),
+                        Constant::U64(n) => n.to_string(),
+                        Constant::U128(n) => n.to_string(),
+                        Constant::U256(n) => n.to_string(),
+                        Constant::Address(a) => a.to_string(),
+                        Constant::ByteArray(_) => return Err("uses vectors".to_string()),
+                    };
+                    self.assign(*dest, value, &mut stmts)?;
+                }
+                Call(_, dests, oper, srcs, abort_action) => {
+                    self.translate_call(dests, oper, srcs, abort_action, &mut stmts)?
+                }
+                Prop(attr_id, kind, exp) => {
+                    let exp = self.exp(exp)?;
+                    match kind {
+                        PropKind::Assume => stmts.push(Stmt::Assume(exp)),
+                        PropKind::Assert => {
+                            self.conditions.push(VerificationCondition {
+                                attr_id: *attr_id,
+                                loc: self.target.get_bytecode_loc(*attr_id),
+                                message: self
+                                    .target
+                                    .get_vc_info(*attr_id)
+                                    .cloned()
+                                    .unwrap_or_else(|| "assertion does not hold".to_string()),
+                            });
+                            stmts.push(Stmt::Assert(self.conditions.len() - 1, exp));
+                        }
+                        PropKind::Modifies => {
+                            return Err("uses modifies specifications".to_string())
+                        }
+                    }
+                }
+                SaveMem(..) | SaveSpecVar(..) => return Err("accesses global storage".to_string()),
+                Jump(_, label) => return Ok((stmts, Exit::Goto(self.label_edge(label)))),
+                Branch(_, then_label, else_label, cond) => {
+                    let cond = self.cur(*cond)?;
+                    let then_edge = self.label_edge(then_label);
+                    let else_edge = self.label_edge(else_label);
+                    return Ok((stmts, Exit::Branch(cond, then_edge, else_edge)));
+                }
+                Ret(..) | Abort(..) => return Ok((stmts, Exit::Return)),
+                Label(..) | Nop(..) => {}
+            }
+        }
+        let exit = match next {
+            Some(block) => Exit::Goto(self.edge(block, vec![])),
+            None => Exit::Return,
+        };
+        Ok((stmts, exit))
+    }
+
+    fn translate_call(
+        &mut self,
+        dests: &[usize],
+        oper: &Operation,
+        srcs: &[usize],
+        abort_action: &Option<AbortAction>,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<(), String> {
+        use Operation::*;
+        match oper {
+            OpaqueCallBegin(..) | OpaqueCallEnd(..) | TraceLocal(_) | TraceReturn(_)
+            | TraceAbort | TraceExp(..) | TraceGlobalMem(_) | Destroy => {}
+            Havoc(HavocKind::Value) => {
+                self.fresh(srcs[0])?;
+            }
+            Stop => stmts.push(Stmt::Assume("false".to_string())),
+            Pack(mid, sid, inst) => {
+                let ctor = self.constructor(&mid.qualified_inst(*sid, inst.clone()))?;
+                let args = srcs
+                    .iter()
+                    .map(|t| self.cur(*t))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                self.assign(dests[0], format!("({} {})", ctor, args.join(" ")), stmts)?;
+            }
+            Unpack(mid, sid, inst) => {
+                let qid = mid.qualified_inst(*sid, inst.clone());
+                let src = self.cur(srcs[0])?;
+                let struct_env = self.env.get_struct(qid.to_qualified_id());
+                for (field_env, dest) in struct_env.get_fields().zip(dests) {
+                    let field_name = field_env
+                        .get_name()
+                        .display(self.env.symbol_pool())
+                        .to_string();
+                    let sel = self.selector(&qid, &field_name)?;
+                    self.assign(*dest, format!("({} {})", sel, src), stmts)?;
+                }
+            }
+            GetField(mid, sid, inst, offset) => {
+                let qid = mid.qualified_inst(*sid, inst.clone());
+                let field_name = self
+                    .env
+                    .get_struct(qid.to_qualified_id())
+                    .get_field_by_offset(*offset)
+                    .get_name()
+                    .display(self.env.symbol_pool())
+                    .to_string();
+                let sel = self.selector(&qid, &field_name)?;
+                let src = self.cur(srcs[0])?;
+                self.assign(dests[0], format!("({} {})", sel, src), stmts)?;
+            }
+            CastU8 | CastU64 | CastU128 => {
+                let max =
+                    max_value(self.target.get_local_type(dests[0])).expect("cast to integer type");
+                let src = self.cur(srcs[0])?;
+                self.abort_if(format!("(> {} {})", src, max), abort_action, stmts)?;
+                self.assign(dests[0], src, stmts)?;
+            }
+            Not => {
+                let src = self.cur(srcs[0])?;
+                self.assign(dests[0], format!("(not {})", src), stmts)?;
+            }
+            And | Or | Eq | Neq | Lt | Gt | Le | Ge => {
+                let (a, b) = (self.cur(srcs[0])?, self.cur(srcs[1])?);
+                let value = match oper {
+                    And => format!("(and {} {})", a, b),
+                    Or => format!("(or {} {})", a, b),
+                    Eq => format!("(= {} {})", a, b),
+                    Neq => format!("(not (= {} {}))", a, b),
+                    Lt => format!("(< {} {})", a, b),
+                    Gt => format!("(> {} {})", a, b),
+                    Le => format!("(<= {} {})", a, b),
+                    _ => format!("(>= {} {})", a, b),
+                };
+                self.assign(dests[0], value, stmts)?;
+            }
+            Add | Sub | Mul | Div | Mod => {
+                let (a, b) = (self.cur(srcs[0])?, self.cur(srcs[1])?);
+                let max = max_value(self.target.get_local_type(dests[0]))
+                    .expect("arithmetic on integer type");
+                let (cond, value) = match oper {
+                    Add => {
+                        let sum = format!("(+ {} {})", a, b);
+                        (format!("(> {} {})", sum, max), sum)
+                    }
+                    Sub => (format!("(< {} {})", a, b), format!("(- {} {})", a, b)),
+                    Mul => {
+                        let product = format!("(* {} {})", a, b);
+                        (format!("(> {} {})", product, max), product)
+                    }
+                    Div => (format!("(= {} 0)", b), format!("(div {} {})", a, b)),
+                    _ => (format!("(= {} 0)", b), format!("(mod {} {})", a, b)),
+                };
+                self.abort_if(cond, abort_action, stmts)?;
+                self.assign(dests[0], value, stmts)?;
+            }
+            Function(mid, fid, _) => {
+                return Err(format!(
+                    "calls the non-opaque function `{}`",
+                    self.env
+                        .get_function(mid.qualified(*fid))
+                        .get_full_name_str()
+                ))
+            }
+            MoveTo(..) | MoveFrom(..) | Exists(..) | BorrowGlobal(..) | GetGlobal(..) => {
+                return Err("accesses global storage".to_string())
+            }
+            BorrowLoc | BorrowField(..) | ReadRef | WriteRef | FreezeRef | Havoc(_)
+            | IsParent(..) | WriteBack(..) | UnpackRef | PackRef | UnpackRefDeep | PackRefDeep => {
+                return Err("uses mutable references".to_string())
+            }
+            BitOr | BitAnd | Xor | Shl | Shr => return Err("uses bitwise operations".to_string()),
+            CastU256 => return Err("uses u256".to_string()),
+            EmitEvent | EventStoreDiverge => return Err("emits events".to_string()),
+        }
+        Ok(())
+    }
+
+    // ---------------------------------------------------------------------------------------
+    // Weakest preconditions
+
+    fn edge_formula(&self, edge: usize) -> String {
+        let edge = &self.edges[edge];
+        let target = block_name(edge.target);
+        if edge.assumptions.is_empty() {
+            target
+        } else {
+            format!("(=> {} {})", conjunction(edge.assumptions.clone()), target)
+        }
+    }
+
+    fn weakest_precondition(&self, stmts: &[Stmt], exit: &Exit) -> String {
+        let mut wp = match exit {
+            Exit::Goto(edge) => self.edge_formula(*edge),
+            Exit::Branch(cond, then_edge, else_edge) => format!(
+                "(and (=> {} {}) (=> (not {}) {}))",
+                cond,
+                self.edge_formula(*then_edge),
+                cond,
+                self.edge_formula(*else_edge)
+            ),
+            Exit::Return => "true".to_string(),
+        };
+        for stmt in stmts.iter().rev() {
+            wp = match stmt {
+                Stmt::Assume(cond) => format!("(=> {} {})", cond, wp),
+                Stmt::Assert(idx, cond) => {
+                    format!("(and (=> (= $sel {}) {}) (=> {} {}))", idx, cond, cond, wp)
+                }
+                Stmt::Split(cond, edge) => format!(
+                    "(and (=> {} {}) (=> (not {}) {}))",
+                    cond,
+                    self.edge_formula(*edge),
+                    cond,
+                    wp
+                ),
+            }
+        }
+        wp
+    }
+
+    // ---------------------------------------------------------------------------------------
+    // Specification expressions
+
+    fn exp(&mut self, exp: &Exp) -> Result<String, String> {
+        match exp.as_ref() {
+            ExpData::Value(_, value) => match value {
+                Value::Bool(b) => Ok(b.to_string()),
+                Value::Address(a) => Ok(a.to_string()),
+                Value::Number(n) => {
+                    let n = n.to_string();
+                    Ok(match n.strip_prefix('-') {
+                        Some(abs) => format!("(- {})", abs),
+                        None => n,
+                    })
+                }
+                Value::ByteArray(_) => Err("uses vectors".to_string()),
+            },
+            ExpData::Temporary(_, temp) => self.cur(*temp),
+            ExpData::LocalVar(_, name) => {
+                Ok(format!("|${}|", name.display(self.env.symbol_pool())))
+            }
+            ExpData::IfElse(_, cond, then_exp, else_exp) => Ok(format!(
+                "(ite {} {} {})",
+                self.exp(cond)?,
+                self.exp(then_exp)?,
+                self.exp(else_exp)?
+            )),
+            ExpData::Block(_, decls, body) => {
+                let mut result = self.exp(body)?;
+                for decl in decls.iter().rev() {
+                    let binding = match &decl.binding {
+                        Some(binding) => self.exp(binding)?,
+                        None => return Err("uses unbound local variables".to_string()),
+                    };
+                    result = format!(
+                        "(let ((|${}| {})) {})",
+                        decl.name.display(self.env.symbol_pool()),
+                        binding,
+                        result
+                    );
+                }
+                Ok(result)
+            }
+            ExpData::Call(id, oper, args) => self.call(*id, oper, args),
+            ExpData::Quant(..) => Err("uses quantifiers".to_string()),
+            _ => Err("uses unsupported specification expressions".to_string()),
+        }
+    }
+
+    fn call(&mut self, id: NodeId, oper: &SpecOp, args: &[Exp]) -> Result<String, String> {
+        use SpecOp::*;
+        let smt_op = match oper {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "div",
+            Mod => "mod",
+            Implies => "=>",
+            Iff | Eq | Identical => "=",
+            Neq => "distinct",
+            And => "and",
+            Or => "or",
+            Not => "not",
+            Lt => "<",
+            Gt => ">",
+            Le => "<=",
+            Ge => ">=",
+            MaxU8 => return Ok(u8::MAX.to_string()),
+            MaxU64 => return Ok(u64::MAX.to_string()),
+            MaxU128 => return Ok(u128::MAX.to_string()),
+            // Execution only continues normally if nothing aborted.
+            AbortFlag => return Ok("false".to_string()),
+            Trace(_) => return self.exp(&args[0]),
+            WellFormed => {
+                let ty = self.env.get_node_type(args[0].node_id());
+                let arg = self.exp(&args[0])?;
+                return self.well_formed(&ty, &arg);
+            }
+            Select(_, _, fid) => {
+                let qid = self.struct_of_type(&self.env.get_node_type(args[0].node_id()))?;
+                let field_name = self
+                    .env
+                    .get_struct(qid.to_qualified_id())
+                    .get_field(*fid)
+                    .get_name()
+                    .display(self.env.symbol_pool())
+                    .to_string();
+                let sel = self.selector(&qid, &field_name)?;
+                return Ok(format!("({} {})", sel, self.exp(&args[0])?));
+            }
+            Pack(..) => {
+                let qid = self.struct_of_type(&self.env.get_node_type(id))?;
+                let ctor = self.constructor(&qid)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.exp(arg))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                return Ok(format!("({} {})", ctor, args.join(" ")));
+            }
+            Function(..) => return Err("uses specification functions".to_string()),
+            Global(_) | Exists(_) | CanModify | ResourceDomain => {
+                return Err("accesses global storage".to_string())
+            }
+            Len | Index | Slice | EmptyVec | SingleVec | UpdateVec | ConcatVec | IndexOfVec
+            | ContainsVec | InRangeVec | RangeVec => return Err("uses vectors".to_string()),
+            _ => return Err(format!("uses the specification operation `{:?}`", oper)),
+        };
+        let args = args
+            .iter()
+            .map(|arg| self.exp(arg))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(format!("({} {})", smt_op, args.join(" ")))
+    }
+
+    /// Returns the constraints for a value of the type to be well-formed.
+    fn well_formed(&mut self, ty: &Type, value: &str) -> Result<String, String> {
+        use PrimitiveType::*;
+        match ty {
+            Type::Primitive(Bool | Num) => Ok("true".to_string()),
+            Type::Primitive(U8 | U64 | U128) => Ok(format!(
+                "(and (<= 0 {}) (<= {} {}))",
+                value,
+                value,
+                max_value(ty).unwrap()
+            )),
+            Type::Primitive(Address | Signer) => Ok(format!("(<= 0 {})", value)),
+            Type::Reference(_, ty) => self.well_formed(ty, value),
+            Type::Struct(mid, sid, inst) => {
+                let qid = mid.qualified_inst(*sid, inst.clone());
+                let struct_env = self.env.get_struct(qid.to_qualified_id());
+                let mut conds = vec![];
+                for field_env in struct_env.get_fields() {
+                    let field_name = field_env
+                        .get_name()
+                        .display(self.env.symbol_pool())
+                        .to_string();
+                    let sel = self.selector(&qid, &field_name)?;
+                    conds.push(self.well_formed(
+                        &field_env.get_type().instantiate(inst),
+                        &format!("({} {})", sel, value),
+                    )?);
+                }
+                Ok(conjunction(conds))
+            }
+            _ => self.sort(ty).map(|_| "true".to_string()),
+        }
+    }
+}
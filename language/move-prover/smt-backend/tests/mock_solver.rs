@@ -0,0 +1,141 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(unix)]
+
+use codespan_reporting::{diagnostic::Severity, term::termcolor::Buffer};
+use move_compiler::shared::PackagePaths;
+use move_model::{model::GlobalEnv, options::ModelBuilderOptions, run_model_builder_with_options};
+use move_prover_smt_backend::{options::SmtOptions, verify, VerificationSummary};
+use move_stackless_bytecode::{
+    function_target_pipeline::FunctionTargetsHolder, options::ProverOptions,
+    pipeline_factory::default_pipeline_with_options,
+};
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+const SOURCE: &str = r#"
+module 0x42::M {
+    public fun add(a: u64, b: u64): u64 {
+        a + b
+    }
+    spec add {
+        ensures result == a + b;
+    }
+}
+"#;
+
+const UNSUPPORTED_SOURCE: &str = r#"
+module 0x42::M {
+    public fun mask(a: u64, b: u64): u64 {
+        a & b
+    }
+    spec mask {
+        ensures result <= a;
+    }
+}
+"#;
+
+/// A solver which finds every query satisfiable and assigns 7 to every constant.
+const MOCK_SOLVER: &str = r#"#!/bin/sh
+while IFS= read -r line; do
+  case "$line" in
+    *check-sat*) echo sat ;;
+    *get-value*)
+      out="("
+      for name in $(echo "$line" | sed 's/(get-value (\(.*\)))/\1/'); do
+        out="$out($name 7)"
+      done
+      echo "$out)" ;;
+  esac
+done
+"#;
+
+/// Builds the model of `source` and runs the default pipeline on its functions.
+fn build_targets(dir: &Path, source: &str) -> (GlobalEnv, FunctionTargetsHolder) {
+    let source_path = dir.join("M.move");
+    fs::write(&source_path, source).unwrap();
+    let env = run_model_builder_with_options(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![source_path.to_string_lossy().to_string()],
+            named_address_map: move_stdlib::move_stdlib_named_addresses(),
+        }],
+        vec![],
+        ModelBuilderOptions::default(),
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+    let prover_options = ProverOptions::default();
+    env.set_extension(prover_options.clone());
+    let mut targets = FunctionTargetsHolder::default();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            targets.add_target(&func_env);
+        }
+    }
+    default_pipeline_with_options(&prover_options).run(&env, &mut targets);
+    (env, targets)
+}
+
+#[test]
+fn counterexamples_are_reported_as_diagnostics() {
+    let dir = std::env::temp_dir().join(format!("smt_backend_mock_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let solver = dir.join("mock_solver.sh");
+    fs::write(&solver, MOCK_SOLVER).unwrap();
+    fs::set_permissions(&solver, fs::Permissions::from_mode(0o755)).unwrap();
+    let (env, targets) = build_targets(&dir, SOURCE);
+
+    let options = SmtOptions {
+        z3_exe: solver.to_string_lossy().to_string(),
+        ..SmtOptions::default()
+    };
+    let summary = verify(&env, &targets, &options).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let mut writer = Buffer::no_color();
+    env.report_diag(&mut writer, Severity::Warning);
+    let out = String::from_utf8_lossy(&writer.into_inner()).to_string();
+    assert_eq!(env.diag_count(Severity::Error), 1, "{}", out);
+    assert!(out.contains("post-condition does not hold"), "{}", out);
+    assert!(out.contains("= a = 7"), "{}", out);
+    assert!(out.contains("= b = 7"), "{}", out);
+    assert_eq!(
+        summary,
+        VerificationSummary {
+            verified: 0,
+            failed: 1,
+            skipped: 0
+        }
+    );
+}
+
+#[test]
+fn unsupported_functions_are_errors_unless_skipping_is_allowed() {
+    let dir = std::env::temp_dir().join(format!("smt_backend_skipped_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (env, targets) = build_targets(&dir, UNSUPPORTED_SOURCE);
+    fs::remove_dir_all(&dir).unwrap();
+    let expected = VerificationSummary {
+        verified: 0,
+        failed: 0,
+        skipped: 1,
+    };
+
+    let options = SmtOptions {
+        generate_only: true,
+        ..SmtOptions::default()
+    };
+    assert_eq!(verify(&env, &targets, &options).unwrap(), expected);
+    assert_eq!(env.diag_count(Severity::Error), 1);
+
+    env.clear_diag();
+    let options = SmtOptions {
+        generate_only: true,
+        allow_skipped: true,
+        ..SmtOptions::default()
+    };
+    assert_eq!(verify(&env, &targets, &options).unwrap(), expected);
+    assert_eq!(env.diag_count(Severity::Error), 0);
+    assert_eq!(env.diag_count(Severity::Warning), 1);
+}
@@ -0,0 +1,116 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_command_line_common::env::read_env_var;
+use move_compiler::shared::PackagePaths;
+use move_model::{options::ModelBuilderOptions, run_model_builder_with_options};
+use move_prover_smt_backend::{options::SmtOptions, verify};
+use move_stackless_bytecode::{
+    function_target_pipeline::FunctionTargetsHolder, options::ProverOptions,
+    pipeline_factory::default_pipeline_with_options,
+};
+use std::{collections::BTreeSet, fs, process::Command};
+
+const SOURCE: &str = r#"
+module 0x42::M {
+    public fun add(a: u64, b: u64): u64 {
+        a + b
+    }
+    spec add {
+        ensures result == a + b;
+    }
+
+    public fun sub(a: u64, b: u64): u64 {
+        a - b
+    }
+    spec sub {
+        aborts_if a < b;
+        ensures result == a - b;
+    }
+}
+"#;
+
+/// Checks that no constant or function of `script` is declared twice in the same scope, which
+/// solvers reject.
+fn check_declarations_are_scoped(script: &str) {
+    let mut scopes = vec![BTreeSet::new()];
+    for line in script.lines() {
+        if line == "(push 1)" {
+            scopes.push(BTreeSet::new());
+        } else if line == "(pop 1)" {
+            scopes.pop();
+            assert!(!scopes.is_empty(), "unbalanced (pop 1) in:\n{}", script);
+        } else if let Some(decl) = line
+            .strip_prefix("(declare-const ")
+            .or_else(|| line.strip_prefix("(define-fun "))
+        {
+            let name = match decl.strip_prefix('|') {
+                Some(quoted) => quoted.split('|').next().unwrap().to_string(),
+                None => decl.split(' ').next().unwrap().to_string(),
+            };
+            assert!(
+                scopes.iter().all(|scope| !scope.contains(&name)),
+                "`{}` declared twice in:\n{}",
+                name,
+                script
+            );
+            scopes.last_mut().unwrap().insert(name);
+        }
+    }
+    assert_eq!(scopes.len(), 1, "unbalanced (push 1) in:\n{}", script);
+}
+
+#[test]
+fn script_file_of_several_functions_is_valid() {
+    let dir = std::env::temp_dir().join(format!("smt_backend_script_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("M.move");
+    fs::write(&source, SOURCE).unwrap();
+    let script_file = dir.join("M.smt2");
+
+    let env = run_model_builder_with_options(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![source.to_string_lossy().to_string()],
+            named_address_map: move_stdlib::move_stdlib_named_addresses(),
+        }],
+        vec![],
+        ModelBuilderOptions::default(),
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+    let prover_options = ProverOptions::default();
+    env.set_extension(prover_options.clone());
+    let mut targets = FunctionTargetsHolder::default();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            targets.add_target(&func_env);
+        }
+    }
+    default_pipeline_with_options(&prover_options).run(&env, &mut targets);
+
+    let options = SmtOptions {
+        script_file: Some(script_file.to_string_lossy().to_string()),
+        generate_only: true,
+        ..SmtOptions::default()
+    };
+    verify(&env, &targets, &options).unwrap();
+    let script = fs::read_to_string(&script_file).unwrap();
+    assert_eq!(script.matches("(declare-const $sel Int)").count(), 2);
+    check_declarations_are_scoped(&script);
+
+    // Run the solver on the written file if one is available
+    let z3_exe = read_env_var("Z3_EXE");
+    if !z3_exe.is_empty() {
+        let output = Command::new(&z3_exe).arg(&script_file).output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(!stdout.contains("error"), "{}", stdout);
+        assert_eq!(
+            stdout.lines().count(),
+            script.matches("(check-sat)").count(),
+            "{}",
+            stdout
+        );
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
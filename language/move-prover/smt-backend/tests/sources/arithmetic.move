@@ -0,0 +1,68 @@
+module 0x42::M {
+    struct Point has copy, drop {
+        x: u64,
+        y: u64,
+    }
+
+    public fun add(a: u64, b: u64): u64 {
+        a + b
+    }
+    spec add {
+        aborts_if a + b > MAX_U64;
+        ensures result == a + b;
+    }
+
+    public fun div_incorrect(a: u64, b: u64): u64 {
+        a / b
+    }
+    spec div_incorrect {
+        aborts_if false;
+        ensures result <= a;
+    }
+
+    public fun max(a: u64, b: u64): u64 {
+        if (a >= b) a else b
+    }
+    spec max {
+        ensures result >= a && result >= b;
+        ensures result == a || result == b;
+    }
+
+    public fun shift(p: Point, d: u64): Point {
+        Point { x: p.x + d, y: p.y }
+    }
+    spec shift {
+        aborts_if p.x + d > MAX_U64;
+        ensures result.x == p.x + d;
+        ensures result == Point { x: p.x + d, y: p.y };
+    }
+
+    public fun narrow(a: u64): u8 {
+        let x = (a as u8);
+        x
+    }
+    spec narrow {
+        aborts_if a > MAX_U8;
+        ensures result == a;
+    }
+
+    public fun sum_to(n: u64): u64 {
+        let i = 0;
+        let s = 0;
+        while ({
+            spec {
+                invariant i <= n;
+                invariant s == i;
+            };
+            i < n
+        }) {
+            i = i + 1;
+            s = s + 1;
+        };
+        s
+    }
+    spec sum_to {
+        aborts_if false;
+        ensures result == n;
+    }
+}
@@ -0,0 +1,52 @@
+module 0x42::N {
+    use Std::Vector;
+
+    struct R has key {
+        value: u64,
+    }
+
+    struct Box<T> has drop {
+        value: T,
+    }
+
+    public fun len(v: vector<u64>): u64 {
+        Vector::length(&v)
+    }
+
+    public fun get(addr: address): u64 acquires R {
+        borrow_global<R>(addr).value
+    }
+    spec get {
+        aborts_if !exists<R>(addr);
+    }
+
+    public fun twice(a: u64): u64 {
+        inner(a) + inner(a)
+    }
+    spec twice {
+        aborts_if 2 * a > MAX_U64;
+    }
+
+    fun inner(a: u64): u64 {
+        a
+    }
+
+    public fun all_small(a: u64): bool {
+        a < 10
+    }
+    spec all_small {
+        ensures result == (forall x: u64 where x <= a: x < 10);
+    }
+
+    public fun unbox<T>(b: Box<T>): T {
+        let Box { value } = b;
+        value
+    }
+
+    public fun mask(a: u64): u64 {
+        a & 1
+    }
+    spec mask {
+        ensures result <= 1;
+    }
+}
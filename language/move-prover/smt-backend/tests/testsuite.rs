@@ -0,0 +1,82 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use codespan_reporting::{diagnostic::Severity, term::termcolor::Buffer};
+use move_command_line_common::testing::EXP_EXT;
+use move_compiler::shared::PackagePaths;
+use move_model::{model::GlobalEnv, options::ModelBuilderOptions, run_model_builder_with_options};
+use move_prover_smt_backend::translator::translate_function;
+use move_prover_test_utils::baseline_test::verify_or_update_baseline;
+use move_stackless_bytecode::{
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant, VerificationFlavor},
+    options::ProverOptions,
+    pipeline_factory::default_pipeline_with_options,
+};
+use std::path::Path;
+
+fn test_runner(path: &Path) -> datatest_stable::Result<()> {
+    let env: GlobalEnv = run_model_builder_with_options(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![path.to_string_lossy().to_string()],
+            named_address_map: move_stdlib::move_stdlib_named_addresses(),
+        }],
+        vec![PackagePaths {
+            name: None,
+            paths: move_stdlib::move_stdlib_files(),
+            named_address_map: move_stdlib::move_stdlib_named_addresses(),
+        }],
+        ModelBuilderOptions::default(),
+    )?;
+    let out = if env.has_errors() {
+        let mut error_writer = Buffer::no_color();
+        env.report_diag(&mut error_writer, Severity::Error);
+        String::from_utf8_lossy(&error_writer.into_inner()).to_string()
+    } else {
+        let options = ProverOptions {
+            stable_test_output: true,
+            ..Default::default()
+        };
+        env.set_extension(options.clone());
+        let mut targets = FunctionTargetsHolder::default();
+        for module_env in env.get_modules() {
+            for func_env in module_env.get_functions() {
+                targets.add_target(&func_env);
+            }
+        }
+        default_pipeline_with_options(&options).run(&env, &mut targets);
+
+        let mut text = String::new();
+        for module_env in env.get_modules() {
+            for func_env in module_env.get_functions() {
+                for (variant, target) in targets.get_targets(&func_env) {
+                    if variant != FunctionVariant::Verification(VerificationFlavor::Regular) {
+                        continue;
+                    }
+                    match translate_function(&env, &target) {
+                        Ok(fun) => {
+                            text += &fun.script;
+                            for (i, vc) in fun.conditions.iter().enumerate() {
+                                text += &format!("; condition {}: {}\n", i, vc.message);
+                            }
+                        }
+                        Err(reason) => {
+                            text += &format!(
+                                "; function {} is not supported: it {}\n",
+                                func_env.get_full_name_str(),
+                                reason
+                            )
+                        }
+                    }
+                    text += "\n";
+                }
+            }
+        }
+        text
+    };
+    let baseline_path = path.with_extension(EXP_EXT);
+    verify_or_update_baseline(baseline_path.as_path(), &out)?;
+    Ok(())
+}
+
+datatest_stable::harness!(test_runner, "tests/sources", r".*\.move");
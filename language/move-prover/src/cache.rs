@@ -0,0 +1,213 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Incremental verification cache.
+//!
+//! For each function which is a target of verification, a hash of everything its verification
+//! depends on is computed from the output of the function target pipeline: the instrumented
+//! bytecode of the function (which contains the specs of the function and the specs of the
+//! callees it treats as opaque), the bytecode of its transitive callees, the structs, spec
+//! functions, spec variables and invariants of the modules involved, the prelude, and the
+//! backend options. Functions whose hash matches the one recorded for a previous successful
+//! verification are not verified again.
+
+use crate::cli::Options;
+use anyhow::Context;
+use log::warn;
+use move_model::model::{FunId, FunctionEnv, GlobalEnv, Loc, ModuleId, QualifiedId};
+use move_prover_boogie_backend::{
+    verification_report::{VerificationReport, VerificationStatus},
+    PRELUDE_SOURCES,
+};
+use move_stackless_bytecode::function_target_pipeline::FunctionTargetsHolder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file in the cache directory which holds the cache.
+const CACHE_FILE: &str = "verification_cache.json";
+
+/// The verification cache, as persisted on disk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VerificationCache {
+    /// The hashes of the verification inputs of the functions which were verified successfully,
+    /// by full function name.
+    verified: BTreeMap<String, String>,
+    /// The file the cache is loaded from and saved to.
+    #[serde(skip)]
+    file: PathBuf,
+}
+
+impl VerificationCache {
+    /// Loads the cache from `dir`. A missing or unreadable cache is treated as empty.
+    pub fn load(dir: &Path) -> Self {
+        let file = dir.join(CACHE_FILE);
+        let mut cache = match fs::read_to_string(&file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!(
+                    "ignoring corrupted verification cache `{}`: {}",
+                    file.display(),
+                    e
+                );
+                VerificationCache::default()
+            }),
+            Err(_) => VerificationCache::default(),
+        };
+        cache.file = file;
+        cache
+    }
+
+    /// Saves the cache to the directory it was loaded from.
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.file, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing verification cache `{}`", self.file.display()))
+    }
+
+    /// Returns true if the function `name` was verified successfully with inputs of hash `hash`.
+    pub fn is_verified(&self, name: &str, hash: &str) -> bool {
+        self.verified.get(name).map(String::as_str) == Some(hash)
+    }
+
+    /// Records that the function `name` was verified successfully with inputs of hash `hash`.
+    pub fn set_verified(&mut self, name: String, hash: String) {
+        self.verified.insert(name, hash);
+    }
+
+    /// Records the functions in `hashes` which `report` lists as verified. Functions which
+    /// failed, timed out, or whose outcome is unknown are not recorded, so they are verified
+    /// again in the next run.
+    pub fn record_verified(
+        &mut self,
+        hashes: &BTreeMap<QualifiedId<FunId>, String>,
+        report: &VerificationReport,
+    ) {
+        for function in &report.functions {
+            if function.status != VerificationStatus::Verified {
+                continue;
+            }
+            if let Some(hash) = hashes.get(&function.fun_id) {
+                self.set_verified(function.name.clone(), hash.clone());
+            }
+        }
+    }
+
+    /// Removes the verification variants of the functions in `hashes` which are in the cache
+    /// from `targets`, so that no verification conditions are generated for them. Returns the
+    /// functions which were removed.
+    pub fn remove_verified(
+        &self,
+        env: &GlobalEnv,
+        hashes: &BTreeMap<QualifiedId<FunId>, String>,
+        targets: &mut FunctionTargetsHolder,
+    ) -> BTreeSet<QualifiedId<FunId>> {
+        let mut removed = BTreeSet::new();
+        for (fun_id, hash) in hashes {
+            let func_env = env.get_function(*fun_id);
+            if !self.is_verified(&func_env.get_full_name_str(), hash) {
+                continue;
+            }
+            for variant in targets.get_target_variants(&func_env) {
+                if variant.is_verified() {
+                    targets.remove_target_data(fun_id, &variant);
+                }
+            }
+            removed.insert(*fun_id);
+        }
+        removed
+    }
+}
+
+/// Computes the hashes of the verification inputs of all functions which have a verification
+/// variant in `targets`.
+pub fn compute_verification_hashes(
+    env: &GlobalEnv,
+    options: &Options,
+    targets: &FunctionTargetsHolder,
+) -> BTreeMap<QualifiedId<FunId>, String> {
+    // The inputs shared by all functions.
+    let mut common = Sha256::new();
+    for source in PRELUDE_SOURCES {
+        common.update(source);
+    }
+    common.update(serde_json::to_string(&options.backend).expect("serializable options"));
+
+    let mut hashes = BTreeMap::new();
+    for fun_id in targets.get_funs() {
+        let func_env = env.get_function(fun_id);
+        if !targets
+            .get_target_variants(&func_env)
+            .iter()
+            .any(|variant| variant.is_verified())
+        {
+            continue;
+        }
+        let mut hasher = common.clone();
+        let mut functions = func_env.get_transitive_closure_of_called_functions();
+        functions.insert(fun_id);
+        let mut modules = BTreeSet::new();
+        for id in functions {
+            let callee_env = env.get_function(id);
+            hash_function_targets(&mut hasher, &callee_env, targets);
+            let module_env = callee_env.module_env;
+            modules.insert(module_env.get_id());
+            modules.extend(module_env.get_used_modules(true));
+        }
+        for module_id in modules {
+            hash_module_context(&mut hasher, env, module_id);
+        }
+        hashes.insert(fun_id, hex::encode(hasher.finalize()));
+    }
+    hashes
+}
+
+/// Hashes the bytecode of all variants of a function. Comments, which carry source locations
+/// and would make the hash depend on the position of the function in its file, are skipped.
+fn hash_function_targets(
+    hasher: &mut Sha256,
+    func_env: &FunctionEnv<'_>,
+    targets: &FunctionTargetsHolder,
+) {
+    hasher.update(func_env.get_full_name_str());
+    for (variant, target) in targets.get_targets(func_env) {
+        hasher.update(variant.to_string());
+        for line in target.to_string().lines() {
+            if !line.trim_start().starts_with('#') {
+                hasher.update(line);
+                hasher.update("\n");
+            }
+        }
+    }
+}
+
+/// Hashes the sources of the declarations of a module which may be referred to by
+/// verification conditions without appearing in the bytecode.
+fn hash_module_context(hasher: &mut Sha256, env: &GlobalEnv, module_id: ModuleId) {
+    let module_env = env.get_module(module_id);
+    let mut hash_source = |loc: &Loc| {
+        if let Ok(source) = env.get_source(loc) {
+            hasher.update(source);
+        }
+    };
+    for struct_env in module_env.get_structs() {
+        hash_source(&struct_env.get_loc());
+    }
+    for (_, decl) in module_env.get_spec_funs() {
+        hash_source(&decl.loc);
+    }
+    for (_, decl) in module_env.get_spec_vars() {
+        hash_source(&decl.loc);
+    }
+    for condition in &module_env.get_spec().conditions {
+        hash_source(&condition.loc);
+    }
+    for invariant in env.get_global_invariants_for_module(module_id) {
+        hash_source(&invariant.loc);
+    }
+}
@@ -37,6 +37,22 @@ static LOGGER_CONFIGURED: AtomicBool = AtomicBool::new(false);
 /// Atomic used to detect whether we are running in test mode.
 static TEST_MODE: AtomicBool = AtomicBool::new(false);
 
+/// The backend which checks the verification conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ProverBackend {
+    /// Generate Boogie code and verify it with Boogie.
+    Boogie,
+    /// Translate directly to SMT-LIB and verify it with the SMT solver. Supports only a
+    /// subset of Move; other functions are skipped with a warning.
+    Smt,
+}
+
+impl Default for ProverBackend {
+    fn default() -> Self {
+        ProverBackend::Boogie
+    }
+}
+
 /// Represents options provided to the tool. Most of those options are configured via a toml
 /// source; some over the command line flags.
 ///
@@ -71,6 +87,26 @@ pub struct Options {
     pub experimental_pipeline: bool,
     /// Options for printing out modules and functions reachable by script functions
     pub script_reach: bool,
+    /// The directory of the incremental verification cache. If set, functions whose
+    /// verification inputs did not change since they were last verified successfully are skipped.
+    pub cache_dir: Option<String>,
+    /// The file to write a JSON report of the verification results to, including the
+    /// counterexamples of failed verification conditions.
+    pub json_output: Option<String>,
+    /// The directory to write unit tests to which reproduce the counterexamples of failed
+    /// verification conditions.
+    pub generate_tests: Option<String>,
+    /// The backend which checks the verification conditions.
+    pub prover_backend: ProverBackend,
+    /// Whether the SMT backend may skip the functions it does not support. Otherwise these
+    /// functions are reported as errors.
+    pub smt_allow_skipped: bool,
+    /// Whether to infer the `modifies` conditions of functions from the read/write set
+    /// analysis and warn about mismatches with the ones in the specs.
+    pub check_modifies: bool,
+    /// Whether to insert the inferred `modifies` conditions into the specs of functions
+    /// which do not declare them.
+    pub suggest_specs: bool,
 
     /// BEGIN OF STRUCTURED OPTIONS. DO NOT ADD VALUE FIELDS AFTER THIS
     /// Options for the model builder.
@@ -110,6 +146,13 @@ impl Default for Options {
             errmapgen: ErrmapOptions::default(),
             experimental_pipeline: false,
             script_reach: false,
+            cache_dir: None,
+            json_output: None,
+            generate_tests: None,
+            prover_backend: ProverBackend::default(),
+            smt_allow_skipped: false,
+            check_modifies: false,
+            suggest_specs: false,
         }
     }
 }
@@ -191,6 +234,20 @@ impl Options {
                                               "SmtArray", "SmtArrayExt", "SmtSeq"])
                     .help("vector theory to use"),
             )
+            .arg(
+                Arg::new("backend")
+                    .long("backend")
+                    .takes_value(true)
+                    .possible_values(&["boogie", "smt"])
+                    .help("the backend to check verification conditions with. The `smt` \
+                    backend translates directly to SMT-LIB and supports only a subset of Move"),
+            )
+            .arg(
+                Arg::new("smt-allow-skipped")
+                    .long("smt-allow-skipped")
+                    .help("with the `smt` backend, skip the functions it does not support \
+                    with a warning instead of reporting them as errors"),
+            )
             .arg(
                 Arg::new("generate-only")
                     .long("generate-only")
@@ -240,6 +297,26 @@ impl Options {
                     .validator(is_number)
                     .help("sets a random seed for the prover (default 0)")
             )
+            .arg(
+                Arg::new("timeout-retries")
+                    .long("timeout-retries")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_number)
+                    .help("sets how many times functions which timed out are verified again, \
+                     each time with a different random seed (default 0)")
+            )
+            .arg(
+                Arg::new("split-on-timeout")
+                    .long("split-on-timeout")
+                    .help("verifies functions which still time out after all retries once more \
+                     with a separate verification condition per assertion")
+            )
+            .arg(
+                Arg::new("time-summary")
+                    .long("time-summary")
+                    .help("reports the time spent on the verification of each function")
+            )
             .arg(
                 Arg::new("cores")
                     .long("cores")
@@ -258,7 +335,8 @@ impl Options {
                     .value_name("NUMBER")
                     .validator(is_number)
                     .help("sets a timeout (in seconds) for each \
-                             individual verification condition (default 40)")
+                             individual verification condition (default 40). Can be overridden \
+                             for a function with `pragma timeout = N`")
             )
             .arg(
                 Arg::new("ignore-pragma-opaque-when-possible")
@@ -500,6 +578,26 @@ impl Options {
                     .long("check-inconsistency")
                     .help("checks whether there is any inconsistency")
             )
+            .arg(
+                Arg::new("infer-loop-invariants")
+                    .long("infer-loop-invariants")
+                    .help("infers simple loop invariants (counter bounds, preserved vector \
+                    lengths and unchanged fields) for loops in verified functions and prints \
+                    them")
+            )
+            .arg(
+                Arg::new("check-modifies")
+                    .long("check-modifies")
+                    .help("infers the global resources modified by each function and warns \
+                    about `modifies` conditions which do not match them")
+            )
+            .arg(
+                Arg::new("suggest-specs")
+                    .long("suggest-specs")
+                    .help("like --check-modifies, and in addition inserts the inferred \
+                    `modifies` conditions into the Move sources for functions which do not \
+                    declare them")
+            )
             .arg(
                 Arg::new("unconditional-abort-as-inconsistency")
                     .long("unconditional-abort-as-inconsistency")
@@ -529,6 +627,31 @@ impl Options {
                     .help("For each script function which is verification target, \
                     print out the names of all called functions, directly or indirectly.")
             )
+            .arg(
+                Arg::new("cache-dir")
+                    .long("cache-dir")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("skip the verification of functions whose verification inputs did not \
+                    change since they were last verified successfully, as recorded in the \
+                    verification cache in DIR")
+            )
+            .arg(
+                Arg::new("json-output")
+                    .long("json-output")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("write the verification results, including counterexamples, \
+                    as JSON to FILE")
+            )
+            .arg(
+                Arg::new("generate-tests")
+                    .long("generate-tests")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("write unit tests which reproduce the counterexamples found \
+                    into DIR, one test-only module per verified module")
+            )
             .after_help("More options available via `--config file` or `--config-str str`. \
             Use `--print-config` to see format and current values. \
             See `move-prover/src/cli.rs::Option` for documentation.");
@@ -588,6 +711,16 @@ impl Options {
                 _ => unreachable!("should not happen"),
             }
         }
+        if matches.is_present("backend") {
+            options.prover_backend = match matches.value_of("backend").unwrap() {
+                "boogie" => ProverBackend::Boogie,
+                "smt" => ProverBackend::Smt,
+                _ => unreachable!("should not happen"),
+            }
+        }
+        if matches.is_present("smt-allow-skipped") {
+            options.smt_allow_skipped = true;
+        }
 
         if matches.is_present("severity") {
             options.prover.report_severity = match matches.value_of("severity").unwrap() {
@@ -724,6 +857,18 @@ impl Options {
         if matches.is_present("timeout") {
             options.backend.vc_timeout = matches.value_of("timeout").unwrap().parse::<usize>()?;
         }
+        if matches.is_present("timeout-retries") {
+            options.backend.timeout_retries = matches
+                .value_of("timeout-retries")
+                .unwrap()
+                .parse::<usize>()?;
+        }
+        if matches.is_present("split-on-timeout") {
+            options.backend.split_on_timeout = true;
+        }
+        if matches.is_present("time-summary") {
+            options.backend.time_summary = true;
+        }
         if matches.is_present("cores") {
             options.backend.proc_cores = matches.value_of("cores").unwrap().parse::<usize>()?;
         }
@@ -752,6 +897,16 @@ impl Options {
         if matches.is_present("check-inconsistency") {
             options.prover.check_inconsistency = true;
         }
+        if matches.is_present("infer-loop-invariants") {
+            options.prover.infer_loop_invariants = true;
+        }
+        if matches.is_present("check-modifies") {
+            options.check_modifies = true;
+        }
+        if matches.is_present("suggest-specs") {
+            options.check_modifies = true;
+            options.suggest_specs = true;
+        }
         if matches.is_present("unconditional-abort-as-inconsistency") {
             options.prover.unconditional_abort_as_inconsistency = true;
         }
@@ -773,6 +928,15 @@ impl Options {
         if matches.is_present("script-reach") {
             options.script_reach = true;
         }
+        if matches.is_present("cache-dir") {
+            options.cache_dir = Some(matches.value_of("cache-dir").unwrap().to_string());
+        }
+        if matches.is_present("json-output") {
+            options.json_output = Some(matches.value_of("json-output").unwrap().to_string());
+        }
+        if matches.is_present("generate-tests") {
+            options.generate_tests = Some(matches.value_of("generate-tests").unwrap().to_string());
+        }
 
         options.backend.derive_options();
 
@@ -3,7 +3,11 @@
 
 #![forbid(unsafe_code)]
 
-use crate::cli::Options;
+use crate::{
+    cache::{compute_verification_hashes, VerificationCache},
+    cli::{Options, ProverBackend},
+    test_generation::{generate_tests, write_generated_tests},
+};
 use anyhow::anyhow;
 use codespan_reporting::{
     diagnostic::Severity,
@@ -17,26 +21,34 @@ use move_docgen::Docgen;
 use move_errmapgen::ErrmapGen;
 use move_model::{
     code_writer::CodeWriter,
-    model::{FunctionVisibility, GlobalEnv},
+    model::{FunId, FunctionVisibility, GlobalEnv, QualifiedId},
     parse_addresses_from_options, run_model_builder_with_options,
 };
 use move_prover_boogie_backend::{
     add_prelude, boogie_wrapper::BoogieWrapper, bytecode_translator::BoogieTranslator,
+    verification_report::VerificationReport,
 };
+use move_prover_smt_backend::{options::SmtOptions, VerificationSummary};
 use move_stackless_bytecode::{
     escape_analysis::EscapeAnalysisProcessor,
     function_target_pipeline::{FunctionTargetPipeline, FunctionTargetsHolder},
+    loop_invariant_inference::InferredLoopInvariants,
     pipeline_factory,
     read_write_set_analysis::{self, ReadWriteSetProcessor},
 };
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fs,
     path::{Path, PathBuf},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
+pub mod cache;
 pub mod cli;
+mod source_edit;
+pub mod spec_coverage;
+pub mod spec_suggestions;
+pub mod test_generation;
 
 // =================================================================================================
 // Prover API
@@ -120,17 +132,29 @@ pub fn run_move_prover_with_model<W: WriteColor>(
         };
     }
 
-    // Check correct backend versions.
-    options.backend.check_tool_versions()?;
+    // Check correct backend versions. The SMT backend does not use Boogie.
+    match options.prover_backend {
+        ProverBackend::Boogie => options.backend.check_tool_versions()?,
+        ProverBackend::Smt => options.backend.check_solver_versions()?,
+    }
 
     // Print functions that are reachable from the script function if the flag is set
     if options.script_reach {
         print_script_reach(env);
     }
 
+    // Infer the frames of functions from the read/write set analysis, which must run on
+    // untransformed targets, and compare them with the `modifies` conditions.
+    if options.check_modifies {
+        let modifies_targets = spec_suggestions::infer_modifies(env);
+        if options.suggest_specs {
+            spec_suggestions::insert_modifies(env, &modifies_targets)?;
+        }
+    }
+
     // Create and process bytecode
     let now = Instant::now();
-    let targets = create_and_process_bytecode(&options, env);
+    let mut targets = create_and_process_bytecode(&options, env);
     let trafo_duration = now.elapsed();
     check_errors(
         env,
@@ -138,22 +162,80 @@ pub fn run_move_prover_with_model<W: WriteColor>(
         error_writer,
         "exiting with bytecode transformation errors",
     )?;
+    if options.prover.infer_loop_invariants {
+        print_inferred_loop_invariants(env, error_writer)?;
+    }
 
-    // Generate boogie code
-    let now = Instant::now();
-    let code_writer = generate_boogie(env, &options, &targets)?;
-    let gen_duration = now.elapsed();
-    check_errors(
-        env,
-        &options,
-        error_writer,
-        "exiting with condition generation errors",
-    )?;
+    // Skip the functions which were verified before with the same inputs.
+    let mut cache = options
+        .cache_dir
+        .as_ref()
+        .map(|dir| VerificationCache::load(Path::new(dir)));
+    let (hashes, skipped) = if let Some(cache) = &cache {
+        let hashes = compute_verification_hashes(env, &options, &targets);
+        let skipped = cache.remove_verified(env, &hashes, &mut targets);
+        info!(
+            "skipping {} of {} functions verified before",
+            skipped.len(),
+            hashes.len()
+        );
+        if !hashes.is_empty() && skipped.len() == hashes.len() {
+            if let Some(path) = &options.json_output {
+                let mut report = VerificationReport::default();
+                report.add_cached(env, &skipped);
+                write_json_report(path, &report)?;
+            }
+            return Ok(());
+        }
+        (hashes, skipped)
+    } else {
+        (BTreeMap::new(), BTreeSet::new())
+    };
 
-    // Verify boogie code.
-    let now = Instant::now();
-    verify_boogie(env, &options, &targets, code_writer)?;
-    let verify_duration = now.elapsed();
+    let (gen_duration, verify_duration, report) = match options.prover_backend {
+        ProverBackend::Boogie => {
+            // Generate boogie code
+            let now = Instant::now();
+            let code_writer = generate_boogie(env, &options, &targets)?;
+            let gen_duration = now.elapsed();
+            check_errors(
+                env,
+                &options,
+                error_writer,
+                "exiting with condition generation errors",
+            )?;
+
+            // Verify boogie code.
+            let now = Instant::now();
+            let mut report = verify_boogie(env, &options, &targets, code_writer)?;
+            let verify_duration = now.elapsed();
+            if let Some(report) = &mut report {
+                if let Some(dir) = &options.generate_tests {
+                    write_generated_tests(Path::new(dir), &generate_tests(env, report))?;
+                }
+                if let Some(path) = &options.json_output {
+                    report.add_cached(env, &skipped);
+                    write_json_report(path, report)?;
+                }
+            }
+            (gen_duration, verify_duration, report)
+        }
+        ProverBackend::Smt => {
+            if options.json_output.is_some() || options.generate_tests.is_some() {
+                warn!("the SMT backend does not produce verification reports");
+            }
+            let now = Instant::now();
+            verify_smt(env, &options, &targets)?;
+            (Duration::default(), now.elapsed(), None)
+        }
+    };
+
+    // Record the functions which were verified successfully. The SMT backend skips functions
+    // it does not support and produces no report, so its results are not recorded.
+    if let (Some(cache), Some(report)) = (&mut cache, &report) {
+        cache.record_verified(&hashes, report);
+        cache.save()?;
+    }
 
     // Report durations.
     info!(
@@ -201,15 +283,19 @@ pub fn generate_boogie(
     Ok(writer)
 }
 
+/// Writes the boogie code and verifies it. If a JSON report, generated tests or a verification
+/// cache are requested, returns the report of
+/// the verification results.
 pub fn verify_boogie(
     env: &GlobalEnv,
     options: &Options,
     targets: &FunctionTargetsHolder,
     writer: CodeWriter,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Option<VerificationReport>> {
     let output_existed = std::path::Path::new(&options.output_path).exists();
     debug!("writing boogie to `{}`", &options.output_path);
     writer.process_result(|result| fs::write(&options.output_path, result))?;
+    let mut report = None;
     if !options.prover.generate_only {
         let boogie = BoogieWrapper {
             env,
@@ -217,11 +303,82 @@ pub fn verify_boogie(
             writer: &writer,
             options: &options.backend,
         };
-        boogie.call_boogie_and_verify_output(&options.output_path)?;
+        let output = boogie.call_boogie_and_verify_output(&options.output_path)?;
+        if options.json_output.is_some()
+            || options.generate_tests.is_some()
+            || options.cache_dir.is_some()
+        {
+            report = Some(boogie.make_report(&output.errors));
+        }
+        if options.backend.time_summary {
+            print_time_summary(env, &boogie.extract_verification_times(&output.all_output));
+        }
         if !output_existed && !options.backend.keep_artifacts {
             std::fs::remove_file(&options.output_path).unwrap_or_default();
         }
     }
+    Ok(report)
+}
+
+/// Verifies the function targets with the SMT-LIB backend, which calls the solver directly.
+/// Returns the number of functions verified, failed and skipped.
+pub fn verify_smt(
+    env: &GlobalEnv,
+    options: &Options,
+    targets: &FunctionTargetsHolder,
+) -> anyhow::Result<VerificationSummary> {
+    let script_file = if options.backend.keep_artifacts || options.prover.generate_only {
+        let path = Path::new(&options.output_path).with_extension("smt2");
+        debug!("writing SMT-LIB to `{}`", path.display());
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    let smt_options = SmtOptions {
+        z3_exe: options.backend.z3_exe.clone(),
+        use_cvc5: options.backend.use_cvc5,
+        cvc5_exe: options.backend.cvc5_exe.clone(),
+        random_seed: options.backend.random_seed,
+        vc_timeout: options.backend.vc_timeout,
+        script_file,
+        generate_only: options.prover.generate_only,
+        allow_skipped: options.smt_allow_skipped,
+    };
+    move_prover_smt_backend::verify(env, targets, &smt_options)
+}
+
+/// Prints the time spent on the verification of each function, slowest first.
+fn print_time_summary(env: &GlobalEnv, times: &BTreeMap<QualifiedId<FunId>, f64>) {
+    let mut times = times.iter().collect::<Vec<_>>();
+    times.sort_by(|(_, t1), (_, t2)| t2.partial_cmp(t1).unwrap());
+    println!("Verification time per function:");
+    for (fun_id, time) in times {
+        println!(
+            "{:>10.3}s  {}",
+            time,
+            env.get_function(*fun_id).get_full_name_str()
+        );
+    }
+}
+
+/// Prints the loop invariants inferred by the bytecode pipeline.
+/// Prints the loop invariants inferred by `--infer-loop-invariants` together with the
+/// diagnostics, so they appear in the same output as the errors they may resolve.
+fn print_inferred_loop_invariants<W: WriteColor>(
+    env: &GlobalEnv,
+    writer: &mut W,
+) -> anyhow::Result<()> {
+    if let Some(inferred) = env.get_extension::<InferredLoopInvariants>() {
+        writeln!(writer, "Inferred {} loop invariants:", inferred.count())?;
+        write!(writer, "{}", inferred.report(env))?;
+    }
+    Ok(())
+}
+
+/// Writes a verification report as JSON.
+pub fn write_json_report(path: &str, report: &VerificationReport) -> anyhow::Result<()> {
+    debug!("writing verification report to `{}`", path);
+    fs::write(path, serde_json::to_string_pretty(report)?)?;
     Ok(())
 }
 
@@ -0,0 +1,53 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for editing Move source files, used to add generated specs to the user's sources.
+
+use std::{cmp::Reverse, ops::Range};
+
+/// An edit of a source file: the byte range to replace, and the replacement text.
+pub type SourceEdit = (Range<usize>, String);
+
+/// Returns the edit which adds `lines` at the end of the block whose closing brace is at byte
+/// offset `close` in `source`. The lines are indented one level deeper than `indent`, the
+/// indentation of the block.
+pub fn insert_at_block_end(
+    source: &str,
+    close: usize,
+    indent: &str,
+    lines: &[String],
+) -> SourceEdit {
+    let line_start = source[..close].rfind('\n').map_or(0, |pos| pos + 1);
+    let mut text = String::new();
+    if source[line_start..close].trim().is_empty() {
+        for line in lines {
+            text += &format!("{}    {}\n", indent, line);
+        }
+        (line_start..line_start, text)
+    } else {
+        // The closing brace follows other content on its line, so put it on its own line.
+        for line in lines {
+            text += &format!("\n{}    {}", indent, line);
+        }
+        text += &format!("\n{}", indent);
+        (source[..close].trim_end().len()..close, text)
+    }
+}
+
+/// Returns the leading whitespace of the line containing byte offset `offset` in `source`.
+pub fn indentation(source: &str, offset: usize) -> String {
+    let line_start = source[..offset].rfind('\n').map_or(0, |pos| pos + 1);
+    source[line_start..offset]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect()
+}
+
+/// Applies non-overlapping `edits` to `source`.
+pub fn apply_edits(source: &mut String, edits: &mut [SourceEdit]) {
+    // Apply the edits from the back so the ranges of the remaining ones stay valid.
+    edits.sort_by_key(|(range, _)| Reverse(range.start));
+    for (range, text) in edits.iter() {
+        source.replace_range(range.clone(), text);
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Specification coverage.
+//!
+//! Summarizes, per target module, how much of the code is specified and which specifications
+//! are not (fully) checked by the prover: functions without a spec, functions excluded from
+//! verification via `pragma verify = false`, functions whose abort behavior is not specified
+//! completely, and struct invariants which are never checked because no function of the
+//! declaring module is verified.
+
+use move_model::{
+    ast::ConditionKind,
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, VerificationScope},
+    pragmas::{ABORTS_IF_IS_PARTIAL_PRAGMA, ABORTS_IF_IS_STRICT_PRAGMA},
+};
+use serde::Serialize;
+use std::fmt;
+
+/// The specification coverage of a set of modules.
+#[derive(Debug, Default, Serialize)]
+pub struct SpecCoverageReport {
+    pub modules: Vec<ModuleSpecCoverage>,
+}
+
+/// The specification coverage of a single module. Functions and structs are given by their
+/// simple names.
+#[derive(Debug, Default, Serialize)]
+pub struct ModuleSpecCoverage {
+    pub name: String,
+    /// The number of functions in the module.
+    pub functions: usize,
+    /// The number of functions which have at least one specification condition.
+    pub specified: usize,
+    /// Functions without any specification condition.
+    pub unspecified: Vec<String>,
+    /// Functions which are excluded from verification with `pragma verify = false`.
+    pub verify_disabled: Vec<String>,
+    /// Specified functions which are verified, but whose aborts conditions are not checked for
+    /// completeness, either because they have no `aborts_if` and `aborts_if_is_strict` is not
+    /// set, or because `aborts_if_is_partial` is set.
+    pub aborts_if_incomplete: Vec<String>,
+    /// Structs with invariants which are never checked, because no function of the module is
+    /// verified.
+    pub unverified_invariants: Vec<String>,
+}
+
+impl SpecCoverageReport {
+    /// Computes the specification coverage of the target modules of `env`, with `scope`
+    /// determining which functions are verified by default.
+    pub fn compute(env: &GlobalEnv, scope: &VerificationScope) -> Self {
+        let modules = env
+            .get_modules()
+            .filter(|module_env| module_env.is_target() && !module_env.is_script_module())
+            .map(|module_env| ModuleSpecCoverage::compute(&module_env, scope))
+            .collect();
+        Self { modules }
+    }
+}
+
+impl ModuleSpecCoverage {
+    fn compute(module_env: &ModuleEnv<'_>, scope: &VerificationScope) -> Self {
+        let env = module_env.env;
+        let mut coverage = ModuleSpecCoverage {
+            name: module_env.get_full_name_str(),
+            ..Default::default()
+        };
+        let mut any_verified = false;
+        for fun_env in module_env.get_functions() {
+            let name = fun_env.get_simple_name_string().to_string();
+            coverage.functions += 1;
+            let verified = fun_env.should_verify(scope);
+            any_verified |= verified;
+            if fun_env.is_explicitly_not_verified(scope) {
+                coverage.verify_disabled.push(name.clone());
+            }
+            if !fun_env.get_spec().has_conditions() {
+                coverage.unspecified.push(name);
+                continue;
+            }
+            coverage.specified += 1;
+            if verified && !fun_env.is_native_or_intrinsic() && !aborts_if_is_complete(&fun_env) {
+                coverage.aborts_if_incomplete.push(name);
+            }
+        }
+        if !any_verified {
+            for struct_env in module_env.get_structs() {
+                if struct_env
+                    .get_spec()
+                    .any_kind(ConditionKind::StructInvariant)
+                {
+                    coverage
+                        .unverified_invariants
+                        .push(struct_env.get_name().display(env.symbol_pool()).to_string());
+                }
+            }
+        }
+        coverage
+    }
+}
+
+/// Returns true if verification checks that the function aborts exactly under the conditions
+/// given by its spec.
+fn aborts_if_is_complete(fun_env: &FunctionEnv<'_>) -> bool {
+    if fun_env.is_pragma_true(ABORTS_IF_IS_PARTIAL_PRAGMA, || false) {
+        return false;
+    }
+    let spec = fun_env.get_spec();
+    spec.any_kind(ConditionKind::AbortsIf)
+        || spec.any_kind(ConditionKind::AbortsWith)
+        || fun_env.is_pragma_true(ABORTS_IF_IS_STRICT_PRAGMA, || false)
+}
+
+impl fmt::Display for SpecCoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut functions = 0;
+        let mut specified = 0;
+        for module in &self.modules {
+            functions += module.functions;
+            specified += module.specified;
+            write!(f, "{}", module)?;
+        }
+        writeln!(
+            f,
+            "Total: {} of {} functions specified ({})",
+            specified,
+            functions,
+            percentage(specified, functions)
+        )
+    }
+}
+
+impl fmt::Display for ModuleSpecCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Module {}: {} of {} functions specified ({})",
+            self.name,
+            self.specified,
+            self.functions,
+            percentage(self.specified, self.functions)
+        )?;
+        let sections = [
+            ("functions without spec", &self.unspecified),
+            (
+                "functions with `pragma verify = false`",
+                &self.verify_disabled,
+            ),
+            (
+                "functions with incomplete aborts_if",
+                &self.aborts_if_incomplete,
+            ),
+            (
+                "structs with unverified invariants",
+                &self.unverified_invariants,
+            ),
+        ];
+        for (title, names) in sections.iter() {
+            if !names.is_empty() {
+                writeln!(f, "  {}: {}", title, names.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn percentage(part: usize, total: usize) -> String {
+    if total == 0 {
+        "n/a".to_string()
+    } else {
+        format!("{:.0}%", part as f64 * 100.0 / total as f64)
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inference of `modifies` conditions and their insertion into the Move sources.
+//!
+//! The frame of each function is inferred by the modifies inference on top of the read/write
+//! set analysis, which also warns about `modifies` conditions which do not match it. On request,
+//! the conditions for the resources a function in a target module modifies but does not declare
+//! are inserted into the function's spec block, which is created if needed. A function is left
+//! unchanged if the address of one of those resources cannot be expressed in terms of its
+//! parameters, since a partial frame would make its verification fail.
+
+use crate::source_edit::{apply_edits, indentation, insert_at_block_end, SourceEdit};
+use anyhow::Context;
+use log::{info, warn};
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::{
+    function_target_pipeline::{FunctionTargetPipeline, FunctionTargetsHolder, FunctionVariant},
+    modifies_inference::{get_inferred_modifies, ModifiesInferenceProcessor},
+    read_write_set_analysis::ReadWriteSetProcessor,
+};
+use std::{collections::BTreeMap, fs};
+
+/// Runs the modifies inference on all functions in `env`.
+pub fn infer_modifies(env: &GlobalEnv) -> FunctionTargetsHolder {
+    let mut targets = FunctionTargetsHolder::default();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            targets.add_target(&func_env)
+        }
+    }
+    let mut pipeline = FunctionTargetPipeline::default();
+    pipeline.add_processor(ReadWriteSetProcessor::new());
+    pipeline.add_processor(ModifiesInferenceProcessor::new());
+    pipeline.run(env, &mut targets);
+    targets
+}
+
+/// Inserts the inferred `modifies` conditions which are missing from the specs of functions in
+/// target modules into the source files.
+pub fn insert_modifies(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> anyhow::Result<()> {
+    // Collect the edits for each file.
+    let mut edits: BTreeMap<String, Vec<SourceEdit>> = BTreeMap::new();
+    for module_env in env.get_modules() {
+        if !module_env.is_target() {
+            continue;
+        }
+        for func_env in module_env.get_functions() {
+            if func_env.is_native_or_intrinsic() {
+                continue;
+            }
+            if let Some(conditions) = missing_conditions(&func_env, targets) {
+                // The spec block may be in a different file than the function.
+                let file = env.get_file(func_env.get_spec_loc().file_id());
+                let path = file.to_string_lossy().to_string();
+                let source =
+                    fs::read_to_string(&path).with_context(|| format!("reading `{}`", path))?;
+                edits
+                    .entry(path)
+                    .or_default()
+                    .push(spec_edit(&func_env, &source, &conditions));
+            }
+        }
+    }
+    for (path, mut file_edits) in edits {
+        let mut source = fs::read_to_string(&path)?;
+        apply_edits(&mut source, &mut file_edits);
+        fs::write(&path, source).with_context(|| format!("writing `{}`", path))?;
+        info!(
+            "inserted `modifies` conditions into {} spec(s) in `{}`",
+            file_edits.len(),
+            path
+        );
+    }
+    Ok(())
+}
+
+/// Returns the `modifies` conditions missing from the spec of `func_env`, or `None` if there
+/// are none or they cannot all be expressed.
+fn missing_conditions(
+    func_env: &FunctionEnv,
+    targets: &FunctionTargetsHolder,
+) -> Option<Vec<String>> {
+    let env = func_env.module_env.env;
+    let target = targets.get_target(func_env, &FunctionVariant::Baseline);
+    let inferred = get_inferred_modifies(&target)?;
+    let declared = func_env.get_modify_targets();
+    let mut conditions = vec![];
+    for resource in inferred.resources() {
+        if declared.contains_key(&resource) {
+            continue;
+        }
+        match inferred.conditions_for(resource) {
+            Some(resource_conditions) => conditions.extend(resource_conditions),
+            None => {
+                warn!(
+                    "cannot suggest `modifies` conditions for `{}`: the address at which \
+                     it modifies `{}` cannot be expressed",
+                    func_env.get_full_name_str(),
+                    env.get_struct(resource).get_full_name_str()
+                );
+                return None;
+            }
+        }
+    }
+    if conditions.is_empty() {
+        None
+    } else {
+        Some(conditions)
+    }
+}
+
+/// Returns the edit of `source` which adds `conditions` to the spec of `func_env`.
+fn spec_edit(func_env: &FunctionEnv, source: &str, conditions: &[String]) -> SourceEdit {
+    let spec_loc = func_env.get_spec_loc();
+    if spec_loc != func_env.get_loc() {
+        // Insert the conditions before the closing brace of the spec block.
+        let indent = indentation(source, spec_loc.span().start().to_usize());
+        let close = spec_loc.span().end().to_usize() - 1;
+        insert_at_block_end(source, close, &indent, conditions)
+    } else {
+        // Add a new spec block right after the function.
+        let fun_loc = func_env.get_loc();
+        let indent = indentation(source, fun_loc.span().start().to_usize());
+        let mut text = format!(
+            "\n{}spec {} {{\n",
+            indent,
+            func_env.get_name().display(func_env.symbol_pool())
+        );
+        for cond in conditions {
+            text += &format!("{}    {}\n", indent, cond);
+        }
+        text += &format!("{}}}", indent);
+        let end = fun_loc.span().end().to_usize();
+        (end..end, text)
+    }
+}
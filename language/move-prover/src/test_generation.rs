@@ -0,0 +1,389 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of unit tests from counterexamples.
+//!
+//! For each module with functions for which the prover found a counterexample, a test-only
+//! module `<module>_prover_tests` is generated. It contains one `#[test]` function per
+//! counterexample, which calls the function with the arguments of the counterexample and
+//! asserts the observed behavior: the abort code if the function aborted, and otherwise the
+//! values it returned.
+//!
+//! A test module can only call public functions, and cannot construct values of structs or
+//! publish resources of other modules. Counterexamples which cannot be reproduced because of
+//! this are listed in a comment in the generated module instead.
+
+use itertools::Itertools;
+use move_model::{
+    model::{FunctionEnv, FunctionVisibility, GlobalEnv, ModuleEnv, ModuleId},
+    ty::{PrimitiveType, Type},
+};
+use move_prover_boogie_backend::verification_report::{
+    Binding, Counterexample, ErrorReport, FunctionReport, VerificationReport,
+};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt::Write, fs, path::Path};
+
+/// The maximal size of a vector which is reconstructed from a partially known vector in a
+/// counterexample.
+const MAX_VECTOR_SIZE: usize = 256;
+
+/// Generates unit tests from the counterexamples in `report`. Returns the name and source of
+/// each generated module.
+pub fn generate_tests(env: &GlobalEnv, report: &VerificationReport) -> BTreeMap<String, String> {
+    let mut by_module: BTreeMap<ModuleId, Vec<&FunctionReport>> = BTreeMap::new();
+    for function in &report.functions {
+        if function
+            .errors
+            .iter()
+            .any(|error| error.counterexample.is_some())
+        {
+            by_module
+                .entry(function.fun_id.module_id)
+                .or_default()
+                .push(function);
+        }
+    }
+    by_module
+        .into_iter()
+        .map(|(module_id, functions)| {
+            let module_env = env.get_module(module_id);
+            let name = format!(
+                "{}_prover_tests",
+                module_env.get_name().display(env.symbol_pool())
+            );
+            let source = generate_module(&module_env, &name, &functions);
+            (name, source)
+        })
+        .collect()
+}
+
+/// Writes the modules generated by `generate_tests` into `dir`, one file per module.
+pub fn write_generated_tests(dir: &Path, modules: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (name, source) in modules {
+        fs::write(dir.join(format!("{}.move", name)), source)?;
+    }
+    Ok(())
+}
+
+fn generate_module(
+    module_env: &ModuleEnv<'_>,
+    name: &str,
+    functions: &[&FunctionReport],
+) -> String {
+    let env = module_env.env;
+    let module_name = module_env.get_name().display(env.symbol_pool()).to_string();
+    let mut tests = vec![];
+    let mut skipped = vec![];
+    for function in functions {
+        let fun_env = env.get_function(function.fun_id);
+        let counterexamples = function
+            .errors
+            .iter()
+            .filter_map(|error| Some((error, error.counterexample.as_ref()?)));
+        for (n, (error, counterexample)) in counterexamples.enumerate() {
+            let test_name = format!(
+                "{}_counterexample_{}",
+                fun_env.get_simple_name_string(),
+                n + 1
+            );
+            match generate_test(&fun_env, &module_name, &test_name, error, counterexample) {
+                Ok(test) => tests.push(test),
+                Err(reason) => skipped.push(format!("{}: {}", test_name, reason)),
+            }
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by the Move prover from counterexamples found in module {}.",
+        module_env.get_full_name_str()
+    )
+    .unwrap();
+    if !skipped.is_empty() {
+        writeln!(
+            out,
+            "//\n// Counterexamples which could not be turned into tests:"
+        )
+        .unwrap();
+        for line in &skipped {
+            writeln!(out, "// - {}", line).unwrap();
+        }
+    }
+    writeln!(out, "#[test_only]").unwrap();
+    writeln!(
+        out,
+        "module {}::{} {{",
+        module_env.self_address().to_hex_literal(),
+        name
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    use {}::{};",
+        module_env.self_address().to_hex_literal(),
+        module_name
+    )
+    .unwrap();
+    for test in tests {
+        writeln!(out).unwrap();
+        out.push_str(&test);
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// Generates a test which reproduces the counterexample, or returns the reason why this is not
+/// possible.
+fn generate_test(
+    fun_env: &FunctionEnv<'_>,
+    module_name: &str,
+    test_name: &str,
+    error: &ErrorReport,
+    counterexample: &Counterexample,
+) -> Result<String, String> {
+    if fun_env.visibility() != FunctionVisibility::Public || fun_env.module_env.is_script_module() {
+        return Err("the function is not public".to_string());
+    }
+    if fun_env.get_type_parameter_count() > 0 {
+        return Err("the function is generic".to_string());
+    }
+    let return_types = fun_env.get_return_types();
+    if return_types.iter().any(|ty| default_value(ty).is_none()) {
+        return Err("the function returns values which cannot be compared".to_string());
+    }
+    if let Some(memory) = counterexample
+        .memory
+        .iter()
+        .find(|memory| requires_resources(&memory.before))
+    {
+        return Err(format!(
+            "the counterexample requires resources of type `{}` to exist",
+            memory.resource
+        ));
+    }
+
+    let env = fun_env.module_env.env;
+    let mut signers = vec![];
+    let mut locals = vec![];
+    let mut args = vec![];
+    for param in fun_env.get_parameters() {
+        let name = param.0.display(env.symbol_pool()).to_string();
+        let value = counterexample
+            .arguments
+            .iter()
+            .find(|binding| binding.name == name)
+            .map_or(&Value::Null, |binding| &binding.value);
+        let (ty, reference) = match &param.1 {
+            Type::Reference(is_mut, ty) => (ty.as_ref(), Some(*is_mut)),
+            ty => (ty, None),
+        };
+        if ty == &Type::Primitive(PrimitiveType::Signer) {
+            let addr = match value {
+                Value::String(addr) if addr.starts_with("0x") => addr.as_str(),
+                _ => "0x0",
+            };
+            signers.push((name.clone(), addr.to_string()));
+            args.push(if reference.is_some() {
+                format!("&{}", name)
+            } else {
+                name
+            });
+            continue;
+        }
+        let value = render_value(ty, value)
+            .ok_or_else(|| format!("cannot construct a value for parameter `{}`", name))?;
+        args.push(match reference {
+            Some(true) => {
+                locals.push(format!("let {} = {};", name, value));
+                format!("&mut {}", name)
+            }
+            Some(false) => format!("&{}", value),
+            None => value,
+        });
+    }
+
+    let mut out = String::new();
+    writeln!(out, "    // {}", error.message).unwrap();
+    if let Some(source) = &error.source {
+        writeln!(out, "    // {}", source.lines().map(str::trim).join(" ")).unwrap();
+    }
+    if signers.is_empty() {
+        writeln!(out, "    #[test]").unwrap();
+    } else {
+        writeln!(
+            out,
+            "    #[test({})]",
+            signers
+                .iter()
+                .map(|(name, addr)| format!("{} = @{}", name, addr))
+                .join(", ")
+        )
+        .unwrap();
+    }
+    if let Some(abort) = &counterexample.abort {
+        match abort.code {
+            Some(code) => writeln!(out, "    #[expected_failure(abort_code = {})]", code),
+            None => writeln!(out, "    #[expected_failure]"),
+        }
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "    fun {}({}) {{",
+        test_name,
+        signers
+            .iter()
+            .map(|(name, _)| format!("{}: signer", name))
+            .join(", ")
+    )
+    .unwrap();
+    for local in &locals {
+        writeln!(out, "        {}", local).unwrap();
+    }
+    let call = format!(
+        "{}::{}({})",
+        module_name,
+        fun_env.get_simple_name_string(),
+        args.join(", ")
+    );
+    let results = observed_results(fun_env, counterexample, &return_types);
+    match results {
+        Some(results) if counterexample.abort.is_none() && !return_types.is_empty() => {
+            let names = (1..=return_types.len())
+                .map(|i| {
+                    if return_types.len() == 1 {
+                        "result".to_string()
+                    } else {
+                        format!("result_{}", i)
+                    }
+                })
+                .collect_vec();
+            if names.len() == 1 {
+                writeln!(out, "        let {} = {};", names[0], call).unwrap();
+            } else {
+                writeln!(out, "        let ({}) = {};", names.join(", "), call).unwrap();
+            }
+            for (name, value) in names.iter().zip(results) {
+                writeln!(out, "        assert!({} == {}, 0);", name, value).unwrap();
+            }
+        }
+        _ => {
+            if return_types.is_empty() {
+                writeln!(out, "        {};", call).unwrap();
+            } else if return_types.len() == 1 {
+                writeln!(out, "        let _ = {};", call).unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "        let ({}) = {};",
+                    return_types.iter().map(|_| "_").join(", "),
+                    call
+                )
+                .unwrap();
+            }
+        }
+    }
+    writeln!(out, "    }}").unwrap();
+    Ok(out)
+}
+
+/// Returns the values the function returned in the counterexample, rendered as Move
+/// expressions, if all of them are known.
+fn observed_results(
+    fun_env: &FunctionEnv<'_>,
+    counterexample: &Counterexample,
+    return_types: &[Type],
+) -> Option<Vec<String>> {
+    let fun_name = fun_env.get_full_name_str();
+    let mut results: BTreeMap<&str, &Binding> = BTreeMap::new();
+    for step in &counterexample.trace {
+        if step.function.as_deref() != Some(fun_name.as_str()) {
+            continue;
+        }
+        for binding in &step.bindings {
+            if binding.name == "result" || binding.name.starts_with("result_") {
+                // The latest value wins.
+                results.insert(&binding.name, binding);
+            }
+        }
+    }
+    return_types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let name = if return_types.len() == 1 {
+                "result".to_string()
+            } else {
+                format!("result_{}", i + 1)
+            };
+            render_value(ty, &results.get(name.as_str())?.value)
+        })
+        .collect()
+}
+
+/// Returns true if the global memory in a counterexample contains any resource.
+fn requires_resources(memory: &Value) -> bool {
+    match memory {
+        Value::Object(entries) => entries.values().any(|value| !value.is_null()),
+        Value::Null => false,
+        _ => true,
+    }
+}
+
+/// Renders a value of a counterexample as a Move expression of type `ty`. Unknown values are
+/// rendered as a default value of the type.
+fn render_value(ty: &Type, value: &Value) -> Option<String> {
+    use PrimitiveType::*;
+    if value.is_null() {
+        return default_value(ty);
+    }
+    Some(match (ty, value) {
+        (Type::Primitive(U8), Value::Number(n)) => format!("{}u8", n.as_u64()?),
+        (Type::Primitive(U64), Value::Number(n)) => format!("{}", n.as_u64()?),
+        (Type::Primitive(U128), Value::String(s)) => format!("{}u128", s.parse::<u128>().ok()?),
+        (Type::Primitive(Bool), Value::Bool(b)) => b.to_string(),
+        (Type::Primitive(Address), Value::String(s)) if s.starts_with("0x") => format!("@{}", s),
+        (Type::Vector(elem_ty), Value::Array(elems)) => format!(
+            "vector[{}]",
+            elems
+                .iter()
+                .map(|elem| render_value(elem_ty, elem))
+                .collect::<Option<Vec<_>>>()?
+                .join(", ")
+        ),
+        (Type::Vector(elem_ty), Value::Object(vector)) => {
+            // A vector of which only some elements are known.
+            let size = vector.get("size")?.as_u64()? as usize;
+            if size > MAX_VECTOR_SIZE {
+                return None;
+            }
+            let elements = vector.get("elements")?.as_object()?;
+            let default = vector.get("default").unwrap_or(&Value::Null);
+            let elems = (0..size)
+                .map(|idx| render_value(elem_ty, elements.get(&idx.to_string()).unwrap_or(default)))
+                .collect::<Option<Vec<_>>>()?;
+            format!("vector[{}]", elems.join(", "))
+        }
+        _ => return None,
+    })
+}
+
+fn default_value(ty: &Type) -> Option<String> {
+    use PrimitiveType::*;
+    Some(match ty {
+        Type::Primitive(U8) => "0u8".to_string(),
+        Type::Primitive(U64) => "0".to_string(),
+        Type::Primitive(U128) => "0u128".to_string(),
+        Type::Primitive(Bool) => "false".to_string(),
+        Type::Primitive(Address) => "@0x0".to_string(),
+        Type::Vector(elem_ty) => {
+            // Check that the element type can be rendered, to give the vector a Move type.
+            default_value(elem_ty)?;
+            "vector[]".to_string()
+        }
+        _ => return None,
+    })
+}
@@ -0,0 +1,203 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_compiler::shared::PackagePaths;
+use move_model::{
+    model::{FunId, GlobalEnv, QualifiedId},
+    run_model_builder_with_options,
+};
+use move_prover::{
+    cache::{compute_verification_hashes, VerificationCache},
+    cli::Options,
+    create_and_process_bytecode,
+};
+use move_prover_boogie_backend::verification_report::{
+    FunctionReport, VerificationReport, VerificationStatus,
+};
+use move_stackless_bytecode::function_target_pipeline::FunctionTargetsHolder;
+use std::{collections::BTreeMap, fs};
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"
+module 0x42::M {
+    struct S has key { x: u64 }
+
+    fun inc(x: u64): u64 { x + 1 }
+    spec inc { ensures result == x + 1; }
+
+    fun twice(x: u64): u64 { inc(inc(x)) }
+    spec twice { ensures result == x + 2; }
+}
+
+module 0x42::N {
+    fun one(): u64 { 1 }
+    spec one { ensures result == 1; }
+}
+"#;
+
+fn build(source: &str) -> (GlobalEnv, FunctionTargetsHolder) {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("M.move");
+    fs::write(&path, source).unwrap();
+    let options = Options::default();
+    let env = run_model_builder_with_options(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![path.to_string_lossy().to_string()],
+            named_address_map: BTreeMap::<String, _>::new(),
+        }],
+        vec![],
+        options.model_builder.clone(),
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+    env.set_extension(options.prover.clone());
+    let targets = create_and_process_bytecode(&options, &env);
+    (env, targets)
+}
+
+/// Returns the hashes of the verified functions, by function name.
+fn hashes_by_name(
+    env: &GlobalEnv,
+    hashes: &BTreeMap<QualifiedId<FunId>, String>,
+) -> BTreeMap<String, String> {
+    hashes
+        .iter()
+        .map(|(fun_id, hash)| {
+            let func_env = env.get_function(*fun_id);
+            (func_env.get_name_string().to_string(), hash.clone())
+        })
+        .collect()
+}
+
+#[test]
+fn hashes_are_stable() {
+    let (env1, targets1) = build(SOURCE);
+    let (env2, targets2) = build(SOURCE);
+    let options = Options::default();
+    let hashes1 = hashes_by_name(
+        &env1,
+        &compute_verification_hashes(&env1, &options, &targets1),
+    );
+    let hashes2 = hashes_by_name(
+        &env2,
+        &compute_verification_hashes(&env2, &options, &targets2),
+    );
+    assert_eq!(
+        hashes1.keys().collect::<Vec<_>>(),
+        vec!["M::inc", "M::twice", "N::one"]
+    );
+    assert_eq!(hashes1, hashes2);
+}
+
+#[test]
+fn changes_invalidate_function_and_callers() {
+    let options = Options::default();
+    let (env1, targets1) = build(SOURCE);
+    let hashes1 = hashes_by_name(
+        &env1,
+        &compute_verification_hashes(&env1, &options, &targets1),
+    );
+
+    let (env2, targets2) = build(&SOURCE.replace("{ x + 1 }", "{ 1 + x }"));
+    let hashes2 = hashes_by_name(
+        &env2,
+        &compute_verification_hashes(&env2, &options, &targets2),
+    );
+    assert_ne!(hashes1["M::inc"], hashes2["M::inc"]);
+    assert_ne!(hashes1["M::twice"], hashes2["M::twice"]);
+    // The declarations of a module are part of the inputs of all its functions, so only
+    // functions of other modules are unaffected.
+    assert_eq!(hashes1["N::one"], hashes2["N::one"]);
+
+    // The backend options are part of the inputs of all functions.
+    let mut options = Options::default();
+    options.backend.vc_timeout += 1;
+    let hashes3 = hashes_by_name(
+        &env1,
+        &compute_verification_hashes(&env1, &options, &targets1),
+    );
+    assert!(hashes1
+        .values()
+        .all(|hash| !hashes3.values().any(|h| h == hash)));
+}
+
+#[test]
+fn verified_functions_are_skipped() {
+    let dir = tempdir().unwrap();
+    let options = Options::default();
+    let (env, mut targets) = build(SOURCE);
+    let hashes = compute_verification_hashes(&env, &options, &targets);
+
+    let mut cache = VerificationCache::load(dir.path());
+    for (fun_id, hash) in &hashes {
+        let func_env = env.get_function(*fun_id);
+        if func_env.get_name_string().to_string() != "M::twice" {
+            cache.set_verified(func_env.get_full_name_str(), hash.clone());
+        }
+    }
+    cache.save().unwrap();
+
+    let cache = VerificationCache::load(dir.path());
+    let skipped = cache.remove_verified(&env, &hashes, &mut targets);
+    let mut skipped: Vec<_> = skipped
+        .into_iter()
+        .map(|fun_id| env.get_function(fun_id).get_name_string().to_string())
+        .collect();
+    skipped.sort();
+    assert_eq!(skipped, vec!["M::inc", "N::one"]);
+    for fun_id in hashes.keys() {
+        let func_env = env.get_function(*fun_id);
+        let verified = targets
+            .get_target_variants(&func_env)
+            .iter()
+            .any(|variant| variant.is_verified());
+        assert_eq!(
+            verified,
+            func_env.get_name_string().to_string() == "M::twice"
+        );
+    }
+}
+
+#[test]
+fn only_verified_functions_are_recorded() {
+    let dir = tempdir().unwrap();
+    let options = Options::default();
+    let (env, targets) = build(SOURCE);
+    let hashes = compute_verification_hashes(&env, &options, &targets);
+
+    // A run in which `M::twice` fails and the other functions verify.
+    let report = VerificationReport {
+        functions: hashes
+            .keys()
+            .map(|fun_id| {
+                let func_env = env.get_function(*fun_id);
+                FunctionReport {
+                    name: func_env.get_full_name_str(),
+                    fun_id: *fun_id,
+                    location: None,
+                    status: if func_env.get_name_string().to_string() == "M::twice" {
+                        VerificationStatus::Failed
+                    } else {
+                        VerificationStatus::Verified
+                    },
+                    cached: false,
+                    errors: vec![],
+                }
+            })
+            .collect(),
+        errors: vec![],
+    };
+    let mut cache = VerificationCache::load(dir.path());
+    cache.record_verified(&hashes, &report);
+    cache.save().unwrap();
+
+    let cache = VerificationCache::load(dir.path());
+    for (fun_id, hash) in &hashes {
+        let func_env = env.get_function(*fun_id);
+        assert_eq!(
+            cache.is_verified(&func_env.get_full_name_str(), hash),
+            func_env.get_name_string().to_string() != "M::twice"
+        );
+    }
+}
@@ -0,0 +1,31 @@
+// flag: --infer-loop-invariants
+// Loops without invariants which can only be verified with the inferred ones. Without the flag,
+// `length_by_iteration` fails to verify, see `loop_invariant_inference_disabled.move`.
+module 0x42::LoopInvariantInference {
+    use Std::Vector;
+
+    fun zero_all(v: &mut vector<u64>) {
+        let i = 0;
+        let len = Vector::length(v);
+        while (i < len) {
+            *Vector::borrow_mut(v, i) = 0;
+            i = i + 1;
+        }
+    }
+    spec zero_all {
+        aborts_if false;
+        ensures len(v) == len(old(v));
+    }
+
+    fun length_by_iteration(v: &vector<u64>): u64 {
+        let i = 0;
+        while (i < Vector::length(v)) {
+            i = i + 1;
+        };
+        i
+    }
+    spec length_by_iteration {
+        aborts_if false;
+        ensures result == len(v);
+    }
+}
@@ -0,0 +1,17 @@
+// A loop without invariants which fails to verify, as nothing is known about the counter after
+// the loop. It verifies with the inferred loop invariants, see `loop_invariant_inference.move`.
+module 0x42::LoopInvariantInferenceDisabled {
+    use Std::Vector;
+
+    fun length_by_iteration(v: &vector<u64>): u64 {
+        let i = 0;
+        while (i < Vector::length(v)) {
+            i = i + 1;
+        };
+        i
+    }
+    spec length_by_iteration {
+        aborts_if false;
+        ensures result == len(v);
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_compiler::shared::PackagePaths;
+use move_model::run_model_builder;
+use move_prover::spec_suggestions::{infer_modifies, insert_modifies};
+use std::{collections::BTreeMap, fs};
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"module 0x42::M {
+    struct R has key { v: u64 }
+
+    public fun set(addr: address, v: u64) acquires R {
+        borrow_global_mut<R>(addr).v = v;
+    }
+    spec set {
+        aborts_if !exists<R>(addr);
+    }
+
+    public fun reset(addr: address) acquires R {
+        borrow_global_mut<R>(addr).v = 0;
+    }
+
+    public fun clear(addr: address) acquires R {
+        borrow_global_mut<R>(addr).v = 0;
+    }
+}
+"#;
+
+const SPEC_SOURCE: &str = r#"spec 0x42::M {
+    spec clear {
+        aborts_if !exists<R>(addr);
+    }
+}
+"#;
+
+const EXPECTED_SOURCE: &str = r#"module 0x42::M {
+    struct R has key { v: u64 }
+
+    public fun set(addr: address, v: u64) acquires R {
+        borrow_global_mut<R>(addr).v = v;
+    }
+    spec set {
+        aborts_if !exists<R>(addr);
+        modifies global<R>(addr);
+    }
+
+    public fun reset(addr: address) acquires R {
+        borrow_global_mut<R>(addr).v = 0;
+    }
+    spec reset {
+        modifies global<R>(addr);
+    }
+
+    public fun clear(addr: address) acquires R {
+        borrow_global_mut<R>(addr).v = 0;
+    }
+}
+"#;
+
+const EXPECTED_SPEC_SOURCE: &str = r#"spec 0x42::M {
+    spec clear {
+        aborts_if !exists<R>(addr);
+        modifies global<R>(addr);
+    }
+}
+"#;
+
+#[test]
+fn modifies_conditions_are_inserted_into_specs() {
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("M.move");
+    let spec_path = dir.path().join("M.spec.move");
+    fs::write(&source_path, SOURCE).unwrap();
+    fs::write(&spec_path, SPEC_SOURCE).unwrap();
+    let env = run_model_builder(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![
+                source_path.to_string_lossy().to_string(),
+                spec_path.to_string_lossy().to_string(),
+            ],
+            named_address_map: BTreeMap::<String, _>::new(),
+        }],
+        vec![],
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+
+    let targets = infer_modifies(&env);
+    insert_modifies(&env, &targets).unwrap();
+    assert_eq!(fs::read_to_string(&source_path).unwrap(), EXPECTED_SOURCE);
+    assert_eq!(
+        fs::read_to_string(&spec_path).unwrap(),
+        EXPECTED_SPEC_SOURCE
+    );
+
+    // The updated sources must still build.
+    let env = run_model_builder(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![
+                source_path.to_string_lossy().to_string(),
+                spec_path.to_string_lossy().to_string(),
+            ],
+            named_address_map: BTreeMap::<String, _>::new(),
+        }],
+        vec![],
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+}
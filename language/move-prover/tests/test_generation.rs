@@ -0,0 +1,218 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_compiler::{
+    diagnostics::report_diagnostics_to_buffer,
+    shared::{Flags, NumericalAddress, PackagePaths},
+    Compiler,
+};
+use move_model::{model::GlobalEnv, run_model_builder};
+use move_prover::test_generation::generate_tests;
+use move_prover_boogie_backend::{
+    boogie_wrapper::BoogieErrorKind,
+    verification_report::{
+        AbortReport, Binding, Counterexample, ErrorReport, FunctionReport, MemoryReport, TraceStep,
+        VerificationReport, VerificationStatus,
+    },
+};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fs};
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"
+module 0x42::M {
+    struct R has key { v: u64 }
+
+    public fun inc(x: u64): u64 { x + 1 }
+    spec inc { ensures result == x + 2; }
+
+    public fun check(account: &signer, bytes: vector<u8>, flag: bool) {
+        let _ = account;
+        let _ = bytes;
+        assert!(!flag, 7);
+    }
+    spec check { aborts_if false; }
+
+    public fun get(addr: address): u64 acquires R { borrow_global<R>(addr).v }
+    spec get { aborts_if false; }
+
+    fun private_inc(x: u64): u64 { x + 1 }
+    spec private_inc { ensures result == x; }
+}
+"#;
+
+const EXPECTED: &str = r#"// Generated by the Move prover from counterexamples found in module 0x42::M.
+//
+// Counterexamples which could not be turned into tests:
+// - get_counterexample_1: the counterexample requires resources of type `M::R` to exist
+// - private_inc_counterexample_1: the function is not public
+#[test_only]
+module 0x42::M_prover_tests {
+    use 0x42::M;
+
+    // function does not abort under this condition
+    // aborts_if false;
+    #[test(account = @0x1)]
+    #[expected_failure(abort_code = 7)]
+    fun check_counterexample_1(account: signer) {
+        M::check(&account, vector[1u8, 0u8, 0u8], true);
+    }
+
+    // post-condition does not hold
+    // ensures result == x + 2;
+    #[test]
+    fun inc_counterexample_1() {
+        let result = M::inc(41);
+        assert!(result == 42, 0);
+    }
+}
+"#;
+
+fn binding(name: &str, ty: &str, value: Value) -> Binding {
+    Binding {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        value,
+    }
+}
+
+fn function(
+    env: &GlobalEnv,
+    name: &str,
+    message: &str,
+    source: &str,
+    counterexample: Counterexample,
+) -> FunctionReport {
+    let module_env = env.get_modules().next().unwrap();
+    let fun_env = module_env
+        .get_functions()
+        .find(|fun_env| fun_env.get_simple_name_string().as_str() == name)
+        .unwrap();
+    FunctionReport {
+        name: fun_env.get_full_name_str(),
+        fun_id: fun_env.get_qualified_id(),
+        location: None,
+        status: VerificationStatus::Failed,
+        cached: false,
+        errors: vec![ErrorReport {
+            kind: BoogieErrorKind::Assertion,
+            message: message.to_string(),
+            location: None,
+            source: Some(source.to_string()),
+            counterexample: Some(counterexample),
+        }],
+    }
+}
+
+#[test]
+fn tests_are_generated_from_counterexamples() {
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("M.move");
+    fs::write(&source_path, SOURCE).unwrap();
+    let source_path = source_path.to_string_lossy().to_string();
+    let env = run_model_builder(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![source_path.clone()],
+            named_address_map: BTreeMap::<String, _>::new(),
+        }],
+        vec![],
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+
+    let report = VerificationReport {
+        functions: vec![
+            function(
+                &env,
+                "check",
+                "function does not abort under this condition",
+                "aborts_if false;",
+                Counterexample {
+                    arguments: vec![
+                        binding("account", "signer", json!("0x1")),
+                        binding(
+                            "bytes",
+                            "vector<u8>",
+                            json!({"size": 3, "elements": {"0": 1}, "default": 0}),
+                        ),
+                        binding("flag", "bool", json!(true)),
+                    ],
+                    abort: Some(AbortReport {
+                        location: None,
+                        code: Some(7),
+                    }),
+                    ..Default::default()
+                },
+            ),
+            function(
+                &env,
+                "get",
+                "function does not abort under this condition",
+                "aborts_if false;",
+                Counterexample {
+                    arguments: vec![binding("addr", "address", json!("0x2"))],
+                    memory: vec![MemoryReport {
+                        resource: "M::R".to_string(),
+                        before: json!({"0x2": {"v": 1}, "default": null}),
+                        after: None,
+                    }],
+                    ..Default::default()
+                },
+            ),
+            function(
+                &env,
+                "inc",
+                "post-condition does not hold",
+                "ensures result == x + 2;",
+                Counterexample {
+                    arguments: vec![binding("x", "u64", json!(41))],
+                    trace: vec![TraceStep {
+                        location: None,
+                        function: Some("M::inc".to_string()),
+                        bindings: vec![binding("result", "u64", json!(42))],
+                    }],
+                    ..Default::default()
+                },
+            ),
+            function(
+                &env,
+                "private_inc",
+                "post-condition does not hold",
+                "ensures result == x;",
+                Counterexample::default(),
+            ),
+        ],
+        errors: vec![],
+    };
+
+    let modules = generate_tests(&env, &report);
+    assert_eq!(modules.len(), 1);
+    let generated = &modules["M_prover_tests"];
+    assert_eq!(generated, EXPECTED);
+
+    // The generated module must compile in test mode together with the module under test.
+    let test_path = dir.path().join("M_prover_tests.move");
+    fs::write(&test_path, generated).unwrap();
+    let unit_test_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../move-stdlib/sources/UnitTest.move"
+    );
+    let (files, units) = Compiler::from_files(
+        vec![source_path, test_path.to_string_lossy().to_string()],
+        vec![unit_test_path.to_string()],
+        BTreeMap::from([(
+            "Std".to_string(),
+            NumericalAddress::parse_str("0x1").unwrap(),
+        )]),
+    )
+    .set_flags(Flags::testing())
+    .build()
+    .unwrap();
+    if let Err(diags) = units {
+        panic!(
+            "{}",
+            String::from_utf8_lossy(&report_diagnostics_to_buffer(&files, diags))
+        );
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use codespan::Span;
+use move_compiler::shared::PackagePaths;
+use move_model::{
+    code_writer::CodeWriter,
+    model::{GlobalEnv, Loc},
+    run_model_builder_with_options,
+};
+use move_prover::{cli::Options, create_and_process_bytecode};
+use move_prover_boogie_backend::{
+    boogie_wrapper::{BoogieError, BoogieErrorKind, BoogieWrapper},
+    verification_report::VerificationStatus,
+};
+use std::{collections::BTreeMap, fs};
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"
+module 0x42::M {
+    fun inc(x: u64): u64 { x + 1 }
+    spec inc { ensures result == x + 2; }
+
+    fun loop_forever() { loop {} }
+
+    fun one(): u64 { 1 }
+    spec one { ensures result == 1; }
+}
+"#;
+
+fn build_env() -> GlobalEnv {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("M.move");
+    fs::write(&path, SOURCE).unwrap();
+    let env = run_model_builder_with_options(
+        vec![PackagePaths {
+            name: None,
+            paths: vec![path.to_string_lossy().to_string()],
+            named_address_map: BTreeMap::<String, _>::new(),
+        }],
+        vec![],
+        Options::default().model_builder,
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+    env
+}
+
+/// Returns the location of the first occurrence of `text` in the source.
+fn loc_of(env: &GlobalEnv, text: &str) -> Loc {
+    let module_loc = env.get_modules().next().unwrap().get_loc();
+    let start = SOURCE.find(text).unwrap() as u32;
+    Loc::new(
+        module_loc.file_id(),
+        Span::new(start, start + text.len() as u32),
+    )
+}
+
+fn error(kind: BoogieErrorKind, loc: Loc, message: &str) -> BoogieError {
+    BoogieError {
+        kind,
+        loc,
+        message: message.to_string(),
+        execution_trace: vec![],
+        model: None,
+    }
+}
+
+#[test]
+fn errors_are_attributed_to_functions() {
+    let env = build_env();
+    let options = Options::default();
+    env.set_extension(options.prover.clone());
+    let targets = create_and_process_bytecode(&options, &env);
+    let writer = CodeWriter::new(env.internal_loc());
+    let wrapper = BoogieWrapper {
+        env: &env,
+        targets: &targets,
+        writer: &writer,
+        options: &options.backend,
+    };
+
+    let report = wrapper.make_report(&[
+        error(
+            BoogieErrorKind::Assertion,
+            loc_of(&env, "ensures result == x + 2;"),
+            "post-condition does not hold",
+        ),
+        error(
+            BoogieErrorKind::Inconclusive,
+            loc_of(&env, "loop {}"),
+            "verification out of resources/timeout",
+        ),
+    ]);
+    let statuses: Vec<_> = report
+        .functions
+        .iter()
+        .map(|function| (function.name.as_str(), function.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("M::inc", VerificationStatus::Failed),
+            ("M::loop_forever", VerificationStatus::Timeout),
+            ("M::one", VerificationStatus::Verified),
+        ]
+    );
+    assert!(report.errors.is_empty());
+
+    let failure = &report.functions[0].errors[0];
+    assert_eq!(failure.source.as_deref(), Some("ensures result == x + 2;"));
+    let span = failure.location.as_ref().unwrap();
+    assert_eq!((span.line, span.column), (4, 16));
+
+    // An error which cannot be attributed makes the outcome of all functions unknown.
+    let report = wrapper.make_report(&[error(
+        BoogieErrorKind::Internal,
+        env.unknown_loc(),
+        "Boogie execution exceeded hard timeout",
+    )]);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].location.is_none());
+    assert!(report
+        .functions
+        .iter()
+        .all(|function| function.status == VerificationStatus::Unknown));
+}
+
+#[test]
+fn timeouts_and_times_are_attributed_to_functions() {
+    let env = build_env();
+    let options = Options::default();
+    env.set_extension(options.prover.clone());
+    let targets = create_and_process_bytecode(&options, &env);
+    let writer = CodeWriter::new(env.internal_loc());
+    let wrapper = BoogieWrapper {
+        env: &env,
+        targets: &targets,
+        writer: &writer,
+        options: &options.backend,
+    };
+
+    let timed_out = wrapper.timed_out_functions(&[
+        error(
+            BoogieErrorKind::Assertion,
+            loc_of(&env, "ensures result == x + 2;"),
+            "post-condition does not hold",
+        ),
+        error(
+            BoogieErrorKind::Inconclusive,
+            loc_of(&env, "loop {}"),
+            "verification out of resources/timeout",
+        ),
+    ]);
+    let names: Vec<_> = timed_out
+        .iter()
+        .map(|fun_id| env.get_function(*fun_id).get_full_name_str())
+        .collect();
+    assert_eq!(names, vec!["M::loop_forever"]);
+
+    let output = r#"
+Verifying $42_M_inc$verify ...
+  [0.250 s, solver resource count: 1024, 2 proof obligations]  error
+Verifying $42_M_loop_forever$verify ...
+  [40.000 s, 1 proof obligation]  timed out
+Verifying $42_M_inc$verify_inconsistency ...
+  [0.125 s, 1 proof obligation]  verified
+Verifying $42_M_loop_forever$verify ...
+  [2.500 s, 3 proof obligations]  verified
+Verifying $1_vector_length ...
+  [0.010 s, 1 proof obligation]  verified
+"#;
+    let times: Vec<_> = wrapper
+        .extract_verification_times(output)
+        .into_iter()
+        .map(|(fun_id, time)| (env.get_function(fun_id).get_full_name_str(), time))
+        .collect();
+    assert_eq!(
+        times,
+        vec![
+            ("M::inc".to_string(), 0.375),
+            ("M::loop_forever".to_string(), 42.5),
+        ]
+    );
+}
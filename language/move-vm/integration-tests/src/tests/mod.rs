@@ -8,3 +8,4 @@ mod function_arg_tests;
 mod loader_tests;
 mod mutated_accounts_tests;
 mod return_value_tests;
+mod storage_metering_tests;
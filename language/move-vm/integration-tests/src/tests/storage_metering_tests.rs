@@ -0,0 +1,260 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compiler::{as_module, compile_units};
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasUnits, InternalGasUnits, StorageGasSchedule},
+    identifier::Identifier,
+    language_storage::ModuleId,
+    value::{serialize_values, MoveValue},
+    vm_status::StatusCode,
+};
+use move_vm_runtime::move_vm::MoveVM;
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas_schedule::{GasStatus, INITIAL_COST_SCHEDULE};
+
+const TEST_ADDR: AccountAddress = AccountAddress::new([42; AccountAddress::LENGTH]);
+
+fn compile_test_module() -> (ModuleId, Vec<u8>) {
+    let code = r#"
+        module {{ADDR}}::M {
+            struct Foo has key { n: u64, v: vector<u64> }
+            public fun publish(addr: &signer) {
+                move_to(addr, Foo { n: 4, v: vector[1, 2, 3, 4] })
+            }
+            public fun len(addr: address): u64 acquires Foo {
+                let n = borrow_global<Foo>(addr).n;
+                n + borrow_global<Foo>(addr).n
+            }
+            public fun remove(addr: address) acquires Foo {
+                let Foo { n: _, v: _ } = move_from<Foo>(addr);
+            }
+            public fun hold(n: u64, v: vector<u64>): vector<u64> {
+                if (n == 0) return v;
+                let w = copy v;
+                let _ = hold(n - 1, v);
+                w
+            }
+        }
+    "#;
+    let code = code.replace("{{ADDR}}", &format!("0x{}", TEST_ADDR));
+    let mut units = compile_units(&code).unwrap();
+    let m = as_module(units.pop().unwrap());
+    let mut blob = vec![];
+    m.serialize(&mut blob).unwrap();
+    (m.self_id(), blob)
+}
+
+#[test]
+fn storage_metering() {
+    let (module_id, blob) = compile_test_module();
+    let schedule = StorageGasSchedule::default();
+
+    let mut storage = InMemoryStorage::new();
+    let vm = MoveVM::new(vec![]).unwrap();
+    let account = AccountAddress::random();
+
+    // Publish the module and a resource.
+    let mut gas_status = GasStatus::new(&INITIAL_COST_SCHEDULE, GasUnits::new(1_000_000))
+        .with_storage_metering(schedule.clone());
+    let mut sess = vm.new_session(&storage);
+    sess.publish_module(blob.clone(), TEST_ADDR, &mut gas_status)
+        .unwrap();
+    sess.execute_function_bypass_visibility(
+        &module_id,
+        &Identifier::new("publish").unwrap(),
+        vec![],
+        serialize_values(&vec![MoveValue::Signer(account)]),
+        &mut gas_status,
+    )
+    .unwrap();
+    let (changes, _, summary) = sess.finish_with_summary(&mut gas_status).unwrap();
+
+    let resource_size = changes
+        .resources()
+        .map(|(_, _, blob_opt)| blob_opt.unwrap().len() as u64)
+        .sum::<u64>();
+    assert_eq!(summary.module_bytes_published, blob.len() as u64);
+    assert_eq!(
+        summary.module_publish_gas,
+        blob.len() as u64 * schedule.module_publish_per_byte.get()
+    );
+    assert_eq!(summary.bytes_written, resource_size);
+    assert_eq!(
+        summary.write_gas,
+        resource_size * schedule.global_write_per_byte.get()
+    );
+    assert_eq!(summary.bytes_read, 0);
+    assert!(summary.peak_value_memory > 0);
+    storage.apply(changes).unwrap();
+
+    // Reading a resource is charged once per session, no matter how often it is borrowed.
+    let mut gas_status = GasStatus::new(&INITIAL_COST_SCHEDULE, GasUnits::new(1_000_000))
+        .with_storage_metering(schedule.clone());
+    let mut sess = vm.new_session(&storage);
+    sess.execute_function_bypass_visibility(
+        &module_id,
+        &Identifier::new("len").unwrap(),
+        vec![],
+        serialize_values(&vec![MoveValue::Address(account)]),
+        &mut gas_status,
+    )
+    .unwrap();
+    let (changes, _, summary) = sess.finish_with_summary(&mut gas_status).unwrap();
+    assert!(changes.accounts().is_empty());
+    assert_eq!(summary.bytes_read, resource_size);
+    assert_eq!(
+        summary.read_gas,
+        resource_size * schedule.global_read_per_byte.get()
+    );
+    assert_eq!(summary.bytes_written, 0);
+
+    // Deletions are recorded, but not charged.
+    let mut gas_status = GasStatus::new_unmetered().with_storage_metering(schedule);
+    let mut sess = vm.new_session(&storage);
+    sess.execute_function_bypass_visibility(
+        &module_id,
+        &Identifier::new("remove").unwrap(),
+        vec![],
+        serialize_values(&vec![MoveValue::Address(account)]),
+        &mut gas_status,
+    )
+    .unwrap();
+    let (_, _, summary) = sess.finish_with_summary(&mut gas_status).unwrap();
+    assert_eq!(summary.resources_deleted, 1);
+    assert_eq!(summary.write_gas, 0);
+    assert_eq!(
+        summary.total_gas(),
+        summary.read_gas + summary.value_memory_gas
+    );
+}
+
+#[test]
+fn storage_metering_out_of_gas() {
+    let (_, blob) = compile_test_module();
+
+    let storage = InMemoryStorage::new();
+    let vm = MoveVM::new(vec![]).unwrap();
+
+    // Not enough gas to pay for the module code.
+    let mut gas_status = GasStatus::new(&INITIAL_COST_SCHEDULE, GasUnits::new(1))
+        .with_storage_metering(StorageGasSchedule::default());
+    let mut sess = vm.new_session(&storage);
+    let err = sess
+        .publish_module(blob, TEST_ADDR, &mut gas_status)
+        .unwrap_err();
+    assert_eq!(err.major_status(), StatusCode::OUT_OF_GAS);
+}
+
+#[test]
+fn value_memory_grows_with_values_held() {
+    let (module_id, blob) = compile_test_module();
+    let mut storage = InMemoryStorage::new();
+    storage.publish_or_overwrite_module(module_id.clone(), blob);
+    let vm = MoveVM::new(vec![]).unwrap();
+
+    let peak_value_memory = |depth: u64, len: u64| {
+        let mut gas_status =
+            GasStatus::new_unmetered().with_storage_metering(StorageGasSchedule::zero());
+        let mut sess = vm.new_session(&storage);
+        sess.execute_function_bypass_visibility(
+            &module_id,
+            &Identifier::new("hold").unwrap(),
+            vec![],
+            serialize_values(&vec![
+                MoveValue::U64(depth),
+                MoveValue::Vector((0..len).map(MoveValue::U64).collect()),
+            ]),
+            &mut gas_status,
+        )
+        .unwrap();
+        sess.finish_with_summary(&mut gas_status)
+            .unwrap()
+            .2
+            .peak_value_memory
+    };
+
+    // Each active call holds a copy of the vector.
+    assert!(peak_value_memory(1, 100) > peak_value_memory(1, 10));
+    assert!(peak_value_memory(10, 100) > peak_value_memory(1, 100));
+    assert!(peak_value_memory(20, 100) - peak_value_memory(10, 100) > 10 * 100 * 8);
+}
+
+#[test]
+fn value_memory_grows_through_references() {
+    let code = r#"
+        module 0x1::Vector {
+            native public fun push_back<Element>(v: &mut vector<Element>, e: Element);
+        }
+        module {{ADDR}}::Grow {
+            use 0x1::Vector;
+            public fun grow(n: u64) {
+                let v = vector[];
+                let i = 0;
+                while (i < n) {
+                    Vector::push_back(&mut v, i);
+                    i = i + 1;
+                };
+            }
+        }
+    "#;
+    let code = code.replace("{{ADDR}}", &format!("0x{}", TEST_ADDR));
+    let mut storage = InMemoryStorage::new();
+    let mut module_id = None;
+    for unit in compile_units(&code).unwrap() {
+        let m = as_module(unit);
+        let mut blob = vec![];
+        m.serialize(&mut blob).unwrap();
+        module_id = Some(m.self_id());
+        storage.publish_or_overwrite_module(m.self_id(), blob);
+    }
+    let module_id = module_id.unwrap();
+    let vm = MoveVM::new(move_stdlib::natives::all_natives(
+        AccountAddress::from_hex_literal("0x1").unwrap(),
+    ))
+    .unwrap();
+
+    let peak_value_memory = |n: u64| {
+        let mut gas_status =
+            GasStatus::new_unmetered().with_storage_metering(StorageGasSchedule::zero());
+        let mut sess = vm.new_session(&storage);
+        sess.execute_function_bypass_visibility(
+            &module_id,
+            &Identifier::new("grow").unwrap(),
+            vec![],
+            serialize_values(&vec![MoveValue::U64(n)]),
+            &mut gas_status,
+        )
+        .unwrap();
+        sess.finish_with_summary(&mut gas_status)
+            .unwrap()
+            .2
+            .peak_value_memory
+    };
+
+    // The vector held in a local grows in place, without being moved or passed to a Move
+    // function.
+    assert!(peak_value_memory(100) - peak_value_memory(10) >= 90 * 8);
+}
+
+#[test]
+fn storage_cost_saturates() {
+    let (_, blob) = compile_test_module();
+
+    let storage = InMemoryStorage::new();
+    let vm = MoveVM::new(vec![]).unwrap();
+
+    // A cost which overflows is charged as the maximal cost.
+    let schedule = StorageGasSchedule {
+        module_publish_per_byte: InternalGasUnits::new(u64::MAX),
+        ..StorageGasSchedule::zero()
+    };
+    let mut gas_status = GasStatus::new(&INITIAL_COST_SCHEDULE, GasUnits::new(1_000_000))
+        .with_storage_metering(schedule);
+    let mut sess = vm.new_session(&storage);
+    let err = sess
+        .publish_module(blob, TEST_ADDR, &mut gas_status)
+        .unwrap_err();
+    assert_eq!(err.major_status(), StatusCode::OUT_OF_GAS);
+}
@@ -7,6 +7,7 @@ use move_binary_format::errors::*;
 use move_core_types::{
     account_address::AccountAddress,
     effects::{AccountChangeSet, ChangeSet, Event},
+    gas_schedule::{AbstractMemorySize, GasAlgebra, GasCarrier},
     identifier::Identifier,
     language_storage::{ModuleId, TypeTag},
     resolver::MoveResolver,
@@ -14,7 +15,7 @@ use move_core_types::{
     vm_status::StatusCode,
 };
 use move_vm_types::{
-    data_store::DataStore,
+    data_store::{DataStore, ResourceLoad},
     loaded_data::runtime_types::Type,
     values::{GlobalValue, GlobalValueEffect, Value},
 };
@@ -153,10 +154,19 @@ impl<'r, 'l, S: MoveResolver> DataStore for TransactionDataCache<'r, 'l, S> {
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<&mut GlobalValue> {
+        Ok(self.load_resource_with_size(addr, ty)?.0)
+    }
+
+    fn load_resource_with_size(
+        &mut self,
+        addr: AccountAddress,
+        ty: &Type,
+    ) -> PartialVMResult<(&mut GlobalValue, ResourceLoad)> {
         let account_cache = Self::get_mut_or_insert_with(&mut self.account_map, &addr, || {
             (addr, AccountDataCache::new())
         });
 
+        let mut load_res = ResourceLoad::Cached;
         if !account_cache.data_map.contains_key(ty) {
             let ty_tag = match self.loader.type_to_type_tag(ty)? {
                 TypeTag::Struct(s_tag) => s_tag,
@@ -182,9 +192,14 @@ impl<'r, 'l, S: MoveResolver> DataStore for TransactionDataCache<'r, 'l, S> {
                         }
                     };
 
+                    load_res =
+                        ResourceLoad::Found(AbstractMemorySize::new(blob.len() as GasCarrier));
                     GlobalValue::cached(val)?
                 }
-                Ok(None) => GlobalValue::none(),
+                Ok(None) => {
+                    load_res = ResourceLoad::NotFound;
+                    GlobalValue::none()
+                }
                 Err(err) => {
                     let msg = format!("Unexpected storage error: {:?}", err);
                     return Err(
@@ -197,11 +212,14 @@ impl<'r, 'l, S: MoveResolver> DataStore for TransactionDataCache<'r, 'l, S> {
             account_cache.data_map.insert(ty.clone(), (ty_layout, gv));
         }
 
-        Ok(account_cache
-            .data_map
-            .get_mut(ty)
-            .map(|(_ty_layout, gv)| gv)
-            .expect("global value must exist"))
+        Ok((
+            account_cache
+                .data_map
+                .get_mut(ty)
+                .map(|(_ty_layout, gv)| gv)
+                .expect("global value must exist"),
+            load_res,
+        ))
     }
 
     fn load_module(&self, module_id: &ModuleId) -> VMResult<Vec<u8>> {
@@ -9,7 +9,7 @@ use crate::{
 use fail::fail_point;
 use move_binary_format::{
     errors::*,
-    file_format::{Bytecode, FunctionHandleIndex, FunctionInstantiationIndex},
+    file_format::{Bytecode, FunctionHandleIndex, FunctionInstantiationIndex, SignatureToken},
     file_format_common::Opcodes,
 };
 use move_core_types::{
@@ -18,7 +18,7 @@ use move_core_types::{
     vm_status::{StatusCode, StatusType},
 };
 use move_vm_types::{
-    data_store::DataStore,
+    data_store::{DataStore, ResourceLoad},
     gas_schedule::GasStatus,
     loaded_data::runtime_types::Type,
     values::{
@@ -65,6 +65,8 @@ pub(crate) struct Interpreter {
     operand_stack: Stack,
     /// The stack of active functions.
     call_stack: CallStack,
+    /// The total size of the locals of all active functions, tracked when storage is metered.
+    locals_size: Option<u64>,
 }
 
 impl Interpreter {
@@ -93,6 +95,7 @@ impl Interpreter {
         Interpreter {
             operand_stack: Stack::new(),
             call_stack: CallStack::new(),
+            locals_size: None,
         }
     }
 
@@ -140,18 +143,30 @@ impl Interpreter {
         }
 
         let mut current_frame = Frame::new(function, ty_args, locals);
+        if gas_status.is_storage_metered() {
+            self.locals_size = Some(current_frame.locals.size().get());
+            self.operand_stack.size = Some(0);
+        }
         loop {
             let resolver = current_frame.resolver(loader);
             let exit_code = current_frame //self
                 .execute_code(&resolver, self, data_store, gas_status)
-                .map_err(|err| self.maybe_core_dump(err, &current_frame))?;
+                .map_err(|err| {
+                    self.maybe_attach_exec_state(self.maybe_core_dump(err, &current_frame), None)
+                })?;
             match exit_code {
                 ExitCode::Return => {
+                    // The locals of the frame may have changed in place through references, so
+                    // take their actual size rather than a tracked one
+                    if let Some(locals_size) = &mut self.locals_size {
+                        *locals_size =
+                            locals_size.saturating_sub(current_frame.locals.size().get());
+                    }
                     if let Some(frame) = self.call_stack.pop() {
                         current_frame = frame;
                         current_frame.pc += 1; // advance past the Call instruction in the caller
                     } else {
-                        return Ok(mem::take(&mut self.operand_stack.0));
+                        return Ok(mem::take(&mut self.operand_stack.values));
                     }
                 }
                 ExitCode::Call(fh_idx) => {
@@ -173,7 +188,8 @@ impl Interpreter {
                             extensions,
                             func,
                             vec![],
-                        )?;
+                        )
+                        .map_err(|err| self.maybe_attach_exec_state(err, Some(&current_frame)))?;
                         current_frame.pc += 1; // advance past the Call instruction in the caller
                         continue;
                     }
@@ -186,6 +202,8 @@ impl Interpreter {
                         self.maybe_core_dump(err, &frame)
                     })?;
                     current_frame = frame;
+                    self.record_value_memory(gas_status)
+                        .map_err(|e| set_err_info!(current_frame, e))?;
                 }
                 ExitCode::CallGeneric(idx) => {
                     let arity = resolver.type_params_count(idx);
@@ -208,7 +226,8 @@ impl Interpreter {
                     if func.is_native() {
                         self.call_native(
                             &resolver, data_store, gas_status, extensions, func, ty_args,
-                        )?;
+                        )
+                        .map_err(|err| self.maybe_attach_exec_state(err, Some(&current_frame)))?;
                         current_frame.pc += 1; // advance past the Call instruction in the caller
                         continue;
                     }
@@ -221,11 +240,44 @@ impl Interpreter {
                         self.maybe_core_dump(err, &frame)
                     })?;
                     current_frame = frame;
+                    self.record_value_memory(gas_status)
+                        .map_err(|e| set_err_info!(current_frame, e))?;
                 }
             }
         }
     }
 
+    /// Record the memory held in values (operand stack and the locals of all frames) with the
+    /// gas meter. This is only done when storage metering is enabled, and it is sampled after
+    /// every instruction and call. The sizes are tracked as values are pushed on and popped off
+    /// the operand stack, stored in and moved out of locals, and changed in place through
+    /// references to locals (see `record_referenced_change`). Values in global storage are not
+    /// included, as reads and writes of global storage are charged separately.
+    fn record_value_memory(&self, gas_status: &mut GasStatus) -> PartialVMResult<()> {
+        match (self.locals_size, self.operand_stack.size) {
+            (Some(locals_size), Some(stack_size)) => gas_status.record_value_memory(
+                AbstractMemorySize::new(locals_size.saturating_add(stack_size)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record that a value held in a local of some frame changed in place through a reference,
+    /// from `size_before` to `size_after`. Sizes are `None` for values in global storage.
+    fn record_referenced_change(
+        &mut self,
+        size_before: Option<AbstractMemorySize<GasCarrier>>,
+        size_after: Option<AbstractMemorySize<GasCarrier>>,
+    ) {
+        if let (Some(locals_size), Some(size_before), Some(size_after)) =
+            (&mut self.locals_size, size_before, size_after)
+        {
+            *locals_size = locals_size
+                .saturating_add(size_after.get())
+                .saturating_sub(size_before.get());
+        }
+    }
+
     /// Returns a `Frame` if the call is to a Move function. Calls to native functions are
     /// "inlined" and this returns `None`.
     ///
@@ -242,7 +294,11 @@ impl Interpreter {
                 )
                 .map_err(|e| self.set_location(e))?;
         }
-        Ok(Frame::new(func, ty_args, locals))
+        let frame = Frame::new(func, ty_args, locals);
+        if let Some(locals_size) = &mut self.locals_size {
+            *locals_size = locals_size.saturating_add(frame.locals.size().get());
+        }
+        Ok(frame)
     }
 
     /// Call a native functions.
@@ -290,6 +346,24 @@ impl Interpreter {
         for _ in 0..expected_args {
             arguments.push_front(self.operand_stack.pop()?);
         }
+        // Keep the mutable references to locals passed to the native function to measure how
+        // the values they point to change, e.g., for `Vector::push_back`
+        let mut mut_refs = vec![];
+        if self.locals_size.is_some() {
+            for (arg, ty) in arguments.iter().zip(&function.parameters().0) {
+                if matches!(ty, SignatureToken::MutableReference(_))
+                    && arg.referenced_local_size().is_some()
+                {
+                    mut_refs.push(arg.copy_value()?);
+                }
+            }
+        }
+        let referenced_size = |refs: &[Value]| {
+            AbstractMemorySize::new(refs.iter().fold(0, |size: u64, r| {
+                size.saturating_add(r.referenced_local_size().map_or(0, |s| s.get()))
+            }))
+        };
+        let size_before = referenced_size(&mut_refs);
         let mut native_context =
             NativeContext::new(self, data_store, gas_status, resolver, extensions);
         let native_function = function.get_native()?;
@@ -301,7 +375,8 @@ impl Interpreter {
         for value in return_values {
             self.operand_stack.push(value)?;
         }
-        Ok(())
+        self.record_referenced_change(Some(size_before), Some(referenced_size(&mut_refs)));
+        self.record_value_memory(gas_status)
     }
 
     /// Perform a binary operation to two values at the top of the stack.
@@ -339,14 +414,21 @@ impl Interpreter {
         self.binop(|lhs, rhs| Ok(Value::bool(f(lhs, rhs)?)))
     }
 
-    /// Load a resource from the data store.
+    /// Load a resource from the data store, charging for the bytes read from storage if the
+    /// resource was not cached yet.
     fn load_resource<'b>(
         data_store: &'b mut impl DataStore,
+        gas_status: &mut GasStatus,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<&'b mut GlobalValue> {
-        match data_store.load_resource(addr, ty) {
-            Ok(gv) => Ok(gv),
+        match data_store.load_resource_with_size(addr, ty) {
+            Ok((gv, load_res)) => {
+                if let ResourceLoad::Found(size) = load_res {
+                    gas_status.charge_global_read(size)?;
+                }
+                Ok(gv)
+            }
             Err(e) => {
                 error!(
                     "[VM] error loading resource at ({}, {:?}): {:?} from data store",
@@ -361,10 +443,11 @@ impl Interpreter {
     fn borrow_global(
         &mut self,
         data_store: &mut impl DataStore,
+        gas_status: &mut GasStatus,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
-        let g = Self::load_resource(data_store, addr, ty)?.borrow_global()?;
+        let g = Self::load_resource(data_store, gas_status, addr, ty)?.borrow_global()?;
         let size = g.size();
         self.operand_stack.push(g)?;
         Ok(size)
@@ -374,10 +457,11 @@ impl Interpreter {
     fn exists(
         &mut self,
         data_store: &mut impl DataStore,
+        gas_status: &mut GasStatus,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
-        let gv = Self::load_resource(data_store, addr, ty)?;
+        let gv = Self::load_resource(data_store, gas_status, addr, ty)?;
         let mem_size = gv.size();
         let exists = gv.exists()?;
         self.operand_stack.push(Value::bool(exists))?;
@@ -388,10 +472,11 @@ impl Interpreter {
     fn move_from(
         &mut self,
         data_store: &mut impl DataStore,
+        gas_status: &mut GasStatus,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
-        let resource = Self::load_resource(data_store, addr, ty)?.move_from()?;
+        let resource = Self::load_resource(data_store, gas_status, addr, ty)?.move_from()?;
         let size = resource.size();
         self.operand_stack.push(resource)?;
         Ok(size)
@@ -401,12 +486,13 @@ impl Interpreter {
     fn move_to(
         &mut self,
         data_store: &mut impl DataStore,
+        gas_status: &mut GasStatus,
         addr: AccountAddress,
         ty: &Type,
         resource: Value,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
         let size = resource.size();
-        Self::load_resource(data_store, addr, ty)?.move_to(resource)?;
+        Self::load_resource(data_store, gas_status, addr, ty)?.move_to(resource)?;
         Ok(size)
     }
 
@@ -512,7 +598,7 @@ impl Interpreter {
             self.debug_print_frame(buf, loader, i, frame)?;
         }
         debug_writeln!(buf, "Operand Stack:")?;
-        for (idx, val) in self.operand_stack.0.iter().enumerate() {
+        for (idx, val) in self.operand_stack.values.iter().enumerate() {
             // TODO: Currently we do not know the types of the values on the operand stack.
             // Revisit.
             debug_write!(buf, "    [{}] ", idx)?;
@@ -562,7 +648,7 @@ impl Interpreter {
         }
         internal_state.push_str(format!("Locals:\n{}\n", current_frame.locals).as_str());
         internal_state.push_str("Operand Stack:\n");
-        for value in &self.operand_stack.0 {
+        for value in &self.operand_stack.values {
             internal_state.push_str(format!("{}\n", value).as_str());
         }
         internal_state
@@ -572,15 +658,31 @@ impl Interpreter {
         err.finish(self.call_stack.current_location())
     }
 
-    fn get_internal_state(&self) -> ExecutionState {
+    /// Attach the frames of the call stack to an error raised during execution, so that tools
+    /// can report where it happened. The innermost frame is `current_frame` if given, e.g., for
+    /// errors of native functions, and otherwise the caller of the function the error is located
+    /// in. Errors raised outside of any call are left unchanged.
+    fn maybe_attach_exec_state(&self, err: VMError, current_frame: Option<&Frame>) -> VMError {
+        if !cfg!(feature = "stacktrace") || err.exec_state().is_some() {
+            return err;
+        }
+        let exec_state = self.get_internal_state(current_frame);
+        if exec_state.stack_trace().is_empty() {
+            return err;
+        }
+        let location = err.location().clone();
+        err.to_partial()
+            .with_exec_state(exec_state)
+            .finish(location)
+    }
+
+    fn get_internal_state(&self, current_frame: Option<&Frame>) -> ExecutionState {
         // collect frames in the reverse order as this is what is
         // normally expected from the stack trace (outermost frame
         // is the last one)
-        let stack_trace = self
-            .call_stack
-            .0
-            .iter()
-            .rev()
+        let stack_trace = current_frame
+            .into_iter()
+            .chain(self.call_stack.0.iter().rev())
             .map(|frame| {
                 (
                     frame.function.module_id().cloned(),
@@ -598,19 +700,29 @@ const OPERAND_STACK_SIZE_LIMIT: usize = 1024;
 const CALL_STACK_SIZE_LIMIT: usize = 1024;
 
 /// The operand stack.
-struct Stack(Vec<Value>);
+struct Stack {
+    values: Vec<Value>,
+    /// The total size of the values on the stack, tracked when storage is metered.
+    size: Option<u64>,
+}
 
 impl Stack {
     /// Create a new empty operand stack.
     fn new() -> Self {
-        Stack(vec![])
+        Stack {
+            values: vec![],
+            size: None,
+        }
     }
 
     /// Push a `Value` on the stack if the max stack size has not been reached. Abort execution
     /// otherwise.
     fn push(&mut self, value: Value) -> PartialVMResult<()> {
-        if self.0.len() < OPERAND_STACK_SIZE_LIMIT {
-            self.0.push(value);
+        if self.values.len() < OPERAND_STACK_SIZE_LIMIT {
+            if let Some(size) = &mut self.size {
+                *size = size.saturating_add(value.size().get());
+            }
+            self.values.push(value);
             Ok(())
         } else {
             Err(PartialVMError::new(StatusCode::EXECUTION_STACK_OVERFLOW))
@@ -619,9 +731,14 @@ impl Stack {
 
     /// Pop a `Value` off the stack or abort execution if the stack is empty.
     fn pop(&mut self) -> PartialVMResult<Value> {
-        self.0
+        let value = self
+            .values
             .pop()
-            .ok_or_else(|| PartialVMError::new(StatusCode::EMPTY_VALUE_STACK))
+            .ok_or_else(|| PartialVMError::new(StatusCode::EMPTY_VALUE_STACK))?;
+        if let Some(size) = &mut self.size {
+            *size = size.saturating_sub(value.size().get());
+        }
+        Ok(value)
     }
 
     /// Pop a `Value` of a given type off the stack. Abort if the value is not of the given
@@ -636,11 +753,16 @@ impl Stack {
     /// Pop n values off the stack.
     fn popn(&mut self, n: u16) -> PartialVMResult<Vec<Value>> {
         let remaining_stack_size = self
-            .0
+            .values
             .len()
             .checked_sub(n as usize)
             .ok_or_else(|| PartialVMError::new(StatusCode::EMPTY_VALUE_STACK))?;
-        let args = self.0.split_off(remaining_stack_size);
+        let args = self.values.split_off(remaining_stack_size);
+        if let Some(size) = &mut self.size {
+            *size = args
+                .iter()
+                .fold(*size, |size, value| size.saturating_sub(value.size().get()));
+        }
         Ok(args)
     }
 }
@@ -819,7 +941,9 @@ impl Frame {
                         interpreter.operand_stack.push(local)?;
                     }
                     Bytecode::MoveLoc(idx) => {
+                        let size_before = self.local_size(interpreter, *idx);
                         let local = self.locals.move_loc(*idx as usize)?;
+                        self.update_locals_size(interpreter, *idx, size_before);
                         gas_status.charge_instr_with_size(Opcodes::MOVE_LOC, local.size())?;
 
                         interpreter.operand_stack.push(local)?;
@@ -828,7 +952,9 @@ impl Frame {
                         let value_to_store = interpreter.operand_stack.pop()?;
                         gas_status
                             .charge_instr_with_size(Opcodes::ST_LOC, value_to_store.size())?;
+                        let size_before = self.local_size(interpreter, *idx);
                         self.locals.store_loc(*idx as usize, value_to_store)?;
+                        self.update_locals_size(interpreter, *idx, size_before);
                     }
                     Bytecode::Call(idx) => {
                         return Ok(ExitCode::Call(*idx));
@@ -936,7 +1062,12 @@ impl Frame {
                         let reference = interpreter.operand_stack.pop_as::<Reference>()?;
                         let value = interpreter.operand_stack.pop()?;
                         gas_status.charge_instr_with_size(Opcodes::WRITE_REF, value.size())?;
+                        let size_before = interpreter
+                            .locals_size
+                            .and_then(|_| reference.referenced_local_size());
+                        let size_after = size_before.map(|_| value.size());
                         reference.write_ref(value)?;
+                        interpreter.record_referenced_change(size_before, size_after);
                     }
                     Bytecode::CastU8 => {
                         gas_status.charge_instr(Opcodes::CAST_U8)?;
@@ -1043,7 +1174,7 @@ impl Frame {
                                 self.pc,
                             ));
                         if cfg!(feature = "testing") {
-                            return Err(error.with_exec_state(interpreter.get_internal_state()));
+                            return Err(error.with_exec_state(interpreter.get_internal_state(None)));
                         } else {
                             return Err(error);
                         }
@@ -1069,33 +1200,33 @@ impl Frame {
                     Bytecode::MutBorrowGlobal(sd_idx) | Bytecode::ImmBorrowGlobal(sd_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
-                        let size = interpreter.borrow_global(data_store, addr, &ty)?;
+                        let size = interpreter.borrow_global(data_store, gas_status, addr, &ty)?;
                         gas_status.charge_instr_with_size(Opcodes::MUT_BORROW_GLOBAL, size)?;
                     }
                     Bytecode::MutBorrowGlobalGeneric(si_idx)
                     | Bytecode::ImmBorrowGlobalGeneric(si_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.borrow_global(data_store, addr, &ty)?;
+                        let size = interpreter.borrow_global(data_store, gas_status, addr, &ty)?;
                         gas_status
                             .charge_instr_with_size(Opcodes::MUT_BORROW_GLOBAL_GENERIC, size)?;
                     }
                     Bytecode::Exists(sd_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
-                        let size = interpreter.exists(data_store, addr, &ty)?;
+                        let size = interpreter.exists(data_store, gas_status, addr, &ty)?;
                         gas_status.charge_instr_with_size(Opcodes::EXISTS, size)?;
                     }
                     Bytecode::ExistsGeneric(si_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.exists(data_store, addr, &ty)?;
+                        let size = interpreter.exists(data_store, gas_status, addr, &ty)?;
                         gas_status.charge_instr_with_size(Opcodes::EXISTS_GENERIC, size)?;
                     }
                     Bytecode::MoveFrom(sd_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
-                        let size = interpreter.move_from(data_store, addr, &ty)?;
+                        let size = interpreter.move_from(data_store, gas_status, addr, &ty)?;
                         // TODO: Have this calculate before pulling in the data based upon
                         // the size of the data that we are about to read in.
                         gas_status.charge_instr_with_size(Opcodes::MOVE_FROM, size)?;
@@ -1103,7 +1234,7 @@ impl Frame {
                     Bytecode::MoveFromGeneric(si_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.move_from(data_store, addr, &ty)?;
+                        let size = interpreter.move_from(data_store, gas_status, addr, &ty)?;
                         // TODO: Have this calculate before pulling in the data based upon
                         // the size of the data that we are about to read in.
                         gas_status.charge_instr_with_size(Opcodes::MOVE_FROM_GENERIC, size)?;
@@ -1118,7 +1249,8 @@ impl Frame {
                             .value_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
                         // REVIEW: Can we simplify Interpreter::move_to?
-                        let size = interpreter.move_to(data_store, addr, &ty, resource)?;
+                        let size =
+                            interpreter.move_to(data_store, gas_status, addr, &ty, resource)?;
                         gas_status.charge_instr_with_size(Opcodes::MOVE_TO, size)?;
                     }
                     Bytecode::MoveToGeneric(si_idx) => {
@@ -1130,7 +1262,8 @@ impl Frame {
                             .read_ref()?
                             .value_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.move_to(data_store, addr, &ty, resource)?;
+                        let size =
+                            interpreter.move_to(data_store, gas_status, addr, &ty, resource)?;
                         gas_status.charge_instr_with_size(Opcodes::MOVE_TO_GENERIC, size)?;
                     }
                     Bytecode::FreezeRef => {
@@ -1184,13 +1317,27 @@ impl Frame {
                         let vec_ref = interpreter.operand_stack.pop_as::<VectorRef>()?;
                         gas_status.charge_instr_with_size(Opcodes::VEC_PUSH_BACK, elem.size())?;
                         let vec_ty_arg = &resolver.instantiate_single_type(*si, self.ty_args())?;
+                        let size_before = interpreter
+                            .locals_size
+                            .and_then(|_| vec_ref.referenced_local_size());
                         vec_ref.push_back(elem, vec_ty_arg)?;
+                        interpreter.record_referenced_change(
+                            size_before,
+                            size_before.and_then(|_| vec_ref.referenced_local_size()),
+                        );
                     }
                     Bytecode::VecPopBack(si) => {
                         let vec_ref = interpreter.operand_stack.pop_as::<VectorRef>()?;
                         gas_status.charge_instr(Opcodes::VEC_POP_BACK)?;
                         let vec_ty_arg = &resolver.instantiate_single_type(*si, self.ty_args())?;
+                        let size_before = interpreter
+                            .locals_size
+                            .and_then(|_| vec_ref.referenced_local_size());
                         let value = vec_ref.pop(vec_ty_arg)?;
+                        interpreter.record_referenced_change(
+                            size_before,
+                            size_before.and_then(|_| vec_ref.referenced_local_size()),
+                        );
                         interpreter.operand_stack.push(value)?;
                     }
                     Bytecode::VecUnpack(si, num) => {
@@ -1212,6 +1359,7 @@ impl Frame {
                         vec_ref.swap(idx1, idx2, vec_ty_arg)?;
                     }
                 }
+                interpreter.record_value_memory(gas_status)?;
                 // invariant: advance to pc +1 is iff instruction at pc executed without aborting
                 self.pc += 1;
             }
@@ -1235,6 +1383,25 @@ impl Frame {
         &self.ty_args
     }
 
+    /// Returns the size of the local at `idx` if the size of locals is tracked.
+    fn local_size(&self, interpreter: &Interpreter, idx: u8) -> Option<u64> {
+        interpreter
+            .locals_size
+            .map(|_| self.locals.size_of_loc(idx as usize).get())
+    }
+
+    /// Updates the tracked size of the locals after the local at `idx` changed from
+    /// `size_before`.
+    fn update_locals_size(&self, interpreter: &mut Interpreter, idx: u8, size_before: Option<u64>) {
+        if let (Some(locals_size), Some(size_before)) = (&mut interpreter.locals_size, size_before)
+        {
+            let size_after = self.locals.size_of_loc(idx as usize).get();
+            *locals_size = locals_size
+                .saturating_add(size_after)
+                .saturating_sub(size_before);
+        }
+    }
+
     fn resolver<'a>(&self, loader: &'a Loader) -> Resolver<'a> {
         self.function.get_resolver(loader)
     }
@@ -19,6 +19,7 @@ use move_binary_format::{
 use move_bytecode_verifier::script_signature;
 use move_core_types::{
     account_address::AccountAddress,
+    gas_schedule::{AbstractMemorySize, GasAlgebra, GasCarrier},
     identifier::{IdentStr, Identifier},
     language_storage::{ModuleId, TypeTag},
     resolver::MoveResolver,
@@ -73,7 +74,7 @@ impl VMRuntime {
         modules: Vec<Vec<u8>>,
         sender: AccountAddress,
         data_store: &mut impl DataStore,
-        _gas_status: &mut GasStatus,
+        gas_status: &mut GasStatus,
     ) -> VMResult<()> {
         // deserialize the modules. Perform bounds check. After this indexes can be
         // used with the `[]` operator
@@ -103,6 +104,13 @@ impl VMRuntime {
             }
         }
 
+        // Charge for the module code before doing any expensive checks on it.
+        for blob in &modules {
+            gas_status
+                .charge_module_publish(AbstractMemorySize::new(blob.len() as GasCarrier))
+                .map_err(|e| e.finish(Location::Undefined))?;
+        }
+
         // Collect ids for modules that are published together
         let mut bundle_unverified = BTreeSet::new();
 
@@ -8,7 +8,8 @@ use crate::{
 use move_binary_format::{errors::*, file_format::LocalIndex};
 use move_core_types::{
     account_address::AccountAddress,
-    effects::{ChangeSet, Event},
+    effects::{ChangeSet, ChangeSetSummary, Event},
+    gas_schedule::{AbstractMemorySize, GasAlgebra, GasCarrier},
     identifier::IdentStr,
     language_storage::{ModuleId, TypeTag},
     resolver::MoveResolver,
@@ -199,6 +200,33 @@ impl<'r, 'l, S: MoveResolver> Session<'r, 'l, S> {
             .map_err(|e| e.finish(Location::Undefined))
     }
 
+    /// Same like `finish`, but also charges `gas_status` for the resources written and the
+    /// events emitted in this session, and returns the storage and memory usage recorded by
+    /// the gas meter along with the charges for it.
+    ///
+    /// Unlike `finish`, this can fail with `OUT_OF_GAS`.
+    pub fn finish_with_summary(
+        self,
+        gas_status: &mut GasStatus,
+    ) -> VMResult<(ChangeSet, Vec<Event>, ChangeSetSummary)> {
+        let (change_set, events) = self.finish()?;
+        for (_, _, blob_opt) in change_set.resources() {
+            match blob_opt {
+                Some(blob) => gas_status
+                    .charge_global_write(AbstractMemorySize::new(blob.len() as GasCarrier))
+                    .map_err(|e| e.finish(Location::Undefined))?,
+                None => gas_status.record_global_delete(),
+            }
+        }
+        for (_, _, _, blob) in &events {
+            gas_status
+                .charge_event(AbstractMemorySize::new(blob.len() as GasCarrier))
+                .map_err(|e| e.finish(Location::Undefined))?;
+        }
+        let summary = gas_status.storage_summary().clone();
+        Ok((change_set, events, summary))
+    }
+
     /// Same like `finish`, but also extracts the native context extensions from the session.
     pub fn finish_with_extensions(
         self,
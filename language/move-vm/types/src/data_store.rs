@@ -7,9 +7,23 @@ use crate::{
 };
 use move_binary_format::errors::{PartialVMResult, VMResult};
 use move_core_types::{
-    account_address::AccountAddress, language_storage::ModuleId, value::MoveTypeLayout,
+    account_address::AccountAddress,
+    gas_schedule::{AbstractMemorySize, GasCarrier},
+    language_storage::ModuleId,
+    value::MoveTypeLayout,
 };
 
+/// How a resource was obtained by `DataStore::load_resource_with_size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceLoad {
+    /// The resource was already cached by the data store, nothing was read from storage.
+    Cached,
+    /// The resource was read from remote storage, where it takes up the given number of bytes.
+    Found(AbstractMemorySize<GasCarrier>),
+    /// The resource was looked up in remote storage, which does not contain it.
+    NotFound,
+}
+
 /// Provide an implementation for bytecodes related to data with a given data store.
 ///
 /// The `DataStore` is a generic concept that includes both data and events.
@@ -29,6 +43,16 @@ pub trait DataStore {
         ty: &Type,
     ) -> PartialVMResult<&mut GlobalValue>;
 
+    /// Like `load_resource`, but also report whether this call read the resource from remote
+    /// storage. Data stores which do not track this report every load as `ResourceLoad::Cached`.
+    fn load_resource_with_size(
+        &mut self,
+        addr: AccountAddress,
+        ty: &Type,
+    ) -> PartialVMResult<(&mut GlobalValue, ResourceLoad)> {
+        Ok((self.load_resource(addr, ty)?, ResourceLoad::Cached))
+    }
+
     /// Get the serialized format of a `CompiledModule` given a `ModuleId`.
     fn load_module(&self, module_id: &ModuleId) -> VMResult<Vec<u8>>;
 
@@ -16,9 +16,10 @@ use move_binary_format::{
     file_format_common::{instruction_key, Opcodes},
 };
 use move_core_types::{
+    effects::ChangeSetSummary,
     gas_schedule::{
         AbstractMemorySize, CostTable, GasAlgebra, GasCarrier, GasConstants, GasCost, GasUnits,
-        InternalGasUnits, MAX_TRANSACTION_SIZE_IN_BYTES,
+        InternalGasUnits, StorageGasSchedule, MAX_TRANSACTION_SIZE_IN_BYTES,
     },
     vm_status::StatusCode,
 };
@@ -34,10 +35,17 @@ static ZERO_COST_SCHEDULE: Lazy<CostTable> =
 /// Provide all the proper guarantees about gas metering in the Move VM.
 ///
 /// Every client must use an instance of this type to interact with the Move VM.
+///
+/// Storage metering is an opt-in extension (see `with_storage_metering`): when enabled, bytes
+/// read from and written to global storage, event payloads and the peak value memory are
+/// charged according to a `StorageGasSchedule`. The usage is always tracked and reported in a
+/// `ChangeSetSummary`.
 pub struct GasStatus<'a> {
     cost_table: &'a CostTable,
     gas_left: InternalGasUnits<GasCarrier>,
     charge: bool,
+    storage_schedule: Option<StorageGasSchedule>,
+    storage_summary: ChangeSetSummary,
 }
 
 impl<'a> GasStatus<'a> {
@@ -50,6 +58,8 @@ impl<'a> GasStatus<'a> {
             gas_left: cost_table.gas_constants.to_internal_units(gas_left),
             cost_table,
             charge: true,
+            storage_schedule: None,
+            storage_summary: ChangeSetSummary::default(),
         }
     }
 
@@ -62,9 +72,30 @@ impl<'a> GasStatus<'a> {
             gas_left: InternalGasUnits::new(0),
             cost_table: &ZERO_COST_SCHEDULE,
             charge: false,
+            storage_schedule: None,
+            storage_summary: ChangeSetSummary::default(),
         }
     }
 
+    /// Enable storage metering with the given schedule.
+    ///
+    /// Charges are computed even if metering is disabled, in which case they are only reported
+    /// in the `ChangeSetSummary` but not deducted.
+    pub fn with_storage_metering(mut self, storage_schedule: StorageGasSchedule) -> Self {
+        self.storage_schedule = Some(storage_schedule);
+        self
+    }
+
+    /// Return whether storage metering is enabled.
+    pub fn is_storage_metered(&self) -> bool {
+        self.storage_schedule.is_some()
+    }
+
+    /// Return the storage and memory usage recorded so far, along with the charges for it.
+    pub fn storage_summary(&self) -> &ChangeSetSummary {
+        &self.storage_summary
+    }
+
     /// Return the `CostTable` behind this `GasStatus`.
     pub fn cost_table(&self) -> &CostTable {
         self.cost_table
@@ -131,6 +162,88 @@ impl<'a> GasStatus<'a> {
     pub fn set_metering(&mut self, enabled: bool) {
         self.charge = enabled
     }
+
+    /// Charge for bytes loaded from global storage.
+    pub fn charge_global_read(
+        &mut self,
+        size: AbstractMemorySize<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        let cost = self.storage_cost(|schedule| schedule.global_read_per_byte, size);
+        let summary = &mut self.storage_summary;
+        summary.bytes_read = summary.bytes_read.saturating_add(size.get());
+        summary.read_gas = summary.read_gas.saturating_add(cost.get());
+        self.deduct_gas(cost)
+    }
+
+    /// Charge for the bytes of a resource written to global storage.
+    pub fn charge_global_write(
+        &mut self,
+        size: AbstractMemorySize<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        let cost = self.storage_cost(|schedule| schedule.global_write_per_byte, size);
+        let summary = &mut self.storage_summary;
+        summary.bytes_written = summary.bytes_written.saturating_add(size.get());
+        summary.write_gas = summary.write_gas.saturating_add(cost.get());
+        self.deduct_gas(cost)
+    }
+
+    /// Record the deletion of a resource from global storage. Deletions are not charged.
+    pub fn record_global_delete(&mut self) {
+        self.storage_summary.resources_deleted =
+            self.storage_summary.resources_deleted.saturating_add(1);
+    }
+
+    /// Charge for the bytes of module code being published.
+    pub fn charge_module_publish(
+        &mut self,
+        size: AbstractMemorySize<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        let cost = self.storage_cost(|schedule| schedule.module_publish_per_byte, size);
+        let summary = &mut self.storage_summary;
+        summary.module_bytes_published = summary.module_bytes_published.saturating_add(size.get());
+        summary.module_publish_gas = summary.module_publish_gas.saturating_add(cost.get());
+        self.deduct_gas(cost)
+    }
+
+    /// Charge for the bytes of a serialized event payload.
+    pub fn charge_event(&mut self, size: AbstractMemorySize<GasCarrier>) -> PartialVMResult<()> {
+        let cost = self.storage_cost(|schedule| schedule.event_per_byte, size);
+        let summary = &mut self.storage_summary;
+        summary.event_bytes = summary.event_bytes.saturating_add(size.get());
+        summary.event_gas = summary.event_gas.saturating_add(cost.get());
+        self.deduct_gas(cost)
+    }
+
+    /// Record the amount of memory currently held in values and charge for the growth of the
+    /// peak, if any.
+    pub fn record_value_memory(
+        &mut self,
+        size: AbstractMemorySize<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        let peak = self.storage_summary.peak_value_memory;
+        if size.get() <= peak {
+            return Ok(());
+        }
+        let growth = AbstractMemorySize::new(size.get() - peak);
+        let cost = self.storage_cost(|schedule| schedule.value_memory_per_byte, growth);
+        let summary = &mut self.storage_summary;
+        summary.peak_value_memory = size.get();
+        summary.value_memory_gas = summary.value_memory_gas.saturating_add(cost.get());
+        self.deduct_gas(cost)
+    }
+
+    fn storage_cost(
+        &self,
+        per_byte: impl Fn(&StorageGasSchedule) -> InternalGasUnits<GasCarrier>,
+        size: AbstractMemorySize<GasCarrier>,
+    ) -> InternalGasUnits<GasCarrier> {
+        match &self.storage_schedule {
+            Some(schedule) => {
+                InternalGasUnits::new(per_byte(schedule).get().saturating_mul(size.get()))
+            }
+            None => InternalGasUnits::new(0),
+        }
+    }
 }
 
 pub fn new_from_instructions(
@@ -1822,12 +1822,40 @@ impl ContainerRef {
     fn size(&self) -> AbstractMemorySize<GasCarrier> {
         REFERENCE_SIZE
     }
+
+    /// Returns the size of the referenced container, or `None` if it lives in global storage.
+    fn referenced_local_size(&self) -> Option<AbstractMemorySize<GasCarrier>> {
+        match self {
+            Self::Local(c) => Some(c.size()),
+            Self::Global { .. } => None,
+        }
+    }
 }
 
 impl IndexedRef {
     fn size(&self) -> AbstractMemorySize<GasCarrier> {
         REFERENCE_SIZE
     }
+
+    /// Returns the size of the referenced element, measured as the size of the value read from
+    /// it, or `None` if it lives in global storage.
+    fn referenced_local_size(&self) -> Option<AbstractMemorySize<GasCarrier>> {
+        let container = match &self.container_ref {
+            ContainerRef::Local(c) => c,
+            ContainerRef::Global { .. } => return None,
+        };
+        Some(match container {
+            Container::Locals(r) | Container::Vec(r) | Container::Struct(r) => r
+                .borrow()
+                .get(self.idx)
+                .map_or(AbstractMemorySize::new(0), |v| v.size()),
+            Container::VecAddress(_) => AbstractMemorySize::new(AccountAddress::LENGTH as u64),
+            Container::VecU8(_)
+            | Container::VecU64(_)
+            | Container::VecU128(_)
+            | Container::VecBool(_) => CONST_SIZE,
+        })
+    }
 }
 
 impl ValueImpl {
@@ -1861,6 +1889,30 @@ impl Value {
     pub fn size(&self) -> AbstractMemorySize<GasCarrier> {
         self.0.size()
     }
+
+    /// Returns the size of the value this reference points to, or `None` if this is not a
+    /// reference or it points into global storage.
+    pub fn referenced_local_size(&self) -> Option<AbstractMemorySize<GasCarrier>> {
+        match &self.0 {
+            ValueImpl::ContainerRef(r) => r.referenced_local_size(),
+            ValueImpl::IndexedRef(r) => r.referenced_local_size(),
+            _ => None,
+        }
+    }
+}
+
+impl Locals {
+    pub fn size(&self) -> AbstractMemorySize<GasCarrier> {
+        Struct::size_impl(&*self.0.borrow())
+    }
+
+    /// Returns the size of the local at `idx`, or zero if there is no such local.
+    pub fn size_of_loc(&self, idx: usize) -> AbstractMemorySize<GasCarrier> {
+        self.0
+            .borrow()
+            .get(idx)
+            .map_or(AbstractMemorySize::new(0), |v| v.size())
+    }
 }
 
 impl ReferenceImpl {
@@ -1876,6 +1928,22 @@ impl Reference {
     pub fn size(&self) -> AbstractMemorySize<GasCarrier> {
         self.0.size()
     }
+
+    /// Returns the size of the value this reference points to, or `None` if it points into
+    /// global storage.
+    pub fn referenced_local_size(&self) -> Option<AbstractMemorySize<GasCarrier>> {
+        match &self.0 {
+            ReferenceImpl::ContainerRef(r) => r.referenced_local_size(),
+            ReferenceImpl::IndexedRef(r) => r.referenced_local_size(),
+        }
+    }
+}
+
+impl VectorRef {
+    /// Returns the size of the referenced vector, or `None` if it lives in global storage.
+    pub fn referenced_local_size(&self) -> Option<AbstractMemorySize<GasCarrier>> {
+        self.0.referenced_local_size()
+    }
 }
 
 impl GlobalValue {
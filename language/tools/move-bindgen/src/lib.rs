@@ -0,0 +1,225 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Generates host language bindings for a Move package: typed builders for the script functions
+//! and transaction scripts of the package, encoders for their arguments, and decoders for the
+//! resources the package declares.
+
+use anyhow::{bail, Result};
+use heck::CamelCase;
+use move_binary_format::{access::ModuleAccess, file_format::Ability, CompiledModule};
+use move_bytecode_utils::layout::SerdeLayoutBuilder;
+use move_core_types::{abi::ScriptABI, language_storage::StructTag};
+use move_package::compilation::compiled_package::CompiledPackage;
+use serde_reflection::Registry;
+use std::{fmt, str::FromStr};
+
+mod python;
+mod rust;
+mod structs;
+mod typescript;
+
+pub use structs::StructBindingsBuilder;
+
+/// Name of the Move `address` type in the serde registry
+const ADDRESS: &str = "AccountAddress";
+
+/// Name of the Move `signer` type in the serde registry
+const SIGNER: &str = "Signer";
+
+/// The first line of every generated file.
+const HEADER: &str = "This file was generated by `move package generate-bindings`. Do not edit.";
+
+/// The languages bindings can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    TypeScript,
+    Python,
+}
+
+impl Language {
+    pub fn all() -> &'static [Language] {
+        &[Language::Rust, Language::TypeScript, Language::Python]
+    }
+
+    /// The extension of the generated source file.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Language::Rust => "rs",
+            Language::TypeScript => "ts",
+            Language::Python => "py",
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Language::Rust => "rust",
+            Language::TypeScript => "typescript",
+            Language::Python => "python",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "rust" => Language::Rust,
+            "typescript" => Language::TypeScript,
+            "python" => Language::Python,
+            _ => bail!(
+                "Unrecognized language {} -- only rust, typescript and python are supported",
+                s
+            ),
+        })
+    }
+}
+
+/// Everything bindings are generated from.
+#[derive(Debug, Clone, Default)]
+pub struct BindingsInput {
+    /// ABIs of the script functions and transaction scripts to generate builders for
+    pub abis: Vec<ScriptABI>,
+    /// Layouts of the resources and of all the types they contain, as built by
+    /// `SerdeLayoutBuilder`
+    pub registry: Registry,
+    /// Registry names of the resources to generate decoders for
+    pub resources: Vec<String>,
+}
+
+impl BindingsInput {
+    /// Collect the ABIs and resource layouts of the root package of `package`. The package must
+    /// have been compiled with ABI generation enabled.
+    pub fn from_package(package: &CompiledPackage) -> Result<Self> {
+        let mut abis = vec![];
+        for (_, abi_bytes) in package.compiled_abis.iter().flatten() {
+            abis.push(bcs::from_bytes::<ScriptABI>(abi_bytes)?);
+        }
+
+        let mut layout_builder = SerdeLayoutBuilder::new(package.transitive_compiled_modules());
+        let mut resources = vec![];
+        for module in package.compiled_modules().iter_modules() {
+            for struct_tag in resource_tags(module) {
+                match layout_builder.build_struct_layout(&struct_tag)? {
+                    serde_reflection::Format::TypeName(name) => resources.push(name),
+                    format => bail!("Unexpected layout {:?} for {}", format, struct_tag),
+                }
+            }
+        }
+
+        Ok(Self {
+            abis,
+            registry: layout_builder.into_registry(),
+            resources,
+        })
+    }
+}
+
+/// The non-generic structs with the `key` ability declared in `module`.
+fn resource_tags(module: &CompiledModule) -> impl Iterator<Item = StructTag> + '_ {
+    module.struct_defs().iter().filter_map(move |def| {
+        let handle = module.struct_handle_at(def.struct_handle);
+        if !handle.abilities.has_ability(Ability::Key) || !handle.type_parameters.is_empty() {
+            return None;
+        }
+        Some(StructTag {
+            address: *module.address(),
+            module: module.name().to_owned(),
+            name: module.identifier_at(handle.name).to_owned(),
+            type_params: vec![],
+        })
+    })
+}
+
+/// Generate the bindings for `input` in `language`.
+pub fn generate(language: Language, input: &BindingsInput) -> Result<String> {
+    let mut abis = input.abis.clone();
+    abis.sort_by_key(|abi| match abi {
+        ScriptABI::ScriptFunction(abi) => (Some(abi.module_name().clone()), abi.name().to_string()),
+        ScriptABI::TransactionScript(abi) => (None, abi.name().to_string()),
+    });
+    match language {
+        Language::Rust => rust::generate(&abis, input),
+        Language::TypeScript => typescript::generate(&abis, input),
+        Language::Python => python::generate(&abis, input),
+    }
+}
+
+/// The host language name of the type registered under `name` in the serde registry, e.g.
+/// `CoinCoinOfXUSXUS` for `0x1::Coin::Coin<0x1::XUS::XUS<>>`.
+fn type_name(name: &str) -> String {
+    let (head, type_args) = match name.find('<') {
+        Some(idx) => (&name[..idx], &name[idx + 1..name.len() - 1]),
+        None => (name, ""),
+    };
+    let mut result = match head.split("::").collect::<Vec<_>>().as_slice() {
+        [_address, module, name] => format!("{}{}", module, name),
+        [ADDRESS] | [SIGNER] | ["address"] | ["signer"] => "Address".to_string(),
+        [primitive] => primitive.to_camel_case(),
+        _ => head.replace("::", "").to_camel_case(),
+    };
+    let type_args = split_type_args(type_args);
+    if !type_args.is_empty() {
+        result.push_str("Of");
+        result.push_str(
+            &type_args
+                .into_iter()
+                .map(type_name)
+                .collect::<Vec<_>>()
+                .join("And"),
+        );
+    }
+    result
+}
+
+/// Split the comma separated type arguments in `type_args` at the top level.
+fn split_type_args(type_args: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, c) in type_args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(type_args[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    if !type_args[start..].trim().is_empty() {
+        result.push(type_args[start..].trim());
+    }
+    result
+}
+
+/// `name`, suffixed with an underscore if it is one of the `keywords` of the target language.
+fn escape(name: &str, keywords: &[&str]) -> String {
+    if keywords.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// The non-empty lines of an ABI doc comment.
+fn doc_lines(doc: &str) -> Vec<&str> {
+    let lines: Vec<_> = doc.lines().map(str::trim).collect();
+    let start = lines
+        .iter()
+        .position(|l| !l.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(start, |idx| idx + 1);
+    lines[start..end].to_vec()
+}
@@ -0,0 +1,272 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Python bindings, building on the `bcs` and `serde_types` runtime modules of `serde-generate`.
+
+use crate::{doc_lines, escape, type_name, BindingsInput, ADDRESS, HEADER, SIGNER};
+use anyhow::{bail, Result};
+use heck::{ShoutySnakeCase, SnakeCase};
+use move_core_types::{
+    abi::{ArgumentABI, ScriptABI, TypeArgumentABI},
+    account_address::AccountAddress,
+    language_storage::TypeTag,
+};
+use serde_reflection::{ContainerFormat, Format};
+use std::fmt::Write;
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+fn preamble() -> String {
+    let address_bytes = vec!["st.uint8"; AccountAddress::LENGTH].join(", ");
+    format!(
+        r#"import typing
+from dataclasses import dataclass
+
+import bcs
+import serde_types as st
+
+# A Move `address`, as {length} bytes.
+AccountAddress = typing.Tuple[{address_bytes}]
+
+
+def address_from_hex(hex: str) -> AccountAddress:
+    return tuple(st.uint8(b) for b in bytes.fromhex(hex[2:].zfill({length} * 2)))
+
+
+@dataclass(frozen=True)
+class ModuleId:
+    address: AccountAddress
+    name: str
+
+
+@dataclass(frozen=True)
+class ScriptFunctionCall:
+    """A call to a script function. The arguments are BCS encoded."""
+
+    module: ModuleId
+    function: str
+    ty_args: typing.Sequence[str]
+    args: typing.Sequence[bytes]
+
+
+@dataclass(frozen=True)
+class ScriptCall:
+    """A call to a transaction script. The arguments are BCS encoded."""
+
+    code: bytes
+    ty_args: typing.Sequence[str]
+    args: typing.Sequence[bytes]
+"#,
+        length = AccountAddress::LENGTH,
+        address_bytes = address_bytes,
+    )
+}
+
+pub(crate) fn generate(abis: &[ScriptABI], input: &BindingsInput) -> Result<String> {
+    let mut out = format!("# {}\n\n{}", HEADER, preamble());
+    for abi in abis {
+        writeln!(out, "\n")?;
+        match abi {
+            ScriptABI::ScriptFunction(abi) => {
+                let module = abi.module_name();
+                let name = format!(
+                    "encode_{}_{}",
+                    module.name().as_str().to_snake_case(),
+                    abi.name()
+                );
+                emit_signature(
+                    &mut out,
+                    &name,
+                    abi.ty_args(),
+                    abi.args(),
+                    "ScriptFunctionCall",
+                )?;
+                emit_doc(&mut out, abi.doc())?;
+                writeln!(out, "    return ScriptFunctionCall(")?;
+                writeln!(
+                    out,
+                    "        module=ModuleId(address_from_hex(\"{}\"), \"{}\"),",
+                    module.address().to_hex_literal(),
+                    module.name()
+                )?;
+                writeln!(out, "        function=\"{}\",", abi.name())?;
+                emit_call_args(&mut out, abi.ty_args(), abi.args())?;
+                writeln!(out, "    )")?;
+            }
+            ScriptABI::TransactionScript(abi) => {
+                let code_name = format!("{}_CODE", abi.name().to_shouty_snake_case());
+                writeln!(out, "{} = bytes([", code_name)?;
+                for chunk in abi.code().chunks(16) {
+                    let bytes: Vec<_> = chunk.iter().map(|b| b.to_string()).collect();
+                    writeln!(out, "    {},", bytes.join(", "))?;
+                }
+                writeln!(out, "])\n\n")?;
+                let name = format!("encode_{}_script", abi.name());
+                emit_signature(&mut out, &name, abi.ty_args(), abi.args(), "ScriptCall")?;
+                emit_doc(&mut out, abi.doc())?;
+                writeln!(out, "    return ScriptCall(")?;
+                writeln!(out, "        code={},", code_name)?;
+                emit_call_args(&mut out, abi.ty_args(), abi.args())?;
+                writeln!(out, "    )")?;
+            }
+        }
+    }
+
+    for (name, container) in &input.registry {
+        let fields = match container {
+            ContainerFormat::Struct(fields) => fields,
+            // The Move ground types are mapped to `AccountAddress`
+            ContainerFormat::NewTypeStruct(_) if name == ADDRESS || name == SIGNER => continue,
+            _ => bail!("Unsupported layout {:?} for {}", container, name),
+        };
+        writeln!(out, "\n\n@dataclass(frozen=True)")?;
+        writeln!(out, "class {}:", type_name(name))?;
+        writeln!(out, "    \"\"\"The Move type `{}`.\"\"\"\n", name)?;
+        for field in fields {
+            writeln!(
+                out,
+                "    {}: {}",
+                escape(&field.name, KEYWORDS),
+                format_type(&field.value)?
+            )?;
+        }
+    }
+
+    for resource in &input.resources {
+        let name = type_name(resource);
+        writeln!(out, "\n")?;
+        writeln!(
+            out,
+            "def decode_{}(content: bytes) -> {}:",
+            name.to_snake_case(),
+            name
+        )?;
+        writeln!(
+            out,
+            "    \"\"\"Decode a BCS encoded `{}` resource.\"\"\"",
+            resource
+        )?;
+        writeln!(
+            out,
+            "    value, buffer = bcs.deserialize(content, {})",
+            name
+        )?;
+        writeln!(out, "    if buffer:")?;
+        writeln!(
+            out,
+            "        raise st.DeserializationError(\"Some input bytes were not read\")"
+        )?;
+        writeln!(out, "    return value")?;
+    }
+    Ok(out)
+}
+
+fn emit_doc(out: &mut String, doc: &str) -> Result<()> {
+    let lines = doc_lines(doc);
+    match lines.as_slice() {
+        [] => (),
+        [line] => writeln!(out, "    \"\"\"{}\"\"\"", line)?,
+        lines => {
+            writeln!(out, "    \"\"\"{}", lines[0])?;
+            for line in &lines[1..] {
+                if line.is_empty() {
+                    writeln!(out)?;
+                } else {
+                    writeln!(out, "    {}", line)?;
+                }
+            }
+            writeln!(out, "    \"\"\"")?;
+        }
+    }
+    Ok(())
+}
+
+fn param_name(name: &str) -> String {
+    escape(&name.to_snake_case(), KEYWORDS)
+}
+
+fn emit_signature(
+    out: &mut String,
+    name: &str,
+    ty_args: &[TypeArgumentABI],
+    args: &[ArgumentABI],
+    return_type: &str,
+) -> Result<()> {
+    let mut params = vec![];
+    for ty_arg in ty_args {
+        params.push(format!("{}: str", param_name(ty_arg.name())));
+    }
+    for arg in args {
+        params.push(format!(
+            "{}: {}",
+            param_name(arg.name()),
+            type_tag_type(arg.type_tag())?
+        ));
+    }
+    writeln!(
+        out,
+        "def {}({}) -> {}:",
+        name,
+        params.join(", "),
+        return_type
+    )?;
+    Ok(())
+}
+
+fn emit_call_args(
+    out: &mut String,
+    ty_args: &[TypeArgumentABI],
+    args: &[ArgumentABI],
+) -> Result<()> {
+    let ty_args: Vec<_> = ty_args
+        .iter()
+        .map(|ty_arg| param_name(ty_arg.name()))
+        .collect();
+    writeln!(out, "        ty_args=[{}],", ty_args.join(", "))?;
+    writeln!(out, "        args=[")?;
+    for arg in args {
+        writeln!(
+            out,
+            "            bcs.serialize({}, {}),",
+            param_name(arg.name()),
+            type_tag_type(arg.type_tag())?
+        )?;
+    }
+    writeln!(out, "        ],")?;
+    Ok(())
+}
+
+fn type_tag_type(type_tag: &TypeTag) -> Result<String> {
+    Ok(match type_tag {
+        TypeTag::Bool => "bool".to_string(),
+        TypeTag::U8 => "st.uint8".to_string(),
+        TypeTag::U64 => "st.uint64".to_string(),
+        TypeTag::U128 => "st.uint128".to_string(),
+        TypeTag::Address => "AccountAddress".to_string(),
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => "bytes".to_string(),
+        TypeTag::Vector(inner) => format!("typing.Sequence[{}]", type_tag_type(inner)?),
+        TypeTag::Signer | TypeTag::Struct(_) => {
+            bail!("Unsupported argument type {}", type_tag)
+        }
+    })
+}
+
+fn format_type(format: &Format) -> Result<String> {
+    Ok(match format {
+        Format::Bool => "bool".to_string(),
+        Format::U8 => "st.uint8".to_string(),
+        Format::U64 => "st.uint64".to_string(),
+        Format::U128 => "st.uint128".to_string(),
+        Format::Bytes => "bytes".to_string(),
+        Format::Seq(inner) => format!("typing.Sequence[{}]", format_type(inner)?),
+        Format::TypeName(name) if name == ADDRESS || name == SIGNER => "AccountAddress".to_string(),
+        // Quoted, as the dataclass may be declared further down
+        Format::TypeName(name) => format!("\"{}\"", type_name(name)),
+        _ => bail!("Unsupported field layout {:?}", format),
+    })
+}
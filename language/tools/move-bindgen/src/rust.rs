@@ -0,0 +1,235 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rust bindings, building on `move-core-types`, `serde` and `bcs`.
+
+use crate::{doc_lines, escape, type_name, BindingsInput, ADDRESS, HEADER, SIGNER};
+use anyhow::{bail, Result};
+use heck::{ShoutySnakeCase, SnakeCase};
+use move_core_types::{
+    abi::{ArgumentABI, ScriptABI, TypeArgumentABI},
+    language_storage::TypeTag,
+};
+use serde_reflection::{ContainerFormat, Format};
+use std::fmt::Write;
+
+pub(crate) const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+const PREAMBLE: &str = r#"#![allow(dead_code, unused_imports, clippy::too_many_arguments)]
+
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, TypeTag},
+};
+use serde::{Deserialize, Serialize};
+
+/// A call to a script function. The arguments are BCS encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptFunctionCall {
+    pub module: ModuleId,
+    pub function: Identifier,
+    pub ty_args: Vec<TypeTag>,
+    pub args: Vec<Vec<u8>>,
+}
+
+/// A call to a transaction script. The arguments are BCS encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptCall {
+    pub code: Vec<u8>,
+    pub ty_args: Vec<TypeTag>,
+    pub args: Vec<Vec<u8>>,
+}
+"#;
+
+pub(crate) fn generate(abis: &[ScriptABI], input: &BindingsInput) -> Result<String> {
+    let mut out = format!("// {}\n\n{}", HEADER, PREAMBLE);
+    for abi in abis {
+        writeln!(out)?;
+        match abi {
+            ScriptABI::ScriptFunction(abi) => {
+                let module = abi.module_name();
+                let name = format!(
+                    "encode_{}_{}",
+                    module.name().as_str().to_snake_case(),
+                    abi.name()
+                );
+                emit_doc(&mut out, abi.doc(), "")?;
+                emit_signature(
+                    &mut out,
+                    &name,
+                    abi.ty_args(),
+                    abi.args(),
+                    "ScriptFunctionCall",
+                )?;
+                writeln!(out, "    ScriptFunctionCall {{")?;
+                writeln!(out, "        module: ModuleId::new(")?;
+                writeln!(
+                    out,
+                    "            AccountAddress::from_hex_literal(\"{}\").unwrap(),",
+                    module.address().to_hex_literal()
+                )?;
+                writeln!(
+                    out,
+                    "            Identifier::new(\"{}\").unwrap(),",
+                    module.name()
+                )?;
+                writeln!(out, "        ),")?;
+                writeln!(
+                    out,
+                    "        function: Identifier::new(\"{}\").unwrap(),",
+                    abi.name()
+                )?;
+                emit_call_args(&mut out, abi.ty_args(), abi.args())?;
+                writeln!(out, "    }}\n}}")?;
+            }
+            ScriptABI::TransactionScript(abi) => {
+                let code_name = format!("{}_CODE", abi.name().to_shouty_snake_case());
+                writeln!(out, "const {}: &[u8] = &[", code_name)?;
+                for chunk in abi.code().chunks(16) {
+                    let bytes: Vec<_> = chunk.iter().map(|b| b.to_string()).collect();
+                    writeln!(out, "    {},", bytes.join(", "))?;
+                }
+                writeln!(out, "];\n")?;
+                emit_doc(&mut out, abi.doc(), "")?;
+                let name = format!("encode_{}_script", abi.name());
+                emit_signature(&mut out, &name, abi.ty_args(), abi.args(), "ScriptCall")?;
+                writeln!(out, "    ScriptCall {{")?;
+                writeln!(out, "        code: {}.to_vec(),", code_name)?;
+                emit_call_args(&mut out, abi.ty_args(), abi.args())?;
+                writeln!(out, "    }}\n}}")?;
+            }
+        }
+    }
+
+    for (name, container) in &input.registry {
+        let fields = match container {
+            ContainerFormat::Struct(fields) => fields,
+            // The Move ground types are mapped to `AccountAddress`
+            ContainerFormat::NewTypeStruct(_) if name == ADDRESS || name == SIGNER => continue,
+            _ => bail!("Unsupported layout {:?} for {}", container, name),
+        };
+        writeln!(out, "\n/// The Move type `{}`.", name)?;
+        writeln!(
+            out,
+            "#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]"
+        )?;
+        writeln!(out, "pub struct {} {{", type_name(name))?;
+        for field in fields {
+            writeln!(
+                out,
+                "    pub {}: {},",
+                escape(&field.name, KEYWORDS),
+                format_type(&field.value)?
+            )?;
+        }
+        writeln!(out, "}}")?;
+    }
+
+    for resource in &input.resources {
+        let name = type_name(resource);
+        writeln!(out, "\n/// Decode a BCS encoded `{}` resource.", resource)?;
+        writeln!(
+            out,
+            "pub fn decode_{}(bytes: &[u8]) -> Result<{}, bcs::Error> {{",
+            name.to_snake_case(),
+            name
+        )?;
+        writeln!(out, "    bcs::from_bytes(bytes)\n}}")?;
+    }
+    Ok(out)
+}
+
+fn emit_doc(out: &mut String, doc: &str, indent: &str) -> Result<()> {
+    for line in doc_lines(doc) {
+        if line.is_empty() {
+            writeln!(out, "{}///", indent)?;
+        } else {
+            writeln!(out, "{}/// {}", indent, line)?;
+        }
+    }
+    Ok(())
+}
+
+fn emit_signature(
+    out: &mut String,
+    name: &str,
+    ty_args: &[TypeArgumentABI],
+    args: &[ArgumentABI],
+    return_type: &str,
+) -> Result<()> {
+    let mut params = vec![];
+    for ty_arg in ty_args {
+        params.push(format!("{}: TypeTag", escape(ty_arg.name(), KEYWORDS)));
+    }
+    for arg in args {
+        params.push(format!(
+            "{}: {}",
+            escape(arg.name(), KEYWORDS),
+            type_tag_type(arg.type_tag())?
+        ));
+    }
+    writeln!(
+        out,
+        "pub fn {}({}) -> {} {{",
+        name,
+        params.join(", "),
+        return_type
+    )?;
+    Ok(())
+}
+
+fn emit_call_args(
+    out: &mut String,
+    ty_args: &[TypeArgumentABI],
+    args: &[ArgumentABI],
+) -> Result<()> {
+    let ty_args: Vec<_> = ty_args
+        .iter()
+        .map(|ty_arg| escape(ty_arg.name(), KEYWORDS))
+        .collect();
+    writeln!(out, "        ty_args: vec![{}],", ty_args.join(", "))?;
+    writeln!(out, "        args: vec![")?;
+    for arg in args {
+        writeln!(
+            out,
+            "            bcs::to_bytes(&{}).unwrap(),",
+            escape(arg.name(), KEYWORDS)
+        )?;
+    }
+    writeln!(out, "        ],")?;
+    Ok(())
+}
+
+fn type_tag_type(type_tag: &TypeTag) -> Result<String> {
+    Ok(match type_tag {
+        TypeTag::Bool => "bool".to_string(),
+        TypeTag::U8 => "u8".to_string(),
+        TypeTag::U64 => "u64".to_string(),
+        TypeTag::U128 => "u128".to_string(),
+        TypeTag::Address => "AccountAddress".to_string(),
+        TypeTag::Vector(inner) => format!("Vec<{}>", type_tag_type(inner)?),
+        TypeTag::Signer | TypeTag::Struct(_) => {
+            bail!("Unsupported argument type {}", type_tag)
+        }
+    })
+}
+
+fn format_type(format: &Format) -> Result<String> {
+    Ok(match format {
+        Format::Bool => "bool".to_string(),
+        Format::U8 => "u8".to_string(),
+        Format::U64 => "u64".to_string(),
+        Format::U128 => "u128".to_string(),
+        Format::Bytes => "Vec<u8>".to_string(),
+        Format::Seq(inner) => format!("Vec<{}>", format_type(inner)?),
+        Format::TypeName(name) if name == ADDRESS || name == SIGNER => "AccountAddress".to_string(),
+        Format::TypeName(name) => type_name(name),
+        _ => bail!("Unsupported field layout {:?}", format),
+    })
+}
@@ -0,0 +1,371 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed Rust mirrors of Move structs. Unlike the layouts built by `SerdeLayoutBuilder`, which are
+//! monomorphized, the generated structs keep the type parameters of their Move counterparts, so
+//! a single Rust struct decodes every instantiation of a generic Move struct.
+
+use crate::{escape, rust::KEYWORDS};
+use anyhow::{anyhow, bail, Result};
+use heck::SnakeCase;
+use move_binary_format::{access::ModuleAccess, file_format::StructFieldInformation, normalized};
+use move_bytecode_utils::module_cache::GetModule;
+use move_core_types::{
+    identifier::{IdentStr, Identifier},
+    language_storage::ModuleId,
+};
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+/// The first line of every generated file.
+const HEADER: &str = "Rust mirrors of Move structs, generated from their bytecode. Do not edit.";
+
+const PREAMBLE: &str = r#"use move_core_types::{
+    account_address::AccountAddress,
+    ident_str,
+    identifier::IdentStr,
+    language_storage::TypeTag,
+    move_resource::{MoveResource, MoveStructType},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// A Rust type mirroring a Move type, whose type tag is known statically.
+pub trait MoveType {
+    fn type_tag() -> TypeTag;
+}
+
+impl MoveType for bool {
+    fn type_tag() -> TypeTag {
+        TypeTag::Bool
+    }
+}
+
+impl MoveType for u8 {
+    fn type_tag() -> TypeTag {
+        TypeTag::U8
+    }
+}
+
+impl MoveType for u64 {
+    fn type_tag() -> TypeTag {
+        TypeTag::U64
+    }
+}
+
+impl MoveType for u128 {
+    fn type_tag() -> TypeTag {
+        TypeTag::U128
+    }
+}
+
+impl MoveType for AccountAddress {
+    fn type_tag() -> TypeTag {
+        TypeTag::Address
+    }
+}
+
+impl<T: MoveType> MoveType for Vec<T> {
+    fn type_tag() -> TypeTag {
+        TypeTag::Vector(Box::new(T::type_tag()))
+    }
+}
+"#;
+
+/// Type for generating Rust struct bindings for Move structs.
+/// Unlike `SerdeLayoutBuilder`, it has no shallow mode: the generated structs refer to the
+/// bindings of the structs of their fields by path, so it always generates the (transitive)
+/// dependencies of the added structs, producing a self-contained Rust module.
+pub struct StructBindingsBuilder<T> {
+    module_resolver: T,
+    /// The structs to generate, by declaring module
+    structs: BTreeMap<ModuleId, BTreeSet<Identifier>>,
+}
+
+impl<T: GetModule> StructBindingsBuilder<T> {
+    /// Create a `StructBindingsBuilder` which fetches modules from `module_resolver`
+    pub fn new(module_resolver: T) -> Self {
+        Self {
+            module_resolver,
+            structs: BTreeMap::new(),
+        }
+    }
+
+    /// Add all the non-native structs declared in the module `module_id`.
+    pub fn add_module(&mut self, module_id: &ModuleId) -> Result<()> {
+        let module = self.get_module(module_id)?;
+        let module = module.borrow();
+        for def in module.struct_defs() {
+            if def.field_information != StructFieldInformation::Native {
+                let handle = module.struct_handle_at(def.struct_handle);
+                self.add_struct(module_id, module.identifier_at(handle.name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the struct `name` declared in the module `module_id`.
+    pub fn add_struct(&mut self, module_id: &ModuleId, name: &IdentStr) -> Result<()> {
+        if !self
+            .structs
+            .entry(module_id.clone())
+            .or_default()
+            .insert(name.to_owned())
+        {
+            return Ok(());
+        }
+        let (_, struct_) = self.normalized_struct(module_id, name)?;
+        for field in &struct_.fields {
+            self.add_type(&field.type_)?;
+        }
+        Ok(())
+    }
+
+    fn add_type(&mut self, type_: &normalized::Type) -> Result<()> {
+        use normalized::Type::*;
+        match type_ {
+            Struct {
+                address,
+                module,
+                name,
+                type_arguments,
+            } => {
+                self.add_struct(&ModuleId::new(*address, module.clone()), name)?;
+                for type_argument in type_arguments {
+                    self.add_type(type_argument)?;
+                }
+            }
+            Vector(inner) | Reference(inner) | MutableReference(inner) => self.add_type(inner)?,
+            Bool | U8 | U64 | U128 | Address | Signer | TypeParameter(_) => (),
+        }
+        Ok(())
+    }
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<T::Item> {
+        self.module_resolver
+            .get_module_by_id(module_id)
+            .map_err(|e| anyhow!("Error fetching module {}: {:?}", module_id, e))?
+            .ok_or_else(|| anyhow!("Could not find module {}", module_id))
+    }
+
+    fn normalized_struct(
+        &self,
+        module_id: &ModuleId,
+        name: &IdentStr,
+    ) -> Result<(bool, normalized::Struct)> {
+        let module = self.get_module(module_id)?;
+        let module = module.borrow();
+        let def = match module.find_struct_def_by_name(name) {
+            Some(def) => def,
+            None => bail!(
+                "Could not find struct named {} in module {}",
+                name,
+                module_id
+            ),
+        };
+        if def.field_information == StructFieldInformation::Native {
+            bail!(
+                "Cannot generate bindings for native struct {}::{}",
+                module_id,
+                name
+            )
+        }
+        let handle = module.struct_handle_at(def.struct_handle);
+        Ok((
+            handle.abilities.has_key(),
+            normalized::Struct::new(module, def).1,
+        ))
+    }
+
+    /// Generate the Rust source of the bindings of the structs added so far. The structs of each
+    /// Move module are placed in a Rust module named after it.
+    pub fn generate(&self) -> Result<String> {
+        let mut out = format!("// {}\n\n{}", HEADER, PREAMBLE);
+        let mut rust_modules = BTreeMap::new();
+        for module_id in self.structs.keys() {
+            if let Some(other) = rust_modules.insert(rust_module_name(module_id), module_id) {
+                bail!(
+                    "Modules {} and {} map to the same Rust module",
+                    other,
+                    module_id
+                )
+            }
+        }
+
+        for (module_id, names) in &self.structs {
+            writeln!(out, "\npub mod {} {{", rust_module_name(module_id))?;
+            writeln!(out, "    use super::*;\n")?;
+            writeln!(
+                out,
+                "    pub const ADDRESS: AccountAddress = AccountAddress::new({:?});",
+                module_id.address().into_bytes()
+            )?;
+            writeln!(
+                out,
+                "    pub const MODULE_NAME: &IdentStr = ident_str!(\"{}\");",
+                module_id.name()
+            )?;
+            for name in names {
+                let (is_resource, struct_) = self.normalized_struct(module_id, name)?;
+                self.generate_struct(&mut out, module_id, name, is_resource, &struct_)?;
+            }
+            writeln!(out, "}}")?;
+        }
+        Ok(out)
+    }
+
+    fn generate_struct(
+        &self,
+        out: &mut String,
+        module_id: &ModuleId,
+        name: &IdentStr,
+        is_resource: bool,
+        struct_: &normalized::Struct,
+    ) -> Result<()> {
+        let type_params: Vec<_> = (0..struct_.type_parameters.len())
+            .map(|idx| format!("T{}", idx))
+            .collect();
+        let generics = if type_params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", type_params.join(", "))
+        };
+        let bounded_generics = if type_params.is_empty() {
+            String::new()
+        } else {
+            let params: Vec<_> = type_params
+                .iter()
+                .map(|param| format!("{}: MoveType", param))
+                .collect();
+            format!("<{}>", params.join(", "))
+        };
+        let rust_name = escape(name.as_str(), KEYWORDS);
+
+        writeln!(
+            out,
+            "\n    /// The Move struct `0x{}::{}::{}`.",
+            module_id.address().short_str_lossless(),
+            module_id.name(),
+            name
+        )?;
+        writeln!(
+            out,
+            "    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]"
+        )?;
+        writeln!(out, "    pub struct {}{} {{", rust_name, generics)?;
+        for field in &struct_.fields {
+            writeln!(
+                out,
+                "        pub {}: {},",
+                escape(field.name.as_str(), KEYWORDS),
+                self.format_type(module_id, &field.type_, &type_params)?
+            )?;
+        }
+        // Phantom type parameters do not occur in the fields, and neither are they part of the
+        // BCS encoding
+        for (idx, param) in struct_.type_parameters.iter().enumerate() {
+            if param.is_phantom {
+                writeln!(out, "        #[serde(skip)]")?;
+                writeln!(
+                    out,
+                    "        pub phantom_{}: PhantomData<{}>,",
+                    type_params[idx].to_snake_case(),
+                    type_params[idx]
+                )?;
+            }
+        }
+        writeln!(out, "    }}\n")?;
+
+        writeln!(
+            out,
+            "    impl{} MoveStructType for {}{} {{",
+            bounded_generics, rust_name, generics
+        )?;
+        writeln!(out, "        const ADDRESS: AccountAddress = ADDRESS;")?;
+        writeln!(
+            out,
+            "        const MODULE_NAME: &'static IdentStr = MODULE_NAME;"
+        )?;
+        writeln!(
+            out,
+            "        const STRUCT_NAME: &'static IdentStr = ident_str!(\"{}\");",
+            name
+        )?;
+        if !type_params.is_empty() {
+            let type_tags: Vec<_> = type_params
+                .iter()
+                .map(|param| format!("{}::type_tag()", param))
+                .collect();
+            writeln!(out, "\n        fn type_params() -> Vec<TypeTag> {{")?;
+            writeln!(out, "            vec![{}]", type_tags.join(", "))?;
+            writeln!(out, "        }}")?;
+        }
+        writeln!(out, "    }}\n")?;
+
+        writeln!(
+            out,
+            "    impl{} MoveType for {}{} {{",
+            bounded_generics, rust_name, generics
+        )?;
+        writeln!(out, "        fn type_tag() -> TypeTag {{")?;
+        writeln!(out, "            TypeTag::Struct(Self::struct_tag())")?;
+        writeln!(out, "        }}")?;
+        writeln!(out, "    }}")?;
+
+        if is_resource {
+            writeln!(
+                out,
+                "\n    impl{} MoveResource for {}{} where Self: DeserializeOwned {{}}",
+                bounded_generics, rust_name, generics
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The Rust type of a field of type `type_`, declared in the module `module_id`.
+    fn format_type(
+        &self,
+        module_id: &ModuleId,
+        type_: &normalized::Type,
+        type_params: &[String],
+    ) -> Result<String> {
+        use normalized::Type::*;
+        Ok(match type_ {
+            Bool => "bool".to_string(),
+            U8 => "u8".to_string(),
+            U64 => "u64".to_string(),
+            U128 => "u128".to_string(),
+            Address | Signer => "AccountAddress".to_string(),
+            Vector(inner) => format!("Vec<{}>", self.format_type(module_id, inner, type_params)?),
+            TypeParameter(idx) => type_params[*idx as usize].clone(),
+            Struct {
+                address,
+                module,
+                name,
+                type_arguments,
+            } => {
+                let declaring_module = ModuleId::new(*address, module.clone());
+                let mut result = escape(name.as_str(), KEYWORDS);
+                if &declaring_module != module_id {
+                    result = format!("super::{}::{}", rust_module_name(&declaring_module), result);
+                }
+                if !type_arguments.is_empty() {
+                    let type_arguments = type_arguments
+                        .iter()
+                        .map(|t| self.format_type(module_id, t, type_params))
+                        .collect::<Result<Vec<_>>>()?;
+                    result = format!("{}<{}>", result, type_arguments.join(", "));
+                }
+                result
+            }
+            Reference(_) | MutableReference(_) => bail!("Unexpected reference type in a field"),
+        })
+    }
+}
+
+fn rust_module_name(module_id: &ModuleId) -> String {
+    escape(&module_id.name().as_str().to_snake_case(), KEYWORDS)
+}
@@ -0,0 +1,369 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! TypeScript bindings, building on the BCS runtime of `serde-generate`, which is expected to be
+//! importable from `./bcs`.
+
+use crate::{doc_lines, escape, type_name, BindingsInput, ADDRESS, HEADER, SIGNER};
+use anyhow::{bail, Result};
+use heck::{CamelCase, MixedCase, ShoutySnakeCase};
+use move_core_types::{
+    abi::{ArgumentABI, ScriptABI, TypeArgumentABI},
+    account_address::AccountAddress,
+    language_storage::TypeTag,
+};
+use serde_reflection::{ContainerFormat, Format};
+use std::fmt::Write;
+
+const KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+fn preamble() -> String {
+    format!(
+        r#"import {{ BcsDeserializer, BcsSerializer }} from "./bcs";
+
+/** A Move `address`, as {length} bytes. */
+export type AccountAddress = Uint8Array;
+
+/** A call to a script function. The arguments are BCS encoded. */
+export interface ScriptFunctionCall {{
+  module: {{ address: AccountAddress; name: string }};
+  function: string;
+  tyArgs: string[];
+  args: Uint8Array[];
+}}
+
+/** A call to a transaction script. The arguments are BCS encoded. */
+export interface ScriptCall {{
+  code: Uint8Array;
+  tyArgs: string[];
+  args: Uint8Array[];
+}}
+
+export function addressFromHex(hex: string): AccountAddress {{
+  const digits = hex.replace(/^0x/, "").padStart({length} * 2, "0");
+  const address = new Uint8Array({length});
+  for (let i = 0; i < {length}; i++) {{
+    address[i] = parseInt(digits.substr(i * 2, 2), 16);
+  }}
+  return address;
+}}
+
+function serializeAccountAddress(value: AccountAddress, serializer: BcsSerializer) {{
+  for (let i = 0; i < {length}; i++) {{
+    serializer.serializeU8(value[i]);
+  }}
+}}
+
+function deserializeAccountAddress(deserializer: BcsDeserializer): AccountAddress {{
+  const address = new Uint8Array({length});
+  for (let i = 0; i < {length}; i++) {{
+    address[i] = deserializer.deserializeU8();
+  }}
+  return address;
+}}
+"#,
+        length = AccountAddress::LENGTH
+    )
+}
+
+pub(crate) fn generate(abis: &[ScriptABI], input: &BindingsInput) -> Result<String> {
+    let mut out = format!("// {}\n\n{}", HEADER, preamble());
+    for abi in abis {
+        writeln!(out)?;
+        match abi {
+            ScriptABI::ScriptFunction(abi) => {
+                let module = abi.module_name();
+                let name = format!(
+                    "encode{}{}",
+                    module.name().as_str().to_camel_case(),
+                    abi.name().to_camel_case()
+                );
+                emit_doc(&mut out, abi.doc())?;
+                emit_signature(
+                    &mut out,
+                    &name,
+                    abi.ty_args(),
+                    abi.args(),
+                    "ScriptFunctionCall",
+                )?;
+                writeln!(out, "  return {{")?;
+                writeln!(
+                    out,
+                    "    module: {{ address: addressFromHex(\"{}\"), name: \"{}\" }},",
+                    module.address().to_hex_literal(),
+                    module.name()
+                )?;
+                writeln!(out, "    function: \"{}\",", abi.name())?;
+                emit_call_args(&mut out, abi.ty_args(), abi.args())?;
+                writeln!(out, "  }};\n}}")?;
+            }
+            ScriptABI::TransactionScript(abi) => {
+                let code_name = format!("{}_CODE", abi.name().to_shouty_snake_case());
+                writeln!(out, "const {} = new Uint8Array([", code_name)?;
+                for chunk in abi.code().chunks(16) {
+                    let bytes: Vec<_> = chunk.iter().map(|b| b.to_string()).collect();
+                    writeln!(out, "  {},", bytes.join(", "))?;
+                }
+                writeln!(out, "]);\n")?;
+                emit_doc(&mut out, abi.doc())?;
+                let name = format!("encode{}Script", abi.name().to_camel_case());
+                emit_signature(&mut out, &name, abi.ty_args(), abi.args(), "ScriptCall")?;
+                writeln!(out, "  return {{")?;
+                writeln!(out, "    code: {},", code_name)?;
+                emit_call_args(&mut out, abi.ty_args(), abi.args())?;
+                writeln!(out, "  }};\n}}")?;
+            }
+        }
+    }
+
+    for (name, container) in &input.registry {
+        let fields = match container {
+            ContainerFormat::Struct(fields) => fields,
+            // The Move ground types are mapped to `AccountAddress`
+            ContainerFormat::NewTypeStruct(_) if name == ADDRESS || name == SIGNER => continue,
+            _ => bail!("Unsupported layout {:?} for {}", container, name),
+        };
+        let ts_name = type_name(name);
+        writeln!(out, "\n/** The Move type `{}`. */", name)?;
+        writeln!(out, "export interface {} {{", ts_name)?;
+        for field in fields {
+            writeln!(
+                out,
+                "  {}: {};",
+                field.name.to_mixed_case(),
+                format_type(&field.value)?
+            )?;
+        }
+        writeln!(out, "}}\n")?;
+        writeln!(
+            out,
+            "function deserialize{}(deserializer: BcsDeserializer): {} {{",
+            ts_name, ts_name
+        )?;
+        writeln!(out, "  return {{")?;
+        for field in fields {
+            writeln!(
+                out,
+                "    {}: {},",
+                field.name.to_mixed_case(),
+                deserialize_expr(&field.value, 0)?
+            )?;
+        }
+        writeln!(out, "  }};\n}}")?;
+    }
+
+    for resource in &input.resources {
+        let name = type_name(resource);
+        writeln!(
+            out,
+            "\n/** Decode a BCS encoded `{}` resource. */",
+            resource
+        )?;
+        writeln!(
+            out,
+            "export function decode{}(bytes: Uint8Array): {} {{",
+            name, name
+        )?;
+        writeln!(
+            out,
+            "  return deserialize{}(new BcsDeserializer(bytes));\n}}",
+            name
+        )?;
+    }
+    Ok(out)
+}
+
+fn emit_doc(out: &mut String, doc: &str) -> Result<()> {
+    let lines = doc_lines(doc);
+    if lines.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "/**")?;
+    for line in lines {
+        if line.is_empty() {
+            writeln!(out, " *")?;
+        } else {
+            writeln!(out, " * {}", line)?;
+        }
+    }
+    writeln!(out, " */")?;
+    Ok(())
+}
+
+fn param_name(name: &str) -> String {
+    escape(&name.to_mixed_case(), KEYWORDS)
+}
+
+fn emit_signature(
+    out: &mut String,
+    name: &str,
+    ty_args: &[TypeArgumentABI],
+    args: &[ArgumentABI],
+    return_type: &str,
+) -> Result<()> {
+    let mut params = vec![];
+    for ty_arg in ty_args {
+        params.push(format!("{}: string", param_name(ty_arg.name())));
+    }
+    for arg in args {
+        params.push(format!(
+            "{}: {}",
+            param_name(arg.name()),
+            type_tag_type(arg.type_tag())?
+        ));
+    }
+    writeln!(
+        out,
+        "export function {}({}): {} {{",
+        name,
+        params.join(", "),
+        return_type
+    )?;
+    Ok(())
+}
+
+fn emit_call_args(
+    out: &mut String,
+    ty_args: &[TypeArgumentABI],
+    args: &[ArgumentABI],
+) -> Result<()> {
+    let ty_args: Vec<_> = ty_args
+        .iter()
+        .map(|ty_arg| param_name(ty_arg.name()))
+        .collect();
+    writeln!(out, "    tyArgs: [{}],", ty_args.join(", "))?;
+    writeln!(out, "    args: [")?;
+    for arg in args {
+        writeln!(out, "      (() => {{")?;
+        writeln!(out, "        const serializer = new BcsSerializer();")?;
+        emit_serialize(out, arg.type_tag(), &param_name(arg.name()), 0, "        ")?;
+        writeln!(out, "        return serializer.getBytes();")?;
+        writeln!(out, "      }})(),")?;
+    }
+    writeln!(out, "    ],")?;
+    Ok(())
+}
+
+/// Emit the statements serializing `value` of type `type_tag` into `serializer`.
+fn emit_serialize(
+    out: &mut String,
+    type_tag: &TypeTag,
+    value: &str,
+    depth: usize,
+    indent: &str,
+) -> Result<()> {
+    match type_tag {
+        TypeTag::Bool => writeln!(out, "{}serializer.serializeBool({});", indent, value)?,
+        TypeTag::U8 => writeln!(out, "{}serializer.serializeU8({});", indent, value)?,
+        TypeTag::U64 => writeln!(out, "{}serializer.serializeU64({});", indent, value)?,
+        TypeTag::U128 => writeln!(out, "{}serializer.serializeU128({});", indent, value)?,
+        TypeTag::Address => writeln!(
+            out,
+            "{}serializeAccountAddress({}, serializer);",
+            indent, value
+        )?,
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+            writeln!(out, "{}serializer.serializeBytes({});", indent, value)?
+        }
+        TypeTag::Vector(inner) => {
+            let item = format!("item{}", depth);
+            writeln!(out, "{}serializer.serializeLen({}.length);", indent, value)?;
+            writeln!(out, "{}for (const {} of {}) {{", indent, item, value)?;
+            emit_serialize(out, inner, &item, depth + 1, &format!("{}  ", indent))?;
+            writeln!(out, "{}}}", indent)?;
+        }
+        TypeTag::Signer | TypeTag::Struct(_) => {
+            bail!("Unsupported argument type {}", type_tag)
+        }
+    }
+    Ok(())
+}
+
+fn type_tag_type(type_tag: &TypeTag) -> Result<String> {
+    Ok(match type_tag {
+        TypeTag::Bool => "boolean".to_string(),
+        TypeTag::U8 => "number".to_string(),
+        TypeTag::U64 | TypeTag::U128 => "bigint".to_string(),
+        TypeTag::Address => "AccountAddress".to_string(),
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => "Uint8Array".to_string(),
+        TypeTag::Vector(inner) => format!("{}[]", type_tag_type(inner)?),
+        TypeTag::Signer | TypeTag::Struct(_) => {
+            bail!("Unsupported argument type {}", type_tag)
+        }
+    })
+}
+
+fn format_type(format: &Format) -> Result<String> {
+    Ok(match format {
+        Format::Bool => "boolean".to_string(),
+        Format::U8 => "number".to_string(),
+        Format::U64 | Format::U128 => "bigint".to_string(),
+        Format::Bytes => "Uint8Array".to_string(),
+        Format::Seq(inner) => format!("{}[]", format_type(inner)?),
+        Format::TypeName(name) if name == ADDRESS || name == SIGNER => "AccountAddress".to_string(),
+        Format::TypeName(name) => type_name(name),
+        _ => bail!("Unsupported field layout {:?}", format),
+    })
+}
+
+/// An expression deserializing a value of layout `format` from `deserializer`.
+fn deserialize_expr(format: &Format, depth: usize) -> Result<String> {
+    Ok(match format {
+        Format::Bool => "deserializer.deserializeBool()".to_string(),
+        Format::U8 => "deserializer.deserializeU8()".to_string(),
+        Format::U64 => "deserializer.deserializeU64()".to_string(),
+        Format::U128 => "deserializer.deserializeU128()".to_string(),
+        Format::Bytes => "deserializer.deserializeBytes()".to_string(),
+        Format::Seq(inner) => {
+            let items = format!("items{}", depth);
+            format!(
+                "(() => {{ const {items}: {ty}[] = []; for (let i = deserializer.deserializeLen(); i > 0; i--) {{ {items}.push({item}); }} return {items}; }})()",
+                items = items,
+                ty = format_type(inner)?,
+                item = deserialize_expr(inner, depth + 1)?
+            )
+        }
+        Format::TypeName(name) if name == ADDRESS || name == SIGNER => {
+            "deserializeAccountAddress(deserializer)".to_string()
+        }
+        Format::TypeName(name) => format!("deserialize{}(deserializer)", type_name(name)),
+        _ => bail!("Unsupported field layout {:?}", format),
+    })
+}
@@ -0,0 +1,142 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that the generated struct bindings compile, and that they decode values encoded by Move.
+
+#[allow(dead_code)]
+mod generated {
+    include!("testsuite/basic/structs.rs.exp");
+}
+
+/// The bindings of the structs of the `Vault` module alone, which include the structs of the
+/// `Coin` module they refer to.
+#[allow(dead_code)]
+mod vault_only {
+    include!("testsuite/basic/vault_structs.rs.exp");
+}
+
+use generated::{
+    coin::{Coin, Gold},
+    vault::{Pair, Treasury, Vault},
+    MoveType,
+};
+use move_bindgen::StructBindingsBuilder;
+use move_command_line_common::testing::{format_diff, read_env_update_baseline};
+use move_core_types::{
+    move_resource::MoveStructType,
+    parser::parse_struct_tag,
+    value::{MoveStruct, MoveValue},
+};
+use move_package::BuildConfig;
+use std::{fs, marker::PhantomData, path::Path};
+use tempfile::tempdir;
+
+fn move_struct(fields: Vec<MoveValue>) -> MoveValue {
+    MoveValue::Struct(MoveStruct::new(fields))
+}
+
+#[test]
+fn struct_tags() {
+    assert_eq!(
+        Vault::<Coin<Gold>>::struct_tag(),
+        parse_struct_tag("0x2::Vault::Vault<0x2::Coin::Coin<0x2::Coin::Gold>>").unwrap()
+    );
+    assert_eq!(
+        Pair::<Vec<u8>, Gold>::struct_tag(),
+        parse_struct_tag("0x2::Vault::Pair<vector<u8>, 0x2::Coin::Gold>").unwrap()
+    );
+    assert_eq!(
+        Treasury::type_tag().to_string(),
+        Treasury::struct_tag().to_string()
+    );
+}
+
+#[test]
+fn decode_move_value() {
+    let coin = |value| move_struct(vec![MoveValue::U64(value)]);
+    let pair = |first, second| move_struct(vec![MoveValue::U64(first), MoveValue::U64(second)]);
+    let treasury = move_struct(vec![
+        move_struct(vec![
+            MoveValue::Vector(vec![coin(1), coin(2)]),
+            MoveValue::U8(7),
+        ]),
+        MoveValue::Vector(vec![pair(3, 4)]),
+    ]);
+    let bytes = treasury.simple_serialize().unwrap();
+
+    let coin = |value| Coin::<Gold> {
+        value,
+        phantom_t0: PhantomData,
+    };
+    let expected = Treasury {
+        gold: Vault {
+            items: vec![coin(1), coin(2)],
+            type_: 7,
+        },
+        pairs: vec![Pair {
+            first: 3,
+            second: 4,
+            phantom_t1: PhantomData,
+        }],
+    };
+    assert_eq!(bcs::from_bytes::<Treasury>(&bytes).unwrap(), expected);
+    assert_eq!(bcs::to_bytes(&expected).unwrap(), bytes);
+}
+
+#[test]
+fn dependencies_of_added_structs_are_generated() {
+    let package_path = Path::new("tests/testsuite/basic");
+    let install_dir = tempdir().unwrap();
+    let package = BuildConfig {
+        dev_mode: true,
+        install_dir: Some(install_dir.path().to_path_buf()),
+        ..Default::default()
+    }
+    .compile_package(package_path, &mut Vec::new())
+    .unwrap();
+
+    let vault = package
+        .compiled_modules()
+        .iter_modules()
+        .into_iter()
+        .map(|module| module.self_id())
+        .find(|module_id| module_id.name().as_str() == "Vault")
+        .unwrap();
+    let mut struct_bindings = StructBindingsBuilder::new(package.transitive_compiled_modules());
+    struct_bindings.add_module(&vault).unwrap();
+    let output = struct_bindings.generate().unwrap();
+    assert!(output.contains("pub mod coin {"));
+
+    // The output is compiled by the `vault_only` module above.
+    let exp_path = package_path.join("vault_structs.rs.exp");
+    if read_env_update_baseline() {
+        fs::write(&exp_path, &output).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&exp_path).unwrap_or_default();
+    assert!(
+        expected == output,
+        "Expected output {} differs from the output (run with `env UPDATE_BASELINE=1` to save the output):\n{}",
+        exp_path.display(),
+        format_diff(expected, output)
+    );
+}
+
+#[test]
+fn decode_with_vault_only_bindings() {
+    let treasury = vault_only::vault::Treasury {
+        gold: vault_only::vault::Vault {
+            items: vec![vault_only::coin::Coin {
+                value: 5,
+                phantom_t0: PhantomData,
+            }],
+            type_: 1,
+        },
+        pairs: vec![],
+    };
+    let bytes = bcs::to_bytes(&treasury).unwrap();
+    assert_eq!(
+        bcs::from_bytes::<vault_only::vault::Treasury>(&bytes).unwrap(),
+        treasury
+    );
+}
@@ -0,0 +1,56 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_bindgen::{generate, BindingsInput, Language, StructBindingsBuilder};
+use move_command_line_common::testing::{format_diff, read_env_update_baseline, EXP_EXT};
+use move_package::BuildConfig;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+fn run_test(path: &Path) -> datatest_stable::Result<()> {
+    let package_path = path.parent().unwrap();
+    let install_dir = tempdir()?;
+    let package = BuildConfig {
+        dev_mode: true,
+        generate_abis: true,
+        install_dir: Some(install_dir.path().to_path_buf()),
+        ..Default::default()
+    }
+    .compile_package(package_path, &mut Vec::new())?;
+
+    let input = BindingsInput::from_package(&package)?;
+    for language in Language::all() {
+        check_output(
+            &package_path.join(format!("bindings.{}.{}", language.extension(), EXP_EXT)),
+            generate(*language, &input)?,
+        )?;
+    }
+
+    let mut struct_bindings = StructBindingsBuilder::new(package.transitive_compiled_modules());
+    for module in package.compiled_modules().iter_modules() {
+        struct_bindings.add_module(&module.self_id())?;
+    }
+    check_output(
+        &package_path.join(format!("structs.rs.{}", EXP_EXT)),
+        struct_bindings.generate()?,
+    )
+}
+
+fn check_output(exp_path: &Path, output: String) -> datatest_stable::Result<()> {
+    if read_env_update_baseline() {
+        fs::write(exp_path, &output)?;
+        return Ok(());
+    }
+    let expected = fs::read_to_string(exp_path).unwrap_or_default();
+    if expected != output {
+        return Err(anyhow::format_err!(
+            "Expected output {} differs from the output (run with `env UPDATE_BASELINE=1` to save the output):\n{}",
+            exp_path.display(),
+            format_diff(expected, output)
+        )
+        .into());
+    }
+    Ok(())
+}
+
+datatest_stable::harness!(run_test, "tests/testsuite", r".*/Move\.toml$");
@@ -0,0 +1,43 @@
+module Basic::Coin {
+    use Std::Vector;
+
+    struct Coin<phantom CoinType> has store {
+        value: u64,
+    }
+
+    struct Gold {}
+
+    /// The balance of an account, with the history of its deposits.
+    struct Balance has key {
+        coin: Coin<Gold>,
+        deposits: vector<u64>,
+        owner: address,
+    }
+
+    struct Registry has key {
+        names: vector<vector<u8>>,
+        admins: vector<address>,
+        frozen: bool,
+        total: u128,
+    }
+
+    /// Publish an empty balance under the account of `account`.
+    public(script) fun publish(account: signer, owner: address) {
+        move_to(&account, Balance { coin: Coin { value: 0 }, deposits: Vector::empty(), owner })
+    }
+
+    /// Deposit `amount` into the balance of `to`.
+    ///
+    /// Aborts if `to` has no balance.
+    public(script) fun deposit(_account: signer, to: address, amount: u64) acquires Balance {
+        let balance = borrow_global_mut<Balance>(to);
+        balance.coin.value = balance.coin.value + amount;
+        Vector::push_back(&mut balance.deposits, amount);
+    }
+
+    public(script) fun register<T>(_account: signer, names: vector<vector<u8>>, admins: vector<address>, type: u8) {
+        _ = names;
+        _ = admins;
+        _ = type;
+    }
+}
@@ -0,0 +1,18 @@
+module Basic::Vault {
+    use Basic::Coin::{Coin, Gold};
+
+    struct Vault<T: store> has key, store {
+        items: vector<T>,
+        type: u8,
+    }
+
+    struct Pair<T: store, phantom Tag> has store {
+        first: T,
+        second: T,
+    }
+
+    struct Treasury has key {
+        gold: Vault<Coin<Gold>>,
+        pairs: vector<Pair<u64, Gold>>,
+    }
+}
@@ -23,6 +23,7 @@ use move_core_types::{
     account_address::AccountAddress, errmap::ErrorMapping, gas_schedule::CostTable,
     identifier::Identifier,
 };
+use move_unit_test::extensions::UnitTestExtensions;
 use move_vm_runtime::native_functions::NativeFunction;
 use std::path::PathBuf;
 
@@ -103,6 +104,26 @@ pub fn run_cli(
     error_descriptions: &ErrorMapping,
     move_args: &Move,
     cmd: &Command,
+) -> Result<()> {
+    run_cli_with_extensions(
+        natives,
+        UnitTestExtensions::default(),
+        cost_table,
+        error_descriptions,
+        move_args,
+        cmd,
+    )
+}
+
+/// Like `run_cli`, but unit tests run by `package test` additionally have access to the native
+/// functions and native context extensions registered in `test_extensions`.
+pub fn run_cli_with_extensions(
+    natives: Vec<NativeFunctionRecord>,
+    test_extensions: UnitTestExtensions,
+    cost_table: &CostTable,
+    error_descriptions: &ErrorMapping,
+    move_args: &Move,
+    cmd: &Command,
 ) -> Result<()> {
     match cmd {
         Command::Sandbox { storage_dir, cmd } => cmd.handle_command(
@@ -118,6 +139,7 @@ pub fn run_cli(
             move_args.build_config.clone(),
             cmd,
             natives,
+            test_extensions,
         ),
     }
 }
@@ -126,10 +148,27 @@ pub fn move_cli(
     natives: Vec<NativeFunctionRecord>,
     cost_table: &CostTable,
     error_descriptions: &ErrorMapping,
+) -> Result<()> {
+    move_cli_with_extensions(
+        natives,
+        UnitTestExtensions::default(),
+        cost_table,
+        error_descriptions,
+    )
+}
+
+/// Like `move_cli`, but unit tests run by `package test` additionally have access to the native
+/// functions and native context extensions registered in `test_extensions`.
+pub fn move_cli_with_extensions(
+    natives: Vec<NativeFunctionRecord>,
+    test_extensions: UnitTestExtensions,
+    cost_table: &CostTable,
+    error_descriptions: &ErrorMapping,
 ) -> Result<()> {
     let args = MoveCLI::parse();
-    run_cli(
+    run_cli_with_extensions(
         natives,
+        test_extensions,
         cost_table,
         error_descriptions,
         &args.move_args,
@@ -8,6 +8,7 @@ use std::{
     io::Write,
     path::{Path, PathBuf},
     process::ExitStatus,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 // if windows
@@ -26,26 +27,45 @@ use clap::Parser;
 use move_command_line_common::files::{FileHash, MOVE_COVERAGE_MAP_EXTENSION};
 use move_compiler::{
     compiled_unit::{CompiledUnit, NamedCompiledModule},
-    diagnostics::{self, codes::Severity},
+    diagnostics::{self, codes::Severity, Diagnostics, FilesSourceText},
     shared::{NumberFormat, NumericalAddress},
     unit_test::{plan_builder::construct_test_plan, TestPlan},
     PASS_CFGIR,
 };
 use move_coverage::{
+    cobertura::write_cobertura,
     coverage_map::{output_map_to_file, CoverageMap},
     format_csv_summary, format_human_summary,
+    lcov::write_lcov,
+    line_coverage::ModuleLineCoverage,
     source_coverage::SourceCoverageBuilder,
     summary::summarize_inst_cov,
 };
 use move_disassembler::disassembler::Disassembler;
 use move_package::{
-    compilation::{build_plan::BuildPlan, compiled_package::CompiledUnitWithSource},
+    compilation::{
+        build_plan::{
+            build_and_report_no_exit, report_diagnostics_no_exit,
+            unwrap_or_report_diagnostics_no_exit, BuildPlan,
+        },
+        compiled_package::{CompiledPackage, CompiledUnitWithSource},
+        package_layout::CompiledPackageLayout,
+    },
     source_package::layout::SourcePackageLayout,
     Architecture, ModelConfig,
 };
-use move_unit_test::UnitTestingConfig;
+use move_unit_test::{
+    extensions::UnitTestExtensions, test_reporter::ReportFormat, UnitTestingConfig,
+};
 
-use crate::{package::prover::run_move_prover, NativeFunctionRecord};
+use crate::{
+    package::{
+        prover::{run_move_prover, run_spec_coverage},
+        test_selection::{select_tests, Changes},
+        watch::watch_package,
+    },
+    NativeFunctionRecord,
+};
 
 #[derive(Parser)]
 pub enum CoverageSummaryOptions {
@@ -71,6 +91,26 @@ pub enum CoverageSummaryOptions {
         #[clap(long = "module")]
         module_name: String,
     },
+    /// Export line and branch coverage for all modules in this package as an LCOV tracefile
+    #[clap(name = "lcov")]
+    Lcov {
+        /// Coverage maps of other test runs to merge into the coverage of this package
+        #[clap(long = "merge")]
+        merge: Vec<String>,
+        /// Write the report to this file instead of stdout
+        #[clap(long = "output", short = 'o')]
+        output: Option<String>,
+    },
+    /// Export line and branch coverage for all modules in this package as a Cobertura XML report
+    #[clap(name = "cobertura")]
+    Cobertura {
+        /// Coverage maps of other test runs to merge into the coverage of this package
+        #[clap(long = "merge")]
+        merge: Vec<String>,
+        /// Write the report to this file instead of stdout
+        #[clap(long = "output", short = 'o')]
+        output: Option<String>,
+    },
 }
 
 #[derive(Parser)]
@@ -84,7 +124,12 @@ pub enum PackageCommand {
     },
     /// Build the package at `path`. If no path is provided defaults to current directory.
     #[clap(name = "build")]
-    Build,
+    Build {
+        /// Rebuild the package whenever its sources or the sources of its local dependencies
+        /// change. Only the packages which changed are recompiled.
+        #[clap(long = "watch")]
+        watch: bool,
+    },
     /// Print address information.
     #[clap(name = "info")]
     Info,
@@ -100,6 +145,17 @@ pub enum PackageCommand {
         #[clap(long, default_value = "error_map", parse(from_os_str))]
         output_file: PathBuf,
     },
+    /// Generate host language bindings for the script functions, transaction scripts and
+    /// resources of the package at `path`.
+    #[clap(name = "generate-bindings")]
+    GenerateBindings {
+        /// The language to generate bindings for: rust, typescript or python.
+        #[clap(long = "lang")]
+        language: move_bindgen::Language,
+        /// The directory to write the bindings to.
+        #[clap(long, default_value = "bindings", parse(from_os_str))]
+        output_dir: PathBuf,
+    },
     /// Run the Move Prover on the package at `path`. If no path is provided defaults to current
     /// directory. Use `.. prove .. -- <options>` to pass on options to the prover.
     #[clap(name = "prove")]
@@ -111,6 +167,22 @@ pub enum PackageCommand {
         /// Internal field indicating that this prover run is for a test.
         #[clap(skip)]
         for_test: bool,
+        /// Verify all functions, instead of skipping those whose verification inputs did not
+        /// change since they were last verified successfully.
+        #[clap(long = "no-cache")]
+        no_cache: bool,
+        /// Instead of verifying, report per module which functions have no spec or are not
+        /// verified, which functions do not specify their abort conditions completely, and which
+        /// struct invariants are never verified.
+        #[clap(long = "spec-coverage")]
+        spec_coverage: bool,
+        /// Print the spec coverage report as JSON.
+        #[clap(long = "json", requires = "spec-coverage")]
+        json: bool,
+        /// Write unit tests which reproduce the counterexamples found by the prover into the
+        /// `tests` directory of the package.
+        #[clap(long = "generate-tests")]
+        generate_tests: bool,
         /// Any options passed to the prover.
         #[clap(subcommand)]
         options: Option<ProverOptions>,
@@ -154,16 +226,75 @@ pub enum PackageCommand {
         /// Show the storage state at the end of execution of a failing test
         #[clap(name = "global_state_on_error", short = 'g', long = "state_on_error")]
         report_storage_on_error: bool,
+        /// A directory with resources every test starts with, in the layout of the sandbox
+        /// storage directory (e.g., created with `sandbox publish` and `sandbox run`). Relative
+        /// paths are resolved against the package root.
+        #[clap(long = "state", parse(from_os_str))]
+        state_dir: Option<PathBuf>,
+        /// A directory with snapshots of the resources written and events emitted by each passing
+        /// test, which are compared against the effects of the tests. Relative paths are resolved
+        /// against the package root.
+        #[clap(long = "snapshots", parse(from_os_str))]
+        snapshot_dir: Option<PathBuf>,
+        /// Overwrite the snapshots with the effects of the tests instead of comparing them. Also
+        /// enabled by setting `UPDATE_BASELINE`.
+        #[clap(long = "update-snapshots", requires = "snapshot-dir")]
+        update_snapshots: bool,
+        /// An error map, as generated by `errmap`, used to explain the codes tests abort with.
+        /// Defaults to `error_map.errmap` in the package root if it exists.
+        #[clap(long = "error-map", parse(from_os_str))]
+        error_map: Option<PathBuf>,
         /// Use the stackless bytecode interpreter to run the tests and cross check its results with
         /// the execution result from Move VM.
         #[clap(long = "stackless")]
         check_stackless_vm: bool,
+        /// Run the tests in the stackless bytecode interpreter as well and check the
+        /// specifications of the executed functions against the concrete values observed.
+        /// Results of the interpreter which differ from the ones of the Move VM are reported as
+        /// test failures. Does not require Boogie or Z3.
+        #[clap(long = "check-specs")]
+        check_specs: bool,
+        /// Run the tests against mutants of the package's modules and report the mutants which
+        /// are not detected by any test.
+        #[clap(long = "mutate")]
+        mutate: bool,
         /// Verbose mode
         #[clap(long = "verbose")]
         verbose_mode: bool,
         /// Collect coverage information for later use with the various `package coverage` subcommands
         #[clap(long = "coverage")]
         compute_coverage: bool,
+        /// The format of the test report: `human`, `junit` for a JUnit XML report, or `json` for
+        /// a stream of JSON events, one per line. Build output goes to stderr for the latter two.
+        #[clap(
+            long = "format",
+            possible_values = ReportFormat::variants(),
+            default_value = "human"
+        )]
+        report_format: ReportFormat,
+        /// Rerun the tests whenever the sources of the package or of its local dependencies
+        /// change. Only the tests of the modules affected by a change are rerun.
+        #[clap(long = "watch")]
+        watch: bool,
+        /// Only run the tests affected by the changes since the git revision REV: the tests
+        /// calling a changed function, directly or transitively, and the tests of modules
+        /// depending on a module changed outside of its functions. All tests are run if the
+        /// impact of a change cannot be determined.
+        #[clap(
+            long = "changed-since",
+            value_name = "REV",
+            conflicts_with_all = &["changed-files", "watch"]
+        )]
+        changed_since: Option<String>,
+        /// Only run the tests affected by changes to these files, as with `--changed-since`.
+        /// Relative paths are resolved against the package root.
+        #[clap(
+            long = "changed-files",
+            parse(from_os_str),
+            multiple_values = true,
+            conflicts_with = "watch"
+        )]
+        changed_files: Vec<PathBuf>,
 
         /// Use the EVM-based execution backend.
         /// Does not work with --stackless.
@@ -216,7 +347,14 @@ impl From<UnitTestResult> for ExitStatus {
 
 impl CoverageSummaryOptions {
     pub fn handle_command(&self, config: move_package::BuildConfig, path: &Path) -> Result<()> {
-        let coverage_map = CoverageMap::from_binary_file(path.join(".coverage_map.mvcov"))?;
+        let mut coverage_map = CoverageMap::from_binary_file(path.join(".coverage_map.mvcov"))?;
+        if let CoverageSummaryOptions::Lcov { merge, .. }
+        | CoverageSummaryOptions::Cobertura { merge, .. } = self
+        {
+            for other in merge {
+                coverage_map.merge(CoverageMap::from_binary_file(other)?);
+            }
+        }
         let package = config.compile_package(path, &mut Vec::new())?;
         let modules: Vec<_> = package
             .modules()?
@@ -270,16 +408,80 @@ impl CoverageSummaryOptions {
                 disassembler.add_coverage_map(coverage_map.to_unified_exec_map());
                 println!("{}", disassembler.disassemble()?);
             }
+            CoverageSummaryOptions::Lcov { output, .. } => {
+                let line_coverage = package_line_coverage(&package, &coverage_map, path)?;
+                write_lcov(&line_coverage, &mut coverage_writer(output)?)?;
+            }
+            CoverageSummaryOptions::Cobertura { output, .. } => {
+                let line_coverage = package_line_coverage(&package, &coverage_map, path)?;
+                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                write_cobertura(
+                    &line_coverage,
+                    package.compiled_package_info.package_name.as_str(),
+                    &path
+                        .canonicalize()
+                        .unwrap_or_else(|_| path.to_path_buf())
+                        .to_string_lossy(),
+                    timestamp,
+                    &mut coverage_writer(output)?,
+                )?;
+            }
         }
         Ok(())
     }
 }
 
+/// Maps the coverage of the modules in `package` to their source lines, with source paths
+/// relative to the package root `path`.
+fn package_line_coverage(
+    package: &CompiledPackage,
+    coverage_map: &CoverageMap,
+    path: &Path,
+) -> Result<Vec<ModuleLineCoverage>> {
+    let coverage_map = coverage_map.to_unified_exec_map();
+    package
+        .modules()?
+        .filter_map(|unit| match &unit.unit {
+            CompiledUnit::Module(NamedCompiledModule {
+                module, source_map, ..
+            }) => Some((module, source_map, &unit.source_path)),
+            _ => None,
+        })
+        .map(|(module, source_map, source_path)| {
+            let mut line_coverage =
+                ModuleLineCoverage::new(module, &coverage_map, source_map, source_path)?;
+            if let Ok(relative_path) = source_path.strip_prefix(path) {
+                line_coverage.source_path = relative_path.to_path_buf();
+            }
+            // Point to the sources of the package instead of their copies in the build directory.
+            let build_sources = CompiledPackageLayout::Root
+                .path()
+                .join(package.compiled_package_info.package_name.as_str())
+                .join(CompiledPackageLayout::Sources.path());
+            if let Ok(file) = line_coverage.source_path.strip_prefix(&build_sources) {
+                let original = SourcePackageLayout::Sources.path().join(file);
+                if path.join(&original).exists() {
+                    line_coverage.source_path = original;
+                }
+            }
+            Ok(line_coverage)
+        })
+        .collect()
+}
+
+fn coverage_writer(output: &Option<String>) -> Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(file) => Box::new(std::fs::File::create(file)?),
+        None => Box::new(std::io::stdout()),
+    })
+}
+
 pub fn handle_package_commands(
     path: &Path,
     config: move_package::BuildConfig,
     cmd: &PackageCommand,
     natives: Vec<NativeFunctionRecord>,
+    test_extensions: UnitTestExtensions,
 ) -> Result<()> {
     // This is the exceptional command as it doesn't need a package to run, so we can't count on
     // being able to root ourselves.
@@ -296,16 +498,27 @@ pub fn handle_package_commands(
     let rerooted_path = PathBuf::from(".");
 
     match cmd {
-        PackageCommand::Build => {
+        PackageCommand::Build { watch } => {
             let architecture = config.architecture.unwrap_or(Architecture::Move);
 
             match architecture {
+                Architecture::Move | Architecture::AsyncMove if *watch => {
+                    watch_package(&rerooted_path, config.clone(), |_| {
+                        config
+                            .clone()
+                            .compile_package_no_exit(&rerooted_path, &mut std::io::stdout())?;
+                        Ok(())
+                    })?;
+                }
                 Architecture::Move | Architecture::AsyncMove => {
                     config.compile_package(&rerooted_path, &mut std::io::stdout())?;
                 }
 
                 #[cfg(feature = "evm-backend")]
                 Architecture::Ethereum => {
+                    if *watch {
+                        bail!("Watch mode is not supported for the Ethereum architecture");
+                    }
                     config.compile_package_evm(&rerooted_path, &mut std::io::stdout())?;
                 }
             }
@@ -370,13 +583,48 @@ pub fn handle_package_commands(
         PackageCommand::Prove {
             target_filter,
             for_test,
+            no_cache,
+            spec_coverage,
+            json,
+            generate_tests,
             options,
         } => {
-            if let Some(ProverOptions::Options(opts)) = options {
-                run_move_prover(config, &rerooted_path, target_filter, *for_test, opts)?
-            } else {
-                run_move_prover(config, &rerooted_path, target_filter, *for_test, &[])?
+            let opts = match options {
+                Some(ProverOptions::Options(opts)) => opts.as_slice(),
+                None => &[],
+            };
+            if *spec_coverage {
+                return run_spec_coverage(config, &rerooted_path, target_filter, *json, opts);
             }
+            run_move_prover(
+                config,
+                &rerooted_path,
+                target_filter,
+                *for_test,
+                *no_cache,
+                *generate_tests,
+                opts,
+            )?
+        }
+        PackageCommand::GenerateBindings {
+            language,
+            output_dir,
+        } => {
+            let mut config = config;
+            config.generate_abis = true;
+            let package = config.compile_package(&rerooted_path, &mut std::io::stdout())?;
+            let input = move_bindgen::BindingsInput::from_package(&package)?;
+            let bindings = move_bindgen::generate(*language, &input)?;
+            create_dir_all(output_dir)?;
+            let output_file = output_dir
+                .join(package.compiled_package_info.package_name.as_str())
+                .with_extension(language.extension());
+            std::fs::write(&output_file, bindings)?;
+            println!(
+                "Generated {} bindings in {}",
+                language,
+                output_file.display()
+            );
         }
         PackageCommand::ErrMapGen {
             error_prefix,
@@ -408,34 +656,86 @@ pub fn handle_package_commands(
             num_threads,
             report_statistics,
             report_storage_on_error,
+            state_dir,
+            snapshot_dir,
+            update_snapshots,
+            error_map,
             check_stackless_vm,
+            check_specs,
+            mutate,
             verbose_mode,
             compute_coverage,
+            report_format,
+            watch,
+            changed_since,
+            changed_files,
 
             #[cfg(feature = "evm-backend")]
             evm,
         } => {
-            let unit_test_config = UnitTestingConfig {
-                instruction_execution_bound: *instruction_execution_bound,
-                filter: filter.clone(),
-                list: *list,
-                num_threads: *num_threads,
-                report_statistics: *report_statistics,
-                report_storage_on_error: *report_storage_on_error,
-                check_stackless_vm: *check_stackless_vm,
-                verbose: *verbose_mode,
-
-                #[cfg(feature = "evm-backend")]
-                evm: *evm,
-
-                ..UnitTestingConfig::default_with_bound(None)
+            let unit_test_config =
+                UnitTestingConfig {
+                    instruction_execution_bound: *instruction_execution_bound,
+                    filter: filter.clone(),
+                    list: *list,
+                    num_threads: *num_threads,
+                    report_statistics: *report_statistics,
+                    report_storage_on_error: *report_storage_on_error,
+                    state_dir: state_dir.clone(),
+                    snapshot_dir: snapshot_dir.clone(),
+                    update_snapshots: *update_snapshots,
+                    error_map: error_map.clone().or_else(|| {
+                        Some(PathBuf::from("error_map").with_extension(
+                            move_command_line_common::files::MOVE_ERROR_DESC_EXTENSION,
+                        ))
+                        .filter(|path| path.is_file())
+                    }),
+                    check_stackless_vm: *check_stackless_vm,
+                    check_specs: *check_specs,
+                    mutate: *mutate,
+                    verbose: *verbose_mode,
+                    report_format: *report_format,
+                    extensions: test_extensions,
+
+                    #[cfg(feature = "evm-backend")]
+                    evm: *evm,
+
+                    ..UnitTestingConfig::default_with_bound(None)
+                };
+            if *watch {
+                let mut watch_config = config.clone();
+                watch_config.test_mode = true;
+                return watch_package(&rerooted_path, watch_config, |changed_files| {
+                    // The first run, with no changes, runs all tests.
+                    let changes = Some(Changes::Files(changed_files.clone()))
+                        .filter(|_| !changed_files.is_empty());
+                    run_move_unit_tests_impl(
+                        &rerooted_path,
+                        config.clone(),
+                        unit_test_config.clone(),
+                        natives.clone(),
+                        *compute_coverage,
+                        changes.as_ref(),
+                        true,
+                    )?;
+                    Ok(())
+                });
+            }
+            let changes = match changed_since {
+                Some(rev) => Some(Changes::GitRevision(rev.clone())),
+                None if !changed_files.is_empty() => {
+                    Some(Changes::Files(changed_files.iter().cloned().collect()))
+                }
+                None => None,
             };
-            let result = run_move_unit_tests(
+            let result = run_move_unit_tests_impl(
                 &rerooted_path,
                 config,
                 unit_test_config,
                 natives,
                 *compute_coverage,
+                changes.as_ref(),
+                false,
             )?;
 
             // Return a non-zero exit code if any test failed
@@ -454,11 +754,48 @@ pub fn handle_package_commands(
 }
 
 pub fn run_move_unit_tests(
+    pkg_path: &Path,
+    build_config: move_package::BuildConfig,
+    unit_test_config: UnitTestingConfig,
+    natives: Vec<NativeFunctionRecord>,
+    compute_coverage: bool,
+) -> Result<UnitTestResult> {
+    run_move_unit_tests_impl(
+        pkg_path,
+        build_config,
+        unit_test_config,
+        natives,
+        compute_coverage,
+        None,
+        false,
+    )
+}
+
+/// Return the result of a compilation step or report its diagnostics. Unless `watching`, this
+/// exits the process on errors.
+fn unwrap_or_report<T>(
+    files: &FilesSourceText,
+    res: std::result::Result<T, Diagnostics>,
+    watching: bool,
+) -> Result<T> {
+    if watching {
+        unwrap_or_report_diagnostics_no_exit(files, res)
+    } else {
+        Ok(diagnostics::unwrap_or_report_diagnostics(files, res))
+    }
+}
+
+/// Run the unit tests of the package at `pkg_path`. If `changes` are given, only the tests
+/// affected by them are run. In watch mode, compilation errors are returned instead of exiting
+/// the process.
+fn run_move_unit_tests_impl(
     pkg_path: &Path,
     mut build_config: move_package::BuildConfig,
     mut unit_test_config: UnitTestingConfig,
     natives: Vec<NativeFunctionRecord>,
     compute_coverage: bool,
+    changes: Option<&Changes>,
+    watching: bool,
 ) -> Result<UnitTestResult> {
     let mut test_plan = None;
     build_config.test_mode = true;
@@ -466,6 +803,11 @@ pub fn run_move_unit_tests(
 
     // Build the resolution graph
     let resolution_graph = build_config.resolution_graph_for_package(pkg_path)?;
+    let package_paths: Vec<_> = resolution_graph
+        .package_table
+        .values()
+        .map(|package| package.package_path.clone())
+        .collect();
 
     // Note: unit_test_config.named_address_values is always set to vec![] (the default value) before
     // being passed in.
@@ -478,6 +820,13 @@ pub fn run_move_unit_tests(
             )
         })
         .collect();
+    // The sources of the root package are the ones under test.
+    unit_test_config.source_files = resolution_graph
+        .get_package(&resolution_graph.root_package.package.name)
+        .get_sources(&resolution_graph.build_options)?
+        .iter()
+        .map(|fname| fname.to_string())
+        .collect();
 
     // Get the source files for all modules. We need this in order to report source-mapped error
     // messages.
@@ -503,25 +852,39 @@ pub fn run_move_unit_tests(
     // process being performed by the Move package system, to first grab the compilation env,
     // construct the test plan from it, and then save it, before resuming the rest of the
     // compilation and returning the results and control back to the Move package system.
-    let pkg = build_plan.compile_with_driver(&mut std::io::stdout(), |compiler, is_root| {
+    // Keep machine-readable test reports on stdout free of build messages.
+    let mut build_output: Box<dyn std::io::Write> =
+        if unit_test_config.report_format == ReportFormat::Human {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(std::io::stderr())
+        };
+    let pkg = build_plan.compile_with_driver(&mut build_output, |compiler, is_root| {
         if !is_root {
-            compiler.build_and_report()
+            if watching {
+                build_and_report_no_exit(compiler)
+            } else {
+                compiler.build_and_report()
+            }
         } else {
             let (files, comments_and_compiler_res) = compiler.run::<PASS_CFGIR>().unwrap();
-            let (_, compiler) =
-                diagnostics::unwrap_or_report_diagnostics(&files, comments_and_compiler_res);
+            let (_, compiler) = unwrap_or_report(&files, comments_and_compiler_res, watching)?;
             let (mut compiler, cfgir) = compiler.into_ast();
             let compilation_env = compiler.compilation_env();
             let built_test_plan = construct_test_plan(compilation_env, &cfgir);
 
             if let Err(diags) = compilation_env.check_diags_at_or_above_severity(Severity::Warning)
             {
-                diagnostics::report_diagnostics(&files, diags);
+                if watching {
+                    report_diagnostics_no_exit(&files, diags);
+                } else {
+                    diagnostics::report_diagnostics(&files, diags);
+                }
             }
 
             let compilation_result = compiler.at_cfgir(cfgir).build();
 
-            let (units, _) = diagnostics::unwrap_or_report_diagnostics(&files, compilation_result);
+            let (units, _) = unwrap_or_report(&files, compilation_result, watching)?;
 
             test_plan = Some((built_test_plan, files.clone(), units.clone()));
             Ok((files, units))
@@ -547,6 +910,10 @@ pub fn run_move_unit_tests(
             }
         }
     }
+    if let Some(changes) = changes {
+        select_tests(&test_plan, changes, pkg_path, &package_paths)
+            .apply(&mut test_plan, &mut build_output)?;
+    }
 
     let trace_path = pkg_path.join(".trace");
     let coverage_map_path = pkg_path
@@ -569,8 +936,7 @@ pub fn run_move_unit_tests(
     // Run the tests. If any of the tests fail, then we don't produce a coverage report, so cleanup
     // the trace files.
     if !unit_test_config
-        .run_and_report_unit_tests(test_plan, Some(natives), std::io::stdout())
-        .unwrap()
+        .run_and_report_unit_tests(test_plan, Some(natives), std::io::stdout())?
         .1
     {
         cleanup_trace();
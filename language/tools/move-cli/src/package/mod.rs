@@ -3,3 +3,5 @@
 
 pub mod cli;
 pub mod prover;
+pub mod test_selection;
+pub mod watch;
@@ -7,8 +7,9 @@ use crate::package::cli;
 use anyhow::bail;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use colored::Colorize;
-use move_package::{BuildConfig, ModelConfig};
-use move_prover::run_move_prover_with_model;
+use move_package::{compilation::package_layout::CompiledPackageLayout, BuildConfig, ModelConfig};
+use move_prover::{run_move_prover_with_model, spec_coverage::SpecCoverageReport};
+use move_unit_test::extensions::UnitTestExtensions;
 use std::{
     io::Write,
     path::{Path, PathBuf},
@@ -71,11 +72,16 @@ impl ProverTest {
             &cli::PackageCommand::Prove {
                 target_filter: None,
                 for_test: true,
+                no_cache: true,
+                spec_coverage: false,
+                json: false,
+                generate_tests: false,
                 options: Some(cli::ProverOptions::Options(std::mem::take(
                     &mut self.options,
                 ))),
             },
             vec![], // prover does not need natives
+            UnitTestExtensions::default(),
         );
         std::env::set_current_dir(saved_cd).expect("restore current directory");
         res.unwrap()
@@ -108,36 +114,29 @@ pub fn run_move_prover(
     path: &Path,
     target_filter: &Option<String>,
     for_test: bool,
+    no_cache: bool,
+    generate_tests: bool,
     options: &[String],
 ) -> anyhow::Result<()> {
     // Always run the prover in dev mode, so addresses get default assignments
     config.dev_mode = true;
-
-    let mut args = vec!["package".to_string()];
-    let prover_toml = Path::new(&path).join("Prover.toml");
-    if prover_toml.exists() {
-        args.push(format!("--config={}", prover_toml.to_string_lossy()));
-    }
-    args.extend(options.iter().cloned());
-    let mut options = move_prover::cli::Options::create_from_args(&args)?;
-    if !options.move_sources.is_empty() {
-        bail!(
-            "move prover options must not specify sources as those are given \
-                     by the package system. Did you meant to prefix `{}` with `-t`?",
-            &options.move_sources[0]
-        );
-    }
-    if !options.move_deps.is_empty() {
-        bail!(
-            "move prover options must not specify dependencies as those are given \
-                     by the package system"
-        );
+    let mut options = prover_options(path, options)?;
+    if generate_tests {
+        options.generate_tests = Some(path.join("tests").to_string_lossy().to_string());
     }
-    if !options.move_named_address_values.is_empty() {
-        bail!(
-            "move prover options must not specify named addresses as those are given \
-                     by the package system"
-        );
+
+    // Unless disabled, keep the verification cache in the build directory of the package, so
+    // it is removed together with the build artifacts.
+    if !for_test && !no_cache && options.cache_dir.is_none() {
+        let cache_dir = config
+            .install_dir
+            .clone()
+            .unwrap_or_else(|| path.to_path_buf())
+            .join(CompiledPackageLayout::Root.path())
+            .join("prover_cache");
+        options.cache_dir = Some(cache_dir.to_string_lossy().to_string());
+    } else if no_cache {
+        options.cache_dir = None;
     }
 
     let mut message_writer = StandardStream::stdout(ColorChoice::Auto);
@@ -191,3 +190,63 @@ pub fn run_move_prover(
     }
     res
 }
+
+/// Creates the prover options for the package at `path` from the `Prover.toml` of the package,
+/// if any, and the given command line options.
+fn prover_options(path: &Path, options: &[String]) -> anyhow::Result<move_prover::cli::Options> {
+    let mut args = vec!["package".to_string()];
+    let prover_toml = Path::new(&path).join("Prover.toml");
+    if prover_toml.exists() {
+        args.push(format!("--config={}", prover_toml.to_string_lossy()));
+    }
+    args.extend(options.iter().cloned());
+    let options = move_prover::cli::Options::create_from_args(&args)?;
+    if !options.move_sources.is_empty() {
+        bail!(
+            "move prover options must not specify sources as those are given \
+                     by the package system. Did you meant to prefix `{}` with `-t`?",
+            &options.move_sources[0]
+        );
+    }
+    if !options.move_deps.is_empty() {
+        bail!(
+            "move prover options must not specify dependencies as those are given \
+                     by the package system"
+        );
+    }
+    if !options.move_named_address_values.is_empty() {
+        bail!(
+            "move prover options must not specify named addresses as those are given \
+                     by the package system"
+        );
+    }
+    Ok(options)
+}
+
+// =================================================================================================
+// Reporting spec coverage as a package command
+
+pub fn run_spec_coverage(
+    mut config: BuildConfig,
+    path: &Path,
+    target_filter: &Option<String>,
+    json: bool,
+    options: &[String],
+) -> anyhow::Result<()> {
+    config.dev_mode = true;
+    let options = prover_options(path, options)?;
+    let model = config.move_model_for_package(
+        path,
+        ModelConfig {
+            all_files_as_targets: false,
+            target_filter: target_filter.clone(),
+        },
+    )?;
+    let report = SpecCoverageReport::compute(&model, &options.prover.verify_scope);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report);
+    }
+    Ok(())
+}
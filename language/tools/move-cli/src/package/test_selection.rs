@@ -0,0 +1,481 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Selection of the unit tests affected by changes to the sources of a package. A test is affected
+//! if it calls a changed function, directly or transitively, or if it is in a module which depends
+//! on a module changed outside of its functions, e.g., in a struct declaration. Changed lines are
+//! mapped to functions with the source maps of the modules, and calls are followed in the call
+//! graph of the move-model. All tests are selected if the impact of a change cannot be determined.
+
+use crate::package::watch::is_manifest;
+use anyhow::{anyhow, bail, Context, Result};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{FunctionDefinitionIndex, TableIndex},
+};
+use move_bytecode_utils::dependency_graph::DependencyGraph;
+use move_command_line_common::files::{extension_equals, MOVE_EXTENSION};
+use move_compiler::{
+    compiled_unit::NamedCompiledModule,
+    unit_test::{filter_test_members::UNIT_TEST_POISON_FUN_NAME, TestPlan},
+};
+use move_core_types::{
+    identifier::{IdentStr, Identifier},
+    language_storage::ModuleId,
+};
+use move_model::{
+    model::{FunId, GlobalEnv, QualifiedId},
+    run_bytecode_model_builder,
+};
+use move_package::compilation::package_layout::CompiledPackageLayout;
+use move_unit_test::format_module_id;
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    ffi::OsStr,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The changes to select the tests to run by.
+#[derive(Debug, Clone)]
+pub enum Changes {
+    /// Files which changed as a whole. Relative paths are resolved against the current directory.
+    Files(BTreeSet<PathBuf>),
+    /// The changes of the working tree since a git revision, including untracked files.
+    GitRevision(String),
+}
+
+/// The ranges of lines which changed in a file, starting at line 1, or `None` if the whole file
+/// changed.
+type ChangedLines = Option<Vec<(usize, usize)>>;
+
+impl Changes {
+    /// The changed files, by canonical path. The changes since a git revision are those of the
+    /// packages at `package_paths` in the repository of the package at `root`.
+    fn changed_lines(
+        &self,
+        root: &Path,
+        package_paths: &[PathBuf],
+    ) -> Result<BTreeMap<PathBuf, ChangedLines>> {
+        match self {
+            Changes::Files(files) => Ok(files.iter().map(|path| (canonical(path), None)).collect()),
+            Changes::GitRevision(rev) => git_changed_lines(rev, root, package_paths)
+                .with_context(|| format!("unable to diff against '{}'", rev)),
+        }
+    }
+}
+
+/// The tests to run, selected by the changes to the package.
+#[derive(Debug)]
+pub enum TestSelection {
+    /// All tests, for the given reason.
+    All(String),
+    /// The tests affected by the changes, with the reason each of them was selected.
+    Tests(BTreeMap<(ModuleId, String), String>),
+}
+
+impl TestSelection {
+    /// Restrict the tests of `test_plan` to the selected ones and report why they were selected.
+    pub fn apply<W: Write>(&self, test_plan: &mut TestPlan, w: &mut W) -> Result<()> {
+        let selected = match self {
+            TestSelection::All(reason) => {
+                writeln!(w, "Running all tests: {}", reason)?;
+                return Ok(());
+            }
+            TestSelection::Tests(selected) => selected,
+        };
+        let num_tests: usize = test_plan
+            .module_tests
+            .values()
+            .map(|module_tests| module_tests.tests.len())
+            .sum();
+        writeln!(
+            w,
+            "Running {} of {} tests affected by the changes",
+            selected.len(),
+            num_tests
+        )?;
+        for ((module_id, test_name), reason) in selected {
+            writeln!(w, "  {}: {}", format_function(module_id, test_name), reason)?;
+        }
+        for (module_id, module_tests) in test_plan.module_tests.iter_mut() {
+            module_tests.tests.retain(|test_name, _| {
+                selected.contains_key(&(module_id.clone(), test_name.clone()))
+            });
+        }
+        test_plan
+            .module_tests
+            .retain(|_, module_tests| !module_tests.tests.is_empty());
+        Ok(())
+    }
+}
+
+/// Select the tests of `test_plan` affected by `changes` to the package at `root`, whose package
+/// and dependencies are at `package_paths`.
+pub fn select_tests(
+    test_plan: &TestPlan,
+    changes: &Changes,
+    root: &Path,
+    package_paths: &[PathBuf],
+) -> TestSelection {
+    changes
+        .changed_lines(root, package_paths)
+        .and_then(|changed| select_affected_tests(test_plan, &changed))
+        .unwrap_or_else(|err| TestSelection::All(format!("{:#}", err)))
+}
+
+fn select_affected_tests(
+    test_plan: &TestPlan,
+    changed: &BTreeMap<PathBuf, ChangedLines>,
+) -> Result<TestSelection> {
+    let mut modules_by_file: BTreeMap<PathBuf, Vec<(&ModuleId, &NamedCompiledModule)>> =
+        BTreeMap::new();
+    for (module_id, module) in &test_plan.module_info {
+        let file_hash = module.source_map.definition_location.file_hash();
+        if let Some((file_name, _)) = test_plan.files.get(&file_hash) {
+            modules_by_file
+                .entry(canonical(Path::new(file_name.as_str())))
+                .or_default()
+                .push((module_id, module));
+        }
+    }
+
+    let mut changed_functions = BTreeSet::new();
+    let mut changed_modules = BTreeSet::new();
+    for (path, lines) in changed {
+        if is_manifest(path) {
+            return Ok(TestSelection::All(format!("{} changed", path.display())));
+        }
+        // Only Move sources and manifests affect the tests.
+        if !extension_equals(path, MOVE_EXTENSION) {
+            continue;
+        }
+        let modules = match modules_by_file.get(path) {
+            Some(modules) => modules,
+            None => {
+                return Ok(TestSelection::All(format!(
+                    "{} does not define a module",
+                    path.display()
+                )))
+            }
+        };
+        let lines = match lines {
+            Some(lines) => lines,
+            None => {
+                changed_modules.extend(modules.iter().map(|(module_id, _)| (*module_id).clone()));
+                continue;
+            }
+        };
+        let file_hash = modules[0].1.source_map.definition_location.file_hash();
+        let contents: Vec<_> = test_plan.files[&file_hash].1.lines().collect();
+        let functions: Vec<_> = modules
+            .iter()
+            .flat_map(|(module_id, module)| {
+                function_lines(module, &test_plan.files[&file_hash].1)
+                    .into_iter()
+                    .map(move |(name, span)| ((*module_id).clone(), name, span))
+            })
+            .collect();
+        for line in lines.iter().flat_map(|(start, end)| *start..=*end) {
+            let mut in_function = false;
+            for (module_id, name, (start, end)) in &functions {
+                if (*start..=*end).contains(&line) {
+                    changed_functions.insert((module_id.clone(), name.clone()));
+                    in_function = true;
+                }
+            }
+            // Changes to blank lines and comments between functions do not affect the tests.
+            let text = contents.get(line - 1).map_or("", |text| text.trim());
+            if !in_function && !text.is_empty() && !text.starts_with("//") {
+                changed_modules.extend(modules.iter().map(|(module_id, _)| (*module_id).clone()));
+            }
+        }
+    }
+
+    let graph = DependencyGraph::new(test_plan.module_info.values().map(|module| &module.module));
+    let env = run_bytecode_model_builder(graph.compute_topological_order()?)?;
+    let changed_functions: BTreeSet<_> = changed_functions
+        .iter()
+        .filter_map(|(module_id, name)| {
+            env.find_function_by_language_storage_id_name(module_id, name)
+                .map(|fun_env| fun_env.get_qualified_id())
+        })
+        .collect();
+
+    // The modules depending on a changed module, directly or transitively, each with one of the
+    // changed modules it depends on.
+    let mut affected_modules: BTreeMap<ModuleId, ModuleId> = changed_modules
+        .into_iter()
+        .map(|module_id| (module_id.clone(), module_id))
+        .collect();
+    loop {
+        let dependents: Vec<_> = test_plan
+            .module_info
+            .iter()
+            .filter(|(module_id, _)| !affected_modules.contains_key(*module_id))
+            .filter_map(|(module_id, module)| {
+                let changed = module
+                    .module
+                    .immediate_dependencies()
+                    .into_iter()
+                    .find_map(|dep| affected_modules.get(&dep))?;
+                Some((module_id.clone(), changed.clone()))
+            })
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        affected_modules.extend(dependents);
+    }
+
+    let mut selected = BTreeMap::new();
+    for (module_id, module_tests) in &test_plan.module_tests {
+        for test_name in module_tests.tests.keys() {
+            let test = env
+                .find_function_by_language_storage_id_name(module_id, IdentStr::new(test_name)?)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unable to find test {}",
+                        format_function(module_id, test_name)
+                    )
+                })?
+                .get_qualified_id();
+            let reason = if let Some(path) = call_path(&env, test, &changed_functions) {
+                format!(
+                    "{} changed",
+                    path.into_iter()
+                        .map(|fun| {
+                            let fun_env = env.get_function(fun);
+                            format_function(
+                                &fun_env.module_env.get_verified_module().self_id(),
+                                fun_env.get_identifier().as_str(),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )
+            } else if let Some(changed) = affected_modules.get(module_id) {
+                if changed == module_id {
+                    format!("{} changed", format_module_id(changed))
+                } else {
+                    format!("depends on {}, which changed", format_module_id(changed))
+                }
+            } else {
+                continue;
+            };
+            selected.insert((module_id.clone(), test_name.clone()), reason);
+        }
+    }
+    Ok(TestSelection::Tests(selected))
+}
+
+/// The lines spanned by each function of `module`, from its name to the end of its code.
+fn function_lines(
+    module: &NamedCompiledModule,
+    contents: &str,
+) -> Vec<(Identifier, (usize, usize))> {
+    let file_hash = module.source_map.definition_location.file_hash();
+    module
+        .module
+        .function_defs()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, def)| {
+            let name = module
+                .module
+                .identifier_at(module.module.function_handle_at(def.function).name)
+                .to_owned();
+            // The function generated for test mode spans the whole module.
+            if name.as_str() == UNIT_TEST_POISON_FUN_NAME {
+                return None;
+            }
+            let function_map = module
+                .source_map
+                .get_function_source_map(FunctionDefinitionIndex(idx as TableIndex))
+                .ok()?;
+            let locs: Vec<_> = std::iter::once(&function_map.definition_location)
+                .chain(function_map.code_map.values())
+                .filter(|loc| loc.file_hash() == file_hash)
+                .collect();
+            let start = locs.iter().map(|loc| loc.start()).min()?;
+            let end = locs.iter().map(|loc| loc.end()).max()?;
+            Some((name, (line_at(contents, start), line_at(contents, end))))
+        })
+        .collect()
+}
+
+/// The line of the byte `offset` in `contents`, starting at line 1.
+fn line_at(contents: &str, offset: u32) -> usize {
+    let offset = (offset as usize).min(contents.len());
+    contents.as_bytes()[..offset]
+        .iter()
+        .filter(|byte| **byte == b'\n')
+        .count()
+        + 1
+}
+
+/// The shortest chain of calls from `test` to one of the `changed` functions, if any.
+fn call_path(
+    env: &GlobalEnv,
+    test: QualifiedId<FunId>,
+    changed: &BTreeSet<QualifiedId<FunId>>,
+) -> Option<Vec<QualifiedId<FunId>>> {
+    let mut callers = BTreeMap::new();
+    let mut queue = VecDeque::from(vec![test]);
+    while let Some(fun) = queue.pop_front() {
+        if changed.contains(&fun) {
+            let mut path = vec![fun];
+            while let Some(caller) = callers.get(path.last().unwrap()) {
+                path.push(*caller);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for callee in env.get_function(fun).get_called_functions() {
+            if callee != test && !callers.contains_key(&callee) {
+                callers.insert(callee, fun);
+                queue.push_back(callee);
+            }
+        }
+    }
+    None
+}
+
+/// The lines changed in the packages at `package_paths` since `rev`, in the git repository of the
+/// package at `root`. Packages outside of the repository are ignored.
+fn git_changed_lines(
+    rev: &str,
+    root: &Path,
+    package_paths: &[PathBuf],
+) -> Result<BTreeMap<PathBuf, ChangedLines>> {
+    let toplevel = canonical(Path::new(
+        git(root, ["rev-parse", "--show-toplevel"])?.trim(),
+    ));
+    let pathspecs: Vec<_> = package_paths
+        .iter()
+        .map(|path| canonical(path))
+        .filter(|path| path.starts_with(&toplevel))
+        .collect();
+    let diff_args = [
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-renames",
+        "--unified=0",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+        rev,
+        "--",
+    ];
+    let diff = git(
+        &toplevel,
+        diff_args
+            .iter()
+            .map(OsStr::new)
+            .chain(pathspecs.iter().map(|path| path.as_os_str())),
+    )?;
+
+    let mut changed: BTreeMap<PathBuf, ChangedLines> = BTreeMap::new();
+    let mut in_header = false;
+    let mut old_path = None;
+    let mut path = None;
+    for line in diff.lines() {
+        if line.starts_with("diff ") {
+            in_header = true;
+        } else if line.starts_with("@@ ") {
+            in_header = false;
+            let (start, len) =
+                parse_hunk(line).ok_or_else(|| anyhow!("unexpected hunk '{}'", line))?;
+            // Lines which were removed are between the line `start` and the next one.
+            let lines = if len == 0 {
+                (start.max(1), start + 1)
+            } else {
+                (start, start + len - 1)
+            };
+            if let Some(Some(changed_lines)) = path.as_ref().and_then(|path| changed.get_mut(path))
+            {
+                changed_lines.push(lines);
+            }
+        } else if in_header {
+            if let Some(file) = line.strip_prefix("--- ") {
+                old_path = file
+                    .trim_end_matches('\t')
+                    .strip_prefix("a/")
+                    .map(|file| toplevel.join(file));
+            } else if let Some(file) = line.strip_prefix("+++ ") {
+                path = file
+                    .trim_end_matches('\t')
+                    .strip_prefix("b/")
+                    .map(|file| canonical(&toplevel.join(file)));
+                match &path {
+                    Some(path) => {
+                        changed.insert(path.clone(), Some(vec![]));
+                    }
+                    // The file was removed.
+                    None => {
+                        if let Some(old_path) = old_path.take() {
+                            changed.insert(old_path, None);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let untracked = git(
+        &toplevel,
+        ["ls-files", "--others", "--exclude-standard", "--"]
+            .iter()
+            .map(OsStr::new)
+            .chain(pathspecs.iter().map(|path| path.as_os_str())),
+    )?;
+    for file in untracked.lines() {
+        changed.insert(canonical(&toplevel.join(file)), None);
+    }
+    // The build directories of the packages hold copies of the sources of their dependencies.
+    let build_dirs: Vec<_> = pathspecs
+        .iter()
+        .map(|path| path.join(CompiledPackageLayout::Root.path()))
+        .collect();
+    changed.retain(|path, _| !build_dirs.iter().any(|dir| path.starts_with(dir)));
+    Ok(changed)
+}
+
+/// The first line and the number of lines of the new side of the hunk header `line`, e.g.,
+/// `@@ -1,2 +3,4 @@`.
+fn parse_hunk(line: &str) -> Option<(usize, usize)> {
+    let new = line.split(' ').find_map(|part| part.strip_prefix('+'))?;
+    let mut parts = new.split(',');
+    let start = parts.next()?.parse().ok()?;
+    let len = match parts.next() {
+        Some(len) => len.parse().ok()?,
+        None => 1,
+    };
+    Some((start, len))
+}
+
+fn git<I, S>(dir: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn format_function(module_id: &ModuleId, name: &str) -> String {
+    format!("{}::{}", format_module_id(module_id), name)
+}
@@ -0,0 +1,138 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Watch mode for package commands: the command is re-run whenever the manifest or a Move source
+//! file of the package or of one of its local dependencies changes. Git dependencies are not
+//! watched, as they are pinned to a revision.
+
+use anyhow::Result;
+use move_package::{
+    resolution::resolution_graph::{ResolvedGraph, ResolvedPackage},
+    source_package::layout::SourcePackageLayout,
+    BuildConfig,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// How often the watched files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The last modification time and length of a watched file.
+type FileStamp = (SystemTime, u64);
+
+/// The packages of a resolution graph whose files are watched.
+struct WatchedPackages {
+    packages: Vec<ResolvedPackage>,
+    build_config: BuildConfig,
+}
+
+impl WatchedPackages {
+    fn new(resolution_graph: ResolvedGraph) -> Self {
+        let git_dependencies: BTreeSet<_> = resolution_graph
+            .package_table
+            .values()
+            .flat_map(|package| {
+                package
+                    .source_package
+                    .dependencies
+                    .iter()
+                    .chain(package.source_package.dev_dependencies.iter())
+            })
+            .filter(|(_, dep)| dep.git_info.is_some())
+            .map(|(name, _)| *name)
+            .collect();
+        Self {
+            packages: resolution_graph
+                .package_table
+                .into_iter()
+                .filter(|(name, _)| !git_dependencies.contains(name))
+                .map(|(_, package)| package)
+                .collect(),
+            build_config: resolution_graph.build_options,
+        }
+    }
+
+    /// The manifests and Move source files of the watched packages, by canonical path. Files
+    /// which are added to a package are picked up as well.
+    fn files(&self) -> BTreeMap<PathBuf, FileStamp> {
+        let mut files = BTreeMap::new();
+        for package in &self.packages {
+            let manifest = package
+                .package_path
+                .join(SourcePackageLayout::Manifest.path());
+            let sources = package.get_sources(&self.build_config).unwrap_or_default();
+            for path in sources
+                .iter()
+                .map(|source| PathBuf::from(source.as_str()))
+                .chain(std::iter::once(manifest))
+            {
+                if let Ok((path, stamp)) = file_stamp(&path) {
+                    files.insert(path, stamp);
+                }
+            }
+        }
+        files
+    }
+}
+
+fn file_stamp(path: &Path) -> Result<(PathBuf, FileStamp)> {
+    let metadata = fs::metadata(path)?;
+    Ok((path.canonicalize()?, (metadata.modified()?, metadata.len())))
+}
+
+/// The files which were added, removed or modified between `old` and `new`.
+fn changed_files(
+    old: &BTreeMap<PathBuf, FileStamp>,
+    new: &BTreeMap<PathBuf, FileStamp>,
+) -> BTreeSet<PathBuf> {
+    old.keys()
+        .chain(new.keys())
+        .filter(|path| old.get(*path) != new.get(*path))
+        .cloned()
+        .collect()
+}
+
+/// Whether `path` is the manifest of a package.
+pub fn is_manifest(path: &Path) -> bool {
+    path.file_name() == Some(SourcePackageLayout::Manifest.path().as_os_str())
+}
+
+/// Run `command` for the package at `path`, then again each time the files of the package or of
+/// one of its local dependencies change, until the process is interrupted. `command` is passed
+/// the canonical paths of the files which changed since its last run, none on the first run.
+/// Errors of `command` are reported, but do not stop watching.
+pub fn watch_package(
+    path: &Path,
+    build_config: BuildConfig,
+    mut command: impl FnMut(&BTreeSet<PathBuf>) -> Result<()>,
+) -> Result<()> {
+    let mut watched =
+        WatchedPackages::new(build_config.clone().resolution_graph_for_package(path)?);
+    let mut changed = BTreeSet::new();
+    loop {
+        // Take the state of the files before running the command, so that changes made while it
+        // runs are not missed.
+        let files = watched.files();
+        if let Err(err) = command(&changed) {
+            eprintln!("Error: {:#}", err);
+        }
+        // The dependencies of the package may have changed along with its manifest.
+        match build_config.clone().resolution_graph_for_package(path) {
+            Ok(resolution_graph) => watched = WatchedPackages::new(resolution_graph),
+            Err(err) => eprintln!("Error: {:#}", err),
+        }
+        println!("Waiting for changes...");
+        changed = loop {
+            thread::sleep(POLL_INTERVAL);
+            let changed = changed_files(&files, &watched.files());
+            if !changed.is_empty() {
+                break changed;
+            }
+        };
+    }
+}
@@ -8,8 +8,9 @@ use crate::{
     },
     Move, NativeFunctionRecord, DEFAULT_BUILD_DIR,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
+use move_command_line_common::files::MOVE_ERROR_DESC_EXTENSION;
 use move_core_types::{
     errmap::ErrorMapping, gas_schedule::CostTable, language_storage::TypeTag, parser,
     transaction_argument::TransactionArgument,
@@ -158,6 +159,7 @@ pub struct StructLayoutOptions {
         long = "type-args",
         parse(try_from_str = parser::parse_type_tag),
         requires="struct",
+        conflicts_with="rust",
         takes_value(true),
         multiple_values(true),
         multiple_occurrences(true)
@@ -167,6 +169,12 @@ pub struct StructLayoutOptions {
     /// When unset, generates bindings for the struct and all of its transitive dependencies.
     #[clap(long = "shallow")]
     shallow: bool,
+    /// Generate typed Rust structs mirroring the Move structs, generic over their type
+    /// parameters, instead of serde-reflection layouts. When `struct` is unset, generates
+    /// bindings for all the structs of the module. The structs they depend on are always
+    /// generated as well, so that the bindings compile.
+    #[clap(long = "rust", conflicts_with = "shallow")]
+    rust: bool,
 }
 
 impl SandboxCommand {
@@ -210,10 +218,22 @@ impl SandboxCommand {
                 let context =
                     PackageContext::new(&move_args.package_path, &move_args.build_config)?;
                 let state = context.prepare_state(storage_dir)?;
+                // Explain the abort codes of the package with its error map, if it has one
+                let mut error_descriptions = error_descriptions.clone();
+                let package_error_map = move_args
+                    .package_path
+                    .join("error_map")
+                    .with_extension(MOVE_ERROR_DESC_EXTENSION);
+                if package_error_map.is_file() {
+                    let bytes = fs::read(&package_error_map)?;
+                    error_descriptions.extend(bcs::from_bytes(&bytes).with_context(|| {
+                        format!("invalid error map `{}`", package_error_map.display())
+                    })?);
+                }
                 sandbox::commands::run(
                     natives,
                     cost_table,
-                    error_descriptions,
+                    &error_descriptions,
                     &state,
                     context.package(),
                     script_file,
@@ -283,6 +303,7 @@ fn handle_generate_commands(cmd: &GenerateCommand, state: &OnDiskStateView) -> R
                 &options.struct_,
                 &options.type_args,
                 options.shallow,
+                options.rust,
                 state,
             )
         }
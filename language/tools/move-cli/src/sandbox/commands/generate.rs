@@ -3,6 +3,7 @@
 
 use crate::sandbox::utils::on_disk_state_view::OnDiskStateView;
 use anyhow::{bail, Result};
+use move_bindgen::StructBindingsBuilder;
 use move_bytecode_utils::layout::SerdeLayoutBuilder;
 use move_core_types::{
     identifier::Identifier,
@@ -15,10 +16,24 @@ pub fn generate_struct_layouts(
     struct_opt: &Option<String>,
     type_params_opt: &Option<Vec<TypeTag>>,
     shallow: bool,
+    rust: bool,
     state: &OnDiskStateView,
 ) -> Result<()> {
     if let Some(module_id) = state.get_module_id(path) {
-        if let Some(struct_) = struct_opt {
+        if rust {
+            // Generate typed Rust structs, for one struct or for the whole module, together with
+            // the structs they depend on
+            let mut bindings_builder = StructBindingsBuilder::new(state);
+            match struct_opt {
+                Some(struct_) => {
+                    bindings_builder.add_struct(&module_id, &Identifier::new(struct_.as_str())?)?
+                }
+                None => bindings_builder.add_module(&module_id)?,
+            }
+            let bindings = bindings_builder.generate()?;
+            state.save_struct_bindings(&bindings)?;
+            println!("{}", bindings);
+        } else if let Some(struct_) = struct_opt {
             // Generate for one struct
             let type_params = type_params_opt.as_ref().cloned().unwrap_or_default();
             let name = Identifier::new(struct_.as_str())?;
@@ -3,8 +3,9 @@
 
 use crate::{
     sandbox::utils::{
-        contains_module, explain_execution_effects, explain_execution_error, get_gas_status,
-        is_bytecode_file, maybe_commit_effects, on_disk_state_view::OnDiskStateView,
+        contains_module, explain_execution_effects, explain_execution_error, explain_stack_trace,
+        get_gas_status, is_bytecode_file, maybe_commit_effects,
+        on_disk_state_view::OnDiskStateView,
     },
     NativeFunctionRecord,
 };
@@ -42,7 +43,7 @@ pub fn run(
         bail!("Script file {:?} does not exist", script_path)
     };
 
-    let bytecode = if is_bytecode_file(script_path) {
+    let (bytecode, script_opt) = if is_bytecode_file(script_path) {
         assert!(
             state.is_module_path(script_path) || !contains_module(script_path),
             "Attempting to run module {:?} outside of the `storage/` directory.
@@ -50,7 +51,7 @@ move run` must be applied to a module inside `storage/`",
             script_path
         );
         // script bytecode; read directly from file
-        fs::read(script_path)?
+        (fs::read(script_path)?, None)
     } else {
         // TODO(tzakian): support calling scripts in transitive deps
         let file_contents = std::fs::read_to_string(script_path)?;
@@ -59,7 +60,7 @@ move run` must be applied to a module inside `storage/`",
             .find(|unit| unit.unit.source_map().check(&file_contents));
         // script source file; package is already compiled so load it up
         match script_opt {
-            Some(unit) => unit.unit.serialize(),
+            Some(unit) => (unit.unit.serialize(), Some(unit)),
             None => bail!("Unable to find script in file {:?}", script_path),
         }
     };
@@ -109,6 +110,7 @@ move run` must be applied to a module inside `storage/`",
     };
 
     if let Err(err) = res {
+        let stack_trace = explain_stack_trace(&err, package, script_opt);
         explain_execution_error(
             error_descriptions,
             err,
@@ -118,7 +120,11 @@ move run` must be applied to a module inside `storage/`",
             &vm_type_args,
             &signer_addresses,
             txn_args,
-        )
+        )?;
+        if !stack_trace.frames.is_empty() {
+            print!("{}", stack_trace);
+        }
+        Ok(())
     } else {
         let (changeset, events) = session.finish().map_err(|e| e.into_vm_status())?;
         if verbose {
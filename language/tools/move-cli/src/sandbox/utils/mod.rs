@@ -7,6 +7,7 @@ use colored::Colorize;
 use difference::{Changeset, Difference};
 use move_binary_format::{
     access::ModuleAccess,
+    binary_views::BinaryIndexedView,
     compatibility::Compatibility,
     errors::VMError,
     file_format::{AbilitySet, CompiledModule, FunctionDefinitionIndex, SignatureToken},
@@ -25,10 +26,11 @@ use move_core_types::{
     gas_schedule::{GasAlgebra, GasUnits},
     language_storage::{ModuleId, TypeTag},
     transaction_argument::TransactionArgument,
-    vm_status::{AbortLocation, StatusCode, VMStatus},
+    vm_status::{AbortLocation, StatusCode, StatusType, VMStatus},
 };
+use move_explain::{CodeUnit, StackTrace};
 use move_ir_types::location::Loc;
-use move_package::compilation::compiled_package::CompiledUnitWithSource;
+use move_package::compilation::compiled_package::{CompiledPackage, CompiledUnitWithSource};
 use move_resource_viewer::{AnnotatedMoveStruct, MoveValueAnnotator};
 use move_vm_types::gas_schedule::GasStatus;
 use std::{
@@ -435,6 +437,18 @@ pub(crate) fn explain_publish_error(
             }
             report_diagnostics(&files, diags)
         }
+        VMStatus::Error(status_code) if status_code.status_type() == StatusType::Verification => {
+            let source_path = unit.source_path.to_string_lossy();
+            let explanation = move_explain::explain_verifier_error(
+                &error_clone,
+                module,
+                Some(unit.unit.source_map()),
+                files
+                    .get(&file_hash)
+                    .map(|(_, contents)| (source_path.as_ref(), contents.as_str())),
+            );
+            print!("Publishing failed with verification error\n{}", explanation)
+        }
         VMStatus::Error(status_code) => {
             println!("Publishing failed with unexpected error {:?}", status_code)
         }
@@ -554,6 +568,58 @@ pub(crate) fn explain_execution_error(
     Ok(())
 }
 
+/// Explain the call stack of an execution error, mapping the frames in the modules of `package`
+/// and its dependencies, or in the `script` that was run, to their source
+pub(crate) fn explain_stack_trace(
+    error: &VMError,
+    package: &CompiledPackage,
+    script: Option<&CompiledUnitWithSource>,
+) -> StackTrace {
+    let mut packages = vec![package];
+    let mut units = vec![];
+    while let Some(package) = packages.pop() {
+        units.extend(
+            package
+                .compiled_units
+                .iter()
+                .filter(|unit| matches!(unit.unit, CompiledUnit::Module(_))),
+        );
+        packages.extend(&package.dependencies);
+    }
+    let units_with_source: Vec<_> = units
+        .into_iter()
+        .chain(script)
+        .map(|unit| {
+            let source = fs::read_to_string(&unit.source_path)
+                .ok()
+                .map(|contents| (unit.source_path.to_string_lossy().to_string(), contents));
+            (unit, source)
+        })
+        .collect();
+    move_explain::explain_stack_trace(error, |module_id| {
+        let (unit, source) =
+            units_with_source
+                .iter()
+                .find(|(unit, _)| match (&unit.unit, module_id) {
+                    (CompiledUnit::Module(module), Some(module_id)) => {
+                        &module.module.self_id() == module_id
+                    }
+                    (CompiledUnit::Script(_), None) => true,
+                    _ => false,
+                })?;
+        Some(CodeUnit {
+            code: match &unit.unit {
+                CompiledUnit::Module(module) => BinaryIndexedView::Module(&module.module),
+                CompiledUnit::Script(script) => BinaryIndexedView::Script(&script.script),
+            },
+            source_map: Some(unit.unit.source_map()),
+            source: source
+                .as_ref()
+                .map(|(file, contents)| (file.as_str(), contents.as_str())),
+        })
+    })
+}
+
 /// Return `true` if `path` is a Move bytecode file based on its extension
 pub(crate) fn is_bytecode_file(path: &Path) -> bool {
     path.extension()
@@ -38,6 +38,9 @@ pub const EVENTS_DIR: &str = "events";
 /// file under `DEFAULT_BUILD_DIR` where a registry of generated struct layouts are stored
 pub const STRUCT_LAYOUTS_FILE: &str = "struct_layouts.yaml";
 
+/// file under `DEFAULT_BUILD_DIR` where generated Rust struct bindings are stored
+pub const STRUCT_BINDINGS_FILE: &str = "struct_bindings.rs";
+
 #[derive(Debug)]
 pub struct OnDiskStateView {
     build_dir: PathBuf,
@@ -73,6 +76,10 @@ impl OnDiskStateView {
         self.build_dir.join(STRUCT_LAYOUTS_FILE)
     }
 
+    pub fn struct_bindings_file(&self) -> PathBuf {
+        self.build_dir.join(STRUCT_BINDINGS_FILE)
+    }
+
     fn is_data_path(&self, p: &Path, parent_dir: &str) -> bool {
         if !p.exists() {
             return false;
@@ -340,6 +347,15 @@ impl OnDiskStateView {
         Ok(fs::write(layouts_file, layouts)?)
     }
 
+    /// Save the Rust struct bindings `bindings` on disk under `build_dir`.
+    pub fn save_struct_bindings(&self, bindings: &str) -> Result<()> {
+        let bindings_file = self.struct_bindings_file();
+        if !bindings_file.exists() {
+            fs::create_dir_all(bindings_file.parent().unwrap())?
+        }
+        Ok(fs::write(bindings_file, bindings)?)
+    }
+
     /// Save all the modules in the local cache, re-generate mv_interfaces if required.
     pub fn save_modules<'a>(
         &self,
@@ -0,0 +1,7 @@
+module A::A {
+    struct Counter has key { value: u64 }
+
+    public(script) fun publish(account: signer, value: u64) {
+        move_to(&account, Counter { value })
+    }
+}
@@ -0,0 +1,36 @@
+module 0x42::M {
+    struct Counter has drop { value: u64 }
+    spec Counter { invariant value < 10; }
+
+    public fun new_counter(): Counter { Counter { value: 0 } }
+    spec new_counter {
+        aborts_if false;
+        ensures result.value == 0;
+    }
+
+    public fun value(c: &Counter): u64 { c.value }
+    spec value { ensures result == c.value; }
+
+    public fun strict_value(c: &Counter): u64 { c.value }
+    spec strict_value {
+        pragma aborts_if_is_strict;
+        ensures result == c.value;
+    }
+
+    public fun unspecified(x: u64): u64 { x + 1 }
+
+    public fun skipped(x: u64): u64 { x }
+    spec skipped {
+        pragma verify = false;
+        ensures result == x;
+    }
+}
+
+module 0x42::N {
+    struct Wrapper has drop { value: u64 }
+    spec Wrapper { invariant value > 0; }
+
+    spec module { pragma verify = false; }
+
+    public fun wrap(value: u64): Wrapper { Wrapper { value } }
+}
@@ -52,3 +52,226 @@ fn cross_process_locking_git_deps() {
         .expect("Package2 failed");
     handle.join().unwrap();
 }
+
+fn read_until_waiting(lines: &std::sync::mpsc::Receiver<String>) -> String {
+    let mut output = String::new();
+    loop {
+        let line = lines
+            .recv_timeout(std::time::Duration::from_secs(300))
+            .expect("watch mode did not wait for changes");
+        if line.contains("Waiting for changes") {
+            return output;
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+}
+
+/// Writes the manifest of a package named `name` into `package`. The package depends on a
+/// package in `deps` which only contains the `UnitTest` module of the standard library, as
+/// cached packages are only reloaded correctly if their sources are named after their modules.
+fn write_test_manifest(package: &std::path::Path, deps: &std::path::Path, name: &str) {
+    use std::fs;
+
+    fs::create_dir_all(deps.join("sources")).unwrap();
+    fs::write(
+        deps.join("Move.toml"),
+        "[package]\nname = \"UnitTest\"\nversion = \"0.0.0\"\n\n[addresses]\nStd = \"_\"\n",
+    )
+    .unwrap();
+    fs::copy(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../move-stdlib/sources/UnitTest.move"),
+        deps.join("sources/UnitTest.move"),
+    )
+    .unwrap();
+    fs::write(
+        package.join("Move.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.0.0\"\n\n[addresses]\nStd = \"0x1\"\n\n\
+             [dependencies]\nUnitTest = {{ local = \"{}\" }}\n",
+            name,
+            deps.display()
+        ),
+    )
+    .unwrap();
+}
+
+/// Kills the child process when dropped, so that it does not outlive a failing test.
+struct KillOnDrop(std::process::Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn watch_reruns_affected_tests() {
+    use std::{
+        fs,
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+    };
+
+    let package = tempfile::tempdir().unwrap();
+    let deps = tempfile::tempdir().unwrap();
+    write_test_manifest(package.path(), deps.path(), "Watch");
+    let sources = package.path().join("sources");
+    fs::create_dir(&sources).unwrap();
+    let module_a = "module 0x2::A { public fun f(): u64 { 1 } \
+                    #[test] fun test_a() { assert!(f() == 1, 0) } }";
+    fs::write(sources.join("A.move"), module_a).unwrap();
+    fs::write(
+        sources.join("B.move"),
+        "module 0x2::B { #[test] fun test_b() {} }",
+    )
+    .unwrap();
+    fs::write(
+        sources.join("C.move"),
+        "module 0x2::C { use 0x2::A; #[test] fun test_c() { assert!(A::f() == 1, 0) } }",
+    )
+    .unwrap();
+
+    let mut child = KillOnDrop(
+        Command::new(env!("CARGO_BIN_EXE_move"))
+            .current_dir(package.path())
+            .args(["package", "test", "--watch"])
+            .env("NO_COLOR", "1")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let stdout = child.0.stdout.take().unwrap();
+    let (sender, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let first_run = read_until_waiting(&lines);
+    // Changing `A` reruns the tests of `A` and of `C`, which depends on it, but not those of `B`.
+    fs::write(sources.join("A.move"), module_a.replace("{ 1 }", "{ 10 }")).unwrap();
+    let second_run = read_until_waiting(&lines);
+    drop(child);
+
+    assert!(first_run.contains("[ PASS    ] 0x2::A::test_a"));
+    assert!(first_run.contains("[ PASS    ] 0x2::B::test_b"));
+    assert!(first_run.contains("[ PASS    ] 0x2::C::test_c"));
+    assert!(second_run.contains("[ FAIL    ] 0x2::A::test_a"));
+    assert!(second_run.contains("[ FAIL    ] 0x2::C::test_c"));
+    assert!(!second_run.contains("test_b"));
+}
+
+#[test]
+fn changed_since_selects_affected_tests() {
+    use std::{fs, process::Command};
+
+    let package = tempfile::tempdir().unwrap();
+    let deps = tempfile::tempdir().unwrap();
+    write_test_manifest(package.path(), deps.path(), "Changes");
+    fs::write(package.path().join(".gitignore"), "build\n").unwrap();
+    let sources = package.path().join("sources");
+    fs::create_dir(&sources).unwrap();
+    let module_a = "module 0x2::A {\n\
+                    public fun one(): u64 { 1 }\n\
+                    public fun two(): u64 { 2 }\n\
+                    #[test] fun test_one() { assert!(one() == 1, 0) }\n\
+                    }\n";
+    fs::write(sources.join("A.move"), module_a).unwrap();
+    fs::write(
+        sources.join("B.move"),
+        "module 0x2::B {\n\
+         use 0x2::A;\n\
+         fun four(): u64 { A::two() + A::two() }\n\
+         #[test] fun test_four() { assert!(four() == 4, 0) }\n\
+         #[test] fun test_one() { assert!(A::one() == 1, 0) }\n\
+         }\n",
+    )
+    .unwrap();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .current_dir(package.path())
+            .args(["-c", "user.name=Move", "-c", "user.email=move@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "Initial commit"]);
+
+    let run_tests = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_move"))
+            .current_dir(package.path())
+            .args(["package", "test", "--changed-since", "HEAD"])
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Only the test calling `A::two`, through `B::four`, is affected by changing it.
+    fs::write(sources.join("A.move"), module_a.replace("{ 2 }", "{ 3 }")).unwrap();
+    let output = run_tests();
+    assert!(output.contains("Running 1 of 3 tests affected by the changes"));
+    assert!(output
+        .contains("0x2::B::test_four: 0x2::B::test_four -> 0x2::B::four -> 0x2::A::two changed"));
+    assert!(output.contains("[ FAIL    ] 0x2::B::test_four"));
+    assert!(!output.contains("test_one"));
+
+    // All tests are run when the manifest changes.
+    fs::write(sources.join("A.move"), module_a).unwrap();
+    let manifest = fs::read_to_string(package.path().join("Move.toml")).unwrap();
+    fs::write(package.path().join("Move.toml"), manifest + "\n").unwrap();
+    let output = run_tests();
+    assert!(output.contains("Running all tests"));
+    assert!(output.contains("Total tests: 3; passed: 3"));
+}
+
+#[test]
+fn missing_state_dir_is_reported_as_error() {
+    use std::{fs, process::Command};
+
+    let package = tempfile::tempdir().unwrap();
+    let stdlib = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../move-stdlib")
+        .canonicalize()
+        .unwrap();
+    fs::write(
+        package.path().join("Move.toml"),
+        format!(
+            "[package]\nname = \"State\"\nversion = \"0.0.0\"\n\n[addresses]\nStd = \"0x1\"\n\n\
+             [dependencies]\nMoveStdlib = {{ local = \"{}\" }}\n",
+            stdlib.display()
+        ),
+    )
+    .unwrap();
+    let sources = package.path().join("sources");
+    fs::create_dir(&sources).unwrap();
+    fs::write(
+        sources.join("A.move"),
+        "module 0x2::A { #[test] fun test_a() {} }",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_move"))
+        .current_dir(package.path())
+        .args(["package", "test", "--state", "missing"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("reading state directory `missing`"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
@@ -0,0 +1,34 @@
+module 0x2::M {
+    struct Counter has drop { value: u64 }
+    spec Counter { invariant value < 10; }
+
+    fun inc(x: u64): u64 { x + 1 }
+    spec inc {
+        aborts_if x + 1 > MAX_U64;
+        ensures result == x + 1;
+    }
+
+    fun double(x: u64): u64 { x + x }
+    spec double { ensures result == 2 * x + 1; }
+
+    fun safe_div(x: u64, y: u64): u64 {
+        if (y == 0) abort 1;
+        x / y
+    }
+    spec safe_div { aborts_if y == 1; }
+
+    fun new_counter(value: u64): Counter { Counter { value } }
+
+    #[test]
+    fun inc_ok() { assert!(inc(1) == 2, 0) }
+
+    #[test]
+    fun double_violates_ensures() { assert!(double(2) == 4, 0) }
+
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun safe_div_violates_aborts_if() { safe_div(1, 0); }
+
+    #[test]
+    fun counter_violates_invariant() { new_counter(42); }
+}
@@ -0,0 +1,7 @@
+module 0x2::M {
+    fun inc(x: u64): u64 { x + 1 }
+    spec inc { ensures result == x + 1; }
+
+    #[test]
+    fun inc_ok() { assert!(inc(1) == 2, 0) }
+}
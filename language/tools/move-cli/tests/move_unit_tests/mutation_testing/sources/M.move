@@ -0,0 +1,39 @@
+module 0x2::M {
+    struct Counter has drop { value: u64 }
+
+    public fun max(x: u64, y: u64): u64 {
+        if (x >= y) x else y
+    }
+
+    public fun checked_div(x: u64, y: u64): u64 {
+        assert!(y != 0, 1);
+        x / y
+    }
+
+    public fun reset(c: &mut Counter) {
+        c.value = 0;
+    }
+
+    #[test]
+    fun test_max() {
+        assert!(max(1, 2) == 2, 0);
+        assert!(max(3, 2) == 3, 0);
+    }
+
+    #[test]
+    fun test_div() {
+        assert!(checked_div(6, 3) == 2, 0);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun test_div_by_zero() {
+        checked_div(1, 0);
+    }
+
+    #[test]
+    fun test_reset() {
+        let c = Counter { value: 5 };
+        reset(&mut c);
+    }
+}
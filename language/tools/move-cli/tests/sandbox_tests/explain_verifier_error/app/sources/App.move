@@ -0,0 +1,7 @@
+module 0x42::App {
+    use 0x42::Dep;
+
+    public fun twice(): u64 {
+        Dep::value() * 2
+    }
+}
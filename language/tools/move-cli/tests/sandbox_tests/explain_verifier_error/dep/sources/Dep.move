@@ -0,0 +1,3 @@
+module 0x42::Dep {
+    public fun value(): u64 { 1 }
+}
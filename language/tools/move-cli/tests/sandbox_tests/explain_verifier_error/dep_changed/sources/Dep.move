@@ -0,0 +1,3 @@
+module 0x42::Dep {
+    public fun other_value(): u64 { 2 }
+}
@@ -0,0 +1,10 @@
+module Std::Branches {
+    public fun clamp(x: u64): u64 {
+        if (x > 10) 10 else x
+    }
+
+    #[test]
+    fun clamp_small() {
+        assert!(clamp(3) == 3, 0)
+    }
+}
@@ -0,0 +1,44 @@
+module 0x2::Counter {
+    use Std::Event::{Self, EventHandle};
+    use Std::Signer;
+
+    struct Counter has key {
+        value: u64,
+        events: EventHandle<Incremented>,
+    }
+
+    struct Incremented has drop, store {
+        value: u64,
+    }
+
+    public fun publish(account: &signer) {
+        move_to(account, Counter { value: 0, events: Event::new_event_handle(account) })
+    }
+
+    public fun increment(addr: address) acquires Counter {
+        let counter = borrow_global_mut<Counter>(addr);
+        counter.value = counter.value + 1;
+        Event::emit_event(&mut counter.events, Incremented { value: counter.value });
+    }
+
+    public fun value(addr: address): u64 acquires Counter {
+        borrow_global<Counter>(addr).value
+    }
+
+    #[test(account = @0x2)]
+    fun publishes(account: signer) {
+        publish(&account);
+    }
+
+    #[test(account = @0x2)]
+    fun increments(account: signer) acquires Counter {
+        publish(&account);
+        increment(Signer::address_of(&account));
+        increment(Signer::address_of(&account));
+    }
+
+    #[test]
+    fun no_effects() {
+        assert!(1 + 1 == 2, 0);
+    }
+}
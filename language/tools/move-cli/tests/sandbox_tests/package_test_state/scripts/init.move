@@ -0,0 +1,7 @@
+script {
+    use 0x2::Fixture;
+
+    fun main(account: signer) {
+        Fixture::init(&account, 42)
+    }
+}
@@ -0,0 +1,17 @@
+module 0x2::Fixture {
+    struct Config has key { value: u64 }
+
+    public fun init(account: &signer, value: u64) {
+        move_to(account, Config { value })
+    }
+
+    #[test]
+    fun modifies_state() acquires Config {
+        borrow_global_mut<Config>(@0x2).value = 0;
+    }
+
+    #[test]
+    fun reads_initial_state() acquires Config {
+        assert!(borrow_global<Config>(@0x2).value == 42, 0);
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Computes which outcomes of the conditional branches of a function were observed from the
+//! execution counts of its instructions.
+
+use crate::coverage_map::FunctionCoverage;
+use move_binary_format::{
+    control_flow_graph::{BlockId, ControlFlowGraph, VMControlFlowGraph},
+    file_format::{Bytecode, CodeOffset},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Coverage of a conditional branch instruction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchCoverage {
+    /// How often the branch instruction was executed.
+    pub executed: u64,
+    /// How often the branch jumped to its target.
+    pub taken: u64,
+    /// How often the branch fell through to the next instruction.
+    pub not_taken: u64,
+}
+
+impl BranchCoverage {
+    /// Returns how many of the two outcomes of the branch were observed.
+    pub fn outcomes_hit(&self) -> usize {
+        (self.taken > 0) as usize + (self.not_taken > 0) as usize
+    }
+
+    /// Returns true if the branch was executed but only ever went one way.
+    pub fn is_partially_covered(&self) -> bool {
+        self.executed > 0 && self.outcomes_hit() < 2
+    }
+}
+
+/// Computes the coverage of the conditional branches in `code`, by code offset, from the
+/// execution counts of its instructions.
+///
+/// How often an outcome of a branch was observed is the execution count of the successor block if
+/// the branch is the only way to enter that block. If both successors can also be entered from
+/// elsewhere (e.g., the target is a loop header), their execution counts are used as estimates.
+pub fn function_branch_coverage(
+    code: &[Bytecode],
+    function_coverage: &FunctionCoverage,
+) -> BTreeMap<CodeOffset, BranchCoverage> {
+    let count_at = |offset: CodeOffset| {
+        function_coverage
+            .get(&(offset as u64))
+            .copied()
+            .unwrap_or(0)
+    };
+    let cfg = VMControlFlowGraph::new(code);
+    let mut predecessors: BTreeMap<BlockId, BTreeSet<BlockId>> = BTreeMap::new();
+    for block in cfg.blocks() {
+        for succ in cfg.successors(block) {
+            predecessors.entry(*succ).or_default().insert(block);
+        }
+    }
+    let has_single_predecessor =
+        |block: BlockId| matches!(predecessors.get(&block), Some(preds) if preds.len() == 1);
+
+    let mut branches = BTreeMap::new();
+    for block in cfg.blocks() {
+        let offset = cfg.block_end(block);
+        let target = match &code[offset as usize] {
+            Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => *target,
+            _ => continue,
+        };
+        let fall_through = offset + 1;
+        let executed = count_at(offset);
+        let (taken, not_taken) = if target == fall_through {
+            (executed, 0)
+        } else if has_single_predecessor(fall_through) {
+            let not_taken = count_at(fall_through).min(executed);
+            (executed - not_taken, not_taken)
+        } else if has_single_predecessor(target) {
+            let taken = count_at(target).min(executed);
+            (taken, executed - taken)
+        } else {
+            (
+                count_at(target).min(executed),
+                count_at(fall_through).min(executed),
+            )
+        };
+        branches.insert(
+            offset,
+            BranchCoverage {
+                executed,
+                taken,
+                not_taken,
+            },
+        );
+    }
+    branches
+}
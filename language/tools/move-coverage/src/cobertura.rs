@@ -0,0 +1,112 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::{branch_coverage::BranchCoverage, line_coverage::ModuleLineCoverage};
+use std::io::{self, Write};
+
+/// Writes the coverage of `modules` as a Cobertura XML report with a single package
+/// `package_name` and one class per module. Source paths are written relative to `source_root`
+/// if they are inside of it.
+pub fn write_cobertura<W: Write>(
+    modules: &[ModuleLineCoverage],
+    package_name: &str,
+    source_root: &str,
+    timestamp: u64,
+    writer: &mut W,
+) -> io::Result<()> {
+    let lines_valid: usize = modules.iter().map(|module| module.lines.len()).sum();
+    let lines_covered: usize = modules.iter().map(|module| module.lines_hit()).sum();
+    let branches_valid: usize = modules.iter().map(|module| module.branches_found()).sum();
+    let branches_covered: usize = modules.iter().map(|module| module.branches_hit()).sum();
+    let line_rate = rate(lines_covered, lines_valid);
+    let branch_rate = rate(branches_covered, branches_valid);
+
+    writeln!(writer, r#"<?xml version="1.0" ?>"#)?;
+    writeln!(
+        writer,
+        r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+    )?;
+    writeln!(
+        writer,
+        r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="0.1" timestamp="{}">"#,
+        line_rate,
+        branch_rate,
+        lines_covered,
+        lines_valid,
+        branches_covered,
+        branches_valid,
+        timestamp
+    )?;
+    writeln!(writer, "  <sources>")?;
+    writeln!(writer, "    <source>{}</source>", escape(source_root))?;
+    writeln!(writer, "  </sources>")?;
+    writeln!(writer, "  <packages>")?;
+    writeln!(
+        writer,
+        r#"    <package name="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+        escape(package_name),
+        line_rate,
+        branch_rate
+    )?;
+    writeln!(writer, "      <classes>")?;
+    for module in modules {
+        let file_name = module
+            .source_path
+            .strip_prefix(source_root)
+            .unwrap_or(&module.source_path);
+        writeln!(
+            writer,
+            r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+            escape(&module.module_name),
+            escape(&file_name.to_string_lossy()),
+            rate(module.lines_hit(), module.lines.len()),
+            rate(module.branches_hit(), module.branches_found())
+        )?;
+        writeln!(writer, "          <methods/>")?;
+        writeln!(writer, "          <lines>")?;
+        for (line_number, line) in &module.lines {
+            if line.branches.is_empty() {
+                writeln!(
+                    writer,
+                    r#"            <line number="{}" hits="{}" branch="false"/>"#,
+                    line_number, line.hits
+                )?;
+            } else {
+                let found = 2 * line.branches.len();
+                let hit: usize = line.branches.iter().map(BranchCoverage::outcomes_hit).sum();
+                writeln!(
+                    writer,
+                    r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                    line_number,
+                    line.hits,
+                    hit * 100 / found,
+                    hit,
+                    found
+                )?;
+            }
+        }
+        writeln!(writer, "          </lines>")?;
+        writeln!(writer, "        </class>")?;
+    }
+    writeln!(writer, "      </classes>")?;
+    writeln!(writer, "    </package>")?;
+    writeln!(writer, "  </packages>")?;
+    writeln!(writer, "</coverage>")
+}
+
+fn rate(covered: usize, total: usize) -> String {
+    if total == 0 {
+        "1".to_string()
+    } else {
+        format!("{:.4}", covered as f64 / total as f64)
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -123,6 +123,30 @@ impl CoverageMap {
         exec_entry.insert(module_addr, module_name, func_name, pc);
     }
 
+    /// Merges the coverage recorded in `other`, e.g. by another test run, into this map. The
+    /// execution counts of instructions covered by both maps are added up.
+    pub fn merge(&mut self, other: CoverageMap) {
+        for (exec_id, exec_map) in other.exec_maps {
+            let exec_entry = self
+                .exec_maps
+                .entry(exec_id.clone())
+                .or_insert_with(|| ExecCoverageMap::new(exec_id));
+            for ((module_addr, module_name), module_map) in exec_map.module_maps {
+                for (func_name, func_map) in module_map.function_maps {
+                    for (pc, count) in func_map {
+                        exec_entry.insert_multi(
+                            module_addr,
+                            module_name.clone(),
+                            func_name.clone(),
+                            pc,
+                            count,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     pub fn to_unified_exec_map(&self) -> ExecCoverageMap {
         let mut unified_map = ExecCoverageMap::new(String::new());
         for (_, exec_map) in self.exec_maps.iter() {
@@ -0,0 +1,101 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::{
+    branch_coverage::BranchCoverage,
+    line_coverage::{LineCoverage, ModuleLineCoverage},
+};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Writes the coverage of `modules` as an LCOV tracefile. Modules defined in the same source
+/// file are combined into a single record.
+pub fn write_lcov<W: Write>(modules: &[ModuleLineCoverage], writer: &mut W) -> io::Result<()> {
+    let mut by_file: BTreeMap<&PathBuf, Vec<&ModuleLineCoverage>> = BTreeMap::new();
+    for module in modules {
+        by_file.entry(&module.source_path).or_default().push(module);
+    }
+
+    for (source_path, modules) in by_file {
+        writeln!(writer, "TN:")?;
+        writeln!(writer, "SF:{}", source_path.display())?;
+
+        let functions: Vec<_> = modules
+            .iter()
+            .flat_map(|module| module.functions.iter())
+            .collect();
+        for function in &functions {
+            writeln!(writer, "FN:{},{}", function.line, function.name)?;
+        }
+        for function in &functions {
+            writeln!(writer, "FNDA:{},{}", function.hits, function.name)?;
+        }
+        writeln!(writer, "FNF:{}", functions.len())?;
+        writeln!(
+            writer,
+            "FNH:{}",
+            functions
+                .iter()
+                .filter(|function| function.hits > 0)
+                .count()
+        )?;
+
+        let mut lines: BTreeMap<u32, LineCoverage> = BTreeMap::new();
+        for module in &modules {
+            for (line_number, line) in &module.lines {
+                let entry = lines.entry(*line_number).or_default();
+                entry.hits = entry.hits.max(line.hits);
+                entry.branches.extend(line.branches.iter().cloned());
+            }
+        }
+
+        // Each branch instruction is a block with two branches: jumping to the target and
+        // falling through.
+        let mut block = 0;
+        for (line_number, line) in &lines {
+            for branch in &line.branches {
+                for (branch_number, count) in [branch.taken, branch.not_taken].iter().enumerate() {
+                    // A `-` marks a branch whose condition was never evaluated.
+                    let taken = if branch.executed == 0 {
+                        "-".to_string()
+                    } else {
+                        count.to_string()
+                    };
+                    writeln!(
+                        writer,
+                        "BRDA:{},{},{},{}",
+                        line_number, block, branch_number, taken
+                    )?;
+                }
+                block += 1;
+            }
+        }
+        writeln!(writer, "BRF:{}", 2 * block)?;
+        writeln!(
+            writer,
+            "BRH:{}",
+            lines
+                .values()
+                .flat_map(|line| line.branches.iter())
+                .map(BranchCoverage::outcomes_hit)
+                .sum::<usize>()
+        )?;
+
+        for (line_number, line) in &lines {
+            writeln!(writer, "DA:{},{}", line_number, line.hits)?;
+        }
+        writeln!(writer, "LF:{}", lines.len())?;
+        writeln!(
+            writer,
+            "LH:{}",
+            lines.values().filter(|line| line.hits > 0).count()
+        )?;
+        writeln!(writer, "end_of_record")?;
+    }
+    Ok(())
+}
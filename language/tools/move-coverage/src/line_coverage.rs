@@ -0,0 +1,145 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Maps the instruction coverage of a module to the lines and branches of its source file, which
+//! is the form of coverage consumed by standard coverage formats such as LCOV and Cobertura.
+
+use crate::{
+    branch_coverage::{function_branch_coverage, BranchCoverage},
+    coverage_map::{ExecCoverageMap, FunctionCoverage},
+};
+use anyhow::{bail, Result};
+use codespan::{ByteIndex, Files};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+    CompiledModule,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleLineCoverage {
+    /// The module name in the form `address::name`.
+    pub module_name: String,
+    /// The path of the source file of the module.
+    pub source_path: PathBuf,
+    pub functions: Vec<FunctionLineCoverage>,
+    /// The coverage of each line with code, by 1-based line number.
+    pub lines: BTreeMap<u32, LineCoverage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionLineCoverage {
+    pub name: String,
+    /// The 1-based line of the function definition.
+    pub line: u32,
+    /// How often the function was called.
+    pub hits: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LineCoverage {
+    /// How often the most executed instruction on the line was executed.
+    pub hits: u64,
+    pub branches: Vec<BranchCoverage>,
+}
+
+impl ModuleLineCoverage {
+    pub fn new(
+        module: &CompiledModule,
+        coverage_map: &ExecCoverageMap,
+        source_map: &SourceMap,
+        source_path: &Path,
+    ) -> Result<Self> {
+        let file_contents = fs::read_to_string(source_path)?;
+        if !source_map.check(&file_contents) {
+            bail!(
+                "File contents of `{}` out of sync with source map",
+                source_path.display()
+            );
+        }
+        let mut files = Files::new();
+        let file_id = files.add(source_path.as_os_str().to_os_string(), file_contents);
+        let line_of = |offset: u32| -> Result<u32> {
+            Ok(files.location(file_id, ByteIndex(offset))?.line.0 + 1)
+        };
+
+        let module_id = module.self_id();
+        let module_map = coverage_map
+            .module_maps
+            .get(&(*module_id.address(), module_id.name().to_owned()));
+        let empty_coverage = FunctionCoverage::new();
+
+        let mut functions = vec![];
+        let mut lines: BTreeMap<u32, LineCoverage> = BTreeMap::new();
+        for (function_def_idx, function_def) in module.function_defs().iter().enumerate() {
+            let code_unit = match &function_def.code {
+                Some(code_unit) => code_unit,
+                None => continue,
+            };
+            let fn_handle = module.function_handle_at(function_def.function);
+            let fn_name = module.identifier_at(fn_handle.name);
+            let function_def_idx = FunctionDefinitionIndex(function_def_idx as u16);
+            let function_map = source_map.get_function_source_map(function_def_idx)?;
+            let counts = module_map
+                .and_then(|module_map| module_map.get_function_coverage(fn_name))
+                .unwrap_or(&empty_coverage);
+            let count_at = |offset: CodeOffset| counts.get(&(offset as u64)).copied().unwrap_or(0);
+
+            functions.push(FunctionLineCoverage {
+                name: fn_name.to_string(),
+                line: line_of(function_map.definition_location.start())?,
+                hits: count_at(0),
+            });
+
+            let branches = function_branch_coverage(&code_unit.code, counts);
+            for offset in 0..code_unit.code.len() as CodeOffset {
+                let loc = source_map.get_code_location(function_def_idx, offset)?;
+                let line = lines.entry(line_of(loc.start())?).or_default();
+                line.hits = line.hits.max(count_at(offset));
+                if let Some(branch) = branches.get(&offset) {
+                    line.branches.push(branch.clone());
+                }
+            }
+        }
+
+        Ok(Self {
+            module_name: format!(
+                "0x{}::{}",
+                module_id.address().short_str_lossless(),
+                module_id.name()
+            ),
+            source_path: source_path.to_path_buf(),
+            functions,
+            lines,
+        })
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|line| line.hits > 0).count()
+    }
+
+    pub fn branches_found(&self) -> usize {
+        2 * self
+            .lines
+            .values()
+            .map(|line| line.branches.len())
+            .sum::<usize>()
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.lines
+            .values()
+            .flat_map(|line| line.branches.iter())
+            .map(BranchCoverage::outcomes_hit)
+            .sum()
+    }
+}
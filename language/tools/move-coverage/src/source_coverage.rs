@@ -3,7 +3,7 @@
 
 #![forbid(unsafe_code)]
 
-use crate::coverage_map::CoverageMap;
+use crate::{branch_coverage::function_branch_coverage, coverage_map::CoverageMap};
 use codespan::{Files, Span};
 use colored::*;
 use move_binary_format::{
@@ -16,7 +16,7 @@ use move_core_types::identifier::Identifier;
 use move_ir_types::location::Loc;
 use serde::Serialize;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fs,
     io::{self, Write},
     path::Path,
@@ -26,6 +26,8 @@ use std::{
 pub struct FunctionSourceCoverage {
     pub fn_is_native: bool,
     pub uncovered_locations: Vec<Loc>,
+    /// The locations of conditional branches which were executed but only ever went one way.
+    pub partially_covered_branches: Vec<Loc>,
 }
 
 #[derive(Debug, Serialize)]
@@ -44,6 +46,8 @@ pub enum AbstractSegment {
 #[derive(Debug, Serialize)]
 pub enum StringSegment {
     Covered(String),
+    /// Covered code on a line with a branch that was executed but only ever went one way.
+    PartiallyCovered(String),
     Uncovered(String),
 }
 
@@ -80,6 +84,7 @@ impl<'a> SourceCoverageBuilder<'a> {
                     None => Some(FunctionSourceCoverage {
                         fn_is_native: true,
                         uncovered_locations: Vec::new(),
+                        partially_covered_branches: Vec::new(),
                     }),
                     Some(code_unit) => {
                         module_map.map(|fn_map| match fn_map.function_maps.get(&fn_name) {
@@ -94,6 +99,7 @@ impl<'a> SourceCoverageBuilder<'a> {
                                 FunctionSourceCoverage {
                                     fn_is_native: false,
                                     uncovered_locations,
+                                    partially_covered_branches: Vec::new(),
                                 }
                             }
                             Some(function_coverage) => {
@@ -113,9 +119,20 @@ impl<'a> SourceCoverageBuilder<'a> {
                                         }
                                     })
                                     .collect();
+                                let partially_covered_branches =
+                                    function_branch_coverage(&code_unit.code, function_coverage)
+                                        .into_iter()
+                                        .filter(|(_, branch)| branch.is_partially_covered())
+                                        .map(|(code_offset, _)| {
+                                            source_map
+                                                .get_code_location(function_def_idx, code_offset)
+                                                .unwrap()
+                                        })
+                                        .collect();
                                 FunctionSourceCoverage {
                                     fn_is_native: false,
                                     uncovered_locations,
+                                    partially_covered_branches,
                                 }
                             }
                         })
@@ -141,8 +158,13 @@ impl<'a> SourceCoverageBuilder<'a> {
         let file_id = files.add(file_path.as_os_str().to_os_string(), file_contents.clone());
 
         let mut uncovered_segments = BTreeMap::new();
+        let mut partially_covered_lines = BTreeSet::new();
 
         for (_, fn_cov) in self.uncovered_locations.iter() {
+            for loc in fn_cov.partially_covered_branches.iter() {
+                let start_loc = files.location(file_id, loc.start()).unwrap();
+                partially_covered_lines.insert(start_loc.line.0);
+            }
             for span in merge_spans(fn_cov.clone()).into_iter() {
                 let start_loc = files.location(file_id, span.start()).unwrap();
                 let end_loc = files.location(file_id, span.end()).unwrap();
@@ -179,8 +201,13 @@ impl<'a> SourceCoverageBuilder<'a> {
 
         let mut annotated_lines = Vec::new();
         for (line_number, mut line) in file_contents.lines().map(|x| x.to_owned()).enumerate() {
+            let covered = if partially_covered_lines.contains(&(line_number as u32)) {
+                StringSegment::PartiallyCovered
+            } else {
+                StringSegment::Covered
+            };
             match uncovered_segments.get(&(line_number as u32)) {
-                None => annotated_lines.push(vec![StringSegment::Covered(line)]),
+                None => annotated_lines.push(vec![covered(line)]),
                 Some(segments) => {
                     // Note: segments are already pre-sorted by construction so don't need to be
                     // resorted.
@@ -192,7 +219,7 @@ impl<'a> SourceCoverageBuilder<'a> {
                                 let length = end - start;
                                 let (before, after) = line.split_at((start - cursor) as usize);
                                 let (uncovered, rest) = after.split_at(length as usize);
-                                line_acc.push(StringSegment::Covered(before.to_string()));
+                                line_acc.push(covered(before.to_string()));
                                 line_acc.push(StringSegment::Uncovered(uncovered.to_string()));
                                 line = rest.to_string();
                                 cursor = *end;
@@ -205,7 +232,7 @@ impl<'a> SourceCoverageBuilder<'a> {
                             }
                             AbstractSegment::BoundedLeft { start } => {
                                 let (before, after) = line.split_at((start - cursor) as usize);
-                                line_acc.push(StringSegment::Covered(before.to_string()));
+                                line_acc.push(covered(before.to_string()));
                                 line_acc.push(StringSegment::Uncovered(after.to_string()));
                                 line = "".to_string();
                                 cursor = 0;
@@ -213,7 +240,7 @@ impl<'a> SourceCoverageBuilder<'a> {
                         }
                     }
                     if !line.is_empty() {
-                        line_acc.push(StringSegment::Covered(line))
+                        line_acc.push(covered(line))
                     }
                     annotated_lines.push(line_acc)
                 }
@@ -230,6 +257,7 @@ impl SourceCoverage {
             for string_segment in line.iter() {
                 match string_segment {
                     StringSegment::Covered(s) => write!(output_writer, "{}", s.green())?,
+                    StringSegment::PartiallyCovered(s) => write!(output_writer, "{}", s.yellow())?,
                     StringSegment::Uncovered(s) => write!(output_writer, "{}", s.bold().red())?,
                 }
             }
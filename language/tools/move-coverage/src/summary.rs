@@ -3,8 +3,11 @@
 
 #![forbid(unsafe_code)]
 
-use crate::coverage_map::{
-    ExecCoverageMap, ExecCoverageMapWithModules, ModuleCoverageMap, TraceMap,
+use crate::{
+    branch_coverage::function_branch_coverage,
+    coverage_map::{
+        ExecCoverageMap, ExecCoverageMapWithModules, FunctionCoverage, ModuleCoverageMap, TraceMap,
+    },
 };
 use move_binary_format::{
     access::ModuleAccess,
@@ -31,6 +34,12 @@ pub struct FunctionSummary {
     pub fn_is_native: bool,
     pub total: u64,
     pub covered: u64,
+    /// The number of branch outcomes, two for each conditional branch instruction.
+    #[serde(default)]
+    pub total_branches: u64,
+    /// The number of branch outcomes that were observed.
+    #[serde(default)]
+    pub covered_branches: u64,
 }
 
 pub struct FunctionInfo {
@@ -104,6 +113,23 @@ impl ModuleSummary {
                     "\t\t% coverage: {:.2}",
                     fn_summary.percent_coverage()
                 )?;
+                if fn_summary.total_branches > 0 {
+                    writeln!(
+                        summary_writer,
+                        "\t\tbranches: {}",
+                        fn_summary.total_branches
+                    )?;
+                    writeln!(
+                        summary_writer,
+                        "\t\tcovered branches: {}",
+                        fn_summary.covered_branches
+                    )?;
+                    writeln!(
+                        summary_writer,
+                        "\t\t% branch coverage: {:.2}",
+                        fn_summary.percent_branch_coverage()
+                    )?;
+                }
             }
         }
 
@@ -121,6 +147,10 @@ impl FunctionSummary {
     pub fn percent_coverage(&self) -> f64 {
         (self.covered as f64) / (self.total as f64) * 100f64
     }
+
+    pub fn percent_branch_coverage(&self) -> f64 {
+        (self.covered_branches as f64) / (self.total_branches as f64) * 100f64
+    }
 }
 
 pub fn summarize_inst_cov_by_module(
@@ -128,6 +158,7 @@ pub fn summarize_inst_cov_by_module(
     module_map: Option<&ModuleCoverageMap>,
 ) -> ModuleSummary {
     let module_name = module.self_id();
+    let empty_coverage = FunctionCoverage::new();
     let function_summaries: BTreeMap<_, _> = module
         .function_defs()
         .iter()
@@ -140,21 +171,25 @@ pub fn summarize_inst_cov_by_module(
                     fn_is_native: true,
                     total: 0,
                     covered: 0,
+                    total_branches: 0,
+                    covered_branches: 0,
                 },
                 Some(code_unit) => {
                     let total_number_of_instructions = code_unit.code.len() as u64;
-                    let covered_instructions = module_map
-                        .and_then(|fn_map| {
-                            fn_map
-                                .function_maps
-                                .get(&fn_name)
-                                .map(|function_map| function_map.len())
-                        })
-                        .unwrap_or(0) as u64;
+                    let function_map = module_map
+                        .and_then(|fn_map| fn_map.function_maps.get(&fn_name))
+                        .unwrap_or(&empty_coverage);
+                    let covered_instructions = function_map.len() as u64;
+                    let branches = function_branch_coverage(&code_unit.code, function_map);
                     FunctionSummary {
                         fn_is_native: false,
                         total: total_number_of_instructions,
                         covered: covered_instructions,
+                        total_branches: 2 * branches.len() as u64,
+                        covered_branches: branches
+                            .values()
+                            .map(|branch| branch.outcomes_hit() as u64)
+                            .sum(),
                     }
                 }
             };
@@ -423,6 +458,8 @@ pub fn summarize_path_cov(module: &CompiledModule, trace_map: &TraceMap) -> Modu
                     fn_is_native: true,
                     total: 0,
                     covered: 0,
+                    total_branches: 0,
+                    covered_branches: 0,
                 },
                 Some(_) => FunctionSummary {
                     fn_is_native: false,
@@ -431,6 +468,8 @@ pub fn summarize_path_cov(module: &CompiledModule, trace_map: &TraceMap) -> Modu
                         None => 0,
                         Some(pathset) => pathset.len() as u64,
                     },
+                    total_branches: 0,
+                    covered_branches: 0,
                 },
             };
 
@@ -0,0 +1,104 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_binary_format::{access::ModuleAccess, CompiledModule};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_core_types::identifier::Identifier;
+use move_coverage::{
+    coverage_map::CoverageMap,
+    source_coverage::{SourceCoverageBuilder, StringSegment},
+    summary::summarize_inst_cov_by_module,
+};
+use move_ir_to_bytecode::{compiler::compile_module, parser::parse_module};
+use std::{collections::BTreeMap, fs};
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"module 0x1.Branches {
+    public clamp(x: u64): u64 {
+    label b0:
+        jump_if (copy(x) > 10) b2;
+    label b1:
+        return move(x);
+    label b2:
+        return 10;
+    }
+}
+"#;
+
+/// The offsets of the instructions of `clamp` executed for an argument of at most 10, which
+/// falls through the branch.
+const EXECUTED: &[u64] = &[0, 1, 2, 3, 4, 5];
+
+fn compile() -> (CompiledModule, SourceMap) {
+    compile_module(parse_module(SOURCE).unwrap(), std::iter::empty()).unwrap()
+}
+
+fn coverage_map(module: &CompiledModule) -> CoverageMap {
+    let mut coverage_map = CoverageMap {
+        exec_maps: BTreeMap::new(),
+    };
+    for pc in EXECUTED {
+        coverage_map.insert(
+            "test",
+            *module.address(),
+            module.name().to_owned(),
+            Identifier::new("clamp").unwrap(),
+            *pc,
+        );
+    }
+    coverage_map
+}
+
+#[test]
+fn branch_taken_one_way_is_partially_covered() {
+    let (module, _) = compile();
+    let coverage_map = coverage_map(&module);
+    let unified_exec_map = coverage_map.to_unified_exec_map();
+    let module_map = unified_exec_map
+        .module_maps
+        .get(&(*module.address(), module.name().to_owned()));
+    let summary = summarize_inst_cov_by_module(&module, module_map);
+    let clamp = &summary.function_summaries[&Identifier::new("clamp").unwrap()];
+    assert_eq!(clamp.total, 8);
+    assert_eq!(clamp.covered, EXECUTED.len() as u64);
+    assert_eq!(clamp.total_branches, 2);
+    assert_eq!(clamp.covered_branches, 1);
+    assert_eq!(clamp.percent_branch_coverage(), 50.0);
+}
+
+#[test]
+fn partially_covered_branch_is_rendered_yellow() {
+    let (module, source_map) = compile();
+    let coverage_map = coverage_map(&module);
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("Branches.mvir");
+    fs::write(&path, SOURCE).unwrap();
+    let source_coverage = SourceCoverageBuilder::new(&module, &coverage_map, &source_map)
+        .compute_source_coverage(&path);
+
+    let line_of = |text: &str| {
+        let index = SOURCE.lines().position(|line| line.contains(text)).unwrap();
+        &source_coverage.annotated_lines[index]
+    };
+    assert!(line_of("jump_if")
+        .iter()
+        .all(|segment| matches!(segment, StringSegment::PartiallyCovered(_))));
+    assert!(line_of("return move(x)")
+        .iter()
+        .all(|segment| matches!(segment, StringSegment::Covered(_))));
+    assert!(line_of("return 10")
+        .iter()
+        .any(|segment| matches!(segment, StringSegment::Uncovered(_))));
+
+    colored::control::set_override(true);
+    let mut output = vec![];
+    source_coverage.output_source_coverage(&mut output).unwrap();
+    colored::control::unset_override();
+    let output = String::from_utf8(output).unwrap();
+    let jump_line = output
+        .lines()
+        .find(|line| line.contains("jump_if"))
+        .unwrap();
+    // Yellow foreground.
+    assert!(jump_line.starts_with("\u{1b}[33m"), "{:?}", jump_line);
+}
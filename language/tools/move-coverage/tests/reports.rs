@@ -0,0 +1,142 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_binary_format::{access::ModuleAccess, CompiledModule};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_core_types::identifier::Identifier;
+use move_coverage::{
+    cobertura::write_cobertura, coverage_map::CoverageMap, line_coverage::ModuleLineCoverage,
+};
+use move_ir_to_bytecode::{compiler::compile_module, parser::parse_module};
+use std::{collections::BTreeMap, fs};
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"module 0x1.Branches {
+    public clamp(x: u64): u64 {
+    label b0:
+        jump_if (copy(x) > 10) b2;
+    label b1:
+        return move(x);
+    label b2:
+        return 10;
+    }
+    public unused() {
+    label b0:
+        return;
+    }
+}
+"#;
+
+/// The offsets of the instructions of `clamp` executed for an argument of at most 10.
+const SMALL: &[u64] = &[0, 1, 2, 3, 4, 5];
+/// The offsets of the instructions of `clamp` executed for an argument greater than 10.
+const LARGE: &[u64] = &[0, 1, 2, 3, 6, 7];
+
+/// The Cobertura report of a run which only called `clamp` with a small argument.
+const EXPECTED_COBERTURA: &str = r#"<?xml version="1.0" ?>
+<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+<coverage line-rate="0.5000" branch-rate="0.5000" lines-covered="2" lines-valid="4" branches-covered="1" branches-valid="2" complexity="0" version="0.1" timestamp="42">
+  <sources>
+    <source>ROOT</source>
+  </sources>
+  <packages>
+    <package name="Test &amp; Co" line-rate="0.5000" branch-rate="0.5000" complexity="0">
+      <classes>
+        <class name="0x1::Branches" filename="Branches.mvir" line-rate="0.5000" branch-rate="0.5000" complexity="0">
+          <methods/>
+          <lines>
+            <line number="4" hits="1" branch="true" condition-coverage="50% (1/2)"/>
+            <line number="6" hits="1" branch="false"/>
+            <line number="8" hits="0" branch="false"/>
+            <line number="12" hits="0" branch="false"/>
+          </lines>
+        </class>
+      </classes>
+    </package>
+  </packages>
+</coverage>
+"#;
+
+fn compile() -> (CompiledModule, SourceMap) {
+    compile_module(parse_module(SOURCE).unwrap(), std::iter::empty()).unwrap()
+}
+
+fn coverage_map(module: &CompiledModule, exec_id: &str, pcs: &[u64]) -> CoverageMap {
+    let mut coverage_map = CoverageMap {
+        exec_maps: BTreeMap::new(),
+    };
+    for pc in pcs {
+        coverage_map.insert(
+            exec_id,
+            *module.address(),
+            module.name().to_owned(),
+            Identifier::new("clamp").unwrap(),
+            *pc,
+        );
+    }
+    coverage_map
+}
+
+#[test]
+fn merge_adds_up_counts() {
+    let (module, _) = compile();
+    let mut merged = coverage_map(&module, "test", SMALL);
+    merged.merge(coverage_map(&module, "test", LARGE));
+    merged.merge(coverage_map(&module, "other", SMALL));
+    assert_eq!(
+        merged.exec_maps.keys().collect::<Vec<_>>(),
+        vec!["other", "test"]
+    );
+
+    let unified = merged.to_unified_exec_map();
+    let counts = unified.module_maps[&(*module.address(), module.name().to_owned())]
+        .get_function_coverage(&Identifier::new("clamp").unwrap())
+        .unwrap();
+    assert_eq!(
+        counts
+            .iter()
+            .map(|(pc, count)| (*pc, *count))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, 3),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+            (4, 2),
+            (5, 2),
+            (6, 1),
+            (7, 1)
+        ]
+    );
+}
+
+#[test]
+fn cobertura_report() {
+    let (module, source_map) = compile();
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("Branches.mvir");
+    fs::write(&path, SOURCE).unwrap();
+
+    let coverage_map = coverage_map(&module, "test", SMALL);
+    let module_coverage = ModuleLineCoverage::new(
+        &module,
+        &coverage_map.to_unified_exec_map(),
+        &source_map,
+        &path,
+    )
+    .unwrap();
+
+    let mut output = vec![];
+    write_cobertura(
+        &[module_coverage],
+        "Test & Co",
+        &dir.path().to_string_lossy(),
+        42,
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output)
+        .unwrap()
+        .replace(&*dir.path().to_string_lossy(), "ROOT");
+    assert_eq!(output, EXPECTED_COBERTURA);
+}
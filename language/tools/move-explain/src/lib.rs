@@ -0,0 +1,629 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Explanations for errors raised by the bytecode verifier. An error is resolved back to the
+//! function and code offset it was raised at and, given a source map and the source file, to the
+//! offending source span. Errors raised during execution are resolved in the same way, for each
+//! frame of the call stack the VM recorded.
+
+use move_binary_format::{
+    access::ModuleAccess,
+    binary_views::BinaryIndexedView,
+    errors::{Location, VMError},
+    file_format::{CodeOffset, CompiledModule, FunctionDefinitionIndex, StructDefinitionIndex},
+    IndexKind,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_core_types::{language_storage::ModuleId, vm_status::StatusCode};
+use move_ir_types::location::Loc;
+use serde::Serialize;
+use std::fmt;
+
+/// A verifier error together with everything we know about where it comes from.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifierErrorExplanation {
+    /// The name of the status code, e.g. `NEGATIVE_STACK_SIZE_WITHIN_BLOCK`
+    pub status_code: String,
+    /// The numeric value of the status code
+    pub status_code_number: u64,
+    /// A human-readable explanation of the status code
+    pub explanation: String,
+    /// The message the verifier attached to the error, if any
+    pub message: Option<String>,
+    /// The module the error was raised in
+    pub module: String,
+    /// The function the error was raised in, if any
+    pub function: Option<String>,
+    /// The code offset within `function` the error was raised at, if any
+    pub code_offset: Option<CodeOffset>,
+    /// The offending source span, if a source map and source file were available
+    pub source_span: Option<SourceSpan>,
+}
+
+/// A span in a source file. Lines and columns are 1-based.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSpan {
+    pub file: String,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// The source lines covered by the span
+    pub text: String,
+}
+
+/// Explain `error`, which the bytecode verifier raised for `module`. The source location is
+/// resolved when both the `source_map` of the module and its `source` (a file name and the file
+/// contents) are provided.
+pub fn explain_verifier_error(
+    error: &VMError,
+    module: &CompiledModule,
+    source_map: Option<&SourceMap>,
+    source: Option<(&str, &str)>,
+) -> VerifierErrorExplanation {
+    let status_code = error.major_status();
+    let mut function = None;
+    let mut code_offset = None;
+    let mut loc = None;
+
+    if let Some((fdef_idx, offset)) = error.offsets().first() {
+        function = function_name(module, *fdef_idx);
+        code_offset = Some(*offset);
+        loc = source_map.and_then(|map| map.get_code_location(*fdef_idx, *offset).ok());
+    }
+    // Fall back to the innermost definition the error points to
+    for (kind, idx) in error.indices() {
+        match kind {
+            IndexKind::FunctionDefinition => {
+                let fdef_idx = FunctionDefinitionIndex(*idx);
+                if function.is_none() {
+                    function = function_name(module, fdef_idx);
+                }
+                if loc.is_none() {
+                    loc = source_map
+                        .and_then(|map| map.get_function_source_map(fdef_idx).ok())
+                        .map(|map| map.definition_location);
+                }
+            }
+            IndexKind::StructDefinition if loc.is_none() => {
+                loc = source_map
+                    .and_then(|map| map.get_struct_source_map(StructDefinitionIndex(*idx)).ok())
+                    .map(|map| map.definition_location);
+            }
+            _ => (),
+        }
+    }
+    let loc = loc.or_else(|| source_map.map(|map| map.definition_location));
+
+    VerifierErrorExplanation {
+        status_code: format!("{:?}", status_code),
+        status_code_number: status_code as u64,
+        explanation: status_code_explanation(status_code)
+            .unwrap_or("The bytecode verifier rejected the code")
+            .to_string(),
+        message: error.message().cloned(),
+        module: module.self_id().to_string(),
+        function,
+        code_offset,
+        source_span: loc
+            .zip(source)
+            .and_then(|(loc, (file, contents))| source_span(file, contents, loc)),
+    }
+}
+
+fn function_name(module: &CompiledModule, fdef_idx: FunctionDefinitionIndex) -> Option<String> {
+    let fdef = module.function_defs().get(fdef_idx.0 as usize)?;
+    let fhandle = module.function_handles().get(fdef.function.0 as usize)?;
+    module
+        .identifiers()
+        .get(fhandle.name.0 as usize)
+        .map(|name| name.to_string())
+}
+
+/// Resolve the byte range of `loc` in `contents` into lines and columns.
+fn source_span(file: &str, contents: &str, loc: Loc) -> Option<SourceSpan> {
+    let range = loc.usize_range();
+    if range.start > range.end || range.end > contents.len() {
+        return None;
+    }
+    let line_col = |offset: usize| {
+        let before = contents.get(..offset)?;
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Some((line, before[line_start..].chars().count() + 1))
+    };
+    let (start_line, start_column) = line_col(range.start)?;
+    let (end_line, end_column) = line_col(range.end)?;
+    let text = contents
+        .lines()
+        .skip(start_line - 1)
+        .take(end_line - start_line + 1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(SourceSpan {
+        file: file.to_string(),
+        start_line,
+        start_column,
+        end_line,
+        end_column,
+        text,
+    })
+}
+
+impl fmt::Display for VerifierErrorExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "error[{}]: {}",
+            self.status_code_number, self.status_code
+        )?;
+        match &self.function {
+            Some(function) => write!(f, "  in {}::{}", self.module, function)?,
+            None => write!(f, "  in {}", self.module)?,
+        }
+        if let Some(offset) = self.code_offset {
+            write!(f, " at code offset {}", offset)?;
+        }
+        writeln!(f)?;
+        if let Some(span) = &self.source_span {
+            write_snippet(f, span, "")?;
+        }
+        writeln!(f, "  = {}", self.explanation)?;
+        if let Some(message) = &self.message {
+            writeln!(f, "  = verifier message: {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the location and the source lines of `span`, indented by `indent`, underlining the span
+/// if it is on a single line.
+fn write_snippet(f: &mut fmt::Formatter<'_>, span: &SourceSpan, indent: &str) -> fmt::Result {
+    writeln!(
+        f,
+        "{}  --> {}:{}:{}",
+        indent, span.file, span.start_line, span.start_column
+    )?;
+    let width = span.end_line.to_string().len();
+    for (line_no, line) in (span.start_line..).zip(span.text.lines()) {
+        writeln!(f, "{}{:>width$} | {}", indent, line_no, line, width = width)?;
+    }
+    if span.start_line == span.end_line {
+        writeln!(
+            f,
+            "{}{:>width$} | {}{}",
+            indent,
+            "",
+            " ".repeat(span.start_column - 1),
+            "^".repeat((span.end_column - span.start_column).max(1)),
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+/// A frame of the call stack of a failed execution.
+#[derive(Debug, Clone, Serialize)]
+pub struct StackFrame {
+    /// The module of the function, or `None` for a script
+    pub module: Option<String>,
+    /// The function executing in this frame, if its code was available
+    pub function: Option<String>,
+    /// The code offset the function was executing at
+    pub code_offset: CodeOffset,
+    /// The source span of the code at `code_offset`, if a source map and source file were
+    /// available
+    pub source_span: Option<SourceSpan>,
+}
+
+/// The call stack of a failed execution, innermost frame first.
+#[derive(Debug, Clone, Serialize)]
+pub struct StackTrace {
+    pub frames: Vec<StackFrame>,
+}
+
+/// The code of a module or script on the call stack of a failed execution, with its source map
+/// and its source (a file name and the file contents) if available.
+#[derive(Clone, Copy)]
+pub struct CodeUnit<'a> {
+    pub code: BinaryIndexedView<'a>,
+    pub source_map: Option<&'a SourceMap>,
+    pub source: Option<(&'a str, &'a str)>,
+}
+
+/// Explain the call stack of `error`, which was raised during execution: the frame the error was
+/// raised in, followed by the frames of its callers as far as the VM recorded them. The code of
+/// the module of a frame, or of the script for `None`, is looked up with `code_unit`.
+pub fn explain_stack_trace<'a>(
+    error: &VMError,
+    code_unit: impl Fn(Option<&ModuleId>) -> Option<CodeUnit<'a>>,
+) -> StackTrace {
+    let raised_in = match error.location() {
+        Location::Module(module_id) => Some(Some(module_id)),
+        Location::Script => Some(None),
+        Location::Undefined => None,
+    };
+    let raised_at = raised_in.and_then(|module| {
+        error
+            .offsets()
+            .first()
+            .map(|(fdef_idx, offset)| (module, *fdef_idx, *offset))
+    });
+    let callers = error.exec_state().into_iter().flat_map(|exec_state| {
+        exec_state
+            .stack_trace()
+            .iter()
+            .map(|(module, fdef_idx, offset)| (module.as_ref(), *fdef_idx, *offset))
+    });
+    let frames = raised_at
+        .into_iter()
+        .chain(callers)
+        .map(|(module, fdef_idx, code_offset)| {
+            let unit = code_unit(module);
+            StackFrame {
+                module: module.map(|module_id| {
+                    format!(
+                        "0x{}::{}",
+                        module_id.address().short_str_lossless(),
+                        module_id.name()
+                    )
+                }),
+                function: unit.and_then(|unit| match unit.code {
+                    BinaryIndexedView::Module(module) => function_name(module, fdef_idx),
+                    BinaryIndexedView::Script(_) => Some("main".to_string()),
+                }),
+                code_offset,
+                source_span: unit.and_then(|unit| {
+                    let loc = unit
+                        .source_map?
+                        .get_code_location(fdef_idx, code_offset)
+                        .ok()?;
+                    let (file, contents) = unit.source?;
+                    source_span(file, contents, loc)
+                }),
+            }
+        })
+        .collect();
+    StackTrace { frames }
+}
+
+impl fmt::Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "stack trace (innermost call first):")?;
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let function = frame.function.as_deref().unwrap_or("<unknown function>");
+            match &frame.module {
+                Some(module) => write!(f, "  {}: {}::{}", idx, module, function)?,
+                None => write!(f, "  {}: script {}", idx, function)?,
+            }
+            writeln!(f, " at code offset {}", frame.code_offset)?;
+            if let Some(span) = &frame.source_span {
+                write_snippet(f, span, "    ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A human-readable explanation of the status codes raised during verification.
+pub fn status_code_explanation(status_code: StatusCode) -> Option<&'static str> {
+    use StatusCode::*;
+    Some(match status_code {
+        UNKNOWN_VERIFICATION_ERROR => "An unknown verification error occurred",
+        INDEX_OUT_OF_BOUNDS => "A table index in the binary points outside of its table",
+        INVALID_SIGNATURE_TOKEN => {
+            "A type is malformed, e.g. a reference occurs inside another type or in a field"
+        }
+        RECURSIVE_STRUCT_DEFINITION => {
+            "A struct contains itself, directly or through other structs"
+        }
+        FIELD_MISSING_TYPE_ABILITY => {
+            "A field type does not have the abilities required by the abilities of its struct"
+        }
+        INVALID_FALL_THROUGH => {
+            "The function body does not end with a return, abort or unconditional branch"
+        }
+        NEGATIVE_STACK_SIZE_WITHIN_BLOCK => {
+            "An instruction pops more values than there are on the stack"
+        }
+        POSITIVE_STACK_SIZE_AT_BLOCK_END => "Values are left on the stack at the end of a block",
+        INVALID_MAIN_FUNCTION_SIGNATURE => "The script function signature is not valid",
+        DUPLICATE_ELEMENT => "A table of the binary contains the same element twice",
+        INVALID_MODULE_HANDLE => "The module handle of the module itself is invalid",
+        UNIMPLEMENTED_HANDLE => "A handle refers to a struct or function which is not defined",
+        LOOKUP_FAILED => "A dependency does not define a struct or function used by the module",
+        TYPE_MISMATCH => "A value does not have the expected type",
+        MISSING_DEPENDENCY => "A module or native function the code depends on could not be found",
+        POP_WITHOUT_DROP_ABILITY => "A value without the `drop` ability is discarded",
+        BR_TYPE_MISMATCH_ERROR => "A conditional branch expects a `bool` on top of the stack",
+        ABORT_TYPE_MISMATCH_ERROR => "An abort expects a `u64` abort code on top of the stack",
+        STLOC_TYPE_MISMATCH_ERROR => "A value is stored into a local of a different type",
+        STLOC_UNSAFE_TO_DESTROY_ERROR => {
+            "Storing into a local would destroy a value without the `drop` ability"
+        }
+        UNSAFE_RET_LOCAL_OR_RESOURCE_STILL_BORROWED => {
+            "The function returns while a local or global value is still borrowed"
+        }
+        RET_TYPE_MISMATCH_ERROR => "The returned values do not match the function signature",
+        RET_BORROWED_MUTABLE_REFERENCE_ERROR => {
+            "A mutable reference is returned while it is still borrowed"
+        }
+        FREEZEREF_TYPE_MISMATCH_ERROR => "Only mutable references can be frozen",
+        FREEZEREF_EXISTS_MUTABLE_BORROW_ERROR => {
+            "A reference is frozen while a mutable borrow of it is still alive"
+        }
+        BORROWFIELD_TYPE_MISMATCH_ERROR => {
+            "A field is borrowed from a value which is not a reference to its struct"
+        }
+        BORROWFIELD_BAD_FIELD_ERROR => {
+            "A field is borrowed from a struct which does not declare it"
+        }
+        BORROWFIELD_EXISTS_MUTABLE_BORROW_ERROR => {
+            "A field is borrowed while a mutable borrow of the struct is still alive"
+        }
+        COPYLOC_UNAVAILABLE_ERROR => "A local is copied but it is unassigned or was moved",
+        COPYLOC_WITHOUT_COPY_ABILITY => "A local is copied but its type has no `copy` ability",
+        COPYLOC_EXISTS_BORROW_ERROR => "A local is copied while it is mutably borrowed",
+        MOVELOC_UNAVAILABLE_ERROR => "A local is moved but it is unassigned or was moved",
+        MOVELOC_EXISTS_BORROW_ERROR => "A local is moved while it is borrowed",
+        BORROWLOC_REFERENCE_ERROR => "A local holding a reference is borrowed",
+        BORROWLOC_UNAVAILABLE_ERROR => "A local is borrowed but it is unassigned or was moved",
+        BORROWLOC_EXISTS_BORROW_ERROR => "A local is borrowed mutably while it is borrowed",
+        CALL_TYPE_MISMATCH_ERROR => "The arguments of a call do not match the callee signature",
+        CALL_BORROWED_MUTABLE_REFERENCE_ERROR => {
+            "A mutable reference passed to a call is still borrowed"
+        }
+        PACK_TYPE_MISMATCH_ERROR => "The values packed do not match the struct fields",
+        UNPACK_TYPE_MISMATCH_ERROR => "The value unpacked is not of the struct type",
+        READREF_TYPE_MISMATCH_ERROR => "A value which is not a reference is dereferenced",
+        READREF_WITHOUT_COPY_ABILITY => {
+            "A reference is read but the referenced type has no `copy` ability"
+        }
+        READREF_EXISTS_MUTABLE_BORROW_ERROR => {
+            "A reference is read while a mutable borrow of it is still alive"
+        }
+        WRITEREF_TYPE_MISMATCH_ERROR => "The value written does not match the reference type",
+        WRITEREF_WITHOUT_DROP_ABILITY => {
+            "Writing through the reference would destroy a value without the `drop` ability"
+        }
+        WRITEREF_EXISTS_BORROW_ERROR => "A reference is written while it is still borrowed",
+        WRITEREF_NO_MUTABLE_REFERENCE_ERROR => "Only mutable references can be written through",
+        INTEGER_OP_TYPE_MISMATCH_ERROR => {
+            "The operands of an integer operation are not integers \
+            of the same type"
+        }
+        BOOLEAN_OP_TYPE_MISMATCH_ERROR => "The operands of a boolean operation are not `bool`s",
+        EQUALITY_OP_TYPE_MISMATCH_ERROR => {
+            "The operands of an equality are of different types or cannot be dropped"
+        }
+        EXISTS_WITHOUT_KEY_ABILITY_OR_BAD_ARGUMENT => {
+            "`exists` is used on a type without the `key` ability or without an address"
+        }
+        BORROWGLOBAL_TYPE_MISMATCH_ERROR => "`borrow_global` expects an address argument",
+        BORROWGLOBAL_WITHOUT_KEY_ABILITY => {
+            "`borrow_global` is used on a type without the `key` ability"
+        }
+        MOVEFROM_TYPE_MISMATCH_ERROR => "`move_from` expects an address argument",
+        MOVEFROM_WITHOUT_KEY_ABILITY => "`move_from` is used on a type without the `key` ability",
+        MOVETO_TYPE_MISMATCH_ERROR => "`move_to` expects a signer reference and a struct value",
+        MOVETO_WITHOUT_KEY_ABILITY => "`move_to` is used on a type without the `key` ability",
+        MODULE_ADDRESS_DOES_NOT_MATCH_SENDER => {
+            "The module is published under an address different from the sender"
+        }
+        NO_MODULE_HANDLES => "The module has no handle for itself",
+        MISSING_ACQUIRES_ANNOTATION => {
+            "The function accesses a global resource of the module without declaring it in \
+             `acquires`"
+        }
+        EXTRANEOUS_ACQUIRES_ANNOTATION => {
+            "The function declares a resource in `acquires` which it never accesses"
+        }
+        DUPLICATE_ACQUIRES_ANNOTATION => "A resource is declared twice in `acquires`",
+        INVALID_ACQUIRES_ANNOTATION => {
+            "A type declared in `acquires` is not a resource defined in the module"
+        }
+        GLOBAL_REFERENCE_ERROR => {
+            "A global resource is accessed while a reference to it is still alive"
+        }
+        CONSTRAINT_NOT_SATISFIED => "A type argument does not satisfy the ability constraints",
+        NUMBER_OF_TYPE_ARGUMENTS_MISMATCH => "The number of type arguments is wrong",
+        LOOP_IN_INSTANTIATION_GRAPH => {
+            "Generic functions instantiate each other with ever growing types"
+        }
+        ZERO_SIZED_STRUCT => "A struct has no fields",
+        LINKER_ERROR => "The module could not be linked against its dependencies",
+        INVALID_CONSTANT_TYPE => "A constant has a type which constants cannot have",
+        MALFORMED_CONSTANT_DATA => "The value of a constant does not match its type",
+        EMPTY_CODE_UNIT => "A function body is empty",
+        INVALID_LOOP_SPLIT => "A jump enters the middle of a loop",
+        INVALID_LOOP_BREAK => "A loop is exited to a location other than the end of the loop",
+        INVALID_LOOP_CONTINUE => "A back jump targets a loop other than the innermost one",
+        UNSAFE_RET_UNUSED_VALUES_WITHOUT_DROP => {
+            "The function returns while locals without the `drop` ability still hold values"
+        }
+        TOO_MANY_LOCALS => "The function declares too many locals",
+        GENERIC_MEMBER_OPCODE_MISMATCH => {
+            "A generic instruction is used on a non-generic member or vice versa"
+        }
+        FUNCTION_RESOLUTION_FAILURE => "A called function could not be resolved",
+        INVALID_OPERATION_IN_SCRIPT => "The instruction is not allowed in a script",
+        DUPLICATE_MODULE_NAME => "A module with the same name is already published",
+        BACKWARD_INCOMPATIBLE_MODULE_UPDATE => {
+            "The new module version is not compatible with the published one"
+        }
+        CYCLIC_MODULE_DEPENDENCY => "The module depends on itself through its dependencies",
+        NUMBER_OF_ARGUMENTS_MISMATCH => "The number of arguments is wrong",
+        INVALID_PARAM_TYPE_FOR_DESERIALIZATION => {
+            "A parameter has a type which cannot be passed as a transaction argument"
+        }
+        FAILED_TO_DESERIALIZE_ARGUMENT => "An argument could not be deserialized",
+        NUMBER_OF_SIGNER_ARGUMENTS_MISMATCH => "The number of signer arguments is wrong",
+        CALLED_SCRIPT_VISIBLE_FROM_NON_SCRIPT_VISIBLE => {
+            "A `public(script)` function is called from a function which is not"
+        }
+        EXECUTE_SCRIPT_FUNCTION_CALLED_ON_NON_SCRIPT_VISIBLE => {
+            "Only `public(script)` functions can be executed as scripts"
+        }
+        INVALID_FRIEND_DECL_WITH_SELF => "A module declares itself as a friend",
+        INVALID_FRIEND_DECL_WITH_MODULES_OUTSIDE_ACCOUNT_ADDRESS => {
+            "A friend module is published under a different address"
+        }
+        INVALID_FRIEND_DECL_WITH_MODULES_IN_DEPENDENCIES => {
+            "A friend module is also a dependency of the module"
+        }
+        CYCLIC_MODULE_FRIENDSHIP => "The friend declarations of the modules form a cycle",
+        INVALID_PHANTOM_TYPE_PARAM_POSITION => {
+            "A phantom type parameter is used in a non-phantom position"
+        }
+        VEC_UPDATE_EXISTS_MUTABLE_BORROW_ERROR => {
+            "A vector is updated while a mutable borrow of it is still alive"
+        }
+        VEC_BORROW_ELEMENT_EXISTS_MUTABLE_BORROW_ERROR => {
+            "A vector element is borrowed while a mutable borrow of the vector is still alive"
+        }
+        TOO_MANY_BASIC_BLOCKS => "The function has more basic blocks than the verifier allows",
+        LOOP_MAX_DEPTH_REACHED => "Loops are nested deeper than the verifier allows",
+        TOO_MANY_BACK_EDGES => "The function has more loops than the verifier allows",
+        TOO_MANY_TYPE_NODES => "A type is larger than the verifier allows",
+        PROGRAM_TOO_COMPLEX => "Verifying the function exceeded the verifier's budget",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::{
+        binary_views::BinaryIndexedView,
+        errors::{ExecutionState, PartialVMError},
+        file_format::{basic_test_module, Bytecode},
+    };
+    use move_command_line_common::files::FileHash;
+    use move_core_types::identifier::Identifier;
+
+    const SOURCE: &str = "module 0x1::M {\n    fun foo() {\n        pop\n    }\n}\n";
+
+    #[test]
+    fn explain_with_source_location() {
+        let mut module = basic_test_module();
+        module.function_defs[0].code.as_mut().unwrap().code = vec![Bytecode::Pop, Bytecode::Ret];
+        let error = move_bytecode_verifier::verify_module(&module).unwrap_err();
+
+        let file_hash = FileHash::new(SOURCE);
+        let pop_start = SOURCE.find("pop").unwrap() as u32;
+        let mut source_map = SourceMap::dummy_from_view(
+            &BinaryIndexedView::Module(&module),
+            Loc::new(file_hash, 0, SOURCE.len() as u32),
+        )
+        .unwrap();
+        source_map
+            .add_code_mapping(
+                FunctionDefinitionIndex(0),
+                0,
+                Loc::new(file_hash, pop_start, pop_start + 3),
+            )
+            .unwrap();
+
+        let explanation =
+            explain_verifier_error(&error, &module, Some(&source_map), Some(("M.move", SOURCE)));
+        assert_eq!(explanation.status_code, "NEGATIVE_STACK_SIZE_WITHIN_BLOCK");
+        assert_eq!(explanation.function.as_deref(), Some("foo"));
+        assert_eq!(explanation.code_offset, Some(0));
+        let span = explanation.source_span.as_ref().unwrap();
+        assert_eq!((span.start_line, span.start_column), (3, 9));
+        assert_eq!((span.end_line, span.end_column), (3, 12));
+        assert_eq!(span.text, "        pop");
+
+        let rendered = explanation.to_string();
+        assert!(rendered.contains("--> M.move:3:9"));
+        assert!(rendered.contains("3 |         pop\n  |         ^^^\n"));
+    }
+
+    #[test]
+    fn explain_without_source() {
+        let mut module = basic_test_module();
+        module.function_defs[0].code.as_mut().unwrap().code = vec![Bytecode::LdTrue];
+        let error = move_bytecode_verifier::verify_module(&module).unwrap_err();
+        let explanation = explain_verifier_error(&error, &module, None, None);
+        assert_eq!(explanation.status_code, "INVALID_FALL_THROUGH");
+        assert!(explanation.source_span.is_none());
+        assert_eq!(
+            explanation.explanation,
+            status_code_explanation(StatusCode::INVALID_FALL_THROUGH).unwrap()
+        );
+    }
+
+    #[test]
+    fn explain_stack_trace_with_source_location() {
+        let module = basic_test_module();
+        let module_id = module.self_id();
+        let file_hash = FileHash::new(SOURCE);
+        let pop_start = SOURCE.find("pop").unwrap() as u32;
+        let mut source_map = SourceMap::dummy_from_view(
+            &BinaryIndexedView::Module(&module),
+            Loc::new(file_hash, 0, SOURCE.len() as u32),
+        )
+        .unwrap();
+        source_map
+            .add_code_mapping(
+                FunctionDefinitionIndex(0),
+                1,
+                Loc::new(file_hash, pop_start, pop_start + 3),
+            )
+            .unwrap();
+
+        // An abort at offset 1 of `foo`, called from a script and from a module without code
+        let other_id = ModuleId::new(
+            module_id.address().to_owned(),
+            Identifier::new("N").unwrap(),
+        );
+        let error = PartialVMError::new(StatusCode::ABORTED)
+            .with_sub_status(7)
+            .at_code_offset(FunctionDefinitionIndex(0), 1)
+            .with_exec_state(ExecutionState::new(vec![
+                (Some(other_id), FunctionDefinitionIndex(3), 4),
+                (None, FunctionDefinitionIndex(0), 2),
+            ]))
+            .finish(Location::Module(module_id.clone()));
+
+        let trace = explain_stack_trace(&error, |id| match id {
+            Some(id) if id == &module_id => Some(CodeUnit {
+                code: BinaryIndexedView::Module(&module),
+                source_map: Some(&source_map),
+                source: Some(("M.move", SOURCE)),
+            }),
+            _ => None,
+        });
+        let frames: Vec<_> = trace
+            .frames
+            .iter()
+            .map(|frame| {
+                (
+                    frame.module.as_deref(),
+                    frame.function.as_deref(),
+                    frame.code_offset,
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (Some("0x0::<SELF>"), Some("foo"), 1),
+                (Some("0x0::N"), None, 4),
+                (None, None, 2),
+            ]
+        );
+        let span = trace.frames[0].source_span.as_ref().unwrap();
+        assert_eq!((span.start_line, span.start_column), (3, 9));
+
+        let rendered = trace.to_string();
+        assert!(rendered.starts_with(
+            "stack trace (innermost call first):\n  0: 0x0::<SELF>::foo at code offset 1\n"
+        ));
+        assert!(rendered.contains("    3 |         pop\n      |         ^^^\n"));
+        assert!(rendered.contains("  1: 0x0::N::<unknown function> at code offset 4\n"));
+        assert!(rendered.ends_with("  2: script <unknown function> at code offset 2\n"));
+    }
+}
@@ -2,34 +2,114 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use clap::Parser;
+use move_binary_format::file_format::CompiledModule;
+use move_bytecode_source_map::utils::source_map_from_file;
 use move_command_line_common::files::MOVE_ERROR_DESC_EXTENSION;
 use move_core_types::{
     account_address::AccountAddress, errmap::ErrorMapping, identifier::Identifier,
     language_storage::ModuleId,
 };
+use move_explain::explain_verifier_error;
+use std::path::{Path, PathBuf};
+
 #[derive(Debug, Parser)]
 #[clap(
     name = "Move Explain",
-    about = "Explain Move abort codes. Errors are defined as a global category + module-specific reason for the error."
+    about = "Explain Move abort codes and bytecode verifier errors. Errors are defined as a global category + module-specific reason for the error."
 )]
 struct Args {
     /// The location (module id) returned with a `MoveAbort` error
-    #[clap(long = "location", short = 'l')]
-    location: String,
+    #[clap(
+        long = "location",
+        short = 'l',
+        required_unless_present = "module-path"
+    )]
+    location: Option<String>,
     /// The abort code returned with a `MoveAbort` error
-    #[clap(long = "abort-code", short = 'a')]
-    abort_code: u64,
+    #[clap(
+        long = "abort-code",
+        short = 'a',
+        required_unless_present = "module-path"
+    )]
+    abort_code: Option<u64>,
     /// Path to the error code mapping file
-    #[clap(long = MOVE_ERROR_DESC_EXTENSION, short = 'e')]
-    errmap_path: String,
+    #[clap(long = MOVE_ERROR_DESC_EXTENSION, short = 'e', required_unless_present = "module-path")]
+    errmap_path: Option<String>,
+    /// Path to a compiled module to run the bytecode verifier on and explain the errors of
+    #[clap(
+        long = "module",
+        short = 'm',
+        conflicts_with_all = &["location", "abort-code", "errmap-path"]
+    )]
+    module_path: Option<PathBuf>,
+    /// Path to the source map of the module, used to locate verifier errors in the source
+    #[clap(long = "source-map", requires_all = &["module-path", "source-path"])]
+    source_map_path: Option<PathBuf>,
+    /// Path to the source file of the module
+    #[clap(long = "source", requires = "source-map-path")]
+    source_path: Option<PathBuf>,
+    /// Print the explanation as JSON
+    #[clap(long = "json")]
+    json: bool,
 }
 
 fn main() {
     let args = Args::parse();
+    match &args.module_path {
+        Some(module_path) => explain_verifier_errors(&args, module_path),
+        None => explain_abort_code(&args),
+    }
+}
+
+fn explain_verifier_errors(args: &Args, module_path: &Path) {
+    let module_bytes = std::fs::read(module_path).expect("Could not load module from file");
+    let module = CompiledModule::deserialize(&module_bytes).expect("Failed to deserialize module");
+    let error = match move_bytecode_verifier::verify_module(&module) {
+        Ok(()) => {
+            println!("Module {} passes the bytecode verifier", module.self_id());
+            return;
+        }
+        Err(error) => error,
+    };
 
-    let mut location = args.location.trim().split("::");
-    let mut address_literal = location.next().expect("Could not find address").to_string();
-    let module_name = location
+    let source_map = args
+        .source_map_path
+        .as_ref()
+        .map(|path| source_map_from_file(path).expect("Could not load source map from file"));
+    let source = args.source_path.as_ref().map(|path| {
+        let contents = std::fs::read_to_string(path).expect("Could not load source from file");
+        (path.to_string_lossy().to_string(), contents)
+    });
+
+    let explanation = explain_verifier_error(
+        &error,
+        &module,
+        source_map.as_ref(),
+        source
+            .as_ref()
+            .map(|(file, contents)| (file.as_str(), contents.as_str())),
+    );
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&explanation).expect("Failed to serialize explanation")
+        );
+    } else {
+        print!("{}", explanation);
+    }
+}
+
+fn explain_abort_code(args: &Args) {
+    let location = args.location.as_ref().unwrap();
+    let abort_code = args.abort_code.unwrap();
+    let errmap_path = args.errmap_path.as_ref().unwrap();
+
+    let mut module_location = location.trim().split("::");
+    let mut address_literal = module_location
+        .next()
+        .expect("Could not find address")
+        .to_string();
+    let module_name = module_location
         .next()
         .expect("Could not find module name")
         .to_string();
@@ -41,14 +121,18 @@ fn main() {
         Identifier::new(module_name).expect("Invalid module name encountered"),
     );
 
-    let errmap_bytes = std::fs::read(&args.errmap_path).expect("Could not load errmap from file");
+    let errmap_bytes = std::fs::read(errmap_path).expect("Could not load errmap from file");
     let errmap: ErrorMapping =
         bcs::from_bytes(&errmap_bytes).expect("Failed to deserialize errmap");
 
-    match errmap.get_explanation(&module_id, args.abort_code) {
+    match errmap.get_explanation(&module_id, abort_code) {
         None => println!(
             "Unable to find a description for {}::{}",
-            args.location, args.abort_code
+            location, abort_code
+        ),
+        Some(error_desc) if args.json => println!(
+            "{}",
+            serde_json::to_string_pretty(&error_desc).expect("Failed to serialize explanation")
         ),
         Some(error_desc) => println!(
             "Category:\n  Name: {}\n  Description: {}\nReason:\n  Name: {}\n  Description: {}",
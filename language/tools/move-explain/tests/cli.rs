@@ -0,0 +1,102 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_binary_format::{
+    binary_views::BinaryIndexedView,
+    file_format::{basic_test_module, Bytecode, CompiledModule, FunctionDefinitionIndex},
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::files::FileHash;
+use move_ir_types::location::Loc;
+use std::{fs, path::Path, process::Command};
+use tempfile::tempdir;
+
+const SOURCE: &str = "module 0x1::M {\n    fun foo() {\n        pop\n    }\n}\n";
+
+/// Writes `module`, a source map which maps its first instruction to the `pop` in `SOURCE`,
+/// and `SOURCE` to `dir`.
+fn write_module(dir: &Path, module: &CompiledModule) {
+    let mut bytes = vec![];
+    module.serialize(&mut bytes).unwrap();
+    fs::write(dir.join("M.mv"), bytes).unwrap();
+
+    let file_hash = FileHash::new(SOURCE);
+    let pop_start = SOURCE.find("pop").unwrap() as u32;
+    let mut source_map = SourceMap::dummy_from_view(
+        &BinaryIndexedView::Module(module),
+        Loc::new(file_hash, 0, SOURCE.len() as u32),
+    )
+    .unwrap();
+    source_map
+        .add_code_mapping(
+            FunctionDefinitionIndex(0),
+            0,
+            Loc::new(file_hash, pop_start, pop_start + 3),
+        )
+        .unwrap();
+    fs::write(dir.join("M.mvsm"), bcs::to_bytes(&source_map).unwrap()).unwrap();
+    fs::write(dir.join("M.move"), SOURCE).unwrap();
+}
+
+fn move_explain(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_move-explain"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn explain_module_with_source() {
+    let dir = tempdir().unwrap();
+    let mut module = basic_test_module();
+    module.function_defs[0].code.as_mut().unwrap().code = vec![Bytecode::Pop, Bytecode::Ret];
+    write_module(dir.path(), &module);
+
+    let output = move_explain(
+        dir.path(),
+        &[
+            "--module",
+            "M.mv",
+            "--source-map",
+            "M.mvsm",
+            "--source",
+            "M.move",
+        ],
+    );
+    assert!(
+        output.contains("NEGATIVE_STACK_SIZE_WITHIN_BLOCK"),
+        "{}",
+        output
+    );
+    assert!(output.contains("--> M.move:3:9"), "{}", output);
+    assert!(
+        output.contains("3 |         pop\n  |         ^^^\n"),
+        "{}",
+        output
+    );
+
+    let output = move_explain(dir.path(), &["--module", "M.mv", "--json"]);
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["status_code"], "NEGATIVE_STACK_SIZE_WITHIN_BLOCK");
+    assert_eq!(json["function"], "foo");
+    assert!(json["source_span"].is_null());
+}
+
+#[test]
+fn explain_verified_module() {
+    let dir = tempdir().unwrap();
+    write_module(dir.path(), &basic_test_module());
+    let output = move_explain(dir.path(), &["--module", "M.mv"]);
+    assert!(
+        output.contains("passes the bytecode verifier"),
+        "{}",
+        output
+    );
+}
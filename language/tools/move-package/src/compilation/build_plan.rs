@@ -5,12 +5,16 @@ use crate::{
     compilation::compiled_package::CompiledPackage, resolution::resolution_graph::ResolvedGraph,
     source_package::parsed_manifest::PackageName,
 };
-use anyhow::Result;
-use move_compiler::{compiled_unit::AnnotatedCompiledUnit, diagnostics::FilesSourceText, Compiler};
+use anyhow::{anyhow, Result};
+use move_compiler::{
+    compiled_unit::AnnotatedCompiledUnit,
+    diagnostics::{report_diagnostics_to_buffer, Diagnostics, FilesSourceText},
+    Compiler,
+};
 use petgraph::algo::toposort;
 use std::{
     collections::{BTreeMap, BTreeSet},
-    io::Write,
+    io::{self, Write},
     path::Path,
 };
 
@@ -56,6 +60,15 @@ impl BuildPlan {
         self.compile_with_driver(writer, |compiler, _| compiler.build_and_report())
     }
 
+    /// Like `compile`, but returns an error instead of exiting the process if a package has
+    /// compilation errors.
+    pub fn compile_no_exit<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(CompiledPackage, CompilationCachingStatus)> {
+        self.compile_with_driver(writer, |compiler, _| build_and_report_no_exit(compiler))
+    }
+
     pub fn compile_with_driver<W: Write>(
         &self,
         writer: &mut W,
@@ -299,3 +312,35 @@ impl BuildPlan {
         Ok(())
     }
 }
+
+/// Build with `compiler` like `Compiler::build_and_report`, but return an error instead of exiting
+/// the process if there are compilation errors.
+pub fn build_and_report_no_exit(
+    compiler: Compiler,
+) -> Result<(FilesSourceText, Vec<AnnotatedCompiledUnit>)> {
+    let (files, units_res) = compiler.build()?;
+    let (units, warnings) = unwrap_or_report_diagnostics_no_exit(&files, units_res)?;
+    report_diagnostics_no_exit(&files, warnings);
+    Ok((files, units))
+}
+
+/// Like `move_compiler::diagnostics::unwrap_or_report_diagnostics`, but return an error instead of
+/// exiting the process.
+pub fn unwrap_or_report_diagnostics_no_exit<T>(
+    files: &FilesSourceText,
+    res: std::result::Result<T, Diagnostics>,
+) -> Result<T> {
+    res.map_err(|diags| {
+        report_diagnostics_no_exit(files, diags);
+        anyhow!("Compilation failed")
+    })
+}
+
+/// Print `diags` to stderr without exiting the process.
+pub fn report_diagnostics_no_exit(files: &FilesSourceText, diags: Diagnostics) {
+    if !diags.is_empty() {
+        io::stderr()
+            .write_all(&report_diagnostics_to_buffer(files, diags))
+            .unwrap();
+    }
+}
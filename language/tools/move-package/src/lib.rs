@@ -185,6 +185,20 @@ impl BuildConfig {
         ret
     }
 
+    /// Like `compile_package_with_caching_info`, but returns an error instead of exiting the
+    /// process if the package or one of its dependencies has compilation errors.
+    pub fn compile_package_no_exit<W: Write>(
+        self,
+        path: &Path,
+        writer: &mut W,
+    ) -> Result<(CompiledPackage, CompilationCachingStatus)> {
+        let resolved_graph = self.resolution_graph_for_package(path)?;
+        let mutx = PackageLock::lock();
+        let ret = BuildPlan::create(resolved_graph)?.compile_no_exit(writer);
+        mutx.unlock();
+        ret
+    }
+
     // NOTE: If there are no renamings, then the root package has the global resolution of all named
     // addresses in the package graph in scope. So we can simply grab all of the source files
     // across all packages and build the Move model from that.
@@ -33,7 +33,10 @@ pub fn run_tests_with_config_and_filter(
 
     let (_, all_tests_passed) = config
         .run_and_report_unit_tests(test_plan, native_function_table, std::io::stdout())
-        .expect("Failed to execute tests");
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to execute tests: {:#}", err);
+            std::process::exit(1)
+        });
 
     // If all tests passed, exit with 0 otherwise with a non-zero exit code.
     if all_tests_passed {
@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! This module manages native extensions supported by the unit testing framework.
-//! Such extensions are enabled by cfg features and must be compiled into the test
-//! to be usable.
+//! Built-in extensions are enabled by cfg features and must be compiled into the test
+//! to be usable. Embedders with their own natives register them at runtime via
+//! `UnitTestExtensions` instead.
 
-use move_vm_runtime::native_extensions::NativeContextExtensions;
-use std::fmt::Write;
+use move_vm_runtime::{
+    native_extensions::NativeContextExtensions, native_functions::NativeFunctionTable,
+};
+use std::{fmt, fmt::Write, sync::Arc};
 
 #[cfg(feature = "table-extension")]
 use itertools::Itertools;
@@ -17,20 +20,85 @@ use move_vm_test_utils::BlankStorage;
 #[cfg(feature = "table-extension")]
 use once_cell::sync::Lazy;
 
+/// Adds a fresh native context extension to the extensions of a test before it is run.
+pub type ExtensionFactory =
+    Arc<dyn for<'a> Fn(&mut NativeContextExtensions<'a>) + Send + Sync + 'static>;
+
+/// Prints the changes recorded by a native context extension when the storage state of a failed
+/// test is reported.
+pub type ChangeSetPrinter =
+    Arc<dyn for<'a> Fn(&mut dyn Write, &mut NativeContextExtensions<'a>) + Send + Sync + 'static>;
+
+/// Native functions and native context extensions registered by an embedder of the unit test
+/// framework, in addition to the built-in ones.
+#[derive(Clone, Default)]
+pub struct UnitTestExtensions {
+    natives: NativeFunctionTable,
+    factories: Vec<ExtensionFactory>,
+    printers: Vec<ChangeSetPrinter>,
+}
+
+impl UnitTestExtensions {
+    /// Registers native functions in addition to the native function table the tests are run
+    /// with.
+    pub fn add_natives(&mut self, natives: NativeFunctionTable) {
+        self.natives.extend(natives)
+    }
+
+    /// Registers a factory which adds a native context extension to every test.
+    pub fn add_extension<F>(&mut self, factory: F)
+    where
+        F: for<'a> Fn(&mut NativeContextExtensions<'a>) + Send + Sync + 'static,
+    {
+        self.factories.push(Arc::new(factory))
+    }
+
+    /// Registers a printer for the changes recorded by a native context extension.
+    pub fn add_change_set_printer<P>(&mut self, printer: P)
+    where
+        P: for<'a> Fn(&mut dyn Write, &mut NativeContextExtensions<'a>) + Send + Sync + 'static,
+    {
+        self.printers.push(Arc::new(printer))
+    }
+
+    pub(crate) fn natives(&self) -> &NativeFunctionTable {
+        &self.natives
+    }
+}
+
+impl fmt::Debug for UnitTestExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnitTestExtensions")
+            .field("natives", &self.natives.len())
+            .field("factories", &self.factories.len())
+            .field("printers", &self.printers.len())
+            .finish()
+    }
+}
+
 /// Create all available native context extensions.
-#[allow(unused_mut, clippy::let_and_return)]
-pub(crate) fn new_extensions<'a>() -> NativeContextExtensions<'a> {
+#[allow(unused_mut)]
+pub(crate) fn new_extensions<'a>(registered: &UnitTestExtensions) -> NativeContextExtensions<'a> {
     let mut e = NativeContextExtensions::default();
     #[cfg(feature = "table-extension")]
     create_table_extension(&mut e);
+    for factory in &registered.factories {
+        factory(&mut e);
+    }
     e
 }
 
 /// Print the change sets for available native context extensions.
-#[allow(unused)]
-pub(crate) fn print_change_sets<W: Write>(_w: &mut W, mut extensions: NativeContextExtensions) {
+pub(crate) fn print_change_sets<W: Write>(
+    w: &mut W,
+    mut extensions: NativeContextExtensions,
+    registered: &UnitTestExtensions,
+) {
     #[cfg(feature = "table-extension")]
-    print_table_extension(_w, &mut extensions);
+    print_table_extension(w, &mut extensions);
+    for printer in &registered.printers {
+        printer(w, &mut extensions);
+    }
 }
 
 // =============================================================================================
@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod cargo_runner;
-mod extensions;
+pub mod extensions;
+pub mod mutation;
+pub mod snapshot;
 pub mod test_reporter;
 pub mod test_runner;
 
-use crate::test_runner::TestRunner;
+use crate::{extensions::UnitTestExtensions, test_reporter::ReportFormat, test_runner::TestRunner};
+use anyhow::Result;
 use clap::*;
 use move_command_line_common::files::verify_and_create_named_address_mapping;
 use move_compiler::{
@@ -17,13 +20,10 @@ use move_compiler::{
     Compiler, Flags, PASS_CFGIR,
 };
 use move_core_types::language_storage::ModuleId;
-use move_vm_runtime::native_functions::NativeFunctionTable;
-use std::{
-    collections::BTreeMap,
-    io::{Result, Write},
-    marker::Send,
-    sync::Mutex,
+use move_vm_runtime::{
+    native_extensions::NativeContextExtensions, native_functions::NativeFunctionTable,
 };
+use std::{collections::BTreeMap, io::Write, marker::Send, path::PathBuf, sync::Mutex};
 
 #[derive(Debug, Parser, Clone)]
 #[clap(name = "Move Unit Test", about = "Unit testing for Move code.")]
@@ -73,13 +73,35 @@ pub struct UnitTestingConfig {
     #[clap(name = "global_state_on_error", short = 'g', long = "state_on_error")]
     pub report_storage_on_error: bool,
 
+    /// A directory with resources every test starts with, in the layout of the storage
+    /// directory of the Move sandbox
+    #[clap(long = "state", parse(from_os_str))]
+    pub state_dir: Option<PathBuf>,
+
+    /// A directory with snapshots of the resources written and events emitted by each passing
+    /// test, which are compared against the effects of the tests
+    #[clap(long = "snapshots", parse(from_os_str))]
+    pub snapshot_dir: Option<PathBuf>,
+
+    /// Overwrite the snapshots with the effects of the tests instead of comparing them. Also
+    /// enabled by setting `UPDATE_BASELINE`.
+    #[clap(long = "update-snapshots", requires = "snapshot-dir")]
+    pub update_snapshots: bool,
+
+    /// Deprecated and ignored: the call stack of a failing test is always reported
     #[clap(
         name = "report_stacktrace_on_abort",
         short = 'r',
-        long = "stacktrace_on_abort"
+        long = "stacktrace_on_abort",
+        hide = true
     )]
     pub report_stacktrace_on_abort: bool,
 
+    /// An error map (as written by `move package errmap`) used, along with the error map of the
+    /// standard library, to explain the codes tests abort with
+    #[clap(long = "error-map", parse(from_os_str))]
+    pub error_map: Option<PathBuf>,
+
     /// Named address mapping
     #[clap(
         name = "NAMED_ADDRESSES",
@@ -103,10 +125,35 @@ pub struct UnitTestingConfig {
     #[clap(long = "stackless")]
     pub check_stackless_vm: bool,
 
+    /// Run the tests in the stackless bytecode interpreter as well and check the specifications
+    /// (`ensures`, `aborts_if`, struct invariants, ...) of the functions executed against the
+    /// concrete values observed. Violations, and results of the interpreter which differ from the
+    /// ones of the Move VM, are reported as test failures.
+    #[clap(long = "check-specs")]
+    pub check_specs: bool,
+
+    /// Instead of reporting the test results, run the tests against mutants of the modules in
+    /// the source files and report the mutants which are not detected by any test.
+    #[clap(long = "mutate")]
+    pub mutate: bool,
+
+    /// The format of the test report: `human`, `junit` for a JUnit XML report, or `json` for a
+    /// stream of JSON events, one per line.
+    #[clap(
+        long = "format",
+        possible_values = ReportFormat::variants(),
+        default_value = "human"
+    )]
+    pub report_format: ReportFormat,
+
     /// Verbose mode
     #[clap(short = 'v', long = "verbose")]
     pub verbose: bool,
 
+    /// Native functions and native context extensions registered by the embedder
+    #[clap(skip)]
+    pub extensions: UnitTestExtensions,
+
     /// Use the EVM-based execution backend.
     /// Does not work with --stackless.
     #[cfg(feature = "evm-backend")]
@@ -114,7 +161,8 @@ pub struct UnitTestingConfig {
     pub evm: bool,
 }
 
-fn format_module_id(module_id: &ModuleId) -> String {
+/// Formats a module id as `0x<address>::<name>`, the way tests are named in reports.
+pub fn format_module_id(module_id: &ModuleId) -> String {
     format!(
         "0x{}::{}",
         module_id.address().short_str_lossless(),
@@ -122,6 +170,12 @@ fn format_module_id(module_id: &ModuleId) -> String {
     )
 }
 
+impl Default for UnitTestingConfig {
+    fn default() -> Self {
+        Self::default_with_bound(None)
+    }
+}
+
 impl UnitTestingConfig {
     /// Create a unit testing config for use with `register_move_unit_tests`
     pub fn default_with_bound(bound: Option<u64>) -> Self {
@@ -131,11 +185,19 @@ impl UnitTestingConfig {
             num_threads: 8,
             report_statistics: false,
             report_storage_on_error: false,
+            state_dir: None,
+            snapshot_dir: None,
+            update_snapshots: false,
             report_stacktrace_on_abort: false,
+            error_map: None,
             source_files: vec![],
             dep_files: vec![],
             check_stackless_vm: false,
+            check_specs: false,
+            mutate: false,
+            report_format: ReportFormat::Human,
             verbose: false,
+            extensions: UnitTestExtensions::default(),
             list: false,
             named_address_values: vec![],
 
@@ -153,6 +215,35 @@ impl UnitTestingConfig {
         self
     }
 
+    /// Make `natives` available to the tests in addition to the native function table they are
+    /// run with.
+    pub fn with_natives(mut self, natives: NativeFunctionTable) -> Self {
+        self.extensions.add_natives(natives);
+        self
+    }
+
+    /// Add a native context extension created by `factory` to every test, for use by natives.
+    pub fn with_extension<F>(mut self, factory: F) -> Self
+    where
+        F: for<'a> Fn(&mut NativeContextExtensions<'a>) + Send + Sync + 'static,
+    {
+        self.extensions.add_extension(factory);
+        self
+    }
+
+    /// Use `printer` to report the changes recorded by a native context extension along with the
+    /// storage state of a failed test.
+    pub fn with_change_set_printer<P>(mut self, printer: P) -> Self
+    where
+        P: for<'a> Fn(&mut dyn std::fmt::Write, &mut NativeContextExtensions<'a>)
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.extensions.add_change_set_printer(printer);
+        self
+    }
+
     fn compile_to_test_plan(
         &self,
         source_files: Vec<String>,
@@ -222,32 +313,44 @@ impl UnitTestingConfig {
             return Ok((shared_writer.into_inner().unwrap(), true));
         }
 
-        writeln!(shared_writer.lock().unwrap(), "Running Move unit tests")?;
-        let mut test_runner = TestRunner::new(
-            self.instruction_execution_bound,
-            self.num_threads,
-            self.check_stackless_vm,
-            self.verbose,
-            self.report_storage_on_error,
-            self.report_stacktrace_on_abort,
-            test_plan,
-            native_function_table,
-            verify_and_create_named_address_mapping(self.named_address_values.clone()).unwrap(),
-            #[cfg(feature = "evm-backend")]
-            self.evm,
-        )
-        .unwrap();
+        if self.mutate {
+            let passed = self.run_and_report_mutation_tests(
+                test_plan,
+                native_function_table,
+                &shared_writer,
+            )?;
+            return Ok((shared_writer.into_inner().unwrap(), passed));
+        }
+
+        let mut test_runner = TestRunner::new(self, test_plan, native_function_table)?;
 
         if let Some(filter_str) = &self.filter {
             test_runner.filter(filter_str)
         }
 
-        let test_results = test_runner.run(&shared_writer).unwrap();
-        if self.report_statistics {
-            test_results.report_statistics(&shared_writer)?;
+        match self.report_format {
+            ReportFormat::Human => {
+                writeln!(shared_writer.lock().unwrap(), "Running Move unit tests")?;
+            }
+            ReportFormat::Json => writeln!(
+                shared_writer.lock().unwrap(),
+                "{}",
+                test_reporter::suite_started_event(test_runner.num_tests())
+            )?,
+            ReportFormat::Junit => (),
         }
 
-        let all_tests_passed = test_results.summarize(&shared_writer)?;
+        let test_results = test_runner.run(&shared_writer)?;
+        let all_tests_passed = match self.report_format {
+            ReportFormat::Human => {
+                if self.report_statistics {
+                    test_results.report_statistics(&shared_writer)?;
+                }
+                test_results.summarize(&shared_writer)?
+            }
+            ReportFormat::Junit => test_results.summarize_junit(&shared_writer)?,
+            ReportFormat::Json => test_results.summarize_json(&shared_writer)?,
+        };
 
         let writer = shared_writer.into_inner().unwrap();
         Ok((writer, all_tests_passed))
@@ -1,15 +1,22 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use anyhow::Result;
 use clap::*;
 use move_unit_test::UnitTestingConfig;
 
-pub fn main() {
+pub fn main() -> Result<()> {
     let args = UnitTestingConfig::parse();
+    if args.report_stacktrace_on_abort {
+        eprintln!(
+            "warning: `--stacktrace_on_abort` is deprecated and will be removed, the call stack \
+             of a failing test is always reported"
+        );
+    }
 
     let test_plan = args.build_test_plan();
     if let Some(test_plan) = test_plan {
-        args.run_and_report_unit_tests(test_plan, None, std::io::stdout())
-            .unwrap();
+        args.run_and_report_unit_tests(test_plan, None, std::io::stdout())?;
     }
+    Ok(())
 }
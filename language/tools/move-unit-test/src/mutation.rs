@@ -0,0 +1,420 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mutation testing for Move unit tests.
+//!
+//! Mutants are derived from the bytecode of the modules under test by applying a single mutation
+//! from a fixed catalogue to one instruction: flipping a comparison, changing a constant, removing
+//! an `abort`, negating a branch condition, or deleting a statement. Mutants rejected by the
+//! bytecode verifier are discarded. The unit tests are then run against each remaining mutant; a
+//! mutant is killed if at least one test fails, and survives otherwise. Surviving mutants point
+//! at behavior the tests do not check.
+
+use crate::{
+    format_module_id, test_reporter::ReportFormat, test_runner::TestRunner, UnitTestingConfig,
+};
+use colored::*;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, CodeOffset, CompiledModule, FunctionDefinitionIndex, TableIndex},
+};
+use move_bytecode_verifier::verify_module;
+use move_compiler::unit_test::{filter_test_members::UNIT_TEST_POISON_FUN_NAME, TestPlan};
+use move_core_types::language_storage::ModuleId;
+use move_vm_runtime::native_functions::NativeFunctionTable;
+use rayon::prelude::*;
+use std::{
+    collections::BTreeSet,
+    io::{Result, Write},
+    sync::Mutex,
+};
+
+/// The kinds of mutations which are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MutationKind {
+    /// Replace a comparison by its negation, e.g. `<` by `>=`.
+    FlipComparison,
+    /// Replace an integer constant `c` by `c + 1` and a boolean constant by its negation.
+    ChangeConstant,
+    /// Replace an `abort` by dropping the abort code, so execution continues.
+    RemoveAbort,
+    /// Negate the condition of a conditional branch.
+    NegateBranch,
+    /// Delete a call to a function without results, or an assignment through a reference.
+    DeleteStatement,
+}
+
+/// A module with one mutation applied.
+#[derive(Debug, Clone)]
+pub struct Mutant {
+    pub module_id: ModuleId,
+    pub function_name: String,
+    pub function: FunctionDefinitionIndex,
+    pub offset: CodeOffset,
+    pub kind: MutationKind,
+    pub description: String,
+    pub module: CompiledModule,
+}
+
+/// The outcome of running the unit tests against all mutants.
+#[derive(Debug, Default)]
+pub struct MutationTestResults {
+    /// The mutants for which at least one unit test failed.
+    pub killed: Vec<Mutant>,
+    /// The mutants for which all unit tests passed.
+    pub survived: Vec<Mutant>,
+    /// The number of mutants which were rejected by the bytecode verifier.
+    pub invalid: usize,
+}
+
+impl MutationTestResults {
+    /// The percentage of killed mutants, if there are any mutants.
+    pub fn score(&self) -> Option<f64> {
+        let total = self.killed.len() + self.survived.len();
+        if total == 0 {
+            None
+        } else {
+            Some(self.killed.len() as f64 * 100.0 / total as f64)
+        }
+    }
+}
+
+/// Generates the mutants of the modules in `targets`. Test functions, and functions added by the
+/// compiler in test mode, are not mutated. Returns the
+/// mutants which pass the bytecode verifier, and the number of mutants which do not.
+pub fn generate_mutants(
+    test_plan: &TestPlan,
+    targets: &BTreeSet<ModuleId>,
+) -> (Vec<Mutant>, usize) {
+    let mut mutants = vec![];
+    let mut invalid = 0;
+    for module_id in targets {
+        let module = match test_plan.module_info.get(module_id) {
+            Some(info) => &info.module,
+            None => continue,
+        };
+        let tests = test_plan
+            .module_tests
+            .get(module_id)
+            .map(|plan| &plan.tests);
+        for (def_idx, fdef) in module.function_defs.iter().enumerate() {
+            let handle = module.function_handle_at(fdef.function);
+            let function_name = module.identifier_at(handle.name).to_string();
+            if function_name == UNIT_TEST_POISON_FUN_NAME
+                || matches!(tests, Some(tests) if tests.contains_key(&function_name))
+            {
+                continue;
+            }
+            let code = match &fdef.code {
+                Some(code_unit) => &code_unit.code,
+                None => continue,
+            };
+            for (offset, instr) in code.iter().enumerate() {
+                for (kind, description, replacement) in mutations(module, code, offset, instr) {
+                    let mut mutated = module.clone();
+                    let code_unit = mutated.function_defs[def_idx].code.as_mut().unwrap();
+                    code_unit.code = replace_instruction(code, offset, replacement);
+                    if verify_module(&mutated).is_err() {
+                        invalid += 1;
+                        continue;
+                    }
+                    mutants.push(Mutant {
+                        module_id: module_id.clone(),
+                        function_name: function_name.clone(),
+                        function: FunctionDefinitionIndex(def_idx as TableIndex),
+                        offset: offset as CodeOffset,
+                        kind,
+                        description,
+                        module: mutated,
+                    });
+                }
+            }
+        }
+    }
+    (mutants, invalid)
+}
+
+/// Returns the mutations of the instruction `instr` at `offset` in `code`, as the mutation kind,
+/// a description, and the instructions replacing `instr`.
+fn mutations(
+    module: &CompiledModule,
+    code: &[Bytecode],
+    offset: usize,
+    instr: &Bytecode,
+) -> Vec<(MutationKind, String, Vec<Bytecode>)> {
+    use Bytecode::*;
+    let comparison = |from: &str, to: &str, replacement: Bytecode| {
+        (
+            MutationKind::FlipComparison,
+            format!("replaced `{}` with `{}`", from, to),
+            vec![replacement],
+        )
+    };
+    let constant = |from: String, to: String, replacement: Bytecode| {
+        (
+            MutationKind::ChangeConstant,
+            format!("replaced `{}` with `{}`", from, to),
+            vec![replacement],
+        )
+    };
+    let deleted_call = |name: &str, num_params: usize| {
+        (
+            MutationKind::DeleteStatement,
+            format!("deleted call to `{}`", name),
+            vec![Pop; num_params],
+        )
+    };
+    match instr {
+        Lt => vec![comparison("<", ">=", Ge)],
+        Ge => vec![comparison(">=", "<", Lt)],
+        Gt => vec![comparison(">", "<=", Le)],
+        Le => vec![comparison("<=", ">", Gt)],
+        Eq => vec![comparison("==", "!=", Neq)],
+        Neq => vec![comparison("!=", "==", Eq)],
+        LdTrue => vec![constant("true".to_string(), "false".to_string(), LdFalse)],
+        LdFalse => vec![constant("false".to_string(), "true".to_string(), LdTrue)],
+        LdU8(c) => {
+            let to = c.wrapping_add(1);
+            vec![constant(c.to_string(), to.to_string(), LdU8(to))]
+        }
+        LdU64(c) => {
+            let to = c.wrapping_add(1);
+            vec![constant(c.to_string(), to.to_string(), LdU64(to))]
+        }
+        LdU128(c) => {
+            let to = c.wrapping_add(1);
+            vec![constant(c.to_string(), to.to_string(), LdU128(to))]
+        }
+        // Dropping the abort code makes execution fall through to the next instruction, so
+        // an abort at the end of the code cannot be removed.
+        Abort if offset + 1 < code.len() => vec![(
+            MutationKind::RemoveAbort,
+            "removed `abort`".to_string(),
+            vec![Pop],
+        )],
+        BrTrue(target) => vec![(
+            MutationKind::NegateBranch,
+            "negated branch condition".to_string(),
+            vec![BrFalse(*target)],
+        )],
+        BrFalse(target) => vec![(
+            MutationKind::NegateBranch,
+            "negated branch condition".to_string(),
+            vec![BrTrue(*target)],
+        )],
+        Call(idx) => {
+            let handle = module.function_handle_at(*idx);
+            if module.signature_at(handle.return_).is_empty() {
+                let num_params = module.signature_at(handle.parameters).len();
+                vec![deleted_call(
+                    module.identifier_at(handle.name).as_str(),
+                    num_params,
+                )]
+            } else {
+                vec![]
+            }
+        }
+        CallGeneric(idx) => {
+            let handle = module.function_handle_at(module.function_instantiation_at(*idx).handle);
+            if module.signature_at(handle.return_).is_empty() {
+                let num_params = module.signature_at(handle.parameters).len();
+                vec![deleted_call(
+                    module.identifier_at(handle.name).as_str(),
+                    num_params,
+                )]
+            } else {
+                vec![]
+            }
+        }
+        WriteRef => vec![(
+            MutationKind::DeleteStatement,
+            "deleted assignment".to_string(),
+            vec![Pop, Pop],
+        )],
+        _ => vec![],
+    }
+}
+
+/// Replaces the instruction at `offset` by `replacement`, adjusting the targets of branches to
+/// the instructions following it. Branches to `offset` go to the start of the replacement.
+fn replace_instruction(
+    code: &[Bytecode],
+    offset: usize,
+    replacement: Vec<Bytecode>,
+) -> Vec<Bytecode> {
+    use Bytecode::*;
+    let adjust = |target: &CodeOffset| -> CodeOffset {
+        if (*target as usize) <= offset {
+            *target
+        } else {
+            (*target as isize + replacement.len() as isize - 1) as CodeOffset
+        }
+    };
+    let adjust_branch = |instr: &Bytecode| match instr {
+        BrTrue(target) => BrTrue(adjust(target)),
+        BrFalse(target) => BrFalse(adjust(target)),
+        Branch(target) => Branch(adjust(target)),
+        _ => instr.clone(),
+    };
+    let mut result: Vec<_> = code[..offset].iter().map(adjust_branch).collect();
+    result.extend(replacement.iter().map(adjust_branch));
+    result.extend(code[offset + 1..].iter().map(adjust_branch));
+    result
+}
+
+impl UnitTestingConfig {
+    /// Runs the unit tests against all mutants of the modules defined in the source files of this
+    /// config and reports the surviving mutants. Returns `false` if the tests do not pass on the
+    /// unmutated code, in which case no mutants are tested.
+    pub(crate) fn run_and_report_mutation_tests<W: Write + Send>(
+        &self,
+        test_plan: TestPlan,
+        native_function_table: Option<NativeFunctionTable>,
+        writer: &Mutex<W>,
+    ) -> Result<bool> {
+        writeln!(writer.lock().unwrap(), "Running Move mutation tests")?;
+        if !self.all_tests_pass(&test_plan, native_function_table.clone()) {
+            writeln!(
+                writer.lock().unwrap(),
+                "{}: not all tests pass on the unmutated code. \
+                 Run the tests without `--mutate` to see the failures.",
+                "error".bold().bright_red()
+            )?;
+            return Ok(false);
+        }
+
+        let targets = test_plan
+            .module_info
+            .iter()
+            .filter(|(_, info)| {
+                let file_hash = info.source_map.definition_location.file_hash();
+                matches!(
+                    test_plan.files.get(&file_hash),
+                    Some((name, _)) if self.source_files.iter().any(|file| file == name.as_str())
+                )
+            })
+            .map(|(module_id, _)| module_id.clone())
+            .collect();
+        let (mutants, invalid) = generate_mutants(&test_plan, &targets);
+
+        let killed: Vec<bool> = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.num_threads)
+            .build()
+            .unwrap()
+            .install(|| {
+                mutants
+                    .par_iter()
+                    .map(|mutant| {
+                        let mut mutated_plan = test_plan.clone();
+                        if let Some(info) = mutated_plan.module_info.get_mut(&mutant.module_id) {
+                            info.module = mutant.module.clone();
+                        }
+                        !self.all_tests_pass(&mutated_plan, native_function_table.clone())
+                    })
+                    .collect()
+            });
+        let mut results = MutationTestResults {
+            invalid,
+            ..Default::default()
+        };
+        for (mutant, killed) in mutants.into_iter().zip(killed) {
+            if killed {
+                results.killed.push(mutant)
+            } else {
+                results.survived.push(mutant)
+            }
+        }
+        report_mutation_results(&test_plan, &results, writer)?;
+        Ok(true)
+    }
+
+    /// Runs the tests of `test_plan` without reporting, and returns whether all of them passed.
+    fn all_tests_pass(
+        &self,
+        test_plan: &TestPlan,
+        native_function_table: Option<NativeFunctionTable>,
+    ) -> bool {
+        // Run the tests on the Move VM only, without output.
+        let config = UnitTestingConfig {
+            num_threads: 1,
+            check_stackless_vm: false,
+            check_specs: false,
+            verbose: false,
+            report_storage_on_error: false,
+            error_map: None,
+            report_format: ReportFormat::Human,
+            snapshot_dir: None,
+            #[cfg(feature = "evm-backend")]
+            evm: false,
+            ..self.clone()
+        };
+        let mut test_runner =
+            match TestRunner::new(&config, test_plan.clone(), native_function_table) {
+                Ok(test_runner) => test_runner,
+                // The modules of the plan cannot be published.
+                Err(_) => return false,
+            };
+        if let Some(filter_str) = &self.filter {
+            test_runner.filter(filter_str)
+        }
+        let test_results = test_runner.run(&Mutex::new(std::io::sink())).unwrap();
+        !test_results.has_failures()
+    }
+}
+
+fn report_mutation_results<W: Write>(
+    test_plan: &TestPlan,
+    results: &MutationTestResults,
+    writer: &Mutex<W>,
+) -> Result<()> {
+    let mut writer = writer.lock().unwrap();
+    for mutant in &results.survived {
+        writeln!(
+            writer,
+            "[ {} ] {} {}::{}: {}",
+            "SURVIVED".bold().bright_red(),
+            mutant_location(test_plan, mutant),
+            format_module_id(&mutant.module_id),
+            mutant.function_name,
+            mutant.description
+        )?;
+    }
+    let total = results.killed.len() + results.survived.len();
+    writeln!(
+        writer,
+        "Mutation score: {}. Total mutants: {}; killed: {}; survived: {}; \
+         rejected by the bytecode verifier: {}",
+        match results.score() {
+            Some(score) => format!("{:.0}%", score).bold(),
+            None => "n/a".bold(),
+        },
+        total,
+        results.killed.len(),
+        results.survived.len(),
+        results.invalid
+    )
+}
+
+/// Returns the source location of the mutated instruction as `file:line:column`.
+fn mutant_location(test_plan: &TestPlan, mutant: &Mutant) -> String {
+    let loc = match test_plan
+        .module_info
+        .get(&mutant.module_id)
+        .and_then(|info| {
+            info.source_map
+                .get_code_location(mutant.function, mutant.offset)
+                .ok()
+        }) {
+        Some(loc) => loc,
+        None => return "<unknown>".to_string(),
+    };
+    match test_plan.files.get(&loc.file_hash()) {
+        Some((name, source)) => {
+            let prefix = &source[..loc.start() as usize];
+            let line = prefix.matches('\n').count() + 1;
+            let column = prefix.len() - prefix.rfind('\n').map_or(0, |pos| pos + 1) + 1;
+            format!("{}:{}:{}", name, line, column)
+        }
+        None => "<unknown>".to_string(),
+    }
+}
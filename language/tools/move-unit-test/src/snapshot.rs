@@ -0,0 +1,111 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Snapshot testing of the effects of unit tests. The resources written and deleted and the
+//! events emitted by a test are rendered as text and compared against a checked-in `.snap` file,
+//! following the conventions of the `.exp` files of the Move test harnesses: a missing snapshot
+//! is treated as empty, and setting `UPDATE_BASELINE` overwrites the snapshots instead.
+
+use anyhow::Result;
+use move_command_line_common::testing::format_diff;
+use move_core_types::{
+    effects::{ChangeSet, Event},
+    language_storage::ModuleId,
+};
+use move_resource_viewer::MoveValueAnnotator;
+use move_vm_test_utils::InMemoryStorage;
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Extension for snapshot files
+pub const SNAP_EXT: &str = "snap";
+
+/// Where the snapshots of tests are kept and whether they are checked or overwritten.
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl Snapshots {
+    /// Check the effects of tests against the snapshots in `dir`, or overwrite the snapshots with
+    /// the effects if `update` is set.
+    pub fn new(dir: PathBuf, update: bool) -> Self {
+        Self { dir, update }
+    }
+
+    /// The snapshot of test `function_name` of module `module_id`, at
+    /// `<dir>/<address>/<module>/<function>.snap`.
+    pub fn path(&self, module_id: &ModuleId, function_name: &str) -> PathBuf {
+        self.dir
+            .join(format!("0x{}", module_id.address().short_str_lossless()))
+            .join(module_id.name().as_str())
+            .join(function_name)
+            .with_extension(SNAP_EXT)
+    }
+
+    /// Compare `actual` with the snapshot at `path`. Returns the diff between the two if they
+    /// differ and snapshots are not being updated.
+    pub fn check(&self, path: &Path, actual: &str) -> Result<Option<String>> {
+        if self.update {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, actual)?;
+            return Ok(None);
+        }
+        let expected = fs::read_to_string(path).unwrap_or_else(|_| "".to_string());
+        Ok(if expected != actual {
+            Some(format_diff(expected, actual))
+        } else {
+            None
+        })
+    }
+}
+
+/// Render the resources written and deleted by `cs` and the `events` emitted by a test, with
+/// values annotated using the modules in `storage`.
+pub(crate) fn render_snapshot(
+    cs: &ChangeSet,
+    events: &[Event],
+    storage: &InMemoryStorage,
+) -> Result<String> {
+    let mut buf = String::new();
+    let annotator = MoveValueAnnotator::new(storage);
+    for (account_addr, account_state) in cs.accounts() {
+        if account_state.resources().is_empty() {
+            continue;
+        }
+        writeln!(&mut buf, "0x{}:", account_addr.short_str_lossless())?;
+        for (tag, resource_opt) in account_state.resources() {
+            match resource_opt {
+                Some(resource) => writeln!(
+                    &mut buf,
+                    "\t{}",
+                    format!("=> {}", annotator.view_resource(tag, resource)?).replace("\n", "\n\t")
+                )?,
+                None => writeln!(&mut buf, "\tdeleted {}", tag)?,
+            }
+        }
+    }
+    if !events.is_empty() {
+        writeln!(&mut buf, "events:")?;
+        for (guid, sequence_number, ty, data) in events {
+            writeln!(
+                &mut buf,
+                "\t{}",
+                format!(
+                    "#{} of {}: {}",
+                    sequence_number,
+                    hex::encode(guid),
+                    annotator.view_value(ty, data)?
+                )
+                .replace("\n", "\n\t")
+            )?;
+        }
+    }
+    Ok(buf)
+}
@@ -5,30 +5,66 @@ use crate::format_module_id;
 use codespan_reporting::files::{Files, SimpleFiles};
 use colored::{control, Colorize};
 use move_binary_format::{
-    access::ModuleAccess,
-    errors::{ExecutionState, Location, VMError, VMResult},
+    binary_views::BinaryIndexedView,
+    errors::{Location, VMError, VMResult},
 };
 use move_command_line_common::files::FileHash;
 use move_compiler::{
     diagnostics::{self, Diagnostic},
     unit_test::{ModuleTestPlan, TestPlan},
 };
-use move_core_types::{effects::ChangeSet, language_storage::ModuleId};
+use move_core_types::{effects::ChangeSet, errmap::ErrorMapping, language_storage::ModuleId};
+use move_explain::CodeUnit;
 use move_ir_types::location::Loc;
 use move_symbol_pool::Symbol;
+use serde_json::json;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     io::{Result, Write},
+    path::Path,
+    str::FromStr,
     sync::Mutex,
     time::Duration,
 };
 
+/// The format in which the results of a test run are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Human-readable progress lines and summary.
+    Human,
+    /// A JUnit XML report, written once all tests have run.
+    Junit,
+    /// A stream of JSON events, one per line, as tests start and finish.
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "human" => Ok(ReportFormat::Human),
+            "junit" => Ok(ReportFormat::Junit),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(anyhow::anyhow!("Invalid report format: {}", s)),
+        }
+    }
+}
+
+impl ReportFormat {
+    pub fn variants() -> [&'static str; 3] {
+        ["human", "junit", "json"]
+    }
+}
+
 #[derive(Debug, Clone, Ord, PartialOrd, PartialEq, Eq)]
 pub enum FailureReason {
     // Expected to abort, but it didn't
     NoAbort(String),
     // Aborted with the wrong code
     WrongAbort(String, u64, u64),
+    // Failed with an error other than the expected one, with the abort code if it aborted
+    WrongError(String, Option<u64>),
     // Abort wasn't expected, but it did
     Aborted(String, u64),
     // Test timed out
@@ -42,6 +78,8 @@ pub enum FailureReason {
     },
     // Property checking failed
     Property(String),
+    // The effects of the test differ from its snapshot, with the diff between the two
+    Snapshot(String, String),
     // The test failed for some unknown reason. This shouldn't be encountered
     Unknown(String),
 
@@ -56,6 +94,8 @@ pub struct TestFailure {
     pub vm_error: Option<VMError>,
     pub failure_reason: FailureReason,
     pub storage_state: Option<String>,
+    /// The reason and category of the abort code, as described by an error map
+    pub abort_reason: Option<String>,
 }
 
 #[derive(Debug, Clone, Ord, PartialOrd, PartialEq, Eq)]
@@ -85,6 +125,35 @@ impl TestRunInfo {
             instructions_executed,
         }
     }
+
+    /// The JSON event reporting that this test of module `module_id` passed.
+    pub fn passed_event(&self, module_id: &ModuleId) -> serde_json::Value {
+        json!({
+            "type": "test",
+            "event": "passed",
+            "name": format!("{}::{}", format_module_id(module_id), self.function_ident),
+            "exec_time": self.elapsed_time.as_secs_f64(),
+            "instructions": self.instructions_executed,
+        })
+    }
+}
+
+/// The JSON event reporting that the test `function_ident` of module `module_id` started.
+pub fn test_started_event(module_id: &ModuleId, function_ident: &str) -> serde_json::Value {
+    json!({
+        "type": "test",
+        "event": "started",
+        "name": format!("{}::{}", format_module_id(module_id), function_ident),
+    })
+}
+
+/// The JSON event reporting that a run of `test_count` tests started.
+pub fn suite_started_event(test_count: usize) -> serde_json::Value {
+    json!({
+        "type": "suite",
+        "event": "started",
+        "test_count": test_count,
+    })
 }
 
 impl FailureReason {
@@ -100,6 +169,13 @@ impl FailureReason {
         )
     }
 
+    pub fn wrong_error(mismatch: String, abort_code: Option<u64>) -> Self {
+        FailureReason::WrongError(
+            format!("Test did not fail with the expected error. {}", mismatch),
+            abort_code,
+        )
+    }
+
     pub fn aborted(abort_code: u64) -> Self {
         FailureReason::Aborted("Test was not expected to abort".to_string(), abort_code)
     }
@@ -126,6 +202,20 @@ impl FailureReason {
         FailureReason::Property(details)
     }
 
+    pub fn snapshot_mismatch(path: &Path, diff: String) -> Self {
+        FailureReason::Snapshot(
+            format!("Test effects differ from snapshot `{}`", path.display()),
+            diff,
+        )
+    }
+
+    pub fn snapshot_error(path: &Path, error: anyhow::Error) -> Self {
+        FailureReason::Snapshot(
+            format!("Unable to check snapshot `{}`: {}", path.display(), error),
+            String::new(),
+        )
+    }
+
     pub fn move_to_evm_error(diagnostics: String) -> Self {
         FailureReason::MoveToEVMError(diagnostics)
     }
@@ -133,6 +223,24 @@ impl FailureReason {
     pub fn unknown() -> Self {
         FailureReason::Unknown("ITE: An unknown error was reported.".to_string())
     }
+
+    /// A short identifier of the kind of failure, used in machine-readable reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FailureReason::NoAbort(_) => "no_abort",
+            FailureReason::WrongAbort(..) => "wrong_abort",
+            FailureReason::WrongError(..) => "wrong_error",
+            FailureReason::Aborted(..) => "aborted",
+            FailureReason::Timeout(_) => "timeout",
+            FailureReason::Mismatch { .. } => "mismatch",
+            FailureReason::Property(_) => "property",
+            FailureReason::Snapshot(..) => "snapshot",
+            FailureReason::Unknown(_) => "unknown",
+
+            #[cfg(feature = "evm-backend")]
+            FailureReason::MoveToEVMError(_) => "move_to_evm_error",
+        }
+    }
 }
 
 impl TestFailure {
@@ -147,23 +255,126 @@ impl TestFailure {
             vm_error,
             failure_reason,
             storage_state,
+            abort_reason: None,
+        }
+    }
+
+    /// Explain the code the test aborted with, if `error_map` describes it for the module the test
+    /// aborted in.
+    pub fn with_abort_reason(mut self, error_map: &ErrorMapping) -> Self {
+        let module_id = match self.vm_error.as_ref().map(|err| err.location()) {
+            Some(Location::Module(module_id)) => module_id,
+            _ => return self,
+        };
+        self.abort_reason = self
+            .abort_code()
+            .and_then(|code| error_map.get_explanation(module_id, code))
+            .map(|context| {
+                format!(
+                    "{} ({}): {}",
+                    context.reason.code_name,
+                    context.category.code_name,
+                    context.reason.code_description.trim()
+                )
+            });
+        self
+    }
+
+    /// The JSON event reporting this failure of a test of module `module_id`.
+    pub fn failed_event(&self, module_id: &ModuleId, test_plan: &TestPlan) -> serde_json::Value {
+        json!({
+            "type": "test",
+            "event": "failed",
+            "name": format!(
+                "{}::{}",
+                format_module_id(module_id),
+                self.test_run_info.function_ident
+            ),
+            "exec_time": self.test_run_info.elapsed_time.as_secs_f64(),
+            "instructions": self.test_run_info.instructions_executed,
+            "reason": self.failure_reason.kind(),
+            "message": self.message(),
+            "abort_code": self.abort_code(),
+            "abort_reason": self.abort_reason,
+            "location": self.location(test_plan),
+        })
+    }
+
+    /// The abort code of the test, if it aborted with one.
+    pub fn abort_code(&self) -> Option<u64> {
+        match &self.failure_reason {
+            FailureReason::WrongAbort(_, _, code) | FailureReason::Aborted(_, code) => Some(*code),
+            FailureReason::WrongError(_, code) => *code,
+            _ => None,
+        }
+    }
+
+    /// The source location (`file:line`) at which the test failed, if known.
+    pub fn location(&self, test_plan: &TestPlan) -> Option<String> {
+        let vm_error = self.vm_error.as_ref()?;
+        let module_id = match vm_error.location() {
+            Location::Module(module_id) => module_id,
+            _ => return None,
+        };
+        let (fdef_idx, offset) = vm_error.offsets().first()?;
+        let loc = test_plan
+            .module_info
+            .get(module_id)?
+            .source_map
+            .get_function_source_map(*fdef_idx)
+            .ok()?
+            .get_code_location(*offset)?;
+        let (files, file_mapping) = Self::source_files(test_plan);
+        Some(format!(
+            "{}:{}",
+            test_plan.files.get(&loc.file_hash())?.0,
+            Self::get_line_number(&loc, &files, &file_mapping)
+        ))
+    }
+
+    /// A short description of the failure, without source locations.
+    pub fn message(&self) -> String {
+        match &self.failure_reason {
+            FailureReason::NoAbort(message)
+            | FailureReason::Timeout(message)
+            | FailureReason::Property(message)
+            | FailureReason::Snapshot(message, _)
+            | FailureReason::WrongError(message, _)
+            | FailureReason::Unknown(message) => message.clone(),
+            FailureReason::WrongAbort(message, expected_code, other_code) => format!(
+                "{}. Expected test to abort with {} but instead it aborted with {}",
+                message, expected_code, other_code,
+            ),
+            FailureReason::Aborted(message, code) => {
+                format!("{} but it aborted with {}", message, code)
+            }
+            FailureReason::Mismatch { .. } => {
+                "Executions via Move VM and stackless VM yield different results".to_string()
+            }
+
+            #[cfg(feature = "evm-backend")]
+            FailureReason::MoveToEVMError(_) => {
+                "Failed to compile Move code into EVM bytecode".to_string()
+            }
         }
     }
 
-    pub fn render_error(&self, test_plan: &TestPlan) -> String {
+    /// Renders the error of the failure, with the source location highlighted in color if
+    /// `colorize` is set.
+    pub fn render_error(&self, test_plan: &TestPlan, colorize: bool) -> String {
         let error_string = match &self.failure_reason {
             FailureReason::NoAbort(message) => message.to_string(),
             FailureReason::Timeout(message) => message.to_string(),
-            FailureReason::WrongAbort(message, expected_code, other_code) => {
-                let base_message = format!(
-                    "{}. Expected test to abort with {} but instead it aborted with {} here",
-                    message, expected_code, other_code,
-                );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
-            }
-            FailureReason::Aborted(message, code) => {
-                let base_message = format!("{} but it aborted with {} here", message, code);
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+            FailureReason::WrongAbort(..)
+            | FailureReason::WrongError(..)
+            | FailureReason::Aborted(..) => {
+                let base_message = match &self.abort_reason {
+                    Some(reason) => {
+                        format!("{} here. The abort code is {}", self.message(), reason)
+                    }
+                    None => format!("{} here", self.message()),
+                };
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             }
             FailureReason::Mismatch {
                 move_vm_return_values,
@@ -185,6 +396,9 @@ impl TestFailure {
                 )
             }
             FailureReason::Property(message) => message.clone(),
+            FailureReason::Snapshot(message, diff) => {
+                format!("{}\n{}", message, diff.trim_end())
+            }
             FailureReason::Unknown(message) => {
                 format!(
                     "{} Location: {}\nVMError (if there is one): {}",
@@ -192,7 +406,8 @@ impl TestFailure {
                     TestFailure::report_error_with_location(
                         test_plan,
                         "".to_string(),
-                        &self.vm_error
+                        &self.vm_error,
+                        colorize
                     ),
                     self.vm_error
                         .as_ref()
@@ -254,62 +469,43 @@ impl TestFailure {
         }
     }
 
-    fn report_exec_state(test_plan: &TestPlan, exec_state: &ExecutionState) -> String {
-        let stack_trace = exec_state.stack_trace();
-        let mut buf = String::new();
-        if !stack_trace.is_empty() {
-            buf.push_str("stack trace\n");
-            let mut files = SimpleFiles::new();
-            let mut file_mapping = HashMap::new();
-            for (fhash, (fname, source)) in &test_plan.files {
-                let id = files.add(*fname, source.as_str());
-                file_mapping.insert(*fhash, id);
-            }
+    fn source_files(test_plan: &TestPlan) -> (SimpleFiles<Symbol, &str>, HashMap<FileHash, usize>) {
+        let mut files = SimpleFiles::new();
+        let mut file_mapping = HashMap::new();
+        for (fhash, (fname, source)) in &test_plan.files {
+            let id = files.add(*fname, source.as_str());
+            file_mapping.insert(*fhash, id);
+        }
+        (files, file_mapping)
+    }
 
-            for frame in stack_trace {
-                let module_id = match &frame.0 {
-                    Some(v) => v,
-                    None => return "\tmalformed stack trace (no module ID)".to_string(),
-                };
-                let named_module = match test_plan.module_info.get(module_id) {
-                    Some(v) => v,
-                    None => return "\tmalformed stack trace (no module)".to_string(),
-                };
-                let function_source_map =
-                    match named_module.source_map.get_function_source_map(frame.1) {
-                        Ok(v) => v,
-                        Err(_) => return "\tmalformed stack trace (no source map)".to_string(),
-                    };
-                // unwrap here is a mirror of the same unwrap in report_error_with_location
-                let loc = function_source_map.get_code_location(frame.2).unwrap();
-                let fn_handle_idx = named_module.module.function_def_at(frame.1).function;
-                let fn_id_idx = named_module.module.function_handle_at(fn_handle_idx).name;
-                let fn_name = named_module.module.identifier_at(fn_id_idx).as_str();
-                let file_name = match test_plan.files.get(&loc.file_hash()) {
-                    Some(v) => format!("{}", v.0),
-                    None => "unknown_source".to_string(),
-                };
-                buf.push_str(
-                    &format!(
-                        "\t{}::{}({}:{})\n",
-                        module_id.name(),
-                        fn_name,
-                        file_name,
-                        Self::get_line_number(&loc, &files, &file_mapping)
-                    )
-                    .to_string(),
-                );
-            }
+    fn report_stack_trace(test_plan: &TestPlan, vm_error: &VMError) -> String {
+        let stack_trace = move_explain::explain_stack_trace(vm_error, |module_id| {
+            let named_module = test_plan.module_info.get(module_id?)?;
+            let source_map = &named_module.source_map;
+            Some(CodeUnit {
+                code: BinaryIndexedView::Module(&named_module.module),
+                source_map: Some(source_map),
+                source: test_plan
+                    .files
+                    .get(&source_map.definition_location.file_hash())
+                    .map(|(file, contents)| (file.as_str(), contents.as_str())),
+            })
+        });
+        if stack_trace.frames.is_empty() {
+            String::new()
+        } else {
+            stack_trace.to_string()
         }
-        buf
     }
 
     fn report_error_with_location(
         test_plan: &TestPlan,
         base_message: String,
         vm_error: &Option<VMError>,
+        colorize: bool,
     ) -> String {
-        let report_diagnostics = if control::SHOULD_COLORIZE.should_colorize() {
+        let report_diagnostics = if colorize {
             diagnostics::report_diagnostics_to_color_buffer
         } else {
             diagnostics::report_diagnostics_to_buffer
@@ -348,16 +544,11 @@ impl TestFailure {
             }
             _ => base_message,
         };
-        match vm_error.exec_state() {
-            None => diags,
-            Some(exec_state) => {
-                let exec_state_str = Self::report_exec_state(test_plan, exec_state);
-                if exec_state_str.is_empty() {
-                    diags
-                } else {
-                    format!("{}\n{}", diags, exec_state_str)
-                }
-            }
+        let stack_trace = Self::report_stack_trace(test_plan, vm_error);
+        if stack_trace.is_empty() {
+            diags
+        } else {
+            format!("{}\n{}", diags, stack_trace)
         }
     }
 }
@@ -495,18 +686,129 @@ impl TestResults {
         writeln!(writer.lock().unwrap())
     }
 
-    /// Returns `true` if all tests passed, `false` if there was a test failure/timeout
-    pub fn summarize<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
-        let num_failed_tests = self
+    /// Returns `true` if there was a test failure/timeout
+    pub fn has_failures(&self) -> bool {
+        !self.final_statistics.failed.is_empty()
+    }
+
+    /// Returns the number of passed and failed tests
+    fn num_passed_and_failed(&self) -> (u64, u64) {
+        let num_passed_tests = self
             .final_statistics
-            .failed
+            .passed
             .iter()
             .fold(0, |acc, (_, fns)| acc + fns.len()) as u64;
-        let num_passed_tests = self
+        let num_failed_tests = self
             .final_statistics
-            .passed
+            .failed
             .iter()
             .fold(0, |acc, (_, fns)| acc + fns.len()) as u64;
+        (num_passed_tests, num_failed_tests)
+    }
+
+    /// Reports the end of the test run as a JSON event. Returns `true` if all tests passed,
+    /// `false` if there was a test failure/timeout
+    pub fn summarize_json<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
+        let (num_passed_tests, num_failed_tests) = self.num_passed_and_failed();
+        let event = json!({
+            "type": "suite",
+            "event": if num_failed_tests == 0 { "ok" } else { "failed" },
+            "passed": num_passed_tests,
+            "failed": num_failed_tests,
+        });
+        writeln!(writer.lock().unwrap(), "{}", event)?;
+        Ok(num_failed_tests == 0)
+    }
+
+    /// Writes the test results as a JUnit XML report with one test suite per module. Returns
+    /// `true` if all tests passed, `false` if there was a test failure/timeout
+    pub fn summarize_junit<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
+        let (num_passed_tests, num_failed_tests) = self.num_passed_and_failed();
+        let mut suites: BTreeMap<&ModuleId, BTreeMap<&str, (Duration, Option<&TestFailure>)>> =
+            BTreeMap::new();
+        for (module_id, test_results) in &self.final_statistics.passed {
+            let suite = suites.entry(module_id).or_default();
+            for test_result in test_results {
+                suite.insert(
+                    &test_result.function_ident,
+                    (test_result.elapsed_time, None),
+                );
+            }
+        }
+        for (module_id, test_failures) in &self.final_statistics.failed {
+            let suite = suites.entry(module_id).or_default();
+            for test_failure in test_failures {
+                let info = &test_failure.test_run_info;
+                suite.insert(
+                    &info.function_ident,
+                    (info.elapsed_time, Some(test_failure)),
+                );
+            }
+        }
+        let total_time: Duration = suites
+            .values()
+            .flat_map(|suite| suite.values())
+            .map(|(time, _)| *time)
+            .sum();
+
+        let mut w = writer.lock().unwrap();
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<testsuites name="Move unit tests" tests="{}" failures="{}" time="{:.3}">"#,
+            num_passed_tests + num_failed_tests,
+            num_failed_tests,
+            total_time.as_secs_f64()
+        )?;
+        for (module_id, suite) in suites {
+            let module_name = format_module_id(module_id);
+            writeln!(
+                w,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+                xml_escape(&module_name),
+                suite.len(),
+                suite
+                    .values()
+                    .filter(|(_, failure)| failure.is_some())
+                    .count(),
+                suite
+                    .values()
+                    .map(|(time, _)| *time)
+                    .sum::<Duration>()
+                    .as_secs_f64()
+            )?;
+            for (function_ident, (time, failure)) in suite {
+                let testcase = format!(
+                    r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+                    xml_escape(function_ident),
+                    xml_escape(&module_name),
+                    time.as_secs_f64()
+                );
+                match failure {
+                    None => writeln!(w, "{}/>", testcase)?,
+                    Some(failure) => {
+                        writeln!(w, "{}>", testcase)?;
+                        writeln!(
+                            w,
+                            r#"      <failure type="{}" message="{}">{}</failure>"#,
+                            failure.failure_reason.kind(),
+                            xml_escape(&failure.message()),
+                            // XML reports should not contain color codes.
+                            xml_escape(&failure.render_error(&self.test_plan, false))
+                        )?;
+                        writeln!(w, "    </testcase>")?;
+                    }
+                }
+            }
+            writeln!(w, "  </testsuite>")?;
+        }
+        writeln!(w, "</testsuites>")?;
+        Ok(num_failed_tests == 0)
+    }
+
+    /// Returns `true` if all tests passed, `false` if there was a test failure/timeout
+    pub fn summarize<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
+        let (num_passed_tests, num_failed_tests) = self.num_passed_and_failed();
         if !self.final_statistics.failed.is_empty() {
             writeln!(writer.lock().unwrap(), "\nTest failures:\n")?;
             for (module_id, test_failures) in &self.final_statistics.failed {
@@ -525,7 +827,10 @@ impl TestResults {
                         writer.lock().unwrap(),
                         "│ {}",
                         test_failure
-                            .render_error(&self.test_plan)
+                            .render_error(
+                                &self.test_plan,
+                                control::SHOULD_COLORIZE.should_colorize()
+                            )
                             .replace("\n", "\n│ ")
                     )?;
                     writeln!(writer.lock().unwrap(), "└──────────────────\n")?;
@@ -548,3 +853,10 @@ impl TestResults {
         Ok(num_failed_tests == 0)
     }
 }
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
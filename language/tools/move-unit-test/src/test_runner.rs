@@ -2,23 +2,39 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    extensions, format_module_id,
-    test_reporter::{FailureReason, TestFailure, TestResults, TestRunInfo, TestStatistics},
+    extensions::{self, UnitTestExtensions},
+    format_module_id,
+    snapshot::{self, Snapshots},
+    test_reporter::{
+        self, FailureReason, ReportFormat, TestFailure, TestResults, TestRunInfo, TestStatistics,
+    },
+    UnitTestingConfig,
 };
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use colored::*;
 
-use move_binary_format::{errors::VMResult, file_format::CompiledModule};
+use move_binary_format::{
+    errors::{Location, VMError, VMResult},
+    file_format::CompiledModule,
+};
 use move_bytecode_utils::Modules;
+use move_command_line_common::{
+    files::verify_and_create_named_address_mapping, testing::read_env_update_baseline,
+};
 use move_compiler::{
     shared::{Flags, NumericalAddress, PackagePaths},
-    unit_test::{ExpectedFailure, ModuleTestPlan, TestCase, TestPlan},
+    unit_test::{
+        ExpectedCode, ExpectedFailure, ExpectedMoveError, ModuleTestPlan, TestCase, TestPlan,
+    },
 };
 use move_core_types::{
     account_address::AccountAddress,
-    effects::ChangeSet,
+    effects::{ChangeSet, Event},
+    errmap::ErrorMapping,
     gas_schedule::{CostTable, GasAlgebra, GasCost, GasUnits},
     identifier::IdentStr,
+    language_storage::TypeTag,
+    parser::parse_type_tag,
     value::serialize_values,
     vm_status::StatusCode,
 };
@@ -36,7 +52,9 @@ use move_vm_runtime::{move_vm::MoveVM, native_functions::NativeFunctionTable};
 use move_vm_test_utils::InMemoryStorage;
 use move_vm_types::gas_schedule::{zero_cost_schedule, GasStatus};
 use rayon::prelude::*;
-use std::{collections::BTreeMap, io::Write, marker::Send, sync::Mutex, time::Instant};
+use std::{
+    collections::BTreeMap, fs, io::Write, marker::Send, path::Path, sync::Mutex, time::Instant,
+};
 
 use move_vm_runtime::native_extensions::NativeContextExtensions;
 #[cfg(feature = "evm-backend")]
@@ -52,7 +70,7 @@ use {
 /// Test state common to all tests
 pub struct SharedTestingConfig {
     save_storage_state_on_failure: bool,
-    report_stacktrace_on_abort: bool,
+    error_map: ErrorMapping,
     execution_bound: u64,
     cost_table: CostTable,
     native_function_table: NativeFunctionTable,
@@ -60,7 +78,11 @@ pub struct SharedTestingConfig {
     source_files: Vec<String>,
     named_address_values: BTreeMap<String, NumericalAddress>,
     check_stackless_vm: bool,
+    check_specs: bool,
     verbose: bool,
+    report_format: ReportFormat,
+    extensions: UnitTestExtensions,
+    snapshots: Option<Snapshots>,
 
     #[cfg(feature = "evm-backend")]
     evm: bool,
@@ -104,11 +126,50 @@ fn setup_test_storage<'a>(
     Ok(storage)
 }
 
+/// The directory of the resources of an account in the storage directory of the Move sandbox.
+const RESOURCES_DIR: &str = "resources";
+
+/// Load the resources in `state_dir`, which has the layout of the storage directory of the Move
+/// sandbox (`<address>/resources/<struct tag>.bcs`), into `storage`.
+fn load_test_state(storage: &mut InMemoryStorage, state_dir: &Path) -> Result<()> {
+    let entries = fs::read_dir(state_dir)
+        .with_context(|| format!("reading state directory `{}`", state_dir.display()))?;
+    for entry in entries {
+        let account_dir = entry?.path();
+        let resources_dir = account_dir.join(RESOURCES_DIR);
+        if !resources_dir.is_dir() {
+            continue;
+        }
+        let addr = account_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| AccountAddress::from_hex_literal(name).ok())
+            .with_context(|| format!("invalid account directory `{}`", account_dir.display()))?;
+        for entry in fs::read_dir(&resources_dir)? {
+            let resource_path = entry?.path();
+            if resource_path.extension().and_then(|ext| ext.to_str()) != Some("bcs") {
+                continue;
+            }
+            let type_tag = resource_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| parse_type_tag(stem).ok());
+            let struct_tag = match type_tag {
+                Some(TypeTag::Struct(struct_tag)) => struct_tag,
+                _ => bail!("invalid resource file `{}`", resource_path.display()),
+            };
+            storage.publish_or_overwrite_resource(addr, struct_tag, fs::read(&resource_path)?);
+        }
+    }
+    Ok(())
+}
+
 /// Print the updates to storage represented by `cs` in the context of the starting storage state
 /// `storage`.
 fn print_resources_and_extensions(
     cs: &ChangeSet,
     extensions: NativeContextExtensions,
+    registered_extensions: &UnitTestExtensions,
     storage: &InMemoryStorage,
 ) -> Result<String> {
     use std::fmt::Write;
@@ -127,23 +188,120 @@ fn print_resources_and_extensions(
             }
         }
     }
-    extensions::print_change_sets(&mut buf, extensions);
+    extensions::print_change_sets(&mut buf, extensions, registered_extensions);
 
     Ok(buf)
 }
 
+// Whether a test with the expected failure `expected_failure` is expected to run out of gas
+fn expects_out_of_gas(expected_failure: Option<&ExpectedFailure>) -> bool {
+    matches!(
+        expected_failure,
+        Some(ExpectedFailure::ExpectedWithError(expected))
+            if expected.major_status == StatusCode::OUT_OF_GAS
+    )
+}
+
+// The abort code of `err`, if it is an abort
+fn abort_code(err: &VMError) -> Option<u64> {
+    match err.major_status() {
+        StatusCode::ABORTED => err.sub_status(),
+        _ => None,
+    }
+}
+
+// Describes how `err` differs from the `expected` error, or returns `None` if it matches
+fn expected_error_mismatch(expected: &ExpectedMoveError, err: &VMError) -> Option<String> {
+    if let Some(mismatch) = abort_mismatch(expected, err.major_status(), abort_code(err)) {
+        return Some(mismatch);
+    }
+    let expected_location = expected.location.as_ref()?;
+    let location = match err.location() {
+        Location::Module(module_id) if module_id == expected_location => return None,
+        Location::Module(module_id) => format_module_id(module_id),
+        Location::Script => "a script".to_string(),
+        Location::Undefined => "an unknown location".to_string(),
+    };
+    let (expected_verb, verb) = if expected.major_status == StatusCode::ABORTED {
+        ("abort", "aborted")
+    } else {
+        ("fail", "failed")
+    };
+    Some(format!(
+        "Expected test to {} in {} but it {} in {}",
+        expected_verb,
+        format_module_id(expected_location),
+        verb,
+        location,
+    ))
+}
+
+// Describes how an error with `major_status` and `abort_code` differs from the `expected` error,
+// without considering its location
+fn abort_mismatch(
+    expected: &ExpectedMoveError,
+    major_status: StatusCode,
+    abort_code: Option<u64>,
+) -> Option<String> {
+    let code_matches =
+        |expected_code: &Option<ExpectedCode>, part: fn(u64) -> u64| match expected_code {
+            Some(code) => abort_code.map(part) == Some(code.value),
+            None => true,
+        };
+    if major_status == expected.major_status
+        && code_matches(&expected.abort_code, |code| code)
+        && code_matches(&expected.category, |code| code & 0xFF)
+        && code_matches(&expected.reason, |code| code >> 8)
+    {
+        return None;
+    }
+
+    let expectation = if expected.major_status != StatusCode::ABORTED {
+        format!("fail with {:?}", expected.major_status)
+    } else if let Some(code) = &expected.abort_code {
+        format!("abort with {}", code)
+    } else {
+        let parts: Vec<_> = vec![
+            expected
+                .category
+                .as_ref()
+                .map(|category| format!("category {}", category)),
+            expected
+                .reason
+                .as_ref()
+                .map(|reason| format!("reason {}", reason)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if parts.is_empty() {
+            "abort".to_string()
+        } else {
+            format!("abort with {}", parts.join(" and "))
+        }
+    };
+    let actual = match abort_code {
+        Some(code) if expected.category.is_some() || expected.reason.is_some() => format!(
+            "aborted with {} (category {}, reason {})",
+            code,
+            code & 0xFF,
+            code >> 8
+        ),
+        Some(code) => format!("aborted with {}", code),
+        None => format!("failed with {:?}", major_status),
+    };
+    Some(format!(
+        "Expected test to {} but it {}",
+        expectation, actual
+    ))
+}
+
 impl TestRunner {
+    /// Creates a runner for the tests of `tests` with the options of `config`.
     pub fn new(
-        execution_bound: u64,
-        num_threads: usize,
-        check_stackless_vm: bool,
-        verbose: bool,
-        save_storage_state_on_failure: bool,
-        report_stacktrace_on_abort: bool,
+        config: &UnitTestingConfig,
         tests: TestPlan,
         native_function_table: Option<NativeFunctionTable>,
-        named_address_values: BTreeMap<String, NumericalAddress>,
-        #[cfg(feature = "evm-backend")] evm: bool,
     ) -> Result<Self> {
         let source_files = tests
             .files
@@ -151,27 +309,63 @@ impl TestRunner {
             .map(|(filepath, _)| filepath.to_string())
             .collect();
         let modules = tests.module_info.values().map(|info| &info.module);
-        let starting_storage_state = setup_test_storage(modules)?;
-        let native_function_table = native_function_table.unwrap_or_else(|| {
+        let mut starting_storage_state = setup_test_storage(modules)?;
+        if let Some(state_dir) = &config.state_dir {
+            // The other backends start from empty storage.
+            #[cfg(feature = "evm-backend")]
+            if config.evm {
+                bail!("Loading the initial state of tests is not supported with the EVM backend");
+            }
+            if config.check_stackless_vm || config.check_specs {
+                bail!("Loading the initial state of tests is not supported with the stackless VM");
+            }
+            load_test_state(&mut starting_storage_state, state_dir)?;
+        }
+        #[cfg(feature = "evm-backend")]
+        if config.evm && config.snapshot_dir.is_some() {
+            bail!("Snapshot testing is not supported with the EVM backend");
+        }
+        let mut native_function_table = native_function_table.unwrap_or_else(|| {
             move_stdlib::natives::all_natives(AccountAddress::from_hex_literal("0x1").unwrap())
         });
+        native_function_table.extend(config.extensions.natives().iter().cloned());
         let num_of_native_funcs = native_function_table.len();
+        let mut error_descriptions: ErrorMapping =
+            bcs::from_bytes(move_stdlib::error_descriptions())?;
+        if let Some(error_map) = &config.error_map {
+            let bytes = fs::read(error_map)
+                .with_context(|| format!("reading error map `{}`", error_map.display()))?;
+            error_descriptions.extend(
+                bcs::from_bytes(&bytes)
+                    .with_context(|| format!("invalid error map `{}`", error_map.display()))?,
+            );
+        }
+        let snapshots = config
+            .snapshot_dir
+            .clone()
+            .map(|dir| Snapshots::new(dir, config.update_snapshots || read_env_update_baseline()));
         Ok(Self {
             testing_config: SharedTestingConfig {
-                save_storage_state_on_failure,
-                report_stacktrace_on_abort,
+                save_storage_state_on_failure: config.report_storage_on_error,
+                error_map: error_descriptions,
                 starting_storage_state,
-                execution_bound,
+                execution_bound: config.instruction_execution_bound,
                 native_function_table,
                 cost_table: unit_cost_table(num_of_native_funcs),
                 source_files,
-                check_stackless_vm,
-                verbose,
-                named_address_values,
+                check_stackless_vm: config.check_stackless_vm,
+                check_specs: config.check_specs,
+                verbose: config.verbose,
+                report_format: config.report_format,
+                extensions: config.extensions.clone(),
+                snapshots,
+                named_address_values: verify_and_create_named_address_mapping(
+                    config.named_address_values.clone(),
+                )?,
                 #[cfg(feature = "evm-backend")]
-                evm,
+                evm: config.evm,
             },
-            num_threads,
+            num_threads: config.num_threads,
             tests,
         })
     }
@@ -186,13 +380,25 @@ impl TestRunner {
                     .tests
                     .module_tests
                     .par_iter()
-                    .map(|(_, test_plan)| self.testing_config.exec_module_tests(test_plan, writer))
+                    .map(|(_, test_plan)| {
+                        self.testing_config
+                            .exec_module_tests(test_plan, &self.tests, writer)
+                    })
                     .reduce(TestStatistics::new, |acc, stats| acc.combine(stats));
 
                 Ok(TestResults::new(final_statistics, self.tests))
             })
     }
 
+    /// The number of tests which will be run.
+    pub fn num_tests(&self) -> usize {
+        self.tests
+            .module_tests
+            .values()
+            .map(|module_test| module_test.tests.len())
+            .sum()
+    }
+
     pub fn filter(&mut self, test_name_slice: &str) {
         for (module_id, module_test) in self.tests.module_tests.iter_mut() {
             if module_id.name().as_str().contains(test_name_slice) {
@@ -211,41 +417,70 @@ impl TestRunner {
 // TODO: do not expose this to backend implementations
 struct TestOutput<'a, 'b, W> {
     test_plan: &'a ModuleTestPlan,
+    tests: &'a TestPlan,
+    format: ReportFormat,
     writer: &'b Mutex<W>,
 }
 
 impl<'a, 'b, W: Write> TestOutput<'a, 'b, W> {
-    fn pass(&self, fn_name: &str) {
-        writeln!(
-            self.writer.lock().unwrap(),
-            "[ {}    ] {}::{}",
-            "PASS".bold().bright_green(),
-            format_module_id(&self.test_plan.module_id),
-            fn_name
-        )
-        .unwrap()
+    fn start(&self, fn_name: &str) {
+        if self.format == ReportFormat::Json {
+            writeln!(
+                self.writer.lock().unwrap(),
+                "{}",
+                test_reporter::test_started_event(&self.test_plan.module_id, fn_name)
+            )
+            .unwrap()
+        }
     }
 
-    fn fail(&self, fn_name: &str) {
-        writeln!(
-            self.writer.lock().unwrap(),
-            "[ {}    ] {}::{}",
-            "FAIL".bold().bright_red(),
-            format_module_id(&self.test_plan.module_id),
-            fn_name,
-        )
-        .unwrap()
+    fn pass(&self, stats: &mut TestStatistics, test_run_info: TestRunInfo) {
+        match self.format {
+            ReportFormat::Human => writeln!(
+                self.writer.lock().unwrap(),
+                "[ {}    ] {}::{}",
+                "PASS".bold().bright_green(),
+                format_module_id(&self.test_plan.module_id),
+                test_run_info.function_ident
+            )
+            .unwrap(),
+            ReportFormat::Json => writeln!(
+                self.writer.lock().unwrap(),
+                "{}",
+                test_run_info.passed_event(&self.test_plan.module_id)
+            )
+            .unwrap(),
+            ReportFormat::Junit => (),
+        }
+        stats.test_success(test_run_info, self.test_plan);
     }
 
-    fn timeout(&self, fn_name: &str) {
-        writeln!(
-            self.writer.lock().unwrap(),
-            "[ {} ] {}::{}",
-            "TIMEOUT".bold().bright_yellow(),
-            format_module_id(&self.test_plan.module_id),
-            fn_name,
-        )
-        .unwrap();
+    fn fail(&self, stats: &mut TestStatistics, test_failure: TestFailure) {
+        match self.format {
+            ReportFormat::Human => {
+                let status = if let FailureReason::Timeout(_) = test_failure.failure_reason {
+                    format!("{} ", "TIMEOUT".bold().bright_yellow())
+                } else {
+                    format!("{}    ", "FAIL".bold().bright_red())
+                };
+                writeln!(
+                    self.writer.lock().unwrap(),
+                    "[ {}] {}::{}",
+                    status,
+                    format_module_id(&self.test_plan.module_id),
+                    test_failure.test_run_info.function_ident,
+                )
+                .unwrap()
+            }
+            ReportFormat::Json => writeln!(
+                self.writer.lock().unwrap(),
+                "{}",
+                test_failure.failed_event(&self.test_plan.module_id, self.tests)
+            )
+            .unwrap(),
+            ReportFormat::Junit => (),
+        }
+        stats.test_failure(test_failure, self.test_plan);
     }
 }
 
@@ -258,11 +493,12 @@ impl SharedTestingConfig {
     ) -> (
         VMResult<ChangeSet>,
         VMResult<NativeContextExtensions>,
+        Vec<Event>,
         VMResult<Vec<Vec<u8>>>,
         TestRunInfo,
     ) {
         let move_vm = MoveVM::new(self.native_function_table.clone()).unwrap();
-        let extensions = extensions::new_extensions();
+        let extensions = extensions::new_extensions(&self.extensions);
         let mut session =
             move_vm.new_session_with_extensions(&self.starting_storage_state, extensions);
         let mut gas_meter = GasStatus::new(&self.cost_table, GasUnits::new(self.execution_bound));
@@ -276,25 +512,28 @@ impl SharedTestingConfig {
             serialize_values(test_info.arguments.iter()),
             &mut gas_meter,
         );
-        let mut return_result = serialized_return_values_result.map(|res| {
+        let return_result = serialized_return_values_result.map(|res| {
             res.return_values
                 .into_iter()
                 .map(|(bytes, _layout)| bytes)
                 .collect()
         });
-        if !self.report_stacktrace_on_abort {
-            if let Err(err) = &mut return_result {
-                err.remove_exec_state();
-            }
-        }
         let test_run_info = TestRunInfo::new(
             function_name.to_string(),
             now.elapsed(),
             self.execution_bound - gas_meter.remaining_gas().get(),
         );
         match session.finish_with_extensions() {
-            Ok((cs, _, extensions)) => (Ok(cs), Ok(extensions), return_result, test_run_info),
-            Err(err) => (Err(err.clone()), Err(err), return_result, test_run_info),
+            Ok((cs, events, extensions)) => {
+                (Ok(cs), Ok(extensions), events, return_result, test_run_info)
+            }
+            Err(err) => (
+                Err(err.clone()),
+                Err(err),
+                vec![],
+                return_result,
+                test_run_info,
+            ),
         }
     }
 
@@ -362,7 +601,8 @@ impl SharedTestingConfig {
             .cloned()
             .collect::<Vec<_>>();
 
-        let stackless_model = if self.check_stackless_vm {
+        let run_stackless_vm = self.check_stackless_vm || self.check_specs;
+        let stackless_model = if run_stackless_vm {
             let model = run_model_builder_with_options_and_compilation_flags(
                 vec![PackagePaths {
                     name: None,
@@ -387,9 +627,10 @@ impl SharedTestingConfig {
         let mut stats = TestStatistics::new();
 
         for (function_name, test_info) in &test_plan.tests {
-            let (cs_result, ext_result, exec_result, test_run_info) =
+            output.start(function_name);
+            let (cs_result, ext_result, events, exec_result, test_run_info) =
                 self.execute_via_move_vm(test_plan, function_name, test_info);
-            if self.check_stackless_vm {
+            if run_stackless_vm {
                 let (stackless_vm_change_set, stackless_vm_result, _, prop_check_result) = self
                     .execute_via_stackless_vm(
                         stackless_model.as_ref().unwrap(),
@@ -400,11 +641,20 @@ impl SharedTestingConfig {
                 let move_vm_result = adapt_move_vm_result(exec_result.clone());
                 let move_vm_change_set =
                     adapt_move_vm_change_set(cs_result.clone(), &self.starting_storage_state);
-                if stackless_vm_result != move_vm_result
-                    || stackless_vm_change_set != move_vm_change_set
+                // A divergence of the interpreter also means that the specs were not checked along
+                // the execution of the Move VM, so it fails the test when only checking specs too.
+                // The interpreter does not meter gas though, so a Move VM running out of gas is
+                // left to be reported as a timeout in that case.
+                let move_vm_out_of_gas = matches!(
+                    &exec_result,
+                    Err(err) if err.major_status() == StatusCode::OUT_OF_GAS
+                );
+                if (self.check_stackless_vm || !move_vm_out_of_gas)
+                    && (stackless_vm_result != move_vm_result
+                        || stackless_vm_change_set != move_vm_change_set)
                 {
-                    output.fail(function_name);
-                    stats.test_failure(
+                    output.fail(
+                        &mut stats,
                         TestFailure::new(
                             FailureReason::mismatch(
                                 move_vm_result,
@@ -416,20 +666,18 @@ impl SharedTestingConfig {
                             None,
                             None,
                         ),
-                        test_plan,
                     );
                     continue;
                 }
                 if let Some(prop_failure) = prop_check_result {
-                    output.fail(function_name);
-                    stats.test_failure(
+                    output.fail(
+                        &mut stats,
                         TestFailure::new(
                             FailureReason::property(prop_failure),
                             test_run_info,
                             None,
                             None,
                         ),
-                        test_plan,
                     );
                     continue;
                 }
@@ -437,11 +685,12 @@ impl SharedTestingConfig {
 
             let save_session_state = || {
                 if self.save_storage_state_on_failure {
-                    cs_result.ok().and_then(|changeset| {
+                    cs_result.as_ref().ok().and_then(|changeset| {
                         ext_result.ok().and_then(|extensions| {
                             print_resources_and_extensions(
-                                &changeset,
+                                changeset,
                                 extensions,
+                                &self.extensions,
                                 &self.starting_storage_state,
                             )
                             .ok()
@@ -453,97 +702,157 @@ impl SharedTestingConfig {
             };
             match exec_result {
                 Err(err) => match (test_info.expected_failure.as_ref(), err.sub_status()) {
-                    // Ran out of ticks, report a test timeout and log a test failure
-                    _ if err.major_status() == StatusCode::OUT_OF_GAS => {
-                        output.timeout(function_name);
-                        stats.test_failure(
+                    // Ran out of ticks, report a test timeout and log a test failure, unless the
+                    // test is expected to run out of gas
+                    _ if err.major_status() == StatusCode::OUT_OF_GAS
+                        && !expects_out_of_gas(test_info.expected_failure.as_ref()) =>
+                    {
+                        output.fail(
+                            &mut stats,
                             TestFailure::new(
                                 FailureReason::timeout(),
                                 test_run_info,
                                 Some(err),
                                 save_session_state(),
                             ),
-                            test_plan,
                         )
                     }
-                    // Expected the test to not abort, but it aborted with `code`
-                    (None, Some(code)) => {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            TestFailure::new(
-                                FailureReason::aborted(code),
-                                test_run_info,
-                                Some(err),
-                                save_session_state(),
+                    // Expected the test to fail with the error described by `expected`, check
+                    // the parts of the error given in the attribute
+                    (Some(ExpectedFailure::ExpectedWithError(expected)), _) => {
+                        match expected_error_mismatch(expected, &err) {
+                            None => output.pass(&mut stats, test_run_info),
+                            Some(mismatch) => output.fail(
+                                &mut stats,
+                                TestFailure::new(
+                                    FailureReason::wrong_error(mismatch, abort_code(&err)),
+                                    test_run_info,
+                                    Some(err),
+                                    save_session_state(),
+                                )
+                                .with_abort_reason(&self.error_map),
                             ),
-                            test_plan,
-                        )
+                        }
                     }
+                    // Expected the test to not abort, but it aborted with `code`
+                    (None, Some(code)) => output.fail(
+                        &mut stats,
+                        TestFailure::new(
+                            FailureReason::aborted(code),
+                            test_run_info,
+                            Some(err),
+                            save_session_state(),
+                        )
+                        .with_abort_reason(&self.error_map),
+                    ),
                     // Expected the test the abort with a specific `code`, and it did abort with
                     // that abort code
                     (Some(ExpectedFailure::ExpectedWithCode(code)), Some(other_code))
                         if err.major_status() == StatusCode::ABORTED && *code == other_code =>
                     {
-                        output.pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
+                        output.pass(&mut stats, test_run_info);
                     }
                     // Expected the test to abort with a specific `code` but it aborted with a
                     // different `other_code`
-                    (Some(ExpectedFailure::ExpectedWithCode(code)), Some(other_code)) => {
-                        output.fail(function_name);
-                        stats.test_failure(
+                    (Some(ExpectedFailure::ExpectedWithCode(code)), Some(other_code)) => output
+                        .fail(
+                            &mut stats,
                             TestFailure::new(
                                 FailureReason::wrong_abort(*code, other_code),
                                 test_run_info,
                                 Some(err),
                                 save_session_state(),
+                            )
+                            .with_abort_reason(&self.error_map),
+                        ),
+                    // Expected the test to abort with a specific `code` but it failed with an
+                    // error other than an abort
+                    (Some(ExpectedFailure::ExpectedWithCode(code)), None)
+                        if err.major_status() != StatusCode::EXECUTED =>
+                    {
+                        let mismatch = format!(
+                            "Expected test to abort with {} but it failed with {:?}",
+                            code,
+                            err.major_status()
+                        );
+                        output.fail(
+                            &mut stats,
+                            TestFailure::new(
+                                FailureReason::wrong_error(mismatch, None),
+                                test_run_info,
+                                Some(err),
+                                save_session_state(),
                             ),
-                            test_plan,
                         )
                     }
                     // Expected the test to abort and it aborted, but we don't need to check the code
                     (Some(ExpectedFailure::Expected), Some(_)) => {
-                        output.pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
+                        output.pass(&mut stats, test_run_info);
                     }
                     // Expected the test to abort and it aborted with internal error
                     (Some(ExpectedFailure::Expected), None)
                         if err.major_status() != StatusCode::EXECUTED =>
                     {
-                        output.pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
+                        output.pass(&mut stats, test_run_info);
                     }
                     // Unexpected return status from the VM, signal that we hit an unknown error.
-                    (_, None) => {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            TestFailure::new(
-                                FailureReason::unknown(),
-                                test_run_info,
-                                Some(err),
-                                save_session_state(),
-                            ),
-                            test_plan,
-                        )
-                    }
+                    (_, None) => output.fail(
+                        &mut stats,
+                        TestFailure::new(
+                            FailureReason::unknown(),
+                            test_run_info,
+                            Some(err),
+                            save_session_state(),
+                        ),
+                    ),
                 },
                 Ok(_) => {
                     // Expected the test to fail, but it executed
                     if test_info.expected_failure.is_some() {
-                        output.fail(function_name);
-                        stats.test_failure(
+                        output.fail(
+                            &mut stats,
                             TestFailure::new(
                                 FailureReason::no_abort(),
                                 test_run_info,
                                 None,
                                 save_session_state(),
                             ),
-                            test_plan,
                         )
+                    } else if let Some(snapshots) = &self.snapshots {
+                        // Expected the test to execute fully and it did, check its effects
+                        // against its snapshot
+                        let path = snapshots.path(&test_plan.module_id, function_name);
+                        let checked = cs_result
+                            .as_ref()
+                            .map_err(|err| anyhow!("{}", err))
+                            .and_then(|cs| {
+                                snapshot::render_snapshot(cs, &events, &self.starting_storage_state)
+                            })
+                            .and_then(|actual| snapshots.check(&path, &actual));
+                        match checked {
+                            Ok(None) => output.pass(&mut stats, test_run_info),
+                            Ok(Some(diff)) => output.fail(
+                                &mut stats,
+                                TestFailure::new(
+                                    FailureReason::snapshot_mismatch(&path, diff),
+                                    test_run_info,
+                                    None,
+                                    None,
+                                ),
+                            ),
+                            Err(err) => output.fail(
+                                &mut stats,
+                                TestFailure::new(
+                                    FailureReason::snapshot_error(&path, err),
+                                    test_run_info,
+                                    None,
+                                    None,
+                                ),
+                            ),
+                        }
                     } else {
                         // Expected the test to execute fully and it did
-                        output.pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
+                        output.pass(&mut stats, test_run_info);
                     }
                 }
             }
@@ -616,6 +925,7 @@ impl SharedTestingConfig {
 
         let gen_options = move_to_yul::options::Options::default();
         for (function_name, test_info) in &test_plan.tests {
+            output.start(function_name);
             let yul_code = match move_to_yul::generator::Generator::run_for_unit_test(
                 &gen_options,
                 &model,
@@ -627,15 +937,14 @@ impl SharedTestingConfig {
                 Err(diagnostics) => {
                     // Failed to generate yul code due to some user errors.
                     // Mark test as failed.
-                    output.fail(function_name);
-                    stats.test_failure(
+                    output.fail(
+                        &mut stats,
                         TestFailure::new(
                             FailureReason::move_to_evm_error(diagnostics),
                             TestRunInfo::new(function_name.to_string(), Duration::ZERO, 0),
                             None,
                             None,
                         ),
-                        test_plan,
                     );
                     return stats;
                 }
@@ -658,29 +967,30 @@ impl SharedTestingConfig {
 
             match (test_info.expected_failure.as_ref(), &res.exit_reason) {
                 // Test expected to succeed or abort with a specific abort code, but ran into an internal error.
-                (None | Some(ExpectedFailure::ExpectedWithCode(_)), ExitReason::Revert(_))
-                    if abort_code() == u64::MAX =>
-                {
-                    output.fail(function_name);
-                    stats.test_failure(
+                (
+                    None
+                    | Some(
+                        ExpectedFailure::ExpectedWithCode(_)
+                        | ExpectedFailure::ExpectedWithError(_),
+                    ),
+                    ExitReason::Revert(_),
+                ) if abort_code() == u64::MAX => {
+                    output.fail(
+                        &mut stats,
                         TestFailure::new(FailureReason::unknown(), test_run_info(), None, None),
-                        test_plan,
                     );
                 }
 
                 // Test expected to succeed, but aborted.
-                (None, ExitReason::Revert(_)) => {
-                    output.fail(function_name);
-                    stats.test_failure(
-                        TestFailure::new(
-                            FailureReason::aborted(abort_code()),
-                            test_run_info(),
-                            None,
-                            None,
-                        ),
-                        test_plan,
-                    )
-                }
+                (None, ExitReason::Revert(_)) => output.fail(
+                    &mut stats,
+                    TestFailure::new(
+                        FailureReason::aborted(abort_code()),
+                        test_run_info(),
+                        None,
+                        None,
+                    ),
+                ),
 
                 // Expect the test to abort with a specific code.
                 (
@@ -689,39 +999,48 @@ impl SharedTestingConfig {
                 ) => {
                     let abort_code = abort_code();
                     if abort_code == *exp_abort_code {
-                        output.pass(function_name);
-                        stats.test_success(test_run_info(), test_plan);
+                        output.pass(&mut stats, test_run_info());
                     } else {
-                        output.fail(function_name);
-                        stats.test_failure(
+                        output.fail(
+                            &mut stats,
                             TestFailure::new(
                                 FailureReason::wrong_abort(*exp_abort_code, abort_code),
                                 test_run_info(),
                                 None,
                                 None,
                             ),
-                            test_plan,
                         );
                     }
                 }
 
-                // Test expected to abort but succeeded.
-                (
-                    Some(ExpectedFailure::Expected | ExpectedFailure::ExpectedWithCode(_)),
-                    ExitReason::Succeed(_),
-                ) => {
-                    output.fail(function_name);
-                    stats.test_failure(
-                        TestFailure::new(FailureReason::no_abort(), test_run_info(), None, None),
-                        test_plan,
-                    )
+                // Expect the test to abort with the error described by `expected`. Locations are
+                // not known here, so they are not checked.
+                (Some(ExpectedFailure::ExpectedWithError(expected)), ExitReason::Revert(_)) => {
+                    let abort_code = abort_code();
+                    match abort_mismatch(expected, StatusCode::ABORTED, Some(abort_code)) {
+                        None => output.pass(&mut stats, test_run_info()),
+                        Some(mismatch) => output.fail(
+                            &mut stats,
+                            TestFailure::new(
+                                FailureReason::wrong_error(mismatch, Some(abort_code)),
+                                test_run_info(),
+                                None,
+                                None,
+                            ),
+                        ),
+                    }
                 }
 
+                // Test expected to abort but succeeded.
+                (Some(_), ExitReason::Succeed(_)) => output.fail(
+                    &mut stats,
+                    TestFailure::new(FailureReason::no_abort(), test_run_info(), None, None),
+                ),
+
                 // Test succeeded or failed as expected.
                 (None, ExitReason::Succeed(_))
                 | (Some(ExpectedFailure::Expected), ExitReason::Revert(_)) => {
-                    output.pass(function_name);
-                    stats.test_success(test_run_info(), test_plan);
+                    output.pass(&mut stats, test_run_info());
                 }
 
                 (exp, reason) => {
@@ -738,9 +1057,15 @@ impl SharedTestingConfig {
     fn exec_module_tests(
         &self,
         test_plan: &ModuleTestPlan,
+        tests: &TestPlan,
         writer: &Mutex<impl Write>,
     ) -> TestStatistics {
-        let output = TestOutput { test_plan, writer };
+        let output = TestOutput {
+            test_plan,
+            tests,
+            format: self.report_format,
+            writer,
+        };
 
         #[cfg(feature = "evm-backend")]
         if self.evm {
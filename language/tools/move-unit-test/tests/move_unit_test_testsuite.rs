@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use move_command_line_common::testing::{format_diff, read_env_update_baseline, EXP_EXT};
-use move_unit_test::{self, UnitTestingConfig};
+use move_unit_test::{self, test_reporter::ReportFormat, UnitTestingConfig};
 use regex::RegexBuilder;
 use std::{
     fs,
@@ -10,9 +10,11 @@ use std::{
 };
 
 // We don't support statistics tests as that includes times which are variable and will make these
-// tests flaky.
+// tests flaky. The times in JSON and JUnit reports are masked instead.
 const TEST_MODIFIER_STRS: &[&str] = &[
     "storage",
+    "json",
+    "junit",
     #[cfg(feature = "evm-backend")]
     "evm",
 ];
@@ -21,6 +23,8 @@ pub fn modify(mut base_config: UnitTestingConfig, modifier_str: &str) -> Option<
     // Add future test modifiers here
     match modifier_str {
         "storage" => base_config.report_storage_on_error = true,
+        "json" => base_config.report_format = ReportFormat::Json,
+        "junit" => base_config.report_format = ReportFormat::Junit,
         #[cfg(feature = "evm-backend")]
         "evm" => base_config.evm = true,
         _ => return None,
@@ -89,14 +93,19 @@ fn run_test_impl(path: &Path) -> anyhow::Result<()> {
         ..UnitTestingConfig::default_with_bound(None)
     };
 
-    let regex = RegexBuilder::new(r"(┌─ ).+/([^/]+)$")
+    let regex = RegexBuilder::new(r"(┌─ |--> ).+/([^/]+)$")
         .multi_line(true)
         .build()
         .unwrap();
 
+    let time_regex = RegexBuilder::new(r#"("exec_time":| time=")[0-9.]+"#)
+        .build()
+        .unwrap();
+
     for ((buffer, _), exp_path) in run_test_with_modifiers(unit_test_config, path)? {
         let base_output = String::from_utf8(buffer)?;
         let cleaned_output = regex.replacen(&base_output, 0, r"$1$2");
+        let cleaned_output = time_regex.replacen(&cleaned_output, 0, r"${1}0");
         if update_baseline {
             fs::write(&exp_path, &*cleaned_output)?
         }
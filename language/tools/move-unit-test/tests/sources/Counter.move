@@ -0,0 +1,21 @@
+module 0x1::Counter {
+    native fun bump(): u64;
+
+    #[test]
+    fun counts_per_test() {
+        assert!(bump() == 1, 0);
+        assert!(bump() == 2, 1);
+    }
+
+    #[test]
+    fun starts_from_zero() {
+        assert!(bump() == 1, 0);
+    }
+
+    #[test]
+    fun reports_changes() {
+        bump();
+        bump();
+        abort 42
+    }
+}
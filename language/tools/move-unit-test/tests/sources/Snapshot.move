@@ -0,0 +1,11 @@
+module 0x1::Snapshot {
+    struct R has key { value: u64 }
+
+    #[test(account = @0x1)]
+    fun publishes(account: signer) {
+        move_to(&account, R { value: 1 })
+    }
+
+    #[test]
+    fun no_effects() {}
+}
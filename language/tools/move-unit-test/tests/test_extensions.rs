@@ -0,0 +1,74 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use better_any::{Tid, TidAble};
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, InternalGasUnits},
+    identifier::Identifier,
+};
+use move_unit_test::{self, UnitTestingConfig};
+use move_vm_runtime::native_functions::{NativeContext, NativeFunctionTable};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, values::Value,
+};
+use smallvec::smallvec;
+use std::{collections::VecDeque, path::PathBuf};
+
+#[derive(Tid)]
+struct Counter {
+    count: u64,
+}
+
+fn native_bump(
+    context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    _args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    let counter = context.extensions_mut().get_mut::<Counter>();
+    counter.count += 1;
+    Ok(NativeResult::ok(
+        InternalGasUnits::new(1),
+        smallvec![Value::u64(counter.count)],
+    ))
+}
+
+fn counter_natives() -> NativeFunctionTable {
+    vec![(
+        AccountAddress::from_hex_literal("0x1").unwrap(),
+        Identifier::new("Counter").unwrap(),
+        Identifier::new("bump").unwrap(),
+        native_bump,
+    )]
+}
+
+// Natives and their extensions can be registered without enabling any cargo feature, and every
+// test gets a fresh extension.
+#[test]
+fn test_registered_extensions() {
+    std::env::set_var("NO_COLOR", "1");
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sources/Counter.move");
+    let mut testing_config = UnitTestingConfig::default_with_bound(None)
+        .with_named_addresses(move_stdlib::move_stdlib_named_addresses())
+        .with_natives(counter_natives())
+        .with_extension(|extensions| extensions.add(Counter { count: 0 }))
+        .with_change_set_printer(|w, extensions| {
+            writeln!(w, "counter: {}", extensions.remove::<Counter>().count).unwrap()
+        });
+    testing_config.source_files = vec![path.to_string_lossy().to_string()];
+    testing_config.dep_files = move_stdlib::move_stdlib_files();
+    testing_config.report_storage_on_error = true;
+
+    let test_plan = testing_config.build_test_plan().unwrap();
+    let (output, all_passed) = testing_config
+        .run_and_report_unit_tests(test_plan, None, vec![])
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(!all_passed);
+    assert!(output.contains("[ PASS    ] 0x1::Counter::counts_per_test"));
+    assert!(output.contains("[ PASS    ] 0x1::Counter::starts_from_zero"));
+    assert!(output.contains("[ FAIL    ] 0x1::Counter::reports_changes"));
+    assert!(output.contains("counter: 2"));
+}
@@ -0,0 +1,52 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_command_line_common::testing::{UB, UPBL, UPDATE_BASELINE};
+use move_unit_test::{self, UnitTestingConfig};
+use std::{fs, path::PathBuf};
+
+fn run_tests(testing_config: &UnitTestingConfig) -> (String, bool) {
+    let test_plan = testing_config.build_test_plan().unwrap();
+    let (output, all_passed) = testing_config
+        .run_and_report_unit_tests(test_plan, None, vec![])
+        .unwrap();
+    (String::from_utf8(output).unwrap(), all_passed)
+}
+
+// Snapshots are created when updating them, checked otherwise, and an outdated snapshot fails its
+// test.
+#[test]
+fn test_snapshots() {
+    std::env::set_var("NO_COLOR", "1");
+    for var in &[UPDATE_BASELINE, UPBL, UB] {
+        std::env::remove_var(var);
+    }
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sources/Snapshot.move");
+    let snapshot_dir =
+        std::env::temp_dir().join(format!("move-unit-test-snapshots-{}", std::process::id()));
+    let mut testing_config = UnitTestingConfig::default_with_bound(None)
+        .with_named_addresses(move_stdlib::move_stdlib_named_addresses());
+    testing_config.source_files = vec![path.to_string_lossy().to_string()];
+    testing_config.dep_files = move_stdlib::move_stdlib_files();
+    testing_config.snapshot_dir = Some(snapshot_dir.clone());
+
+    testing_config.update_snapshots = true;
+    let (_, all_passed) = run_tests(&testing_config);
+    assert!(all_passed);
+    let snapshot_path = snapshot_dir.join("0x1/Snapshot/publishes.snap");
+    let snapshot = fs::read_to_string(&snapshot_path).unwrap();
+    assert!(snapshot.contains("value: 1"));
+
+    testing_config.update_snapshots = false;
+    let (_, all_passed) = run_tests(&testing_config);
+    assert!(all_passed);
+
+    fs::write(&snapshot_path, snapshot.replace("value: 1", "value: 2")).unwrap();
+    let (output, all_passed) = run_tests(&testing_config);
+    fs::remove_dir_all(&snapshot_dir).unwrap();
+
+    assert!(!all_passed);
+    assert!(output.contains("[ PASS    ] 0x1::Snapshot::no_effects"));
+    assert!(output.contains("[ FAIL    ] 0x1::Snapshot::publishes"));
+    assert!(output.contains("Test effects differ from snapshot"));
+}
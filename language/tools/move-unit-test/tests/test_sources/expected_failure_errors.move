@@ -0,0 +1,102 @@
+module 0x1::Abort {
+    use Std::Errors;
+
+    const E_NOT_FOUND: u64 = 3;
+
+    public fun abort_not_found() {
+        abort Errors::not_published(E_NOT_FOUND)
+    }
+}
+
+module 0x1::M {
+    use 0x1::Abort;
+    use Std::Errors;
+    use Std::Vector;
+
+    const E_FOO: u64 = 1;
+    const E_BAR: u64 = 2;
+
+    #[test]
+    #[expected_failure(abort_code = E_FOO)]
+    fun named_code() {
+        abort E_FOO
+    }
+
+    #[test]
+    #[expected_failure(abort_code = Self::E_FOO)]
+    fun named_code_wrong() {
+        abort E_BAR
+    }
+
+    #[test]
+    #[expected_failure(category = Errors::NOT_PUBLISHED, reason = 3)]
+    fun category_and_reason() {
+        Abort::abort_not_found()
+    }
+
+    #[test]
+    #[expected_failure(category = Errors::INVALID_ARGUMENT)]
+    fun category_wrong() {
+        Abort::abort_not_found()
+    }
+
+    #[test]
+    #[expected_failure(reason = E_FOO)]
+    fun reason_wrong() {
+        Abort::abort_not_found()
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 1, location = Self)]
+    fun location() {
+        abort 1
+    }
+
+    #[test]
+    #[expected_failure(category = Errors::NOT_PUBLISHED, location = Self)]
+    fun location_wrong() {
+        Abort::abort_not_found()
+    }
+
+    #[test]
+    #[expected_failure(location = 0x1::Abort)]
+    fun location_of_other_module() {
+        Abort::abort_not_found()
+    }
+
+    #[test]
+    #[expected_failure(arithmetic_error, location = Self)]
+    fun arithmetic_error() {
+        1 / 0;
+    }
+
+    #[test]
+    #[expected_failure(arithmetic_error)]
+    fun arithmetic_error_wrong() {
+        abort 0
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 0)]
+    fun abort_code_but_arithmetic_error() {
+        18446744073709551615 + 1;
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 1, location = Std::Vector)]
+    fun vector_out_of_bounds() {
+        Vector::borrow(&Vector::empty<u64>(), 0);
+    }
+
+    #[test]
+    #[expected_failure(out_of_gas)]
+    fun out_of_gas() {
+        loop {}
+    }
+
+    #[test]
+    #[expected_failure(out_of_gas)]
+    fun out_of_gas_wrong() {
+        1 - 2;
+    }
+}